@@ -1,496 +1,2491 @@
 use gpui::*;
+use gpui::prelude::*;
 use std::sync::Arc;
 use parking_lot::Mutex;
-use chrono::Utc;
-
-use crate::config::Config;
+use chrono::{Duration, Local, NaiveDate, Utc};
+use futures::StreamExt;
+
+use crate::blocker::Blocker;
+use crate::calendar::{self, CalendarSource, Meeting};
+use crate::clock::SystemClock;
+use crate::config::{AlarmMode, Config, MAX_DURATION_MINUTES};
+use crate::events::{EventBus, SessionEvent};
+use crate::feed::FeedWriter;
+use crate::focus_apps;
+use crate::import;
+use crate::journal::{Journal, MAX_NOTE_CHARS};
+use crate::lan::{self, GroupState, LanLeader};
+use crate::mqtt::{MqttCommand, MqttPublisher};
 use crate::notifications;
-use crate::persistence::Persistence;
-use crate::state::{SessionInfo, TimerState};
-use crate::theme::{Theme, ThemeMode};
+use crate::persistence::{Persistence, StateWriter};
+use crate::placement::{self, Spot};
+use crate::plugins::{self, PluginCommand, PluginHost};
+use crate::power;
+use crate::quick_timer::QuickTimer;
+use crate::recovery::{CrashGuard, Recovery};
+use crate::remote::{RemoteCommand, WebRemote};
+use crate::schedule::{self, WorkSchedule};
+use crate::scripts::{self, ScriptCommand, ScriptRunner};
+use crate::state::{shown_input, ActiveTimer, shown_label, HistoryFilter, HistoryRange, HistoryRetention, QueuedLabel, SessionInfo, TimerState, TimerView};
+use crate::sound::{self, SoundPlayer};
+use crate::stats;
+use crate::stats::goals::{GoalPeriod, GoalProgress};
+use crate::stats::summary::StatsRecorder;
+use crate::stats::usage::UsageRecorder;
+use crate::status;
+use crate::strict::{self, Interruption};
+use crate::theme::{Theme, ThemePreference};
 use crate::timer::Timer;
-use crate::ui::CircularTimer;
+use crate::url_scheme::{self, UrlAction, UrlRequest};
+use crate::ui::detached::SavedBounds;
+use crate::ui::label_input;
+use crate::ui::particles::Burst;
+use crate::ui::{CircularTimer, ControlFocus, DetachedPanel, DiagnosticsPanel, HistoryPanel, NewTimerDialog, NewTimerDraft, NotificationTestStatus, Presentation, Prompt, QueuePanel, SettingsPanel, StatsContent, StatsPanel, StatsTab, TimerProps, WindowLayout};
+
+// Full-window panels that replace the timer view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivePanel {
+    Timer,
+    Settings,
+    Queue,
+    NewTimer,
+    History,
+    Stats,
+    Diagnostics,
+}
 
-actions!(pomodoro, [ToggleTimer, ResetTimer, SkipSession, QuitApp, NewTimer, NavigateHistoryPrev, NavigateHistoryNext]);
+// How much of the window still shows once auto_hide has faded it
+const HIDDEN_OPACITY: f32 = 0.1;
+
+actions!(pomodoro, [ToggleTimer, ResetTimer, SkipSession, QuitApp, NewTimer, NavigateHistoryPrev, NavigateHistoryNext, FocusNext, FocusPrev, OpenSettings, About, Present, PasteLabel, CopyLabel]);
 
 pub struct PomodoroApp {
     session_info: Arc<Mutex<SessionInfo>>,
     timer: Arc<Timer>,
+    events: EventBus,  // Session lifecycle events, see events.rs
+    state_writer: StateWriter,  // Saves the state in the background after changes
+    _flush_on_quit: Subscription,
+    blocker: Option<Arc<Blocker>>,  // Site blocker commands, lifted again on quit
+    stats: Arc<StatsRecorder>,  // Daily totals the stats panel reads
+    usage: Option<Arc<UsageRecorder>>,  // Usage counts for Stats, when usage_stats is on
+    sound: Arc<SoundPlayer>,  // Focus sound during work sessions
+    alarm: Option<TimerState>,  // Session whose alarm is ringing, with alarm_mode = "until_dismissed"
+    _watch_display: Vec<Subscription>,  // Reopens the window if its display goes away
+    main_window: Option<AnyWindowHandle>,  // The timer's window; None while it's being reopened
+    displays: Vec<DisplayId>,  // Connected when last looked
     config: Config,
     focus_handle: FocusHandle,
+    control_focus: ControlFocus,  // Tab stops for the clickable controls
     label_input: String,  // Current text in label input field
     is_editing_label: bool,  // True when actively editing label
+    label_marked: Option<std::ops::Range<usize>>,  // Text the IME is still composing in the label being typed, in bytes
+    label_cursor: Option<usize>,  // Caret in the label being typed, in bytes; None at the end
     show_preset_menu: bool,  // True when preset timer menu is visible
+    presentation: Option<WindowHandle<Presentation>>,  // Fullscreen window for a projector, while open
+    active_panel: ActivePanel,  // Which panel fills the window
+    window_layout: WindowLayout,  // Panels popped out into windows of their own
+    detached: Vec<(ActivePanel, WindowHandle<DetachedPanel>)>,  // Their windows, while open
+    queue_input: String,  // Text typed into the queue panel
+    new_timer: NewTimerDraft,  // Choices in the new-timer dialog
+    history_filter: HistoryFilter,  // Search in the history panel
+    history_selected: usize,  // Highlighted row among the history results
+    stats_tab: StatsTab,  // Page shown in the stats panel
+    stats_range: HistoryRange,  // Range for the per-label totals
+    stats_query: String,  // Label search in the per-label totals
+    journal: Journal,  // Notes for each day
+    note_input: String,  // Today's note while it's being written
+    is_editing_note: bool,
+    privacy_mode: bool,  // Labels hidden on screen and in notifications
+    notification_test: Option<NotificationTestStatus>,  // Result of the last test notification
+    import_status: Option<String>,  // Result of the last CSV import
+    last_announcement: String,  // Last state summary exposed to screen readers
+    notices: Vec<String>,  // Startup messages (e.g. config warnings) shown until dismissed
+    history_prune_prompt: Option<u32>,  // history_max_age_days waiting for the user to agree to the deletion
+    state_callback: Option<String>,  // x-success link with the state, waiting for the user to agree to send it
+    stale_session: Option<u32>,  // Seconds a session was left running with the app closed, until the user says what happened
+    long_break_declined: Option<String>,  // Id of the break kept short when a long one was suggested
+    interrupting: Option<String>,  // Id of the strict-mode pomodoro pause was pressed in, asking what interrupted it
+    recovery: Option<Recovery>,  // Session saved when the app last crashed, until restored or dismissed
+    plugin_badges: Vec<(String, String)>,  // Badge text set by each plugin, in plugin order
+    remote_url: Option<String>,  // Address of the phone remote, when it's running
+    show_remote_qr: bool,  // Settings shows the QR code for the phone remote
+    meeting: Option<Meeting>,  // Calendar meeting under way
+    paused_for_meeting: bool,  // The meeting paused a running session
+    schedule: WorkSchedule,  // Work hours and the daily hard stop
+    wound_down_on: Option<NaiveDate>,  // Day the wind-down notification went out
+    redrawing_for_time_of_day: bool,  // The minute loop for theme = "time" is running
+    polling_displays: bool,  // The display check for the presentation and detached panels is running
+    auto_hidden: bool,  // Faded out partway into a work session, with auto_hide
+    peeking: bool,  // The pointer is over the window, showing it while faded
+    _auto_hide_timer: Option<Task<()>>,  // Fades the window once the work session has run a while
+    quick_timer: Option<QuickTimer>,  // One-off countdown running beside the session
+    _quick_timer_ticks: Option<Task<()>>,  // Counts it down; dropping it stops the loop
 }
 
 impl PomodoroApp {
-    pub fn new(config: Config, cx: &mut Context<'_, Self>) -> Self {
+    pub fn new(config: Config, notices: Vec<String>, window: &mut Window, cx: &mut Context<'_, Self>) -> Self {
         // Load persisted state
         let session_info = match Persistence::load() {
             Ok(mut info) => {
                 notifications::log_info("Loaded persisted timer state");
-                // Initialize timers from config if they're at 0
-                if info.time_remaining_secs == 0 {
-                    info.time_remaining_secs = config.work_duration_secs();
+                // A running or paused session decides the mode; an idle one keeps the mode it was left in
+                if info.current_state != TimerState::Idle {
+                    info.is_focus_mode = info.current_state.is_work();
                 }
-                if info.rest_time_remaining_secs == 0 {
-                    info.rest_time_remaining_secs = config.short_break_duration_secs();
+                // An armed start that came while the app was closed doesn't go off late
+                if let Some(at) = info.starts_at.take_if(|at| *at <= Utc::now()) {
+                    notifications::log_info(&format!("Not starting the session armed for {}, the app wasn't running", at.with_timezone(&Local).format("%H:%M")));
                 }
-                // Set focus mode based on current state
-                info.is_focus_mode = info.current_state.is_work() || info.current_state == TimerState::Idle;
                 Arc::new(Mutex::new(info))
             }
             Err(e) => {
                 notifications::log_error(&format!("Failed to load state: {}", e));
-                let mut info = SessionInfo::new();
-                // Initialize both timers with config values
-                info.time_remaining_secs = config.work_duration_secs();
-                info.rest_time_remaining_secs = config.short_break_duration_secs();
-                Arc::new(Mutex::new(info))
+                Arc::new(Mutex::new(SessionInfo::new()))
             }
         };
 
-        let timer = Arc::new(Timer::new(session_info.clone(), config.clone()));
-
-        // Spawn background tick loop using background_spawn
-        let session_info_for_tick = session_info.clone();
-        let config_for_tick = config.clone();
-        cx.spawn(async move |this, cx| {
-            loop {
-                // Sleep for 1 second using background_spawn
-                cx.background_spawn(async {
-                    std::thread::sleep(std::time::Duration::from_secs(1));
-                }).await;
-
-                let (is_running, just_completed) = {
-                    let mut info = session_info_for_tick.lock();
-                    let is_running = info.current_state.is_running();
-
-                    let just_completed = if is_running {
-                        // Decrement the appropriate timer based on current state
-                        if info.current_state.is_work() {
-                            if info.time_remaining_secs > 0 {
-                                info.time_remaining_secs -= 1;
-                                info.last_updated = Utc::now();
-                                info.time_remaining_secs == 0
-                            } else {
-                                false
-                            }
-                        } else {
-                            if info.rest_time_remaining_secs > 0 {
-                                info.rest_time_remaining_secs -= 1;
-                                info.last_updated = Utc::now();
-                                info.rest_time_remaining_secs == 0
-                            } else {
-                                false
-                            }
-                        }
-                    } else {
-                        false
-                    };
-
-                    (is_running, just_completed)
-                };
-
-                // Trigger UI update every second when running
-                if is_running {
-                    let _ = this.update(cx, |_, cx| cx.notify());
-                }
-
-                if just_completed {
-                    notifications::log_info("Timer completed!");
-
-                    // Send notification and transition to Idle state
-                    {
-                        let mut info = session_info_for_tick.lock();
-
-                        notifications::log_info(&format!(
-                            "Timer completion detected. State: {:?}, Notifications enabled: {}",
-                            info.current_state, config_for_tick.enable_notifications
-                        ));
-
-                        if config_for_tick.enable_notifications {
-                            match info.current_state {
-                                TimerState::Working => {
-                                    notifications::log_info("Triggering work complete notification");
-                                    notifications::notify_work_complete();
-                                }
-                                TimerState::ShortBreak => {
-                                    notifications::log_info("Triggering break complete notification");
-                                    notifications::notify_break_complete();
-                                }
-                                TimerState::LongBreak => {
-                                    notifications::log_info("Triggering long break complete notification");
-                                    notifications::notify_long_break_complete();
-                                }
-                                _ => {
-                                    notifications::log_info(&format!(
-                                        "No notification for state: {:?}",
-                                        info.current_state
-                                    ));
-                                }
-                            }
-                        } else {
-                            notifications::log_info("Notifications are disabled in config");
-                        }
+        // Age pruning deletes history, so the first time it waits for a yes
+        let mut history_prune_prompt = None;
+        {
+            let mut info = session_info.lock();
+            info.retention = HistoryRetention {
+                limit: (config.history_limit > 0).then_some(config.history_limit as usize),
+                max_age_days: None,
+            };
+            if let Some(days) = config.history_max_age_days {
+                if info.history_max_age_confirmed == Some(days) || info.older_than(days, Utc::now()) == 0 {
+                    info.history_max_age_confirmed = Some(days);
+                    info.retention.max_age_days = Some(days);
+                } else {
+                    history_prune_prompt = Some(days);
+                }
+            }
+            let pruned = info.prune_history(Utc::now());
+            if pruned > 0 {
+                notifications::log_info(&format!("Dropped {} old session(s) from History", pruned));
+            }
+        }
 
-                        // Transition to Idle state and enable celebration breathing effect
-                        info.current_state = TimerState::Idle;
-                        info.show_celebration = true;
-                        info.last_updated = Utc::now();
+        // A crash saves the session it interrupted, offered back once
+        let recovery = Recovery::take();
+
+        // A session left running when the app quit or crashed waits, paused,
+        // to be resumed, fast-forwarded or discarded
+        let stale_session = {
+            let mut info = session_info.lock();
+            let stale = info.stale_secs(Utc::now());
+            if let Some(paused) = stale.and_then(|_| info.current_state.pause()) {
+                notifications::log_info("The last session was left running, paused it until asked");
+                info.current_state = paused;
+            }
+            stale
+        };
 
-                        // Save state
-                        if let Err(e) = Persistence::save(&info) {
-                            notifications::log_error(&format!("Failed to save state: {}", e));
-                        }
-                    }
+        // Durations and sound come from the picked template, if any
+        let session_config = Self::with_template(&config, session_info.lock().template.as_deref());
+        let timer = Arc::new(Timer::new(session_info.clone(), session_config.clone(), Arc::new(SystemClock)));
+        timer.preload();
+        let work_minutes = session_config.work_duration;
+        let privacy_mode = config.privacy_mode;
+        notifications::set_private(privacy_mode);
+
+        // Find out what the notification server supports before the first completion
+        cx.background_spawn(async {
+            notifications::probe_capabilities();
+        })
+        .detach();
 
-                    // Trigger UI update to show Idle state with celebration
-                    let _ = this.update(cx, |_, cx| cx.notify());
+        // Subscribers that react to session events
+        let events = EventBus::default();
+        let state_writer = StateWriter::spawn(session_info.clone(), events.clone());
+        let writer = state_writer.clone();
+        events.subscribe(move |event, _info| writer.on_event(event));
+        let crash_guard = CrashGuard::install(&session_info.lock());
+        events.subscribe(move |event, info| crash_guard.on_event(event, info));
+        let notifier = Mutex::new(notifications::Notifier::new(config.clone()));
+        events.subscribe(move |event, info| notifier.lock().on_event(event, info));
+
+        let (mqtt_tx, mut mqtt_rx) = futures::channel::mpsc::unbounded();
+        if let Some(mqtt) = MqttPublisher::spawn(&config, mqtt_tx) {
+            // Brokers keep the state, so send the current one right away
+            mqtt.on_event(&SessionEvent::Changed, &session_info.lock());
+            events.subscribe(move |event, info| mqtt.on_event(event, info));
+        }
+        cx.spawn(async move |this, cx| {
+            while let Some(command) = mqtt_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_mqtt_command(command, cx)).is_err() {
+                    break;
                 }
             }
         })
         .detach();
 
-        // Periodically save state
-        let session_info_clone = session_info.clone();
-        cx.spawn(async move |_this, cx| {
-            loop {
-                // Sleep for 5 seconds using background_spawn
-                cx.background_spawn(async {
-                    std::thread::sleep(std::time::Duration::from_secs(5));
-                }).await;
-
-                let info = session_info_clone.lock();
-                if let Err(e) = Persistence::save(&info) {
-                    notifications::log_error(&format!("Failed to auto-save state: {}", e));
+        // User scripts see lifecycle events; their commands come back to the UI thread
+        let (script_tx, mut script_rx) = futures::channel::mpsc::unbounded();
+        let scripts = ScriptRunner::new(scripts::find_scripts(), script_tx);
+        events.subscribe(move |event, info| scripts.on_event(event, info));
+        cx.spawn(async move |this, cx| {
+            while let Some(command) = script_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_script_command(command, cx)).is_err() {
+                    break;
                 }
             }
         })
         .detach();
 
-        Self {
-            session_info,
-            timer,
-            config,
-            focus_handle: cx.focus_handle(),
-            label_input: String::new(),
-            is_editing_label: false,
-            show_preset_menu: false,
-        }
-    }
-
-    pub fn handle_new_timer(&mut self, cx: &mut Context<'_, Self>) {
-        let session_info = self.session_info.clone();
-        let label = self.label_input.clone();
-
+        // Plugins work the same way, with badges shown under the timer
+        let (plugin_tx, mut plugin_rx) = futures::channel::mpsc::unbounded();
+        let plugins = PluginHost::spawn(plugins::find_plugins(), plugin_tx);
+        events.subscribe(move |event, info| plugins.on_event(event, info));
         cx.spawn(async move |this, cx| {
-            use uuid::Uuid;
-
-            // Reset to idle and create new timer with new ID
-            {
-                let mut info = session_info.lock();
-                info.current_state = TimerState::Idle;
-                info.time_remaining_secs = 0;
-                info.current_id = Uuid::new_v4().to_string();  // Generate new UUID
-                info.current_label = label;
-                info.exit_history();
-                info.last_updated = Utc::now();
-            }
-
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+            while let Some(command) = plugin_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_plugin_command(command, cx)).is_err() {
+                    break;
+                }
             }
-
-            // Clear label input and trigger UI update
-            let _ = this.update(cx, |app, cx| {
-                app.label_input.clear();
-                cx.notify();
-            });
         })
         .detach();
-    }
-
-    pub fn handle_toggle(&mut self, cx: &mut Context<'_, Self>) {
-        let timer = self.timer.clone();
-        let session_info = self.session_info.clone();
 
+        // Phone remote: presses on the page come back to the UI thread
+        let (remote_tx, mut remote_rx) = futures::channel::mpsc::unbounded();
+        let remote = WebRemote::spawn(&config, remote_tx);
+        let remote_url = remote.as_ref().map(|remote| remote.url().to_string());
+        if let Some(remote) = remote {
+            remote.on_event(&SessionEvent::Changed, &session_info.lock());
+            events.subscribe(move |event, info| remote.on_event(event, info));
+        }
         cx.spawn(async move |this, cx| {
-            let (current_state, is_focus_mode) = {
-                let info = session_info.lock();
-                (info.current_state.clone(), info.is_focus_mode)
-            };
-
-            match current_state {
-                TimerState::Idle => {
-                    // Start based on current mode (focus or rest)
-                    if is_focus_mode {
-                        timer.start_work().await;
-                        notifications::log_info("Started work session");
-                    } else {
-                        timer.start_short_break().await;
-                        notifications::log_info("Started rest session");
-                    }
-                }
-                TimerState::Working | TimerState::ShortBreak | TimerState::LongBreak => {
-                    // Pause
-                    timer.pause().await;
-                    notifications::log_info("Paused timer");
-                }
-                TimerState::WorkPaused | TimerState::BreakPaused | TimerState::LongBreakPaused => {
-                    // Resume
-                    timer.resume().await;
-                    notifications::log_info("Resumed timer");
+            while let Some(command) = remote_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_remote_command(command, cx)).is_err() {
+                    break;
                 }
             }
+        })
+        .detach();
 
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+        // Team timer on the local network: lead it, or follow its leader
+        if let Some(leader) = LanLeader::spawn(&config) {
+            leader.on_event(&SessionEvent::Changed, &session_info.lock());
+            events.subscribe(move |event, info| leader.on_event(event, info));
+        }
+        let (group_tx, mut group_rx) = futures::channel::mpsc::unbounded();
+        lan::spawn_follower(&config, group_tx);
+        cx.spawn(async move |this, cx| {
+            while let Some(state) = group_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_group_state(state, cx)).is_err() {
+                    break;
+                }
             }
-
-            // Trigger UI update
-            let _ = this.update(cx, |_, cx| cx.notify());
         })
         .detach();
-    }
 
-    pub fn handle_skip(&mut self, cx: &mut Context<'_, Self>) {
-        let session_info = self.session_info.clone();
+        // Totals for the stats panel, kept up to date as sessions end
+        let stats = StatsRecorder::spawn(&config, &session_info.lock());
+        let recorder = stats.clone();
+        events.subscribe(move |event, info| recorder.on_event(event, info));
+
+        let journal = Journal::load().unwrap_or_else(|e| {
+            notifications::log_error(&format!("{:#}", e));
+            Journal::default()
+        });
+        let window_layout = WindowLayout::load().unwrap_or_else(|e| {
+            notifications::log_error(&format!("{:#}", e));
+            WindowLayout::default()
+        });
+
+        // Opt-in usage counts, kept up to date as sessions end
+        let usage = UsageRecorder::spawn(&config, &session_info.lock());
+        if let Some(recorder) = usage.clone() {
+            events.subscribe(move |event, info| recorder.on_event(event, info));
+        }
 
-        cx.spawn(async move |this, cx| {
-            // Stop timer and add to history, then navigate to previous
-            {
-                let mut info = session_info.lock();
+        // Feed of completed sessions for other tools, when session_feed is set
+        if let Some(feed) = FeedWriter::spawn(&config, &session_info.lock()) {
+            events.subscribe(move |event, info| feed.on_event(event, info));
+        }
 
-                // If running, stop and add to history
-                if info.current_state.is_running() {
-                    let session_type = info.current_state.display_name().to_string();
-                    let elapsed = info.time_remaining_secs;
-                    let id = info.current_id.clone();
-                    let label = info.current_label.clone();
+        // Completed breaks as mindfulness minutes, when built with the health feature
+        #[cfg(feature = "health")]
+        if let Some(log) = crate::health::HealthLog::spawn(&config, &session_info.lock()) {
+            events.subscribe(move |event, info| log.on_event(event, info));
+        }
 
-                    info.add_to_history(id, label, elapsed, session_type);
-                    info.current_state = TimerState::Idle;
-                    info.time_remaining_secs = 0;
-                    notifications::log_info("Timer stopped and saved to history");
-                }
+        // Block distractions while a work session counts down
+        let blocker = Blocker::spawn(&config);
+        if let Some(blocker) = blocker.clone() {
+            blocker.on_event(&SessionEvent::Changed, &session_info.lock());
+            events.subscribe(move |event, info| blocker.on_event(event, info));
+        }
 
-                // Navigate to previous in history
-                info.navigate_history_prev();
-            }
+        // Focus sound while a work session counts down
+        let sound = Arc::new(SoundPlayer::spawn(&session_config));
+        let player = sound.clone();
+        player.on_event(&SessionEvent::Changed, &session_info.lock());
+        events.subscribe(move |event, info| player.on_event(event, info));
 
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+        // Calendar meetings pause the timer and hold back notifications
+        let (meeting_tx, mut meeting_rx) = futures::channel::mpsc::unbounded();
+        if let Some(source) = CalendarSource::from_config(&config) {
+            calendar::spawn_watcher(source, meeting_tx);
+        }
+        cx.spawn(async move |this, cx| {
+            while let Some(meeting) = meeting_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_meeting(meeting, cx)).is_err() {
+                    break;
+                }
             }
-
-            // Trigger UI update
-            let _ = this.update(cx, |_, cx| cx.notify());
         })
         .detach();
-    }
 
+        // With a hard stop, the workday countdown moves on and the wind-down
+        // notification goes out even while the timer is idle
+        let schedule = WorkSchedule::from_config(&config).unwrap_or_default();
+        if schedule.has_hard_stop() {
+            cx.spawn(async move |this, cx| loop {
+                if this.update(cx, |app, cx| app.handle_workday_minute(cx)).is_err() {
+                    break;
+                }
+                cx.background_spawn(async {
+                    std::thread::sleep(std::time::Duration::from_secs(60));
+                })
+                .await;
+            })
+            .detach();
+        }
 
-    pub fn handle_reset(&mut self, cx: &mut Context<'_, Self>) {
-        let timer = self.timer.clone();
-        let session_info = self.session_info.clone();
+        // Time per frontmost app during work sessions, when track_apps is on
+        focus_apps::spawn_monitor(&config, session_info.clone());
+        focus_apps::spawn_label_suggestions(&config, &events, session_info.clone());
 
+        // Re-render after events; the channel hands them to the UI thread
+        let (redraw_tx, mut redraw_rx) = futures::channel::mpsc::unbounded();
+        events.subscribe(move |event, _info| {
+            let _ = redraw_tx.unbounded_send(event.clone());
+        });
         cx.spawn(async move |this, cx| {
-            timer.reset().await;
-            notifications::log_info("Reset timer");
-
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+            while let Some(event) = redraw_rx.next().await {
+                let updated = this.update(cx, |app, cx| {
+                    if let SessionEvent::Completed { state, .. } = &event {
+                        app.handle_alarm(state.clone(), cx);
+                    }
+                    if event != SessionEvent::Tick {
+                        app.auto_hide(cx);
+                    }
+                    // Only the timer view and the presentation show the countdown, other panels skip the per-second ticks
+                    if event != SessionEvent::Tick || app.active_panel == ActivePanel::Timer || app.presentation.is_some() {
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
             }
-
-            // Trigger UI update
-            let _ = this.update(cx, |_, cx| cx.notify());
         })
         .detach();
-    }
-
-    pub fn handle_switch_to_focus(&mut self, cx: &mut Context<'_, Self>) {
-        let session_info = self.session_info.clone();
-        let config = self.config.clone();
-
-        cx.spawn(async move |this, cx| {
-            {
-                let mut info = session_info.lock();
 
-                // Only proceed if we're NOT already in focus mode
-                if info.is_focus_mode {
-                    return;
+        // Count down once a second while a session runs. With nothing running the
+        // loop sleeps until an event arrives, so an idle app doesn't wake up at all.
+        let (wake_tx, mut wake_rx) = futures::channel::mpsc::unbounded();
+        events.subscribe(move |event, _info| {
+            if *event != SessionEvent::Tick {
+                let _ = wake_tx.unbounded_send(());
+            }
+        });
+        let timer_for_tick = timer.clone();
+        let session_info_for_tick = session_info.clone();
+        let events_for_tick = events.clone();
+        cx.spawn(async move |_this, cx| {
+            loop {
+                let (running, starts_at) = {
+                    let info = session_info_for_tick.lock();
+                    (info.current_state.is_running(), info.starts_at)
+                };
+                if !running {
+                    match starts_at {
+                        None => {
+                            if wake_rx.next().await.is_none() {
+                                break;
+                            }
+                            continue;
+                        }
+                        // An armed session gets one timer for its start, cut short by
+                        // any event in case it was disarmed or moved
+                        Some(at) => {
+                            let wait = (at - Utc::now()).to_std().unwrap_or_default();
+                            let start = cx.background_executor().timer(wait);
+                            if let futures::future::Either::Right((None, _)) = futures::future::select(start, wake_rx.next()).await {
+                                break;
+                            }
+                        }
+                    }
+                } else {
+                    // Sleep for 1 second using background_spawn
+                    cx.background_spawn(async {
+                        std::thread::sleep(std::time::Duration::from_secs(1));
+                    }).await;
                 }
 
-                // Initialize work timer if it's at 0
-                if info.time_remaining_secs == 0 {
-                    info.time_remaining_secs = config.work_duration_secs();
+                // The timer works out how far to count down from the clock
+                let happened = timer_for_tick.tick();
+                if !happened.is_empty() {
+                    let info = session_info_for_tick.lock();
+                    for event in happened {
+                        events_for_tick.publish(event, &info);
+                    }
                 }
-
-                // Switch to focus mode and idle state, preserving the timer value
-                info.is_focus_mode = true;
-                info.current_state = TimerState::Idle;
-                info.last_updated = chrono::Utc::now();
-            }
-
-            notifications::log_info("Switched to focus mode");
-
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
             }
-
-            // Trigger UI update
-            let _ = this.update(cx, |_, cx| cx.notify());
         })
         .detach();
-    }
-
-    pub fn handle_switch_to_rest(&mut self, cx: &mut Context<'_, Self>) {
-        let session_info = self.session_info.clone();
-        let config = self.config.clone();
 
+        // Go easy on the battery with battery_saver; the watcher says when that starts and stops
+        let (power_tx, mut power_rx) = futures::channel::mpsc::unbounded();
+        power::spawn_watcher(&config, power_tx);
         cx.spawn(async move |this, cx| {
-            {
-                let mut info = session_info.lock();
-
-                // Only proceed if we're NOT already in rest mode
-                if !info.is_focus_mode {
-                    return;
-                }
-
-                // Initialize rest timer if it's at 0
-                if info.rest_time_remaining_secs == 0 {
-                    info.rest_time_remaining_secs = config.short_break_duration_secs();
+            while let Some(saving) = power_rx.next().await {
+                if this.update(cx, |app, cx| app.handle_battery_saver(saving, cx)).is_err() {
+                    break;
                 }
-
-                // Switch to rest mode and idle state, preserving the timer value
-                info.is_focus_mode = false;
-                info.current_state = TimerState::Idle;
-                info.last_updated = chrono::Utc::now();
             }
+        })
+        .detach();
 
-            notifications::log_info("Switched to rest mode");
-
-            // Save state
-            let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+        // Don't lose a pending write when quitting, drop any crash recovery, lift the site block, give other apps their volume
+        // back, and note where the detached panels are
+        let flush_on_quit = cx.on_app_quit(|app, _cx| {
+            app.state_writer.flush();
+            app.save_window_layout();
+            Recovery::clear();
+            if let Some(blocker) = &app.blocker {
+                blocker.restore();
             }
+            app.sound.restore();
+            async {}
+        });
 
-            // Trigger UI update
-            let _ = this.update(cx, |_, cx| cx.notify());
-        })
-        .detach();
+        let mut app = Self {
+            session_info,
+            timer,
+            events,
+            state_writer,
+            _flush_on_quit: flush_on_quit,
+            blocker,
+            stats,
+            usage,
+            sound,
+            alarm: None,
+            presentation: None,
+            _watch_display: Self::watch_display(window, cx),
+            main_window: Some(window.window_handle()),
+            displays: placement::display_ids(cx),
+            config,
+            focus_handle: cx.focus_handle(),
+            control_focus: ControlFocus::new(cx),
+            label_input: String::new(),
+            is_editing_label: false,
+            label_marked: None,
+            label_cursor: None,
+            show_preset_menu: false,
+            active_panel: ActivePanel::Timer,
+            window_layout,
+            detached: Vec::new(),
+            queue_input: String::new(),
+            history_filter: HistoryFilter::default(),
+            history_selected: 0,
+            stats_tab: StatsTab::Today,
+            stats_range: HistoryRange::Week,
+            stats_query: String::new(),
+            journal,
+            note_input: String::new(),
+            is_editing_note: false,
+            new_timer: NewTimerDraft { label: String::new(), minutes: work_minutes, is_focus: true, category: None },
+            privacy_mode,
+            notification_test: None,
+            import_status: None,
+            last_announcement: String::new(),
+            notices,
+            history_prune_prompt,
+            state_callback: None,
+            stale_session,
+            long_break_declined: None,
+            interrupting: None,
+            recovery,
+            plugin_badges: Vec::new(),
+            remote_url,
+            show_remote_qr: false,
+            meeting: None,
+            paused_for_meeting: false,
+            schedule,
+            wound_down_on: None,
+            redrawing_for_time_of_day: false,
+            polling_displays: false,
+            auto_hidden: false,
+            peeking: false,
+            _auto_hide_timer: None,
+            quick_timer: None,
+            _quick_timer_ticks: None,
+        };
+        app.redraw_for_time_of_day(cx);
+
+        // Panels that were open in windows of their own at the last quit
+        let reopen = app.window_layout.open_panels();
+        if !reopen.is_empty() {
+            cx.spawn(async move |this, cx| {
+                let _ = this.update(cx, |app, cx| {
+                    for panel in reopen {
+                        app.open_detached(panel, cx);
+                    }
+                });
+            })
+            .detach();
+        }
+        app
     }
 
-    pub fn handle_edit_label(&mut self, cx: &mut Context<'_, Self>) {
-        // Load current label into edit buffer and enter edit mode
-        let current_label = self.session_info.lock().current_label.clone();
-        self.label_input = current_label;
-        self.is_editing_label = true;
+    // A detached panel opens or closes its window instead
+    fn toggle_panel(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) {
+        self.show_preset_menu = false;
+        self.is_editing_note = false;
+        if !self.window_layout.is_detached(panel) {
+            self.active_panel = if self.active_panel == panel { ActivePanel::Timer } else { panel };
+        } else if !self.close_detached(panel, cx) {
+            self.open_detached(panel, cx);
+        }
         cx.notify();
     }
 
-    pub fn handle_done_label(&mut self, cx: &mut Context<'_, Self>) {
-        // Save label and exit edit mode
-        let label = self.label_input.clone();
-        let session_info = self.session_info.clone();
+    // Whether the panel is up, in the timer window or its own
+    fn shows(&self, panel: ActivePanel) -> bool {
+        self.active_panel == panel || self.detached.iter().any(|(open, _)| *open == panel)
+    }
 
-        cx.spawn(async move |_this, cx| {
-            {
-                let mut info = session_info.lock();
-                info.current_label = label;
-            }
-            let _ = cx.update(|_cx| {});
-        }).detach();
+    // Pop the panel out of the timer window into one of its own, from then on
+    pub fn handle_detach_panel(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) {
+        if self.active_panel == panel {
+            self.active_panel = ActivePanel::Timer;
+        }
+        self.open_detached(panel, cx);
+        cx.notify();
+    }
 
-        self.label_input.clear();
-        self.is_editing_label = false;
+    // Back into the timer window, shown there right away
+    pub fn handle_dock_panel(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) {
+        self.close_detached(panel, cx);
+        self.window_layout.dock(panel);
+        self.save_window_layout();
+        self.active_panel = panel;
         cx.notify();
     }
 
-    pub fn handle_mouse_over(&mut self, cx: &mut Context<'_, Self>) {
-        // Disable celebration breathing effect on mouse over
-        let session_info = self.session_info.clone();
+    fn open_detached(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) {
+        if self.shows(panel) {
+            return;
+        }
+        let saved = self.window_layout.window(panel).and_then(|window| window.bounds);
+        // Going back where it was needs no display looked up
+        let wanted = if placement::saved_place(saved, cx).is_some() { None } else { self.config.display.clone() };
+        self.with_spot(wanted.as_deref(), cx, move |app, spot, cx| app.open_detached_at(panel, saved, spot, cx));
+    }
 
-        cx.spawn(async move |_this, cx| {
-            {
-                let mut info = session_info.lock();
-                if info.show_celebration {
-                    info.show_celebration = false;
-                    // Save state
-                    if let Err(e) = Persistence::save(&info) {
-                        notifications::log_error(&format!("Failed to save state: {}", e));
-                    }
-                }
+    fn open_detached_at(&mut self, panel: ActivePanel, saved: Option<SavedBounds>, spot: Option<Spot>, cx: &mut Context<'_, Self>) {
+        // It may have been opened while the display was looked up
+        if self.shows(panel) {
+            return;
+        }
+        let title = if panel == ActivePanel::Stats { "Stats" } else { "Settings" };
+        let options = placement::detached_window_options(&self.config, title, saved, spot, cx);
+        let app = cx.entity();
+        match cx.open_window(options, |window, cx| cx.new(|cx| DetachedPanel::new(app, panel, window, cx))) {
+            Ok(handle) => {
+                self.detached.push((panel, handle));
+                self.window_layout.set_open(panel, true);
+                self.save_window_layout();
+                self.poll_displays(cx);
             }
-            let _ = cx.update(|_cx| {});
-        }).detach();
+            Err(e) => notifications::log_error(&format!("Failed to open the {} window: {}", title, e)),
+        }
+    }
 
-        cx.notify();
+    // Close the panel's window, if it's open. It stays detached.
+    fn close_detached(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) -> bool {
+        let Some(index) = self.detached.iter().position(|(open, _)| *open == panel) else {
+            return false;
+        };
+        let (_, handle) = self.detached.remove(index);
+        // Later, as this may run in the window's own key handler
+        cx.defer(move |cx| {
+            let _ = handle.update(cx, |_detached, window, _cx| window.remove_window());
+        });
+        self.handle_detached_closed(panel, cx);
+        true
     }
 
-    pub fn handle_toggle_preset_menu(&mut self, cx: &mut Context<'_, Self>) {
-        self.show_preset_menu = !self.show_preset_menu;
+    // The panel's window went away, possibly from its title bar
+    pub fn handle_detached_closed(&mut self, panel: ActivePanel, cx: &mut Context<'_, Self>) {
+        self.detached.retain(|(open, _)| *open != panel);
+        if panel == ActivePanel::Stats {
+            self.is_editing_note = false;
+        }
+        self.window_layout.set_open(panel, false);
+        self.save_window_layout();
         cx.notify();
     }
 
-    pub fn handle_close_preset_menu(&mut self, cx: &mut Context<'_, Self>) {
-        if self.show_preset_menu {
-            self.show_preset_menu = false;
-            cx.notify();
+    // Saved when the window closes or the app quits
+    pub fn handle_detached_moved(&mut self, panel: ActivePanel, bounds: SavedBounds, cx: &mut Context<'_, Self>) {
+        self.window_layout.moved(panel, bounds);
+        // It may have been moved off a display that was unplugged
+        self.check_displays_soon(cx);
+    }
+
+    fn save_window_layout(&self) {
+        if let Err(e) = self.window_layout.save() {
+            notifications::log_error(&format!("{:#}", e));
         }
     }
 
-    pub fn handle_set_preset_timer(&mut self, minutes: u32, cx: &mut Context<'_, Self>) {
-        let session_info = self.session_info.clone();
+    // Whether a field takes the text typed in the window showing `panel`
+    pub fn is_typing_in(&self, panel: ActivePanel) -> bool {
+        match panel {
+            _ if self.is_editing_note => panel == ActivePanel::Stats,
+            ActivePanel::NewTimer | ActivePanel::Queue => true,
+            ActivePanel::Timer => self.is_editing_label,
+            _ => false,
+        }
+    }
 
-        cx.spawn(async move |_this, cx| {
-            {
-                let mut info = session_info.lock();
+    pub fn render_detached(&mut self, panel: ActivePanel, window: &mut Window, cx: &mut Context<'_, Self>) -> AnyElement {
+        let label_caret = self.label_caret();
+        let theme = Theme::current(self.config.theme, window.appearance());
+        let session_info = self.session_info.lock();
+        self.render_panel(panel, &session_info, label_caret, theme, cx.entity())
+    }
 
-                // Set timer to preset duration (in seconds)
-                info.time_remaining_secs = minutes * 60;
-                info.current_state = TimerState::Idle;
-                info.is_focus_mode = true;
-                info.last_updated = Utc::now();
+    // Keys for the stats panel, in the timer window or its own: the day's note
+    // while it's written, and the label search. False for keys left to the rest.
+    fn handle_stats_key(&mut self, key: &str, cx: &mut Context<'_, Self>) -> bool {
+        if self.is_editing_note {
+            // Typing reaches the note through the input handler
+            match key {
+                "enter" => self.handle_done_note(cx),
+                "escape" => self.handle_cancel_note(cx),
+                _ => {
+                    self.edit_typed_label(key, cx);
+                }
             }
-
-            // Save state
+            return true;
+        }
+        if self.stats_tab != StatsTab::Labels {
+            return false;
+        }
+        let query_len = self.stats_query.len();
+        match key {
+            "escape" => self.handle_toggle_stats(cx),
+            "backspace" => {
+                self.stats_query.pop();
+                cx.notify();
+            }
+            "space" if query_len > 0 && query_len < 30 => {
+                self.stats_query.push(' ');
+                cx.notify();
+            }
+            _ if key.len() == 1 && query_len < 30 => {
+                self.stats_query.push_str(key);
+                cx.notify();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    // Escape closes the window; the stats panel's note and search take their keys first
+    pub fn handle_detached_key(&mut self, panel: ActivePanel, key: &str, cx: &mut Context<'_, Self>) {
+        if panel == ActivePanel::Stats && self.handle_stats_key(key, cx) {
+            return;
+        }
+        if key == "escape" {
+            self.close_detached(panel, cx);
+        }
+    }
+
+    // gpui doesn't say when a display comes or goes, and not every window
+    // system moves windows off one that was unplugged. So check where the
+    // window ended up whenever its bounds change, and look at the displays then
+    // and when it's activated. Only the other windows, which may be all that's
+    // left on an unplugged display, are worth a check every few seconds.
+    fn watch_display(window: &mut Window, cx: &mut Context<'_, Self>) -> Vec<Subscription> {
+        vec![
+            cx.observe_window_bounds(window, |app, window, cx| {
+                if !placement::is_reachable(window.bounds(), cx) {
+                    app.reopen_window(cx);
+                }
+                app.check_displays_soon(cx);
+            }),
+            cx.observe_window_activation(window, |app, window, cx| {
+                if window.is_window_active() {
+                    app.check_displays_soon(cx);
+                }
+            }),
+        ]
+    }
+
+    // Call `open` with where the pointer or the window in front is when the
+    // `wanted` display goes by that. Those are looked up off the UI thread, as
+    // that can mean waiting for another program; otherwise `open` runs now.
+    fn with_spot(
+        &mut self,
+        wanted: Option<&str>,
+        cx: &mut Context<'_, Self>,
+        open: impl FnOnce(&mut Self, Option<Spot>, &mut Context<'_, Self>) + 'static,
+    ) {
+        if !placement::needs_lookup(wanted) {
+            open(self, None, cx);
+            return;
+        }
+        let wanted = wanted.map(str::to_string);
+        cx.spawn(async move |this, cx| {
+            let spot = cx.background_executor().spawn(async move { placement::locate(wanted.as_deref()) }).await;
+            let _ = this.update(cx, |app, cx| open(app, spot, cx));
+        })
+        .detach();
+    }
+
+    // gpui can't move a window, so open a new one on a connected display and close the old one
+    fn reopen_window(&mut self, cx: &mut Context<'_, Self>) {
+        // Already under way
+        let Some(old_window) = self.main_window.take() else {
+            return;
+        };
+        notifications::log_info("Window is no longer on a connected display, reopening it");
+        let wanted = self.config.display.clone();
+        self.with_spot(wanted.as_deref(), cx, move |app, spot, cx| app.reopen_window_at(old_window, spot, cx));
+    }
+
+    fn reopen_window_at(&mut self, old_window: AnyWindowHandle, spot: Option<Spot>, cx: &mut Context<'_, Self>) {
+        let view = cx.entity();
+        let options = placement::window_options(&self.config, spot, cx);
+
+        cx.defer(move |cx| {
+            let reopened = cx.open_window(options, |window, cx| {
+                view.update(cx, |app, cx| app._watch_display = Self::watch_display(window, cx));
+                view.clone()
+            });
+            let main_window = match reopened {
+                Ok(window) => {
+                    let _ = old_window.update(cx, |_, window, _| window.remove_window());
+                    window.into()
+                }
+                Err(e) => {
+                    notifications::log_error(&format!("Failed to reopen window: {}", e));
+                    old_window
+                }
+            };
+            view.update(cx, |app, _cx| app.main_window = Some(main_window));
+        });
+    }
+
+    // Animations hold still with reduce_motion, and while saving the battery
+    fn reduce_motion(&self) -> bool {
+        self.config.reduce_motion || power::saving()
+    }
+
+    fn handle_battery_saver(&mut self, saving: bool, cx: &mut Context<'_, Self>) {
+        if self.config.battery_saver_mutes_sound {
+            self.sound.set_quiet(saving);
+        }
+        cx.notify();
+    }
+
+    // Once the window being updated is done, as the check looks into every window
+    fn check_displays_soon(&mut self, cx: &mut Context<'_, Self>) {
+        let view = cx.entity();
+        cx.defer(move |cx| view.update(cx, |app, cx| app.check_displays(cx)));
+    }
+
+    // When a display comes or goes, move the windows left off every display to
+    // one still connected: the timer, the presentation and the detached panels
+    fn check_displays(&mut self, cx: &mut Context<'_, Self>) {
+        let displays = placement::display_ids(cx);
+        if displays == self.displays {
+            return;
+        }
+        notifications::log_info(&format!("Displays changed, {} connected", displays.len()));
+        self.displays = displays;
+        // A window that's gone already needs nothing
+        let stranded = |window: AnyWindowHandle, cx: &mut App| {
+            window.update(cx, |_, window, cx| !placement::is_reachable(window.bounds(), cx)).unwrap_or(false)
+        };
+
+        if self.main_window.is_some_and(|window| stranded(window, cx)) {
+            self.reopen_window(cx);
+        }
+        if let Some(presentation) = self.presentation.take_if(|presentation| stranded((*presentation).into(), cx)) {
+            let _ = presentation.update(cx, |_presentation, window, _cx| window.remove_window());
+            self.open_presentation(cx);
+        }
+        let panels: Vec<ActivePanel> =
+            self.detached.iter().filter(|(_, window)| stranded((*window).into(), cx)).map(|(panel, _)| *panel).collect();
+        for panel in panels {
+            // Its saved place is off every display, so it opens centered
+            self.close_detached(panel, cx);
+            self.open_detached(panel, cx);
+        }
+    }
+
+    pub fn handle_toggle_settings(&mut self, cx: &mut Context<'_, Self>) {
+        self.notification_test = None;
+        self.import_status = None;
+        self.show_remote_qr = false;
+        self.toggle_panel(ActivePanel::Settings, cx);
+    }
+
+    pub fn handle_toggle_remote_qr(&mut self, cx: &mut Context<'_, Self>) {
+        self.show_remote_qr = !self.show_remote_qr;
+        cx.notify();
+    }
+
+    pub fn handle_cycle_ambient_sound(&mut self, cx: &mut Context<'_, Self>) {
+        self.config.ambient_sound = sound::cycle_sound(self.config.ambient_sound.as_deref());
+        self.sound.set_ambient_sound(self.config.ambient_sound.clone());
+        self.save_config();
+        cx.notify();
+    }
+
+    pub fn handle_cycle_theme(&mut self, cx: &mut Context<'_, Self>) {
+        self.config.theme = self.config.theme.next();
+        self.save_config();
+        self.redraw_for_time_of_day(cx);
+        cx.notify();
+    }
+
+    // The time-of-day theme changes with the clock rather than with events, so
+    // redraw once a minute while it's picked
+    // The presentation and detached panels aren't watched like the timer's
+    // window, so while one is open the displays are looked at now and then
+    fn poll_displays(&mut self, cx: &mut Context<'_, Self>) {
+        if self.polling_displays {
+            return;
+        }
+        self.polling_displays = true;
+        cx.spawn(async move |this, cx| loop {
+            cx.background_executor().timer(std::time::Duration::from_secs(10)).await;
+            let still_open = this.update(cx, |app, cx| {
+                app.polling_displays = app.presentation.is_some() || !app.detached.is_empty();
+                if app.polling_displays {
+                    app.check_displays(cx);
+                }
+                app.polling_displays
+            });
+            if !matches!(still_open, Ok(true)) {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    fn redraw_for_time_of_day(&mut self, cx: &mut Context<'_, Self>) {
+        if self.config.theme != ThemePreference::Time || self.redrawing_for_time_of_day {
+            return;
+        }
+        self.redrawing_for_time_of_day = true;
+        cx.spawn(async move |this, cx| loop {
+            cx.background_spawn(async {
+                std::thread::sleep(std::time::Duration::from_secs(60));
+            })
+            .await;
+            let still_picked = this.update(cx, |app, cx| {
+                app.redrawing_for_time_of_day = app.config.theme == ThemePreference::Time;
+                cx.notify();
+                app.redrawing_for_time_of_day
+            });
+            if !matches!(still_picked, Ok(true)) {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    pub fn handle_set_ambient_volume(&mut self, percent: u32, cx: &mut Context<'_, Self>) {
+        self.config.ambient_volume = percent.min(100);
+        self.sound.set_volume(self.config.ambient_volume);
+        self.save_config();
+        cx.notify();
+    }
+
+    // Keep choices made in the settings panel for the next launch
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            notifications::log_error(&format!("Failed to save config: {:#}", e));
+        }
+    }
+
+    // Preferences… in the app menu: only ever opens settings
+    pub fn handle_open_settings(&mut self, cx: &mut Context<'_, Self>) {
+        if !self.shows(ActivePanel::Settings) {
+            self.handle_toggle_settings(cx);
+        }
+    }
+
+    // The window has no room for an About box, so show it as a notice
+    pub fn handle_about(&mut self, cx: &mut Context<'_, Self>) {
+        self.notices.insert(0, format!("Pomodoro Timer {}", env!("CARGO_PKG_VERSION")));
+        cx.notify();
+    }
+
+    pub fn handle_toggle_queue(&mut self, cx: &mut Context<'_, Self>) {
+        self.queue_input.clear();
+        self.label_cursor = None;
+        self.toggle_panel(ActivePanel::Queue, cx);
+    }
+
+    pub fn handle_toggle_diagnostics(&mut self, cx: &mut Context<'_, Self>) {
+        self.toggle_panel(ActivePanel::Diagnostics, cx);
+    }
+
+    // The log lines, oldest first, for pasting into a bug report
+    pub fn handle_copy_diagnostics(&mut self, cx: &mut Context<'_, Self>) {
+        let lines = notifications::recent_log();
+        if !lines.is_empty() {
+            cx.write_to_clipboard(ClipboardItem::new_string(lines.join("\n")));
+        }
+    }
+
+    // Apply a change to the shared session state and publish it
+    fn update_session(&self, update: impl FnOnce(&mut SessionInfo)) {
+        let mut info = self.session_info.lock();
+        update(&mut info);
+        info.last_updated = Utc::now();
+        self.events.publish(SessionEvent::Changed, &info);
+    }
+
+    pub fn handle_add_to_queue(&mut self, _cx: &mut Context<'_, Self>) {
+        let label = self.config.clean_label(&self.queue_input);
+        if label.is_empty() {
+            return;
+        }
+        self.queue_input.clear();
+        self.label_cursor = None;
+        self.update_session(|info| info.enqueue_label(label));
+    }
+
+    pub fn handle_adjust_queue_entry(&mut self, index: usize, delta: i32, _cx: &mut Context<'_, Self>) {
+        self.update_session(|info| {
+            if let Some(entry) = info.label_queue.get_mut(index) {
+                entry.count = entry.count.saturating_add_signed(delta);
+                if entry.count == 0 {
+                    info.label_queue.remove(index);
+                }
+            }
+        });
+    }
+
+    pub fn handle_remove_queue_entry(&mut self, index: usize, _cx: &mut Context<'_, Self>) {
+        self.update_session(|info| {
+            if index < info.label_queue.len() {
+                info.label_queue.remove(index);
+            }
+        });
+    }
+
+    pub fn handle_toggle_privacy(&mut self, cx: &mut Context<'_, Self>) {
+        self.privacy_mode = !self.privacy_mode;
+        self.show_preset_menu = false;
+        notifications::set_private(self.privacy_mode);
+        notifications::log_info(if self.privacy_mode { "Privacy mode on" } else { "Privacy mode off" });
+        cx.notify();
+    }
+
+    pub fn handle_test_notification(&mut self, cx: &mut Context<'_, Self>) {
+        self.notification_test = Some(NotificationTestStatus::Sending);
+        cx.notify();
+
+        let config = self.config.clone();
+        cx.spawn(async move |this, cx| {
+            // Showing a notification can block on the notification server
+            let result = cx.background_spawn(async move {
+                notifications::send_test_notification(&config)
+            }).await;
+
+            let _ = this.update(cx, |app, cx| {
+                app.notification_test = Some(match result {
+                    Ok(()) => NotificationTestStatus::Sent,
+                    Err(e) => NotificationTestStatus::Failed(e),
+                });
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    pub fn handle_import_csv(&mut self, cx: &mut Context<'_, Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import".into()),
+        });
+        let session_info = self.session_info.clone();
+        let events = self.events.clone();
+        let stats = self.stats.clone();
+
+        cx.spawn(async move |this, cx| {
+            let path = match paths.await {
+                Ok(Ok(Some(paths))) => paths.into_iter().next(),
+                Ok(Err(e)) => {
+                    notifications::log_error(&format!("Failed to open file picker: {}", e));
+                    None
+                }
+                _ => None,
+            };
+            let Some(path) = path else {
+                return;
+            };
+
+            let parsed = cx.background_spawn(async move { import::read_csv(&path) }).await;
+
+            // Merge into the live state so the autosave doesn't overwrite the import
+            let status = match parsed {
+                Ok((records, skipped)) => {
+                    let mut info = session_info.lock();
+                    let summary = import::merge(&mut info, records, skipped);
+                    stats.add_imported(&summary.added);
+                    events.publish(SessionEvent::Changed, &info);
+                    summary.describe()
+                }
+                Err(e) => format!("Import failed: {:#}", e),
+            };
+            notifications::log_info(&status);
+
+            let _ = this.update(cx, |app, cx| {
+                app.import_status = Some(status);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    pub fn handle_confirm_history_prune(&mut self, cx: &mut Context<'_, Self>) {
+        let Some(days) = self.history_prune_prompt.take() else {
+            return;
+        };
+        let mut pruned = 0;
+        self.update_session(|info| {
+            info.history_max_age_confirmed = Some(days);
+            info.retention.max_age_days = Some(days);
+            pruned = info.prune_history(Utc::now());
+        });
+        notifications::log_info(&format!("Dropped {} session(s) older than {} days from History", pruned, days));
+        cx.notify();
+    }
+
+    // Asked again at the next launch
+    pub fn handle_keep_history(&mut self, cx: &mut Context<'_, Self>) {
+        if self.history_prune_prompt.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    pub fn handle_send_state(&mut self, cx: &mut Context<'_, Self>) {
+        if let Some(callback) = self.state_callback.take() {
+            cx.open_url(&callback);
+            cx.notify();
+        }
+    }
+
+    pub fn handle_withhold_state(&mut self, cx: &mut Context<'_, Self>) {
+        if self.state_callback.take().is_some() {
+            notifications::log_info("Not sending the timer state to the x-success link");
+            cx.notify();
+        }
+    }
+
+    // Make the short break a long one, as suggested after a long focus run
+    pub fn handle_take_long_break(&mut self, cx: &mut Context<'_, Self>) {
+        if let Some(event) = self.timer.take_long_break() {
+            self.events.publish(event, &self.session_info.lock());
+        }
+        cx.notify();
+    }
+
+    // Keep this break short; the suggestion comes back at the next one
+    pub fn handle_decline_long_break(&mut self, cx: &mut Context<'_, Self>) {
+        self.long_break_declined = Some(self.session_info.lock().current_id.clone());
+        cx.notify();
+    }
+
+    pub fn handle_internal_interruption(&mut self, cx: &mut Context<'_, Self>) {
+        self.log_interruption(Interruption::Internal, cx);
+    }
+
+    pub fn handle_external_interruption(&mut self, cx: &mut Context<'_, Self>) {
+        self.log_interruption(Interruption::External, cx);
+    }
+
+    // Void the strict-mode pomodoro the interruption broke
+    fn log_interruption(&mut self, interruption: Interruption, cx: &mut Context<'_, Self>) {
+        self.interrupting = None;
+        if let Some(event) = self.timer.interrupt(interruption) {
+            notifications::log_info(&format!("Pomodoro voided by an {} interruption", interruption.name()));
+            self.events.publish(event, &self.session_info.lock());
+        }
+        cx.notify();
+    }
+
+    // The pomodoro goes on, as if pause hadn't been pressed
+    pub fn handle_keep_going(&mut self, cx: &mut Context<'_, Self>) {
+        self.interrupting = None;
+        cx.notify();
+    }
+
+    // Bring back the session the crash interrupted. One that was running is
+    // paused, with the usual question about the time since.
+    pub fn handle_restore_recovery(&mut self, cx: &mut Context<'_, Self>) {
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        let config = Self::with_template(&self.config, recovery.session.template.as_deref());
+        self.timer.set_config(config.clone());
+        self.sound.set_ambient_sound(config.ambient_sound.clone());
+        let mut info = self.session_info.lock();
+        recovery.restore_into(&mut info);
+        self.stale_session = info.stale_secs(Utc::now());
+        if let Some(paused) = self.stale_session.and_then(|_| info.current_state.pause()) {
+            info.current_state = paused;
+        }
+        notifications::log_info("Restored the session interrupted by the crash");
+        self.events.publish(SessionEvent::Changed, &info);
+        drop(info);
+        cx.notify();
+    }
+
+    pub fn handle_dismiss_recovery(&mut self, cx: &mut Context<'_, Self>) {
+        if self.recovery.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    // Carry on from where the session was left
+    pub fn handle_resume_stale(&mut self, cx: &mut Context<'_, Self>) {
+        if self.stale_session.take().is_some() && self.session_info.lock().current_state.resume().is_some() {
+            self.handle_toggle(cx);
+        }
+    }
+
+    // Count the time the app was closed, as if the session had run all along
+    pub fn handle_fast_forward_stale(&mut self, cx: &mut Context<'_, Self>) {
+        let Some(secs) = self.stale_session.take() else {
+            return;
+        };
+        let happened = self.timer.fast_forward(secs);
+        let info = self.session_info.lock();
+        for event in happened {
+            self.events.publish(event, &info);
+        }
+        drop(info);
+        cx.notify();
+    }
+
+    pub fn handle_discard_stale(&mut self, cx: &mut Context<'_, Self>) {
+        if self.stale_session.take().is_some() {
+            self.handle_stop(cx);
+        }
+    }
+
+    pub fn handle_dismiss_notice(&mut self, cx: &mut Context<'_, Self>) {
+        if !self.notices.is_empty() {
+            self.notices.remove(0);
+            cx.notify();
+        }
+    }
+
+    pub fn handle_new_timer(&mut self, cx: &mut Context<'_, Self>) {
+        // Open the dialog with a fresh focus session preselected
+        self.new_timer = NewTimerDraft {
+            label: String::new(),
+            minutes: self.session_config().work_duration,
+            is_focus: true,
+            category: None,
+        };
+        self.is_editing_label = false;
+        self.label_cursor = None;
+        self.active_panel = ActivePanel::NewTimer;
+        self.show_preset_menu = false;
+        cx.notify();
+    }
+
+    pub fn handle_cancel_new_timer(&mut self, cx: &mut Context<'_, Self>) {
+        self.active_panel = ActivePanel::Timer;
+        cx.notify();
+    }
+
+    pub fn handle_set_new_timer_kind(&mut self, is_focus: bool, cx: &mut Context<'_, Self>) {
+        if self.new_timer.is_focus != is_focus {
+            self.new_timer.is_focus = is_focus;
+            let config = self.session_config();
+            self.new_timer.minutes = if is_focus { config.work_duration } else { config.short_break_duration };
+            cx.notify();
+        }
+    }
+
+    pub fn handle_set_new_timer_category(&mut self, category: Option<String>, cx: &mut Context<'_, Self>) {
+        self.new_timer.category = category;
+        cx.notify();
+    }
+
+    pub fn handle_adjust_new_timer_minutes(&mut self, delta: i32, cx: &mut Context<'_, Self>) {
+        // Focus sessions move in 5-minute steps, breaks minute by minute
+        let step = if self.new_timer.is_focus { 5 } else { 1 };
+        let minutes = self.new_timer.minutes as i32 + delta * step;
+        self.new_timer.minutes = minutes.clamp(1, MAX_DURATION_MINUTES as i32) as u32;
+        cx.notify();
+    }
+
+    pub fn handle_start_new_timer(&mut self, cx: &mut Context<'_, Self>) {
+        let mut draft = self.new_timer.clone();
+        draft.label = self.config.clean_label(&draft.label);
+        // Strict mode has only the one length
+        let focus_secs = if self.config.strict_mode { strict::CLASSIC_WORK_SECS } else { draft.minutes * 60 };
+        let session_info = self.session_info.clone();
+        let timer = self.timer.clone();
+        let events = self.events.clone();
+        self.active_panel = ActivePanel::Timer;
+
+        cx.spawn(async move |_this, _cx| {
+            use uuid::Uuid;
+
+            // Create the new timer with a new ID and the chosen duration
+            {
+                let mut info = session_info.lock();
+                timer.abandon(&mut info);
+                info.current_id = Uuid::new_v4().to_string();  // Generate new UUID
+                info.continued_task = None;
+                info.set_label(draft.label);
+                info.current_category = draft.category.filter(|_| draft.is_focus);
+                info.elapsed_secs = 0;
+                info.exit_history();
+                info.show_celebration = false;
+                if draft.is_focus {
+                    info.focus = ActiveTimer::new(focus_secs);
+                } else {
+                    info.rest = ActiveTimer::new(draft.minutes * 60);
+                }
+            }
+
+            // Start immediately
+            if draft.is_focus {
+                timer.start_work().await;
+            } else {
+                timer.start_short_break().await;
+            }
+
+            let info = session_info.lock();
+            events.publish(SessionEvent::Started(info.current_state.clone()), &info);
+        })
+        .detach();
+    }
+
+    pub fn handle_toggle(&mut self, cx: &mut Context<'_, Self>) {
+        self.handle_dismiss_alarm(cx);
+        // A strict-mode pomodoro isn't paused; the interruption is logged and voids it
+        let pomodoro = {
+            let info = self.session_info.lock();
+            self.timer.strict_work(&info).then(|| info.current_id.clone())
+        };
+        if pomodoro.is_some() {
+            self.interrupting = pomodoro;
+            cx.notify();
+            return;
+        }
+        let timer = self.timer.clone();
+        let session_info = self.session_info.clone();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            let (current_state, is_focus_mode) = {
+                let info = session_info.lock();
+                (info.current_state.clone(), info.is_focus_mode)
+            };
+
+            let event = match current_state {
+                TimerState::Idle => {
+                    // Start based on current mode (focus or rest)
+                    if is_focus_mode {
+                        timer.start_work().await;
+                    } else {
+                        timer.start_break().await;
+                    }
+                    SessionEvent::Started(session_info.lock().current_state.clone())
+                }
+                TimerState::Working | TimerState::ShortBreak | TimerState::LongBreak => {
+                    timer.pause().await;
+                    SessionEvent::Paused
+                }
+                TimerState::WorkPaused | TimerState::BreakPaused | TimerState::LongBreakPaused => {
+                    timer.resume().await;
+                    SessionEvent::Resumed
+                }
+            };
+
+            events.publish(event, &session_info.lock());
+        })
+        .detach();
+    }
+
+    // Turn the session into a break, or a break into work, without waiting for
+    // it to end; the time already spent is kept in history
+    pub fn handle_switch_session(&mut self, cx: &mut Context<'_, Self>) {
+        self.show_preset_menu = false;
+        if let Some(event) = self.timer.switch_session() {
+            self.events.publish(event, &self.session_info.lock());
+        }
+        cx.notify();
+    }
+
+    // End the current session early and move on to the next one: work → break → work
+    pub fn handle_skip(&mut self, cx: &mut Context<'_, Self>) {
+        self.handle_dismiss_alarm(cx);
+        let timer = self.timer.clone();
+        let session_info = self.session_info.clone();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            let started = timer.skip_to_next().await;
+            let info = session_info.lock();
+            events.publish(SessionEvent::Skipped, &info);
+            if let Some(started) = started {
+                events.publish(started, &info);
+            }
+        })
+        .detach();
+    }
+
+    // Step through past sessions on the timer view: older with a negative delta, newer otherwise
+    pub fn handle_browse_history(&mut self, delta: i32, _cx: &mut Context<'_, Self>) {
+        self.update_session(|info| {
+            if delta < 0 {
+                info.navigate_history_prev();
+            } else {
+                info.navigate_history_next();
+            }
+        });
+    }
+
+    pub fn handle_toggle_history(&mut self, cx: &mut Context<'_, Self>) {
+        self.history_filter.query.clear();
+        self.history_selected = 0;
+        self.toggle_panel(ActivePanel::History, cx);
+    }
+
+    pub fn handle_toggle_stats(&mut self, cx: &mut Context<'_, Self>) {
+        self.stats_tab = StatsTab::Today;
+        self.toggle_panel(ActivePanel::Stats, cx);
+    }
+
+    pub fn handle_next_stats_tab(&mut self, cx: &mut Context<'_, Self>) {
+        self.stats_tab = self.stats_tab.next();
+        self.stats_query.clear();
+        self.is_editing_note = false;
+        cx.notify();
+    }
+
+    // Write or change today's note, under today's stats
+    pub fn handle_edit_note(&mut self, cx: &mut Context<'_, Self>) {
+        self.note_input = self.journal.note(Local::now().date_naive()).unwrap_or_default().to_string();
+        self.is_editing_note = true;
+        self.label_marked = None;
+        self.label_cursor = None;
+        cx.notify();
+    }
+
+    pub fn handle_done_note(&mut self, cx: &mut Context<'_, Self>) {
+        self.journal.set(Local::now().date_naive(), &self.note_input);
+        if let Err(e) = self.journal.save() {
+            notifications::log_error(&format!("{:#}", e));
+        }
+        self.handle_cancel_note(cx);
+    }
+
+    pub fn handle_cancel_note(&mut self, cx: &mut Context<'_, Self>) {
+        self.is_editing_note = false;
+        self.note_input.clear();
+        self.label_marked = None;
+        cx.notify();
+    }
+
+    // Save this week's report, with the notes, as Markdown in the data directory
+    pub fn handle_export_week(&mut self, cx: &mut Context<'_, Self>) {
+        let monday = stats::report::week_start(Local::now().date_naive());
+        let report = self.stats.read(|summary| stats::report::weekly(summary, &self.journal, monday));
+        let notice = match stats::report::save(&format!("week-{}", monday.format("%Y-%m-%d")), &report) {
+            Ok(path) => format!("Saved the weekly report to {}", path.display()),
+            Err(e) => format!("{:#}", e),
+        };
+        self.notices.insert(0, notice);
+        self.active_panel = ActivePanel::Timer;
+        cx.notify();
+    }
+
+    // Save today's queued plan against the sessions done, as Markdown in the data directory
+    pub fn handle_export_plan(&mut self, cx: &mut Context<'_, Self>) {
+        let today = Local::now().date_naive();
+        let report = {
+            let info = self.session_info.lock();
+            let lines = stats::plan::compare(&info.plan_for(today), &info.history, today, &Local);
+            stats::plan::markdown(&lines, today)
+        };
+        let notice = match stats::report::save(&format!("plan-{}", today.format("%Y-%m-%d")), &report) {
+            Ok(path) => format!("Saved planned vs actual to {}", path.display()),
+            Err(e) => format!("{:#}", e),
+        };
+        self.notices.insert(0, notice);
+        self.active_panel = ActivePanel::Timer;
+        cx.notify();
+    }
+
+    pub fn handle_cycle_stats_range(&mut self, cx: &mut Context<'_, Self>) {
+        self.stats_range = self.stats_range.next();
+        cx.notify();
+    }
+
+    pub fn handle_cycle_history_type(&mut self, cx: &mut Context<'_, Self>) {
+        self.history_filter.next_session_type();
+        self.history_selected = 0;
+        cx.notify();
+    }
+
+    pub fn handle_cycle_history_range(&mut self, cx: &mut Context<'_, Self>) {
+        self.history_filter.range = self.history_filter.range.next();
+        self.history_selected = 0;
+        cx.notify();
+    }
+
+    fn handle_move_history_selection(&mut self, delta: i32, cx: &mut Context<'_, Self>) {
+        let count = self.session_info.lock().search_history(&self.history_filter).len();
+        if count > 0 {
+            self.history_selected = (self.history_selected as i32 + delta).clamp(0, count as i32 - 1) as usize;
+            cx.notify();
+        }
+    }
+
+    fn handle_open_selected_history(&mut self, cx: &mut Context<'_, Self>) {
+        let results = self.session_info.lock().search_history(&self.history_filter);
+        if let Some(&index) = results.get(self.history_selected) {
+            self.handle_open_history_entry(index, cx);
+        }
+    }
+
+    pub fn handle_open_history_entry(&mut self, index: usize, cx: &mut Context<'_, Self>) {
+        // Show the entry in the timer view, where it can be continued
+        self.active_panel = ActivePanel::Timer;
+        cx.notify();
+        self.update_session(|info| {
+            if index < info.history.len() {
+                info.history_index = Some(index);
+            }
+        });
+    }
+
+    pub fn handle_exit_history(&mut self, _cx: &mut Context<'_, Self>) {
+        self.update_session(|info| info.exit_history());
+    }
+
+    pub fn handle_continue_from_history(&mut self, cx: &mut Context<'_, Self>) {
+        let session_info = self.session_info.clone();
+        let timer = self.timer.clone();
+        let work_secs = self.session_config().work_duration_secs();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            {
+                let mut info = session_info.lock();
+                let Some(entry) = info.history_index.and_then(|index| info.history.get(index)).cloned() else {
+                    return;
+                };
+
+                // The session joins the entry's task
+                timer.abandon(&mut info);
+                info.continued_task = Some(entry.task_id().to_string());
+                info.set_label(entry.label);
+                info.current_icon = entry.icon;
+                info.elapsed_secs = 0;
+                info.exit_history();
+                info.show_celebration = false;
+                info.focus = ActiveTimer::new(work_secs);
+            }
+
+            timer.start_work().await;
+            notifications::log_info("Continued task from history");
+
             let info = session_info.lock();
-            if let Err(e) = Persistence::save(&info) {
-                notifications::log_error(&format!("Failed to save state: {}", e));
+            events.publish(SessionEvent::Started(info.current_state.clone()), &info);
+        })
+        .detach();
+    }
+
+    pub fn handle_stop(&mut self, cx: &mut Context<'_, Self>) {
+        let timer = self.timer.clone();
+        let session_info = self.session_info.clone();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            timer.stop().await;
+            events.publish(SessionEvent::Stopped, &session_info.lock());
+        })
+        .detach();
+    }
+
+    pub fn handle_reset(&mut self, cx: &mut Context<'_, Self>) {
+        let timer = self.timer.clone();
+        let session_info = self.session_info.clone();
+
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            timer.reset().await;
+            events.publish(SessionEvent::Reset, &session_info.lock());
+        })
+        .detach();
+    }
+
+    pub fn handle_switch_to_focus(&mut self, cx: &mut Context<'_, Self>) {
+        let session_info = self.session_info.clone();
+        let timer = self.timer.clone();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            {
+                let mut info = session_info.lock();
+
+                // Only proceed if we're NOT already in focus mode
+                if info.is_focus_mode {
+                    return;
+                }
+
+                // Switch to focus mode and idle state, preserving the timer value
+                info.is_focus_mode = true;
+                info.current_state = TimerState::Idle;
+                info.last_updated = chrono::Utc::now();
+            }
+            timer.preload();
+
+            notifications::log_info("Switched to focus mode");
+            events.publish(SessionEvent::Changed, &session_info.lock());
+        })
+        .detach();
+    }
+
+    pub fn handle_switch_to_rest(&mut self, cx: &mut Context<'_, Self>) {
+        let session_info = self.session_info.clone();
+        let timer = self.timer.clone();
+        let events = self.events.clone();
+
+        cx.spawn(async move |_this, _cx| {
+            {
+                let mut info = session_info.lock();
+
+                // Only proceed if we're NOT already in rest mode
+                if !info.is_focus_mode {
+                    return;
+                }
+
+                // Switch to rest mode and idle state, preserving the timer value
+                info.is_focus_mode = false;
+                info.current_state = TimerState::Idle;
+                info.last_updated = chrono::Utc::now();
+            }
+            timer.preload();
+
+            notifications::log_info("Switched to rest mode");
+            events.publish(SessionEvent::Changed, &session_info.lock());
+        })
+        .detach();
+    }
+
+    // Paste into the label being typed: in the label editor, the new-timer
+    // dialog, or the queue. Lines are joined with spaces.
+    pub fn handle_paste(&mut self, cx: &mut Context<'_, Self>) {
+        let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) else {
+            return;
+        };
+        let text = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ");
+        let config = self.config.clone();
+        let is_note = self.is_editing_note;
+        let Some(input) = self.typed_label() else {
+            return;
+        };
+        if is_note {
+            let end = input.len();
+            label_input::replace((MAX_NOTE_CHARS, ""), input, end..end, &text);
+        } else {
+            *input = config.clean_label(&format!("{}{}", input, text));
+        }
+        self.label_cursor = None;
+        cx.notify();
+    }
+
+    // Caret movement and deletion in the label being typed; false for other keys
+    fn edit_typed_label(&mut self, key: &str, cx: &mut Context<'_, Self>) -> bool {
+        let cursor = self.label_cursor;
+        let Some(text) = self.typed_label() else {
+            return false;
+        };
+        let Some(caret) = label_input::edit(text, label_input::caret(text, cursor), key) else {
+            return false;
+        };
+        self.label_cursor = Some(caret);
+        self.label_marked = None;
+        cx.notify();
+        true
+    }
+
+    // Characters before the caret in the label being typed, for drawing it
+    fn label_caret(&mut self) -> usize {
+        let cursor = self.label_cursor;
+        self.typed_label().map_or(0, |text| text[..label_input::caret(text, cursor)].chars().count())
+    }
+
+    // The label being typed, if any: in the label editor, the new-timer dialog, or
+    // the queue. Or the day's note in the stats panel, which may be in its own window.
+    fn typed_label(&mut self) -> Option<&mut String> {
+        if self.is_editing_note {
+            return Some(&mut self.note_input);
+        }
+        match self.active_panel {
+            ActivePanel::NewTimer => Some(&mut self.new_timer.label),
+            ActivePanel::Queue => Some(&mut self.queue_input),
+            ActivePanel::Timer if self.is_editing_label => Some(&mut self.label_input),
+            _ => None,
+        }
+    }
+
+    // How long the text being typed may get and what's left out of it
+    fn typing_rules(&self) -> (usize, &str) {
+        if self.is_editing_note {
+            (MAX_NOTE_CHARS, "")
+        } else {
+            label_input::label_rules(&self.config)
+        }
+    }
+
+    // Copy the label being typed, or else the current one
+    pub fn handle_copy(&mut self, cx: &mut Context<'_, Self>) {
+        let label = if self.is_editing_label {
+            self.label_input.clone()
+        } else {
+            self.session_info.lock().current_label.clone()
+        };
+        if !label.is_empty() {
+            cx.write_to_clipboard(ClipboardItem::new_string(label));
+        }
+    }
+
+    pub fn handle_edit_label(&mut self, cx: &mut Context<'_, Self>) {
+        // Load current label, or the suggested one, into edit buffer and enter edit mode
+        let current_label = {
+            let info = self.session_info.lock();
+            if info.current_label.is_empty() { info.suggested_label.clone() } else { info.current_label.clone() }
+        };
+        self.label_input = current_label;
+        self.label_marked = None;
+        self.label_cursor = None;
+        self.is_editing_label = true;
+        cx.notify();
+    }
+
+    pub fn handle_done_label(&mut self, cx: &mut Context<'_, Self>) {
+        // Save label and exit edit mode
+        let label = self.config.clean_label(&self.label_input);
+        self.update_session(|info| info.set_label(label));
+
+        self.label_input.clear();
+        self.is_editing_label = false;
+        cx.notify();
+    }
+
+    // Leave the label as it was before editing
+    pub fn handle_cancel_label(&mut self, cx: &mut Context<'_, Self>) {
+        self.label_input.clear();
+        self.label_marked = None;
+        self.is_editing_label = false;
+        cx.notify();
+    }
+
+    // Picking the emoji already shown takes it off
+    pub fn handle_pick_icon(&mut self, icon: &'static str, cx: &mut Context<'_, Self>) {
+        self.update_session(|info| {
+            info.current_icon = if info.current_icon == icon { String::new() } else { icon.to_string() };
+        });
+        cx.notify();
+    }
+
+    pub fn handle_mouse_over(&mut self, _cx: &mut Context<'_, Self>) {
+        // Disable celebration breathing effect on mouse over
+        if self.session_info.lock().show_celebration {
+            self.update_session(|info| info.show_celebration = false);
+        }
+    }
+
+    pub fn handle_toggle_preset_menu(&mut self, cx: &mut Context<'_, Self>) {
+        self.show_preset_menu = !self.show_preset_menu;
+        cx.notify();
+    }
+
+    pub fn handle_close_preset_menu(&mut self, cx: &mut Context<'_, Self>) {
+        if self.show_preset_menu {
+            self.show_preset_menu = false;
+            cx.notify();
+        }
+    }
+
+    fn handle_mqtt_command(&mut self, command: MqttCommand, cx: &mut Context<'_, Self>) {
+        let MqttCommand::SetRunning(running) = command;
+        if self.session_info.lock().current_state.is_running() != running {
+            self.handle_toggle(cx);
+        }
+    }
+
+    // Ring until dismissed, for people who miss a single notification
+    fn handle_alarm(&mut self, finished: TimerState, cx: &mut Context<'_, Self>) {
+        if self.config.alarm_mode != AlarmMode::UntilDismissed || notifications::is_quiet() {
+            return;
+        }
+        self.alarm = Some(finished);
+        self.sound.set_alarm(true);
+        self.active_panel = ActivePanel::Timer;
+        cx.notify();
+    }
+
+    // With auto_hide, fade the window a while into a running work session and
+    // bring it back for anything else
+    fn auto_hide(&mut self, cx: &mut Context<'_, Self>) {
+        if !self.config.auto_hide {
+            return;
+        }
+        if self.session_info.lock().current_state != TimerState::Working {
+            self.auto_hidden = false;
+            self._auto_hide_timer = None;
+            return;
+        }
+        if self.auto_hidden || self._auto_hide_timer.is_some() {
+            return;
+        }
+        let delay = std::time::Duration::from_secs(self.config.auto_hide_after as u64);
+        self._auto_hide_timer = Some(cx.spawn(async move |this, cx| {
+            cx.background_spawn(async move {
+                std::thread::sleep(delay);
+            })
+            .await;
+            let _ = this.update(cx, |app, cx| {
+                app._auto_hide_timer = None;
+                app.auto_hidden = app.session_info.lock().current_state == TimerState::Working;
+                cx.notify();
+            });
+        }));
+    }
+
+    pub fn handle_peek(&mut self, hovered: bool, cx: &mut Context<'_, Self>) {
+        self.peeking = hovered;
+        if self.auto_hidden {
+            cx.notify();
+        }
+    }
+
+    pub fn handle_dismiss_alarm(&mut self, cx: &mut Context<'_, Self>) {
+        if self.alarm.take().is_some() {
+            self.sound.set_alarm(false);
+            cx.notify();
+        }
+    }
+
+    fn handle_meeting(&mut self, meeting: Option<Meeting>, cx: &mut Context<'_, Self>) {
+        notifications::set_quiet(meeting.is_some());
+        let (state, label) = {
+            let info = self.session_info.lock();
+            (info.current_state.clone(), info.current_label.clone())
+        };
+        match &meeting {
+            Some(meeting) => {
+                notifications::log_info(&format!("In a meeting until {}", meeting.end.format("%H:%M")));
+                if state.is_running() {
+                    self.paused_for_meeting = true;
+                    self.handle_toggle(cx);
+                }
+            }
+            None => {
+                notifications::log_info("Meeting over");
+                // Nudge back to the session the meeting interrupted, unless it was picked up already
+                if std::mem::take(&mut self.paused_for_meeting) && state.resume().is_some() {
+                    let config = self.config.clone();
+                    std::thread::spawn(move || {
+                        let _ = notifications::notify_meeting_over(&config, &label);
+                    });
+                }
+            }
+        }
+        self.meeting = meeting;
+        cx.notify();
+    }
+
+    // Once only a work session's length is left before the hard stop, say so, once a day
+    fn handle_workday_minute(&mut self, cx: &mut Context<'_, Self>) {
+        let now = Local::now();
+        // A day off: nothing to count down, though the text under the timer may have to go
+        let Some(left) = self.schedule.workday_left(now) else {
+            cx.notify();
+            return;
+        };
+        let last_session = Duration::seconds(self.session_config().work_duration_secs() as i64);
+        if left > Duration::zero() && left <= last_session && self.wound_down_on != Some(now.date_naive()) {
+            self.wound_down_on = Some(now.date_naive());
+            if !notifications::is_quiet() {
+                let config = self.config.clone();
+                let hard_stop = (now + left).format("%H:%M").to_string();
+                std::thread::spawn(move || {
+                    let _ = notifications::notify_wind_down(&config, &hard_stop);
+                });
+            }
+        }
+        cx.notify();
+    }
+
+    fn handle_remote_command(&mut self, command: RemoteCommand, cx: &mut Context<'_, Self>) {
+        match command {
+            RemoteCommand::Toggle => self.handle_toggle(cx),
+            RemoteCommand::Skip => self.handle_skip(cx),
+        }
+    }
+
+    // Keep in step with the LAN group's leader
+    fn handle_group_state(&mut self, leader: GroupState, cx: &mut Context<'_, Self>) {
+        let timer = leader.timer();
+        let Some(event) = self.timer.follow(leader.state, leader.focus_mode, timer) else {
+            return;
+        };
+        if matches!(event, SessionEvent::Started(_)) {
+            self.handle_dismiss_alarm(cx);
+        }
+        self.events.publish(event, &self.session_info.lock());
+    }
+
+    fn handle_plugin_command(&mut self, command: PluginCommand, cx: &mut Context<'_, Self>) {
+        match command {
+            PluginCommand::Notify(message) => {
+                let config = self.config.clone();
+                std::thread::spawn(move || {
+                    let _ = notifications::notify_script(&config, &message);
+                });
+            }
+            PluginCommand::SetBadge { plugin, text } => {
+                let position = self.plugin_badges.binary_search_by(|(name, _)| name.cmp(&plugin));
+                match (position, text.is_empty()) {
+                    (Ok(index), true) => {
+                        self.plugin_badges.remove(index);
+                    }
+                    (Ok(index), false) => self.plugin_badges[index].1 = text,
+                    (Err(_), true) => {}
+                    (Err(index), false) => self.plugin_badges.insert(index, (plugin, text)),
+                }
+                cx.notify();
+            }
+        }
+    }
+
+    fn handle_script_command(&mut self, command: ScriptCommand, _cx: &mut Context<'_, Self>) {
+        match command {
+            ScriptCommand::Notify(message) => {
+                let config = self.config.clone();
+                std::thread::spawn(move || {
+                    let _ = notifications::notify_script(&config, &message);
+                });
+            }
+            ScriptCommand::SetLabel(label) => {
+                let label = self.config.clean_label(&label);
+                self.update_session(|info| info.set_label(label));
+            }
+            ScriptCommand::SetDuration(minutes) => {
+                let total_secs = minutes.min(MAX_DURATION_MINUTES) * 60;
+                self.update_session(|info| info.active_timer_mut().set_total(total_secs));
+            }
+        }
+    }
+
+    // A pomodoro:// link from Shortcuts, Raycast or another app, see url_scheme.rs.
+    // Returns the status or state JSON for those links and "ok" for the others.
+    pub fn handle_url(&mut self, url: &str, cx: &mut Context<'_, Self>) -> Result<String, String> {
+        let request = match UrlRequest::parse(url) {
+            Ok(request) => request,
+            Err(e) => {
+                let message = format!("{:#}", e);
+                notifications::log_error(&format!("Ignoring {}: {}", url, message));
+                if let Some(error) = url_scheme::error_callback(url) {
+                    cx.open_url(&url_scheme::callback_url(&error, &[("errorMessage".to_string(), message.clone())]));
+                }
+                return Err(message);
+            }
+        };
+
+        let is_running = self.session_info.lock().current_state.is_running();
+        let sends_state = request.action == UrlAction::State;
+        let status = match request.action {
+            UrlAction::Start { label, minutes } => {
+                if let Some(label) = label {
+                    let label = self.config.clean_label(&label);
+                    self.update_session(|info| info.set_label(label));
+                }
+                if let Some(minutes) = minutes.map(|minutes| minutes.min(MAX_DURATION_MINUTES)) {
+                    if self.session_info.lock().current_state == TimerState::Idle {
+                        self.handle_set_preset_timer(minutes, cx);
+                    } else {
+                        self.update_session(|info| info.active_timer_mut().set_total(minutes * 60));
+                    }
+                }
+                if !is_running {
+                    self.handle_toggle(cx);
+                }
+                None
+            }
+            UrlAction::Pause => {
+                if is_running {
+                    self.handle_toggle(cx);
+                }
+                None
+            }
+            UrlAction::Status => {
+                let sessions_until_long_break = self.session_config().cycle_length();
+                Some(self.session_info.lock().status_json(self.privacy_mode, sessions_until_long_break))
+            }
+            UrlAction::State => {
+                let config = self.session_config();
+                Some(status::state_dump(&self.session_info.lock(), &config, self.privacy_mode, Local::now()))
+            }
+        };
+
+        if let Some(success) = &request.success {
+            // Status fields become callback parameters, e.g. ?state=Ready&time_text=25:00
+            let params: Vec<(String, String)> = status
+                .as_ref()
+                .and_then(|status| status.as_object())
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let callback = url_scheme::callback_url(success, &params);
+            // Any link can name any address, so the state only goes out once the user agrees
+            if sends_state {
+                self.state_callback = Some(callback);
+                cx.notify();
+            } else {
+                cx.open_url(&callback);
+            }
+        }
+        Ok(status.map(|status| status.to_string()).unwrap_or_else(|| "ok".to_string()))
+    }
+
+    // The config with a template's choices applied; the config itself when the
+    // template is None or no longer exists
+    fn with_template(config: &Config, template: Option<&str>) -> Config {
+        match template.and_then(|name| config.template(name)) {
+            Some(template) => template.apply(config),
+            None => config.clone(),
+        }
+    }
+
+    // The config sessions currently run with
+    fn session_config(&self) -> Config {
+        Self::with_template(&self.config, self.session_info.lock().template.as_deref())
+    }
+
+    // Switch to a template's plan, or back to the config's own with None. The
+    // cycle starts over and the template's labels replace the label queue.
+    pub fn handle_pick_template(&mut self, name: Option<String>, cx: &mut Context<'_, Self>) {
+        let config = Self::with_template(&self.config, name.as_deref());
+        let labels = name
+            .as_deref()
+            .and_then(|name| self.config.template(name))
+            .map(|template| template.labels.iter().map(|label| self.config.clean_label(label)).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        self.timer.set_config(config.clone());
+        self.timer.restart_cycle();
+        self.sound.set_ambient_sound(config.ambient_sound.clone());
+        self.update_session(|info| {
+            info.template = name;
+            if !labels.is_empty() {
+                info.label_queue.clear();
+                info.set_label(String::new());
+                for label in labels {
+                    info.enqueue_label(label);
+                }
+            }
+        });
+
+        self.show_preset_menu = false;
+        cx.notify();
+    }
+
+    // Pick the plan of the project the app was opened for, unless it's already
+    // the one running, so a relaunch doesn't start the cycle over
+    pub fn handle_open_project(&mut self, name: String, cx: &mut Context<'_, Self>) {
+        if self.session_info.lock().template.as_deref() != Some(name.as_str()) {
+            self.handle_pick_template(Some(name), cx);
+        }
+    }
+
+    // What the timer shows right now, for other windows
+    pub fn timer_view(&self) -> TimerView {
+        self.session_info.lock().timer_view(self.privacy_mode)
+    }
+
+    // Open the presentation window, or close it when it's already open
+    pub fn handle_present(&mut self, cx: &mut Context<'_, Self>) {
+        self.show_preset_menu = false;
+        if let Some(presentation) = self.presentation.take() {
+            // Err when it was closed from outside already
+            if presentation.update(cx, |_presentation, window, _cx| window.remove_window()).is_ok() {
+                return;
+            }
+        }
+        self.open_presentation(cx);
+    }
+
+    fn open_presentation(&mut self, cx: &mut Context<'_, Self>) {
+        let wanted = placement::presentation_display(&self.config).map(str::to_string);
+        self.with_spot(wanted.as_deref(), cx, |app, spot, cx| app.open_presentation_at(spot, cx));
+    }
+
+    fn open_presentation_at(&mut self, spot: Option<Spot>, cx: &mut Context<'_, Self>) {
+        // It may have been opened while the display was looked up
+        if self.presentation.is_some() {
+            return;
+        }
+        let app = cx.entity();
+        let options = placement::presentation_window_options(&self.config, spot, cx);
+        match cx.open_window(options, |_window, cx| cx.new(|cx| Presentation::new(app, cx))) {
+            Ok(presentation) => {
+                self.presentation = Some(presentation);
+                self.poll_displays(cx);
             }
+            Err(e) => notifications::log_error(&format!("Failed to open the presentation: {}", e)),
+        }
+    }
 
-            // Trigger UI update and close menu
-            let _ = cx.update(|_cx| {});
-        }).detach();
+    pub fn handle_set_preset_timer(&mut self, minutes: u32, cx: &mut Context<'_, Self>) {
+        self.update_session(|info| {
+            // Set timer to preset duration (in seconds)
+            info.focus = ActiveTimer::new(minutes * 60);
+            info.current_state = TimerState::Idle;
+            info.is_focus_mode = true;
+        });
 
         // Close the preset menu
         self.show_preset_menu = false;
         cx.notify();
     }
 
-    fn get_total_duration(&self, state: &TimerState) -> u32 {
-        match state {
-            TimerState::Working | TimerState::WorkPaused => self.config.work_duration_secs(),
-            TimerState::ShortBreak | TimerState::BreakPaused => {
-                self.config.short_break_duration_secs()
+    // Have the next session start by itself in a few minutes, from the ⋮ menu
+    pub fn handle_start_in(&mut self, minutes: u32, cx: &mut Context<'_, Self>) {
+        if let Some(event) = self.timer.arm(Utc::now() + Duration::minutes(minutes as i64)) {
+            self.events.publish(event, &self.session_info.lock());
+        }
+        self.show_preset_menu = false;
+        cx.notify();
+    }
+
+    // Call off an armed session, leaving the timer idle
+    pub fn handle_cancel_start(&mut self, cx: &mut Context<'_, Self>) {
+        if let Some(event) = self.timer.disarm() {
+            self.events.publish(event, &self.session_info.lock());
+        }
+        cx.notify();
+    }
+
+    // Start one of the configured quick timers, in place of any that's running
+    pub fn handle_start_quick_timer(&mut self, index: usize, cx: &mut Context<'_, Self>) {
+        let Some(preset) = self.config.quick_timers.get(index) else {
+            return;
+        };
+        self.quick_timer = Some(QuickTimer::start(preset, Utc::now()));
+        self.show_preset_menu = false;
+        self._quick_timer_ticks = Some(cx.spawn(async move |this, cx| loop {
+            cx.background_spawn(async {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            })
+            .await;
+            if !this.update(cx, |app, cx| app.tick_quick_timer(cx)).unwrap_or(false) {
+                break;
+            }
+        }));
+        cx.notify();
+    }
+
+    pub fn handle_cancel_quick_timer(&mut self, cx: &mut Context<'_, Self>) {
+        self.quick_timer = None;
+        self._quick_timer_ticks = None;
+        cx.notify();
+    }
+
+    // Ring once the quick timer is over. False when there's nothing left to count down.
+    fn tick_quick_timer(&mut self, cx: &mut Context<'_, Self>) -> bool {
+        let Some(quick_timer) = &self.quick_timer else {
+            return false;
+        };
+        let running = !quick_timer.is_over(Utc::now());
+        if !running {
+            let config = self.config.clone();
+            let (name, minutes) = (quick_timer.name.clone(), quick_timer.minutes);
+            // Talking to the notification server can block
+            std::thread::spawn(move || notifications::notify_quick_timer(&config, &name, minutes));
+            self.quick_timer = None;
+        }
+        if !running || self.active_panel == ActivePanel::Timer {
+            cx.notify();
+        }
+        running
+    }
+
+    // Give the break a preset's length; the time it has already run still counts
+    pub fn handle_pick_break_preset(&mut self, index: usize, cx: &mut Context<'_, Self>) {
+        let Some(preset) = self.config.break_presets.get(index).cloned() else {
+            return;
+        };
+        self.update_session(|info| {
+            if info.is_focus_mode {
+                return;
+            }
+            let total_secs = preset.duration * 60;
+            info.rest = ActiveTimer { remaining_secs: total_secs.saturating_sub(info.elapsed_secs).max(1), total_secs };
+            info.current_break_preset = Some(preset.name);
+        });
+        cx.notify();
+    }
+
+    // The panel's view, in the timer window or a window of its own
+    fn render_panel(&self, panel: ActivePanel, session_info: &SessionInfo, label_caret: usize, theme: Theme, view_for_ui: Entity<Self>) -> AnyElement {
+        let private = self.privacy_mode;
+        let detached = self.window_layout.is_detached(panel);
+        match panel {
+            ActivePanel::Settings => SettingsPanel::new(
+                self.config.enable_notifications,
+                self.notification_test.clone(),
+                self.import_status.clone(),
+                notifications::capabilities().cloned(),
+                self.remote_url.clone(),
+                self.show_remote_qr,
+                self.config.ambient_sound.clone(),
+                self.config.ambient_volume,
+                self.config.theme,
+                self.timer.tick_stats(),
+                detached,
+                view_for_ui,
+                theme,
+            )
+            .into_any_element(),
+            ActivePanel::Stats => {
+                let content = match self.stats_tab {
+                    StatsTab::Today => {
+                        // The note as typed, with its caret, while it's written
+                        let note = if self.is_editing_note {
+                            Some(label_input::with_caret(&shown_input(&self.note_input, private), label_caret))
+                        } else {
+                            self.journal.note(Local::now().date_naive()).map(|note| shown_input(note, private))
+                        };
+                        let strict = session_info.strict.day(Local::now().date_naive());
+                        StatsContent::Today {
+                            timeline: stats::timeline::today(&session_info.history, chrono::Local::now()),
+                            note,
+                            editing_note: self.is_editing_note,
+                            strict: (self.config.strict_mode || !strict.is_empty()).then_some(strict),
+                        }
+                    }
+                    StatsTab::Trends => {
+                        let today = Local::now().date_naive();
+                        let goals = [
+                            (GoalPeriod::Week, self.config.weekly_goal_hours),
+                            (GoalPeriod::Month, self.config.monthly_goal_hours),
+                        ];
+                        StatsContent::Trends {
+                            analytics: self.stats.read(stats::analytics::Analytics::compute),
+                            goals: self.stats.read(|summary| {
+                                goals
+                                    .iter()
+                                    .filter_map(|(period, hours)| GoalProgress::compute(summary, *period, *hours, today))
+                                    .collect()
+                            }),
+                            pomodoro_secs: self.config.work_duration_secs(),
+                            breaks: self.stats.read(|summary| stats::breaks::recent(summary, today)),
+                            break_target: self.config.break_nudge_below,
+                        }
+                    }
+                    StatsTab::Tasks => StatsContent::Tasks(
+                        stats::tasks::by_task(&session_info.history, &chrono::Local)
+                            .into_iter()
+                            .map(|mut task| {
+                                task.label = shown_label(&task.label, private);
+                                task
+                            })
+                            .collect(),
+                    ),
+                    StatsTab::Usage => StatsContent::Usage(self.usage.as_ref().map(|usage| Box::new(usage.usage()))),
+                    StatsTab::Labels => StatsContent::Labels {
+                        totals: self
+                            .stats
+                            .read(|summary| {
+                                let since = self.stats_range.start(chrono::Local::now());
+                                stats::labels::by_label(summary, since.map(|since| since.with_timezone(&chrono::Local).date_naive()), &self.stats_query)
+                            })
+                        .into_iter()
+                        .map(|mut total| {
+                            total.label = shown_label(&total.label, private);
+                            total
+                        })
+                        .collect(),
+                        icons: if private {
+                            Vec::new()
+                        } else {
+                            self.stats.read(|summary| {
+                                let since = self.stats_range.start(chrono::Local::now());
+                                stats::labels::by_icon(summary, since.map(|since| since.with_timezone(&chrono::Local).date_naive()))
+                            })
+                        },
+                        categories: self.stats.read(|summary| {
+                            let since = self.stats_range.start(chrono::Local::now());
+                            stats::labels::by_category(summary, since.map(|since| since.with_timezone(&chrono::Local).date_naive()))
+                        }),
+                        query: shown_input(&self.stats_query, private),
+                        range: self.stats_range,
+                    },
+                };
+                StatsPanel::new(content, detached, view_for_ui, theme).into_any_element()
             }
-            TimerState::LongBreak | TimerState::LongBreakPaused => {
-                self.config.long_break_duration_secs()
+            ActivePanel::History => {
+                let results = session_info
+                    .search_history(&self.history_filter)
+                    .into_iter()
+                    .map(|index| {
+                        let mut entry = session_info.history[index].clone();
+                        entry.label = shown_label(&entry.label, private);
+                        (index, entry)
+                    })
+                    .collect();
+                let mut filter = self.history_filter.clone();
+                filter.query = shown_input(&filter.query, private);
+                HistoryPanel::new(
+                    results,
+                    session_info.history.len(),
+                    filter,
+                    self.history_selected,
+                    view_for_ui,
+                    theme,
+                )
+                .into_any_element()
             }
-            TimerState::Idle => 0,
+            ActivePanel::NewTimer => NewTimerDialog::new(
+                NewTimerDraft { label: shown_input(&self.new_timer.label, private), ..self.new_timer.clone() },
+                label_caret,
+                self.config.session_categories.clone(),
+                view_for_ui,
+                theme,
+            )
+            .into_any_element(),
+            ActivePanel::Queue => QueuePanel::new(
+                session_info
+                    .label_queue
+                    .iter()
+                    .map(|entry| QueuedLabel { label: shown_label(&entry.label, private), count: entry.count })
+                    .collect(),
+                shown_input(&self.queue_input, private),
+                label_caret,
+                view_for_ui,
+                theme,
+            )
+            .into_any_element(),
+            ActivePanel::Diagnostics => DiagnosticsPanel::new(notifications::recent_log(), view_for_ui, theme).into_any_element(),
+            ActivePanel::Timer => CircularTimer::new(
+                TimerProps {
+                    timer: session_info.timer_view(private),
+                    sessions_until_long_break: Self::with_template(&self.config, session_info.template.as_deref()).cycle_length(),
+                    label_input: shown_input(&self.label_input, private),
+                    label_caret,
+                    is_editing_label: self.is_editing_label,
+                    show_preset_menu: self.show_preset_menu,
+                    private,
+                    notice: self.notices.first().cloned(),
+                    prompt: self.prompt(session_info),
+                    badges: self.plugin_badges.iter().map(|(_, text)| text.clone()).collect(),
+                    meeting: self.meeting.as_ref().map(|meeting| format!("In a meeting until {}", meeting.end.format("%H:%M"))),
+                    alarm: self.alarm.clone(),
+                    workday: self.schedule.workday_left(Local::now()).map(schedule::workday_text),
+                    templates: self.config.all_templates().map(|template| template.name.clone()).collect(),
+                    template: session_info.template.clone(),
+                    break_presets: self.config.break_presets.iter().map(|preset| (preset.name.clone(), preset.duration)).collect(),
+                    break_preset: session_info.current_break_preset.clone(),
+                    quick_timers: self.config.quick_timers.iter().map(|preset| (preset.name.clone(), preset.duration)).collect(),
+                    quick_timer: self.quick_timer.as_ref().map(|quick_timer| quick_timer.text(Utc::now())),
+                    reduce_motion: self.reduce_motion(),
+                    // Seeded by the history, so each celebration throws a new burst
+                    confetti: Burst::new(&self.config, &theme, session_info.history.len() as u64).filter(|_| !power::saving()),
+                },
+                view_for_ui,
+                self.control_focus.clone(),
+                theme,
+            )
+            .into_any_element(),
+        }
+    }
+
+    // Question waiting on the timer view: a crashed session first, then a stale
+    // one, since it's paused meanwhile
+    fn prompt(&self, info: &SessionInfo) -> Option<Prompt> {
+        if let Some(recovery) = &self.recovery {
+            let ending = recovery
+                .ends_at
+                .map(|ends_at| format!(", due to end at {}", ends_at.with_timezone(&Local).format("%H:%M")))
+                .unwrap_or_default();
+            return Some(Prompt {
+                message: format!(
+                    "The timer crashed at {} during a {}{}. Restore it?",
+                    recovery.crashed_at.with_timezone(&Local).format("%H:%M"),
+                    recovery.session.current_state.display_name(),
+                    ending
+                ),
+                choices: vec![("Restore", Self::handle_restore_recovery), ("Dismiss", Self::handle_dismiss_recovery)],
+            });
+        }
+        if let Some(callback) = &self.state_callback {
+            // Just where it goes, not the state itself
+            let target = callback.split('?').next().unwrap_or(callback);
+            return Some(Prompt {
+                message: format!("A link asks to send the timer's state and settings to {}. Send it?", target),
+                choices: vec![("Send", Self::handle_send_state), ("Don't send", Self::handle_withhold_state)],
+            });
+        }
+        // Resuming or stopping it directly answers it too
+        if let Some(secs) = self.stale_session.filter(|_| info.current_state.resume().is_some()) {
+            let minutes = secs / 60;
+            let ago = if minutes < 60 { format!("{} min", minutes) } else { format!("{}h {:02}m", minutes / 60, minutes % 60) };
+            return Some(Prompt {
+                message: format!("This {} was left running {} ago.", info.current_state.display_name(), ago),
+                choices: vec![
+                    ("Resume", Self::handle_resume_stale),
+                    ("Fast-forward", Self::handle_fast_forward_stale),
+                    ("Discard", Self::handle_discard_stale),
+                ],
+            });
+        }
+        if self.interrupting.as_deref() == Some(info.current_id.as_str()) && self.timer.strict_work(info) {
+            return Some(Prompt {
+                message: "A strict pomodoro can't be paused. Log the interruption to void it, or keep going.".to_string(),
+                choices: vec![
+                    ("Internal", Self::handle_internal_interruption),
+                    ("External", Self::handle_external_interruption),
+                    ("Keep going", Self::handle_keep_going),
+                ],
+            });
+        }
+        if self.timer.long_break_suggested(info) && self.long_break_declined.as_deref() != Some(info.current_id.as_str()) {
+            let minutes = info.focus_run.secs / 60;
+            return Some(Prompt {
+                message: format!("{}h {:02}m of focus since your last long break. Make this break a long one?", minutes / 60, minutes % 60),
+                choices: vec![("Long break", Self::handle_take_long_break), ("Not now", Self::handle_decline_long_break)],
+            });
         }
+        self.history_prune_prompt.map(|days| Prompt {
+            message: format!("Delete {} session(s) older than {} days from History?", info.older_than(days, Utc::now()), days),
+            choices: vec![("Delete", Self::handle_confirm_history_prune), ("Not now", Self::handle_keep_history)],
+        })
+    }
+}
+
+// Platform text input for the label being typed
+impl EntityInputHandler for PomodoroApp {
+    fn text_for_range(
+        &mut self,
+        range: std::ops::Range<usize>,
+        adjusted_range: &mut Option<std::ops::Range<usize>>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<String> {
+        let text = self.typed_label()?;
+        let range = label_input::range_from_utf16(text, &range);
+        *adjusted_range = Some(label_input::range_to_utf16(text, &range));
+        Some(text[range].to_string())
+    }
+
+    fn selected_text_range(&mut self, _ignore_disabled_input: bool, _window: &mut Window, _cx: &mut Context<Self>) -> Option<UTF16Selection> {
+        let cursor = self.label_cursor;
+        let text = self.typed_label()?;
+        let caret = label_input::to_utf16(text, label_input::caret(text, cursor));
+        Some(UTF16Selection { range: caret..caret, reversed: false })
+    }
+
+    fn marked_text_range(&self, _window: &mut Window, _cx: &mut Context<Self>) -> Option<std::ops::Range<usize>> {
+        let text = match self.active_panel {
+            _ if self.is_editing_note => &self.note_input,
+            ActivePanel::NewTimer => &self.new_timer.label,
+            ActivePanel::Queue => &self.queue_input,
+            _ => &self.label_input,
+        };
+        let marked = self.label_marked.clone().filter(|marked| text.get(marked.clone()).is_some())?;
+        Some(label_input::range_to_utf16(text, &marked))
+    }
+
+    fn unmark_text(&mut self, _window: &mut Window, _cx: &mut Context<Self>) {
+        self.label_marked = None;
+    }
+
+    fn replace_text_in_range(
+        &mut self,
+        range: Option<std::ops::Range<usize>>,
+        text: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.replace_and_mark_text_in_range(range, text, None, window, cx);
+        self.label_marked = None;
+    }
+
+    fn replace_and_mark_text_in_range(
+        &mut self,
+        range: Option<std::ops::Range<usize>>,
+        new_text: &str,
+        _new_selected_range: Option<std::ops::Range<usize>>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (max_chars, forbidden) = self.typing_rules();
+        let forbidden = forbidden.to_string();
+        let marked = self.label_marked.take();
+        let cursor = self.label_cursor;
+        let Some(text) = self.typed_label() else {
+            return;
+        };
+        // Composed text replaces what the IME marked, or else goes in at the caret
+        let caret = label_input::caret(text, cursor);
+        let range = match range {
+            Some(range) => label_input::range_from_utf16(text, &range),
+            None => marked.filter(|marked| text.get(marked.clone()).is_some()).unwrap_or(caret..caret),
+        };
+        let inserted = label_input::replace((max_chars, &forbidden), text, range, new_text);
+        self.label_cursor = Some(inserted.end);
+        self.label_marked = (!inserted.is_empty()).then_some(inserted);
+        cx.notify();
+    }
+
+    fn bounds_for_range(
+        &mut self,
+        _range_utf16: std::ops::Range<usize>,
+        element_bounds: Bounds<Pixels>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<Bounds<Pixels>> {
+        // Near enough for the IME to put its candidate window by the field
+        Some(element_bounds)
+    }
+
+    fn character_index_for_point(&mut self, _point: Point<Pixels>, _window: &mut Window, _cx: &mut Context<Self>) -> Option<usize> {
+        None
     }
 }
 
@@ -498,39 +2493,127 @@ impl Render for PomodoroApp {
     fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
         let view_for_keyboard = cx.entity().clone();
         let view_for_ui = cx.entity().clone();
+        let view_for_input = cx.entity().clone();
         let focus_handle = self.focus_handle.clone();
+        let typing = self.is_typing_in(self.active_panel);
+        let label_caret = self.label_caret();
+
+        // Request focus unless a control already holds it (keeps tab navigation working)
+        if window.focused(cx).is_none() {
+            self.focus_handle.focus(window);
+        }
 
-        // Request focus
-        self.focus_handle.focus(window);
+        // Borrow the session for this frame (blocking is ok for render); panels
+        // copy out only what they show
+        let session_info = self.session_info.lock();
+        let private = self.privacy_mode;
 
-        // Get current session info (blocking is ok for render)
-        let session_info = self.session_info.lock().clone();
-        let total_duration = self.get_total_duration(&session_info.current_state);
-        let is_editing = self.is_editing_label;
-        let show_preset_menu = self.show_preset_menu;
+        // Announce state changes through the window title, which screen readers read out
+        let announcement = session_info.announcement(private);
+        if announcement != self.last_announcement {
+            window.set_window_title(&announcement);
+            self.last_announcement = announcement;
+        }
 
-        div()
+        // Faded with auto_hide, unless something on the timer needs an answer
+        let faded = self.auto_hidden
+            && !self.peeking
+            && self.active_panel == ActivePanel::Timer
+            && self.notices.is_empty()
+            && self.alarm.is_none()
+            && self.prompt(&session_info).is_none();
+        let reduce_motion = self.reduce_motion();
+
+        let root = div()
+            .id("timer-window")
             .w_full()
             .h_full()
             .track_focus(&focus_handle)
+            .on_hover(cx.listener(|app, hovered: &bool, _window, cx| app.handle_peek(*hovered, cx)))
             .on_key_down(move |event, _window, cx| {
                 let keystroke = &event.keystroke;
                 let key = keystroke.key.as_str();
 
+                // Cmd and Ctrl shortcuts are key bindings, don't also treat them as typing
+                if keystroke.modifiers.platform || keystroke.modifiers.control {
+                    return;
+                }
+
                 // Check edit state once
-                let is_editing = cx.update_entity(&view_for_keyboard, |app, _cx| app.is_editing_label);
+                let (is_editing, active_panel) = cx.update_entity(&view_for_keyboard, |app, _cx| (app.is_editing_label, app.active_panel));
 
-                if is_editing {
+                if active_panel == ActivePanel::Stats && cx.update_entity(&view_for_keyboard, |app, cx| app.handle_stats_key(key, cx)) {
+                    // Taken by the day's note or the label search
+                } else if active_panel == ActivePanel::History {
+                    // HISTORY PANEL: typing searches labels, arrows pick a result
+                    cx.update_entity(&view_for_keyboard, |app, cx| {
+                        let query_len = app.history_filter.query.len();
+                        match key {
+                            "enter" => app.handle_open_selected_history(cx),
+                            "escape" => app.handle_toggle_history(cx),
+                            "up" => app.handle_move_history_selection(-1, cx),
+                            "down" => app.handle_move_history_selection(1, cx),
+                            "backspace" => {
+                                app.history_filter.query.pop();
+                                app.history_selected = 0;
+                                cx.notify();
+                            }
+                            "space" if query_len > 0 && query_len < 30 => {
+                                app.history_filter.query.push(' ');
+                                cx.notify();
+                            }
+                            _ if key.len() == 1 && query_len < 30 => {
+                                app.history_filter.query.push_str(key);
+                                app.history_selected = 0;
+                                cx.notify();
+                            }
+                            _ => {}
+                        }
+                    });
+                } else if active_panel == ActivePanel::NewTimer {
+                    // NEW TIMER DIALOG: typing goes into the label, arrows change the duration
+                    cx.update_entity(&view_for_keyboard, |app, cx| {
+                        match key {
+                            "enter" => app.handle_start_new_timer(cx),
+                            "escape" => app.handle_cancel_new_timer(cx),
+                            "up" => app.handle_adjust_new_timer_minutes(1, cx),
+                            "down" => app.handle_adjust_new_timer_minutes(-1, cx),
+                            // Typing reaches the label through the input handler
+                            _ => {
+                                app.edit_typed_label(key, cx);
+                            }
+                        }
+                    });
+                } else if active_panel == ActivePanel::Queue {
+                    // QUEUE PANEL: typing goes into the new queue entry
+                    cx.update_entity(&view_for_keyboard, |app, cx| {
+                        match key {
+                            "enter" => app.handle_add_to_queue(cx),
+                            "escape" => app.handle_toggle_queue(cx),
+                            _ => {
+                                app.edit_typed_label(key, cx);
+                            }
+                        }
+                    });
+                } else if active_panel == ActivePanel::Diagnostics {
+                    cx.update_entity(&view_for_keyboard, |app, cx| {
+                        match key {
+                            "c" => app.handle_copy_diagnostics(cx),
+                            "escape" => app.handle_toggle_diagnostics(cx),
+                            _ => {}
+                        }
+                    });
+                } else if is_editing {
                     // EDIT MODE: Only handle text input, block all shortcuts
                     cx.update_entity(&view_for_keyboard, |app, cx| {
-                        if key == "backspace" {
-                            app.label_input.pop();
-                            cx.notify();
-                        } else if key.len() == 1 && app.label_input.len() < 30 {
-                            app.label_input.push_str(key);
-                            cx.notify();
+                        // Typing reaches the label through the input handler; no shortcuts here
+                        match key {
+                            "enter" => app.handle_done_label(cx),
+                            "escape" => app.handle_cancel_label(cx),
+                            _ => {
+                                app.edit_typed_label(key, cx);
+                            }
                         }
-                        // All other keys (including s, space, escape) are ignored
                     });
                 } else {
                     // NOT EDITING: Handle shortcuts
@@ -541,34 +2624,91 @@ impl Render for PomodoroApp {
                         "s" => {
                             cx.update_entity(&view_for_keyboard, |app, cx| app.handle_skip(cx));
                         }
+                        "left" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_browse_history(-1, cx));
+                        }
+                        "right" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_browse_history(1, cx));
+                        }
                         "escape" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_stop(cx));
+                        }
+                        "r" => {
                             cx.update_entity(&view_for_keyboard, |app, cx| app.handle_reset(cx));
                         }
+                        "x" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_switch_session(cx));
+                        }
                         "n" => {
                             cx.update_entity(&view_for_keyboard, |app, cx| app.handle_new_timer(cx));
                         }
+                        "t" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_toggle_stats(cx));
+                        }
+                        "h" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_toggle_history(cx));
+                        }
+                        "c" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_continue_from_history(cx));
+                        }
+                        "," => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_toggle_settings(cx));
+                        }
+                        "p" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_toggle_privacy(cx));
+                        }
+                        "f" => {
+                            cx.update_entity(&view_for_keyboard, |app, cx| app.handle_present(cx));
+                        }
                         _ => {}
                     }
                 }
             })
-            .on_action(|_: &QuitApp, _window, cx| {
-                cx.quit();
+            .on_action(cx.listener(|app, _: &ToggleTimer, _window, cx| app.handle_toggle(cx)))
+            .on_action(cx.listener(|app, _: &ResetTimer, _window, cx| app.handle_reset(cx)))
+            .on_action(cx.listener(|app, _: &SkipSession, _window, cx| app.handle_skip(cx)))
+            .on_action(cx.listener(|app, _: &OpenSettings, _window, cx| app.handle_open_settings(cx)))
+            .on_action(cx.listener(|app, _: &About, _window, cx| app.handle_about(cx)))
+            .on_action(cx.listener(|app, _: &Present, _window, cx| app.handle_present(cx)))
+            .on_action(cx.listener(|app, _: &PasteLabel, _window, cx| app.handle_paste(cx)))
+            .on_action(cx.listener(|app, _: &CopyLabel, _window, cx| app.handle_copy(cx)))
+            .on_action(|_: &FocusNext, window, _cx| {
+                window.focus_next();
             })
-            .child({
-                let appearance = window.appearance();
-                let theme_mode = ThemeMode::from_appearance(appearance);
-                let theme = Theme::from_mode(theme_mode);
-
-                CircularTimer::new(
-                    session_info,
-                    self.config.sessions_until_long_break,
-                    total_duration,
-                    self.label_input.clone(),
-                    is_editing,
-                    show_preset_menu,
-                    view_for_ui,
-                    theme,
+            .on_action(|_: &FocusPrev, window, _cx| {
+                window.focus_prev();
+            })
+            // Typed text goes to the label through the platform's input handling
+            .when(typing, |d| {
+                d.child(
+                    canvas(
+                        |_bounds, _window, _cx| {},
+                        move |bounds, _, window, cx| {
+                            if let Some(focused) = window.focused(cx) {
+                                window.handle_input(&focused, ElementInputHandler::new(bounds, view_for_input), cx);
+                            }
+                        },
+                    )
+                    .absolute()
+                    .size_full(),
                 )
             })
+            .child({
+                let theme = Theme::current(self.config.theme, window.appearance());
+                self.render_panel(self.active_panel, &session_info, label_caret, theme, view_for_ui)
+            });
+
+        if !faded {
+            root.into_any_element()
+        } else if reduce_motion {
+            root.opacity(HIDDEN_OPACITY).into_any_element()
+        } else {
+            root.with_animation(
+                "auto-hide-fade",
+                Animation::new(std::time::Duration::from_millis(800)).with_easing(ease_in_out),
+                |root, delta| root.opacity(1.0 - (1.0 - HIDDEN_OPACITY) * delta),
+            )
+            .into_any_element()
+        }
     }
 }
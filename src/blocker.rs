@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::{SessionInfo, TimerState};
+
+// Runs the user's blocking command while a work session counts down and the
+// unblocking command once it stops: paused, finished, skipped, reset, or the
+// app quitting. The commands switch a hosts-file blocklist, SelfControl, a
+// Focus mode or similar.
+pub struct Blocker {
+    jobs: Sender<Job>,  // Handled one at a time, in order, off the UI thread
+}
+
+enum Job {
+    // Block while a work session runs, unblock otherwise
+    Set(bool),
+    // Unblock before the app quits, then answer
+    Restore(Sender<()>),
+}
+
+// Runs a command, Err with what went wrong unless it succeeded
+type Runner = Box<dyn FnMut(&str) -> Result<(), String> + Send>;
+
+// Owns the block state on the worker thread, so it only changes once a
+// command has worked
+struct Worker {
+    block_command: Option<String>,
+    unblock_command: Option<String>,
+    blocked: bool,
+    marker: Option<PathBuf>,
+    run: Runner,
+}
+
+// Present while the block is on, so a crash while blocking is undone at the next launch
+fn marker_path() -> Option<PathBuf> {
+    Persistence::data_dir().ok().map(|dir| dir.join("blocking"))
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+fn run_shell(command: &str) -> Result<(), String> {
+    match shell(command).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("failed ({})", status)),
+        Err(e) => Err(format!("couldn't be run: {}", e)),
+    }
+}
+
+impl Worker {
+    fn set(&mut self, block: bool) {
+        if self.blocked == block {
+            return;
+        }
+        let what = if block { "block" } else { "unblock" };
+        // Only one of them configured: the other side needs nothing run
+        let command = if block { &self.block_command } else { &self.unblock_command };
+        if let Some(command) = command {
+            if let Err(e) = (self.run)(command) {
+                // Still as it was, so the next change of session tries again
+                notifications::log_error(&format!("The {} command {}", what, e));
+                return;
+            }
+            notifications::log_info(&format!("Ran the {} command", what));
+        }
+        self.blocked = block;
+        if let Some(marker) = &self.marker {
+            let _ = if block { std::fs::write(marker, "") } else { std::fs::remove_file(marker) };
+        }
+    }
+}
+
+impl Blocker {
+    // None unless a block or unblock command is configured
+    pub fn spawn(config: &Config) -> Option<Arc<Self>> {
+        let configured = |command: &Option<String>| command.clone().filter(|command| !command.trim().is_empty());
+        let marker = marker_path();
+        let worker = Worker {
+            block_command: configured(&config.block_command),
+            unblock_command: configured(&config.unblock_command),
+            // Left blocking by a run that didn't get to unblock
+            blocked: marker.as_ref().is_some_and(|marker| marker.exists()),
+            marker,
+            run: Box::new(run_shell),
+        };
+        if worker.block_command.is_none() && worker.unblock_command.is_none() {
+            return None;
+        }
+        Self::start(worker).map(Arc::new)
+    }
+
+    fn start(mut worker: Worker) -> Option<Self> {
+        let (jobs, pending) = channel::<Job>();
+        std::thread::Builder::new()
+            .name("blocker".to_string())
+            .spawn(move || {
+                for job in pending {
+                    match job {
+                        Job::Set(block) => worker.set(block),
+                        Job::Restore(done) => {
+                            worker.set(false);
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+            .ok()?;
+        Some(Self { jobs })
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        if *event == SessionEvent::Tick {
+            return;
+        }
+        self.set_blocked(info.current_state == TimerState::Working);
+    }
+
+    fn set_blocked(&self, block: bool) {
+        let _ = self.jobs.send(Job::Set(block));
+    }
+
+    // Lift the block before the app quits, once the commands already queued
+    // have run. Waits for it, since the worker thread won't get the chance later.
+    pub fn restore(&self) {
+        let (done, finished) = channel();
+        if self.jobs.send(Job::Restore(done)).is_ok() {
+            let _ = finished.recv();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    // A worker whose commands are logged instead of run, failing while `fails` is set
+    fn fake(log: &Arc<Mutex<Vec<String>>>, fails: &Arc<Mutex<bool>>) -> Worker {
+        let (log, fails) = (log.clone(), fails.clone());
+        Worker {
+            block_command: Some("block".to_string()),
+            unblock_command: Some("unblock".to_string()),
+            blocked: false,
+            marker: None,
+            run: Box::new(move |command| {
+                log.lock().push(command.to_string());
+                if *fails.lock() { Err("failed (exit status: 1)".to_string()) } else { Ok(()) }
+            }),
+        }
+    }
+
+    #[test]
+    fn blocks_only_once_the_command_worked() {
+        let (log, fails) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(true)));
+        let mut worker = fake(&log, &fails);
+        worker.set(true);
+        assert!(!worker.blocked);
+        // Nothing to lift
+        worker.set(false);
+        *fails.lock() = false;
+        worker.set(true);
+        worker.set(true);
+        assert!(worker.blocked);
+        assert_eq!(*log.lock(), ["block", "block"]);
+    }
+
+    #[test]
+    fn restores_after_the_queued_commands() {
+        let (log, fails) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(false)));
+        let blocker = Blocker::start(fake(&log, &fails)).unwrap();
+        blocker.set_blocked(true);
+        blocker.restore();
+        assert_eq!(*log.lock(), ["block", "unblock"]);
+        // Already lifted
+        blocker.restore();
+        assert_eq!(log.lock().len(), 2);
+    }
+}
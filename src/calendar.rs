@@ -0,0 +1,494 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use futures::channel::mpsc::UnboundedSender;
+use ical::parser::ical::component::IcalEvent;
+use ical::property::Property;
+use std::collections::HashSet;
+use std::io::BufReader;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::notifications;
+
+// How often the calendar is read again: files are cheap, servers get a break
+const FILE_REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+const SERVER_REFRESH: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+// How often the watcher checks whether a meeting started or ended
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+// Recurring events are expanded up to this many occurrences
+const MAX_OCCURRENCES: usize = 10_000;
+
+// A scheduled meeting, as far as the timer cares
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    pub title: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+// The parts of an RRULE the timer understands. Monthly rules repeat on the
+// start's day of the month; other frequencies only count the first occurrence.
+#[derive(Debug, Default)]
+struct Recurrence {
+    frequency: String,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    weekdays: Vec<Weekday>,
+}
+
+impl Recurrence {
+    fn parse(rule: &str) -> Self {
+        let mut recurrence = Self { interval: 1, ..Self::default() };
+        for (key, value) in rule.split(';').filter_map(|part| part.split_once('=')) {
+            match key {
+                "FREQ" => recurrence.frequency = value.to_string(),
+                "INTERVAL" => recurrence.interval = value.parse().unwrap_or(1).max(1),
+                "COUNT" => recurrence.count = value.parse().ok(),
+                "UNTIL" => recurrence.until = parse_time(value, false).map(|(time, _)| time.naive_local()),
+                "BYDAY" => {
+                    // Plain weekdays only; "1MO" style positions are a monthly feature
+                    recurrence.weekdays = value.split(',').filter_map(|day| day.parse::<Weekday>().ok()).collect();
+                }
+                _ => {}
+            }
+        }
+        recurrence
+    }
+
+    // Start times from `start` until one begins after `until`
+    fn occurrences(&self, start: NaiveDateTime, until: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let limit = self.count.unwrap_or(MAX_OCCURRENCES).min(MAX_OCCURRENCES);
+        let last = self.until.map_or(until, |rule_until| rule_until.min(until));
+        let mut starts = Vec::new();
+        let mut step = 0u32;
+        while starts.len() < limit {
+            let candidates: Vec<NaiveDateTime> = match self.frequency.as_str() {
+                "DAILY" => vec![start + Duration::days((step * self.interval) as i64)],
+                "WEEKLY" if !self.weekdays.is_empty() => {
+                    let week = start.date() - Duration::days(start.weekday().num_days_from_monday() as i64)
+                        + Duration::weeks((step * self.interval) as i64);
+                    let mut days: Vec<NaiveDateTime> = self
+                        .weekdays
+                        .iter()
+                        .map(|day| (week + Duration::days(day.num_days_from_monday() as i64)).and_time(start.time()))
+                        .filter(|time| *time >= start)
+                        .collect();
+                    days.sort();
+                    days
+                }
+                "WEEKLY" => vec![start + Duration::weeks((step * self.interval) as i64)],
+                "MONTHLY" => start
+                    .checked_add_months(Months::new(step * self.interval))
+                    .filter(|time| time.day() == start.day())
+                    .into_iter()
+                    .collect(),
+                _ if step == 0 => vec![start],
+                _ => return starts,
+            };
+            for candidate in candidates {
+                if candidate > last || starts.len() >= limit {
+                    return starts;
+                }
+                starts.push(candidate);
+            }
+            step += 1;
+        }
+        starts
+    }
+}
+
+// A DATE or DATE-TIME value. Times with a TZID are read as local time, which
+// is right for the usual case of a calendar in the user's own time zone.
+// The bool is true for all-day dates.
+fn parse_time(value: &str, date_only: bool) -> Option<(DateTime<Local>, bool)> {
+    if date_only || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?, true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).with_timezone(&Local), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((Local.from_local_datetime(&time).earliest()?, false))
+}
+
+// VALUE=DATE marks an all-day date
+fn is_date(property: &Property) -> bool {
+    property
+        .params
+        .iter()
+        .flatten()
+        .any(|(name, values)| name == "VALUE" && values.iter().any(|value| value == "DATE"))
+}
+
+fn property_time(property: &Property) -> Option<(DateTime<Local>, bool)> {
+    parse_time(property.value.as_deref()?, is_date(property))
+}
+
+// ISO 8601 durations as used by DURATION, e.g. PT45M or P1DT2H
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('+').unwrap_or(value).strip_prefix('P')?;
+    let (mut total, mut number, mut in_time) = (Duration::zero(), String::new(), false);
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            unit => {
+                let n: i64 = std::mem::take(&mut number).parse().ok()?;
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(total)
+}
+
+fn value<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a str> {
+    event.properties.iter().find(|property| property.name == name)?.value.as_deref()
+}
+
+fn times<'a>(event: &'a IcalEvent, name: &'a str) -> impl Iterator<Item = DateTime<Local>> + 'a {
+    event
+        .properties
+        .iter()
+        .filter(move |property| property.name == name)
+        .flat_map(|property| {
+            let date_only = is_date(property);
+            // EXDATE may list several times separated by commas
+            property
+                .value
+                .iter()
+                .flat_map(|value| value.split(','))
+                .filter_map(move |value| parse_time(value, date_only).map(|(time, _)| time))
+                .collect::<Vec<_>>()
+        })
+}
+
+// Meetings in an iCalendar text that overlap `from`..`to`. All-day events,
+// free time, and cancelled events aren't meetings.
+pub fn meetings_between(ics: &str, from: DateTime<Local>, to: DateTime<Local>) -> Result<Vec<Meeting>> {
+    let mut events = Vec::new();
+    for calendar in ical::IcalParser::new(BufReader::new(ics.as_bytes())) {
+        events.extend(calendar.context("Failed to read the calendar")?.events);
+    }
+
+    // Occurrences of a recurring event that were moved or changed are separate events
+    let overridden: HashSet<(String, DateTime<Local>)> = events
+        .iter()
+        .filter_map(|event| Some((value(event, "UID")?.to_string(), times(event, "RECURRENCE-ID").next()?)))
+        .collect();
+
+    let mut meetings = Vec::new();
+    for event in &events {
+        if value(event, "STATUS") == Some("CANCELLED") || value(event, "TRANSP") == Some("TRANSPARENT") {
+            continue;
+        }
+        let Some((start, all_day)) = event.properties.iter().find(|p| p.name == "DTSTART").and_then(property_time) else {
+            continue;
+        };
+        if all_day {
+            continue;
+        }
+        let length = match event.properties.iter().find(|p| p.name == "DTEND").and_then(property_time) {
+            Some((end, _)) => end - start,
+            None => value(event, "DURATION").and_then(parse_duration).unwrap_or_else(Duration::zero),
+        };
+        if length <= Duration::zero() {
+            continue;
+        }
+
+        let title = value(event, "SUMMARY").unwrap_or("Meeting").to_string();
+        let uid = value(event, "UID").unwrap_or_default();
+        let is_override = times(event, "RECURRENCE-ID").next().is_some();
+        let excluded: Vec<DateTime<Local>> = times(event, "EXDATE").collect();
+        let starts = match value(event, "RRULE") {
+            Some(rule) if !is_override => {
+                Recurrence::parse(rule).occurrences(start.naive_local(), to.naive_local())
+            }
+            _ => vec![start.naive_local()],
+        };
+
+        for naive in starts {
+            let Some(start) = Local.from_local_datetime(&naive).earliest() else {
+                continue;
+            };
+            let end = start + length;
+            if end <= from || start >= to || excluded.contains(&start) {
+                continue;
+            }
+            if !is_override && overridden.contains(&(uid.to_string(), start)) {
+                continue;
+            }
+            meetings.push(Meeting { title: title.clone(), start, end });
+        }
+    }
+    meetings.sort_by_key(|meeting| meeting.start);
+    Ok(meetings)
+}
+
+// The meeting under way at `now`. Back-to-back and overlapping meetings count
+// as one that lasts until the last of them ends.
+pub fn current_meeting(meetings: &[Meeting], now: DateTime<Local>) -> Option<Meeting> {
+    let mut current = meetings.iter().find(|meeting| meeting.start <= now && now < meeting.end)?.clone();
+    for meeting in meetings {
+        if meeting.start <= current.end && meeting.end > current.end {
+            current.end = meeting.end;
+        }
+    }
+    Some(current)
+}
+
+// Texts inside <calendar-data> elements of a CalDAV multistatus response
+fn calendar_data(xml: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find("calendar-data") {
+        let tag_start = rest[..open].rfind('<').unwrap_or(open);
+        let Some(tag_end) = rest[open..].find('>').map(|end| open + end) else {
+            break;
+        };
+        let is_closing = rest[tag_start..open].starts_with("</");
+        let is_empty = rest[..tag_end].ends_with('/');
+        rest = &rest[tag_end + 1..];
+        if is_closing || is_empty {
+            continue;
+        }
+        let Some(close) = rest.find("</") else {
+            break;
+        };
+        let text = rest[..close].trim();
+        let text = text.strip_prefix("<![CDATA[").and_then(|text| text.strip_suffix("]]>")).unwrap_or(text);
+        texts.push(
+            text.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&#13;", "\r")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[close..];
+    }
+    texts
+}
+
+fn caldav_time(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Where meetings come from: an .ics file, or a calendar on a server
+#[derive(Debug, Clone)]
+pub struct CalendarSource {
+    location: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl CalendarSource {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let location = config.calendar.clone().filter(|location| !location.trim().is_empty())?;
+        Some(Self {
+            location: location.trim().to_string(),
+            username: config.calendar_username.clone(),
+            password: config.calendar_password.clone(),
+        })
+    }
+
+    fn is_remote(&self) -> bool {
+        self.location.starts_with("http://") || self.location.starts_with("https://")
+    }
+
+    fn request(&self, method: &str) -> ureq::Request {
+        let request = ureq::request(method, &self.location).timeout(std::time::Duration::from_secs(30));
+        match &self.username {
+            Some(username) => {
+                use base64::Engine;
+                let credentials = format!("{}:{}", username, self.password.as_deref().unwrap_or_default());
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                request.set("Authorization", &format!("Basic {}", encoded))
+            }
+            None => request,
+        }
+    }
+
+    // Meetings between `from` and `to`. CalDAV servers are asked to expand
+    // recurring events themselves; plain .ics links are downloaded whole.
+    pub fn meetings(&self, from: DateTime<Local>, to: DateTime<Local>) -> Result<Vec<Meeting>> {
+        if !self.is_remote() {
+            let path = self.location.strip_prefix("file://").unwrap_or(&self.location);
+            let ics = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+            return meetings_between(&ics, from, to);
+        }
+
+        let (start, end) = (caldav_time(from), caldav_time(to));
+        let query = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data><c:expand start="{start}" end="{end}"/></c:calendar-data></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VEVENT"><c:time-range start="{start}" end="{end}"/></c:comp-filter></c:comp-filter></c:filter>
+</c:calendar-query>"#
+        );
+        let report = self
+            .request("REPORT")
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(&query);
+        match report {
+            Ok(response) if response.status() == 207 => {
+                let xml = response.into_string().context("Failed to read the calendar server's answer")?;
+                let mut meetings = Vec::new();
+                for ics in calendar_data(&xml) {
+                    meetings.extend(meetings_between(&ics, from, to)?);
+                }
+                meetings.sort_by_key(|meeting| meeting.start);
+                Ok(meetings)
+            }
+            // Not a CalDAV collection; read it as an .ics feed
+            Ok(_) | Err(ureq::Error::Status(400..=499 | 501, _)) => {
+                let ics = match self.request("GET").call() {
+                    Ok(response) => response.into_string().context("Failed to download the calendar")?,
+                    Err(ureq::Error::Status(code, _)) => bail!("The calendar server answered {}", code),
+                    Err(e) => return Err(e).context("Failed to reach the calendar server"),
+                };
+                meetings_between(&ics, from, to)
+            }
+            Err(ureq::Error::Status(code, _)) => bail!("The calendar server answered {}", code),
+            Err(e) => Err(e).context("Failed to reach the calendar server"),
+        }
+    }
+}
+
+// Reads the calendar in the background and reports each time a meeting starts
+// (Some) or the last one ends (None)
+pub fn spawn_watcher(source: CalendarSource, meetings_tx: UnboundedSender<Option<Meeting>>) {
+    let refresh = if source.is_remote() { SERVER_REFRESH } else { FILE_REFRESH };
+    let spawned = std::thread::Builder::new().name("calendar".to_string()).spawn(move || {
+        let mut meetings = Vec::new();
+        let mut loaded_at: Option<Instant> = None;
+        let mut current: Option<Meeting> = None;
+        let mut failing = false;
+        loop {
+            let now = Local::now();
+            if loaded_at.is_none_or(|at| at.elapsed() >= refresh) {
+                match source.meetings(now - Duration::days(1), now + Duration::days(1)) {
+                    Ok(loaded) => {
+                        meetings = loaded;
+                        failing = false;
+                    }
+                    // Keep the meetings from the last read; say so only once
+                    Err(e) if !failing => {
+                        notifications::log_error(&format!("Failed to read the calendar: {:#}", e));
+                        failing = true;
+                    }
+                    Err(_) => {}
+                }
+                loaded_at = Some(Instant::now());
+            }
+
+            let meeting = current_meeting(&meetings, now);
+            if meeting != current {
+                current = meeting.clone();
+                if meetings_tx.unbounded_send(meeting).is_err() {
+                    break;
+                }
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+    if let Err(e) = spawned {
+        notifications::log_error(&format!("Failed to start the calendar watcher: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(text: &str) -> DateTime<Local> {
+        let time = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&time).unwrap()
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Standup\r
+DTSTART;TZID=Europe/Berlin:20261005T093000\r
+DURATION:PT15M\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR\r
+EXDATE;TZID=Europe/Berlin:20261016T093000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+RECURRENCE-ID;TZID=Europe/Berlin:20261014T093000\r
+SUMMARY:Standup (moved)\r
+DTSTART;TZID=Europe/Berlin:20261014T110000\r
+DTEND;TZID=Europe/Berlin:20261014T111500\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:review\r
+SUMMARY:Design review\r
+DTSTART:20261014T100000\r
+DTEND:20261014T110000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:cancelled\r
+SUMMARY:Cancelled sync\r
+STATUS:CANCELLED\r
+DTSTART:20261014T140000\r
+DTEND:20261014T150000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:holiday\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20261014\r
+DTEND;VALUE=DATE:20261015\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn expands_recurring_meetings() {
+        let meetings = meetings_between(CALENDAR, local("2026-10-12 00:00"), local("2026-10-17 00:00")).unwrap();
+        let found: Vec<(&str, DateTime<Local>)> = meetings.iter().map(|m| (m.title.as_str(), m.start)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Standup", local("2026-10-12 09:30")),
+                ("Design review", local("2026-10-14 10:00")),
+                ("Standup (moved)", local("2026-10-14 11:00")),
+            ]
+        );
+        assert_eq!(meetings[0].end, local("2026-10-12 09:45"));
+    }
+
+    #[test]
+    fn back_to_back_meetings_are_one() {
+        let meetings = meetings_between(CALENDAR, local("2026-10-14 00:00"), local("2026-10-15 00:00")).unwrap();
+        let meeting = current_meeting(&meetings, local("2026-10-14 10:30")).unwrap();
+        assert_eq!((meeting.title.as_str(), meeting.end), ("Design review", local("2026-10-14 11:15")));
+        assert_eq!(current_meeting(&meetings, local("2026-10-14 11:15")), None);
+    }
+
+    #[test]
+    fn reads_caldav_answers() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+<d:response><d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:Q&amp;A&#13;
+END:VCALENDAR</cal:calendar-data></d:prop></d:propstat></d:response>
+<d:response><d:propstat><d:prop><cal:calendar-data/></d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        assert_eq!(calendar_data(xml), vec!["BEGIN:VCALENDAR\r\nSUMMARY:Q&A\r\nEND:VCALENDAR".to_string()]);
+        assert_eq!(parse_duration("P1DT2H30M"), Some(Duration::minutes(26 * 60 + 30)));
+    }
+}
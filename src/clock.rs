@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+
+// Source of the current time for the timer, so tests can move time by hand
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// Clock that only moves when told to
+#[cfg(test)]
+pub struct ManualClock(parking_lot::Mutex<DateTime<Utc>>);
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new() -> Self {
+        Self(parking_lot::Mutex::new(Utc::now()))
+    }
+
+    pub fn advance(&self, secs: i64) {
+        *self.0.lock() += chrono::Duration::seconds(secs);
+    }
+
+    pub fn advance_millis(&self, millis: i64) {
+        *self.0.lock() += chrono::Duration::milliseconds(millis);
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.0.lock() = now;
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock()
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::git;
+use crate::persistence::{Persistence, PASSPHRASE_ENV};
+use crate::state::{CompletedTimer, TimerState};
+
+const TRAILER: &str = "Pomodoros:";
+
+// A line in the installed hook, to tell it from someone else's
+const HOOK_MARKER: &str = "# Installed by pomodoro-timer";
+
+// "Pomodoros: 3 (75m)": the completed work sessions labelled with `branch`,
+// cut and cleaned the way the label was. None when there are none.
+pub fn trailer(history: &[CompletedTimer], branch: &str, config: &Config) -> Option<String> {
+    let label = config.clean_label(branch);
+    let (count, secs) = history
+        .iter()
+        .filter(|entry| entry.session_type == TimerState::Working.display_name() && entry.ran_out() && entry.label == label)
+        .fold((0, 0), |(count, secs), entry| (count + 1, secs + entry.duration_secs));
+    (count > 0).then(|| format!("{} {} ({}m)", TRAILER, count, secs / 60))
+}
+
+// The commit message with the trailer after its text, ahead of git's comment
+// lines. None when it already has one, e.g. on an amend.
+pub fn append_trailer(message: &str, trailer: &str) -> Option<String> {
+    let lines: Vec<&str> = message.lines().collect();
+    if lines.iter().any(|line| line.starts_with(TRAILER)) {
+        return None;
+    }
+    let comments = lines.iter().position(|line| line.starts_with('#')).unwrap_or(lines.len());
+    let text = lines[..comments].join("\n");
+    let mut updated = format!("{}\n\n{}\n", text.trim_end(), trailer);
+    if comments < lines.len() {
+        updated.push('\n');
+        updated.push_str(&lines[comments..].join("\n"));
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+// `pomodoro-timer prepare-commit-msg <file> [source]`, run by git through the
+// hook. Problems are printed but never stop the commit.
+pub fn run(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("--install") => match install(Path::new(".")) {
+            Ok(path) => {
+                println!("Installed {}", path.display());
+                0
+            }
+            Err(e) => {
+                eprintln!("Failed to install the hook: {:#}", e);
+                1
+            }
+        },
+        Some(file) => {
+            // Merges and squashes have a message of their own
+            if matches!(args.get(1).map(String::as_str), Some("merge" | "squash")) {
+                return 0;
+            }
+            if let Err(e) = add_trailer(Path::new(file)) {
+                eprintln!("pomodoro-timer: no Pomodoros trailer: {:#}", e);
+            }
+            0
+        }
+        None => {
+            eprintln!("Usage: pomodoro-timer prepare-commit-msg <message-file> [source] | --install");
+            2
+        }
+    }
+}
+
+fn add_trailer(file: &Path) -> Result<()> {
+    let Some(branch) = git::current_branch(Path::new("."))? else {
+        return Ok(());  // Detached HEAD
+    };
+    let config = if Config::exists() { Config::load().unwrap_or_default() } else { Config::default() };
+    Persistence::unlock_from_env(config.encrypt_state)
+        .with_context(|| format!("Failed to unlock state with {}", PASSPHRASE_ENV))?;
+    let history = Persistence::load()?.history;
+    let Some(trailer) = trailer(&history, &branch, &config) else {
+        return Ok(());
+    };
+    let message = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    if let Some(updated) = append_trailer(&message, &trailer) {
+        std::fs::write(file, updated).with_context(|| format!("Failed to write {}", file.display()))?;
+    }
+    Ok(())
+}
+
+// Write the prepare-commit-msg hook into the repository at `repo`, calling
+// this executable
+fn install(repo: &Path) -> Result<std::path::PathBuf> {
+    let dir = git::git_dir(repo).context("Run this in the top folder of a git checkout")?.join("hooks");
+    let path = dir.join("prepare-commit-msg");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        if !existing.contains(HOOK_MARKER) {
+            bail!("{} already exists; add `pomodoro-timer prepare-commit-msg \"$@\"` to it", path.display());
+        }
+    }
+    let exe = std::env::current_exe().context("Failed to find this executable")?;
+    let script = format!("#!/bin/sh\n{}\nexec \"{}\" prepare-commit-msg \"$@\"\n", HOOK_MARKER, exe.display());
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&path, script).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {} executable", path.display()))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+    use chrono::Utc;
+
+    fn entry(label: &str, duration_mins: u32, session_type: &str, abandoned: bool) -> CompletedTimer {
+        CompletedTimer { label: label.to_string(), ..completed(&Utc::now().to_rfc3339(), duration_mins, session_type, abandoned) }
+    }
+
+    #[test]
+    fn adds_the_branch_pomodoros_to_the_message() {
+        let history = vec![
+            entry("feature/login", 25, "Work Session", false),
+            entry("feature/login", 50, "Work Session", false),
+            entry("feature/login", 10, "Work Session", true),
+            entry("feature/login", 5, "Short Break", false),
+            entry("main", 25, "Work Session", false),
+        ];
+        let config = Config::default();
+        let line = trailer(&history, "feature/login", &config).unwrap();
+        assert_eq!(line, "Pomodoros: 2 (75m)");
+        assert_eq!(trailer(&history, "fix-crash", &config), None);
+
+        let message = "Add login form\n\n# Please enter the commit message for your changes.\n# On branch feature/login\n";
+        let updated = append_trailer(message, &line).unwrap();
+        assert_eq!(
+            updated,
+            "Add login form\n\nPomodoros: 2 (75m)\n\n# Please enter the commit message for your changes.\n# On branch feature/login\n"
+        );
+        // Once is enough
+        assert_eq!(append_trailer(&updated, &line), None);
+        assert_eq!(append_trailer("Fix typo\n", &line).unwrap(), "Fix typo\n\nPomodoros: 2 (75m)\n");
+    }
+
+    #[test]
+    fn matches_branches_cut_to_the_label_length() {
+        let config = Config { label_max_length: 12, label_forbidden_chars: "#".to_string(), ..Config::default() };
+        let branch = "feature/#42-login-form";
+        let history = vec![entry(&config.clean_label(branch), 25, "Work Session", false)];
+        assert_eq!(trailer(&history, branch, &config).as_deref(), Some("Pomodoros: 1 (25m)"));
+    }
+}
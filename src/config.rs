@@ -1,9 +1,115 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::feed::FeedFormat;
+use crate::milestones::Milestone;
+use crate::schedule::WorkSchedule;
+use crate::state::HISTORY_SESSION_TYPES;
+use crate::strict;
+use crate::theme::{parse_hex, ThemePreference};
+
+/// Longest allowed session or break, in minutes (8 hours)
+pub const MAX_DURATION_MINUTES: u32 = 480;
+
+/// Most work sessions allowed before a long break
+pub const MAX_SESSIONS_UNTIL_LONG_BREAK: u32 = 24;
+
+/// How a finished session is announced
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlarmMode {
+    /// The notification and its sound
+    #[default]
+    Once,
+    /// Also a chime that repeats, and a banner, until dismissed or the next session starts
+    UntilDismissed,
+}
+
+/// When to go easy on the battery
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatterySaver {
+    /// Never
+    #[default]
+    Off,
+    /// While the computer runs on battery
+    Auto,
+    /// All the time, plugged in or not
+    Always,
+}
+
+/// What's thrown over the window when a session completes
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfettiStyle {
+    /// Scraps falling from the top
+    #[default]
+    Confetti,
+    /// Sparks bursting out of a few points
+    Fireworks,
+}
+
+/// A named session plan picked from the timer menu. Durations left out are taken
+/// from the rest of the config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Template {
+    pub name: String,
+    pub work_duration: Option<u32>,
+    pub short_break_duration: Option<u32>,
+    pub long_break_duration: Option<u32>,
+    pub sessions_until_long_break: Option<u32>,
+    /// Labels for the next work sessions, one session each
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Focus sound while the template is picked
+    pub ambient_sound: Option<String>,
+}
+
+impl Template {
+    /// The config with this template's choices in place of its own
+    pub fn apply(&self, config: &Config) -> Config {
+        Config {
+            work_duration: self.work_duration.unwrap_or(config.work_duration),
+            short_break_duration: self.short_break_duration.unwrap_or(config.short_break_duration),
+            long_break_duration: self.long_break_duration.unwrap_or(config.long_break_duration),
+            sessions_until_long_break: self.sessions_until_long_break.unwrap_or(config.sessions_until_long_break),
+            ambient_sound: self.ambient_sound.clone().or_else(|| config.ambient_sound.clone()),
+            ..config.clone()
+        }
+    }
+}
+
+/// A named break length offered when a break starts, such as a walk or lunch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakPreset {
+    pub name: String,
+    /// Minutes
+    pub duration: u32,
+}
+
+/// A one-off countdown offered in the ⋮ menu, apart from the pomodoro cycle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickTimerPreset {
+    pub name: String,
+    /// Minutes
+    pub duration: u32,
+}
+
+/// A config value that is allowed but probably not what the user meant
+#[derive(Debug, Clone)]
+pub struct ConfigWarning {
+    pub message: String,
+    pub hint: String,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     /// Work session duration in minutes
     pub work_duration: u32,
@@ -17,14 +123,238 @@ pub struct Config {
     /// Number of work sessions before a long break
     pub sessions_until_long_break: u32,
 
+    /// Break lengths in minutes after the 1st, 2nd, 3rd... work session completed
+    /// in a row, e.g. [5, 5, 10, 20], in place of the short and long breaks. The
+    /// last one is the long break that ends the cycle; a work session skipped or
+    /// cut short starts the count over.
+    pub break_durations: Vec<u32>,
+
     /// Enable desktop notifications
     pub enable_notifications: bool,
 
+    /// Send completion notifications with critical urgency so they stay until dismissed
+    pub persistent_notifications: bool,
+
+    /// Show a live notification with the remaining time, updated every minute (Linux)
+    pub progress_notifications: bool,
+
+    /// Points in a work session to announce: "halfway", a percentage done like "75%",
+    /// or minutes left like "5m". Empty announces nothing.
+    pub milestones: Vec<String>,
+
+    /// Read milestones out loud as well as notifying
+    pub speak_milestones: bool,
+
+    /// "once", or "until_dismissed" to repeat a chime and show a banner until you react
+    pub alarm_mode: AlarmMode,
+
+    /// Turn other apps' audio down while the alarm rings (PulseAudio/PipeWire, macOS music players)
+    pub duck_audio: bool,
+
     /// Auto-start breaks after work completes
     pub auto_start_breaks: bool,
 
     /// Auto-start work after breaks complete
     pub auto_start_work: bool,
+
+    /// Color theme: "system", "light", "dark" or "time" (dark from 7pm to 7am)
+    pub theme: ThemePreference,
+
+    /// Start with labels hidden in the window and notifications (toggle with P)
+    pub privacy_mode: bool,
+
+    /// Longest label, in characters
+    pub label_max_length: u32,
+
+    /// Characters left out of labels, e.g. ",;" to keep them out of the way in exports
+    pub label_forbidden_chars: String,
+
+    /// Encrypt the saved state and history with a passphrase asked for at startup
+    pub encrypt_state: bool,
+
+    /// Sessions kept in History, the most recent ones; 0 keeps them all
+    pub history_limit: u32,
+
+    /// Drop History entries older than this many days. The first time,
+    /// the app asks before deleting anything.
+    pub history_max_age_days: Option<u32>,
+
+    /// Display to open the window on: a number counting from 1, the display's UUID,
+    /// "cursor" for the one with the mouse pointer, or "focused" for the one with the
+    /// window in front. Unset uses the main display.
+    pub display: Option<String>,
+
+    /// Display the presentation mode goes fullscreen on, like `display`. Unset uses
+    /// the timer's display.
+    pub presentation_display: Option<String>,
+
+    /// MQTT broker to publish the timer state to, e.g. "mqtt://homeassistant.local:1883"
+    pub mqtt_broker: Option<String>,
+
+    /// Prefix of the MQTT topics: <prefix>/state, <prefix>/event and <prefix>/status
+    pub mqtt_topic_prefix: String,
+
+    /// MQTT credentials, if the broker needs them
+    pub mqtt_username: Option<String>,
+    pub mqtt_password: Option<String>,
+
+    /// Announce the timer to Home Assistant through MQTT discovery
+    pub mqtt_discovery: bool,
+
+    /// Serve a control page for phones on the local network, linked by a QR code in Settings
+    pub web_remote: bool,
+
+    /// Port of the phone remote
+    pub web_remote_port: u16,
+
+    /// Team group on the local network to run in step with, e.g. "office"
+    pub lan_group: Option<String>,
+
+    /// Lead the group: the others follow this timer
+    pub lan_leader: bool,
+
+    /// UDP port the group leader listens on
+    pub lan_port: u16,
+
+    /// Note which app is in front during work sessions, shown per session in History
+    pub track_apps: bool,
+
+    /// Suggest the title of the window in front as the label when a work session
+    /// starts without one. Off by default since titles can be private.
+    pub label_from_window: bool,
+
+    /// Git checkout whose current branch is offered as the label when a work session
+    /// starts without one, ahead of the window title
+    pub git_repository: Option<String>,
+
+    /// Apps that count as distractions, e.g. ["Slack", "Discord"]. Matched
+    /// case-insensitively against part of the app name.
+    pub distracting_apps: Vec<String>,
+
+    /// Flag a work session once distracting apps were in front for longer than this, in minutes
+    pub distraction_threshold_minutes: u32,
+
+    /// Count sessions by weekday and hour, and interruptions, for the Usage
+    /// page in Stats. Kept on this computer only.
+    pub usage_stats: bool,
+
+    /// Keep a feed of the latest completed sessions for other tools to poll: "json"
+    /// (JSON Feed) or "atom". Written to sessions.json or sessions.atom in the data
+    /// directory each time a session completes. Unset writes none.
+    pub session_feed: Option<FeedFormat>,
+
+    /// Where to write the session feed instead, e.g. into a static site's folder
+    pub session_feed_path: Option<String>,
+
+    /// Log completed breaks as mindfulness minutes in mindfulness.csv in the data
+    /// directory, to import into Apple Health or Google Fit. Needs a build with the
+    /// health feature.
+    pub mindfulness_export: bool,
+
+    /// Suggest a long break once this many minutes of focus have built up since
+    /// the last one, however the sessions were started; 0 never does
+    pub long_break_after_focus_minutes: u32,
+
+    /// Take that long break instead of suggesting it
+    pub force_long_break: bool,
+
+    /// Strict classic mode: work sessions are 25 minutes whatever work_duration
+    /// says, and one that is paused, stopped or skipped is void and doesn't
+    /// count. Pausing asks what interrupted it first.
+    pub strict_mode: bool,
+
+    /// Nudge once a day when fewer than this percent of breaks over the last
+    /// week were taken in full; 0 never nudges
+    pub break_nudge_below: u32,
+
+    /// Focus hours to aim for each week (Monday to Sunday), shown with a pace in Stats; 0 for none
+    pub weekly_goal_hours: u32,
+
+    /// Focus hours to aim for each calendar month; 0 for none
+    pub monthly_goal_hours: u32,
+
+    /// Kinds of shallow work a focus session can be started as from the new-timer
+    /// dialog. They're recorded as the session type and kept out of deep-work stats.
+    pub session_categories: Vec<String>,
+
+    /// Shell command run when a work session starts counting down, e.g. to turn on a site blocker
+    pub block_command: Option<String>,
+
+    /// Shell command run when it stops (pause, break, reset, quit) to lift the block
+    pub unblock_command: Option<String>,
+
+    /// Calendar to pause for meetings: an .ics file, an .ics link, or a CalDAV calendar URL
+    pub calendar: Option<String>,
+
+    /// Credentials for the calendar server, if it needs them
+    pub calendar_username: Option<String>,
+    pub calendar_password: Option<String>,
+
+    /// Sound played during work sessions: "brown", "pink", "white", or an audio file in
+    /// the sounds folder next to this file. Unset plays nothing.
+    pub ambient_sound: Option<String>,
+
+    /// Volume of the focus sound, from 0 to 100
+    pub ambient_volume: u32,
+
+    /// Soft tick every second through the last minute of a work session
+    pub tick_sound: bool,
+
+    /// Go easy on the battery: animations hold still and the countdown is saved
+    /// less often. "auto" does it while on battery, "always" all the time.
+    pub battery_saver: BatterySaver,
+
+    /// Also keep the focus sound quiet while battery_saver is in effect
+    pub battery_saver_mutes_sound: bool,
+
+    /// Keep the window still: no pulse in the last seconds, no breathing celebration or confetti
+    pub reduce_motion: bool,
+
+    /// Fade the window out once a work session has run for auto_hide_after seconds, and
+    /// bring it back when the session ends or pauses. Hovering it shows it meanwhile.
+    pub auto_hide: bool,
+
+    /// Seconds into a work session before the window fades with auto_hide
+    pub auto_hide_after: u32,
+
+    /// Celebration when a session completes: "confetti" or "fireworks"
+    pub confetti_style: ConfettiStyle,
+
+    /// How many scraps or sparks, 0 for none
+    pub confetti_count: u32,
+
+    /// How long they fly, in seconds
+    pub confetti_seconds: u32,
+
+    /// Their colors as hex, e.g. ["#ef4444", "#facc15"]. Empty uses the theme's session colors.
+    pub confetti_colors: Vec<String>,
+
+    /// Time the workday ends (HH:MM). The timer counts down to it, and no work session
+    /// is started by auto_start_work that would run past it.
+    pub hard_stop: Option<String>,
+
+    /// Dates (YYYY-MM-DD) auto_start_work never starts a work session on, such as holidays
+    pub days_off: Vec<String>,
+
+    /// Hours auto_start_work may start a work session in, per weekday: mon = "09:00-17:30".
+    /// Weekdays left out are days off; with none listed any time is fine.
+    pub work_hours: BTreeMap<String, String>,
+
+    /// Session plans to pick from, as [[templates]] tables
+    pub templates: Vec<Template>,
+
+    /// Project folders with a .pomodoro.toml, offered in the timer menu like templates
+    pub projects: Vec<String>,
+
+    /// Break lengths to pick from while on a break, as [[break_presets]] tables
+    pub break_presets: Vec<BreakPreset>,
+
+    /// One-off timers to start from the ⋮ menu, such as tea, as [[quick_timers]] tables
+    pub quick_timers: Vec<QuickTimerPreset>,
+
+    /// Plans read from the projects' .pomodoro.toml files at startup; never saved here
+    #[serde(skip)]
+    pub project_templates: Vec<Template>,
 }
 
 impl Default for Config {
@@ -34,23 +364,104 @@ impl Default for Config {
             short_break_duration: 5,
             long_break_duration: 15,
             sessions_until_long_break: 4,
+            break_durations: Vec::new(),
             enable_notifications: true,
+            persistent_notifications: false,
+            progress_notifications: false,
+            milestones: Vec::new(),
+            speak_milestones: false,
+            alarm_mode: AlarmMode::Once,
+            duck_audio: false,
             auto_start_breaks: false,
             auto_start_work: false,
+            theme: ThemePreference::System,
+            privacy_mode: false,
+            label_max_length: 30,
+            label_forbidden_chars: String::new(),
+            encrypt_state: false,
+            history_limit: 50,
+            history_max_age_days: None,
+            display: None,
+            presentation_display: None,
+            mqtt_broker: None,
+            mqtt_topic_prefix: "pomodoro".to_string(),
+            mqtt_username: None,
+            mqtt_password: None,
+            mqtt_discovery: false,
+            web_remote: false,
+            web_remote_port: 8787,
+            lan_group: None,
+            lan_leader: false,
+            lan_port: 47474,
+            track_apps: false,
+            label_from_window: false,
+            git_repository: None,
+            distracting_apps: Vec::new(),
+            distraction_threshold_minutes: 5,
+            usage_stats: false,
+            session_feed: None,
+            session_feed_path: None,
+            mindfulness_export: false,
+            long_break_after_focus_minutes: 0,
+            force_long_break: false,
+            strict_mode: false,
+            break_nudge_below: 50,
+            weekly_goal_hours: 0,
+            monthly_goal_hours: 0,
+            session_categories: vec!["Meeting".to_string(), "Admin".to_string(), "Learning".to_string()],
+            block_command: None,
+            unblock_command: None,
+            calendar: None,
+            calendar_username: None,
+            calendar_password: None,
+            ambient_sound: None,
+            ambient_volume: 40,
+            tick_sound: false,
+            battery_saver: BatterySaver::Off,
+            battery_saver_mutes_sound: false,
+            reduce_motion: false,
+            auto_hide: false,
+            auto_hide_after: 5,
+            confetti_style: ConfettiStyle::Confetti,
+            confetti_count: 60,
+            confetti_seconds: 3,
+            confetti_colors: Vec::new(),
+            hard_stop: None,
+            days_off: Vec::new(),
+            work_hours: BTreeMap::new(),
+            templates: Vec::new(),
+            projects: Vec::new(),
+            break_presets: Vec::new(),
+            quick_timers: vec![
+                QuickTimerPreset { name: "Tea".to_string(), duration: 3 },
+                QuickTimerPreset { name: "Standup".to_string(), duration: 15 },
+            ],
+            project_templates: Vec::new(),
         }
     }
 }
 
 impl Config {
+    #[cfg(not(windows))]
     pub fn config_dir() -> Result<PathBuf> {
         let home = std::env::var("HOME").context("HOME environment variable not set")?;
         Ok(PathBuf::from(home).join(".config/pomodoro-timer"))
     }
 
+    #[cfg(windows)]
+    pub fn config_dir() -> Result<PathBuf> {
+        let app_data = std::env::var("APPDATA").context("APPDATA environment variable not set")?;
+        Ok(PathBuf::from(app_data).join("pomodoro-timer"))
+    }
+
     pub fn config_path() -> Result<PathBuf> {
         Ok(Self::config_dir()?.join("config.toml"))
     }
 
+    pub fn exists() -> bool {
+        Self::config_path().map(|path| path.exists()).unwrap_or(false)
+    }
+
     pub fn load() -> Result<Self> {
         let config_path = Self::config_path()?;
 
@@ -86,18 +497,112 @@ impl Config {
     }
 
     pub fn work_duration_secs(&self) -> u32 {
+        if self.strict_mode {
+            return strict::CLASSIC_WORK_SECS;
+        }
         self.work_duration * 60
     }
 
     pub fn short_break_duration_secs(&self) -> u32 {
-        self.short_break_duration * 60
+        self.break_durations.first().unwrap_or(&self.short_break_duration) * 60
     }
 
     pub fn long_break_duration_secs(&self) -> u32 {
-        self.long_break_duration * 60
+        self.break_durations.last().unwrap_or(&self.long_break_duration) * 60
+    }
+
+    // Work sessions in a cycle, up to and including the one before the long break
+    pub fn cycle_length(&self) -> u32 {
+        match self.break_durations.len() {
+            0 => self.sessions_until_long_break,
+            len => len as u32,
+        }
+    }
+
+    // The break after `streak` work sessions completed in a row, from
+    // break_durations. None when that isn't set.
+    pub fn grown_break_secs(&self, streak: u32) -> Option<u32> {
+        let index = (streak.max(1) as usize - 1).min(self.break_durations.len().checked_sub(1)?);
+        Some(self.break_durations[index] * 60)
+    }
+
+    pub fn template(&self, name: &str) -> Option<&Template> {
+        self.all_templates().find(|template| template.name == name)
+    }
+
+    /// The configured templates, then the projects'
+    pub fn all_templates(&self) -> impl Iterator<Item = &Template> {
+        self.templates.iter().chain(&self.project_templates)
+    }
+
+    /// Offer a project's plan next to the templates, once it's checked like one
+    pub fn add_project(&mut self, template: Template) -> Result<()> {
+        if self.template(&template.name).is_some() {
+            anyhow::bail!("There's already a template named \"{}\"", template.name);
+        }
+        template.apply(self).validate_durations()?;
+        self.project_templates.push(template);
+        Ok(())
+    }
+
+    /// A label as it's kept, whether typed, sent by a script or link, or taken
+    /// from a template: trimmed, without forbidden characters, cut to length
+    pub fn clean_label(&self, label: &str) -> String {
+        let allowed: String = label.chars().filter(|c| !self.label_forbidden_chars.contains(*c)).collect();
+        allowed.trim().chars().take(self.label_max_length as usize).collect::<String>().trim_end().to_string()
     }
 
     pub fn validate(&self) -> Result<()> {
+        self.validate_durations()?;
+        if self.label_max_length == 0 {
+            anyhow::bail!("label_max_length must be at least 1");
+        }
+        WorkSchedule::from_config(self)?;
+        for (index, template) in self.templates.iter().enumerate() {
+            if template.name.trim().is_empty() {
+                anyhow::bail!("Template {} has no name", index + 1);
+            }
+            if self.templates[..index].iter().any(|other| other.name == template.name) {
+                anyhow::bail!("There are two templates named \"{}\"", template.name);
+            }
+            template
+                .apply(self)
+                .validate_durations()
+                .with_context(|| format!("In template \"{}\"", template.name))?;
+        }
+        if self.history_max_age_days == Some(0) {
+            anyhow::bail!("history_max_age_days must be at least 1");
+        }
+        for preset in &self.break_presets {
+            if preset.name.trim().is_empty() {
+                anyhow::bail!("A break preset has no name");
+            }
+            if !(1..=MAX_DURATION_MINUTES).contains(&preset.duration) {
+                anyhow::bail!(
+                    "Break preset \"{}\" must last 1 to {} minutes (got {})",
+                    preset.name,
+                    MAX_DURATION_MINUTES,
+                    preset.duration
+                );
+            }
+        }
+        for preset in &self.quick_timers {
+            if preset.name.trim().is_empty() {
+                anyhow::bail!("A quick timer has no name");
+            }
+            if !(1..=MAX_DURATION_MINUTES).contains(&preset.duration) {
+                anyhow::bail!(
+                    "Quick timer \"{}\" must last 1 to {} minutes (got {})",
+                    preset.name,
+                    MAX_DURATION_MINUTES,
+                    preset.duration
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_durations(&self) -> Result<()> {
         if self.work_duration == 0 {
             anyhow::bail!("Work duration must be greater than 0");
         }
@@ -110,6 +615,183 @@ impl Config {
         if self.sessions_until_long_break == 0 {
             anyhow::bail!("Sessions until long break must be greater than 0");
         }
+
+        let durations = [
+            ("Work duration", self.work_duration),
+            ("Short break duration", self.short_break_duration),
+            ("Long break duration", self.long_break_duration),
+        ];
+        for (name, minutes) in durations {
+            if minutes > MAX_DURATION_MINUTES {
+                anyhow::bail!(
+                    "{} must be at most {} minutes (got {})",
+                    name,
+                    MAX_DURATION_MINUTES,
+                    minutes
+                );
+            }
+        }
+        if self.sessions_until_long_break > MAX_SESSIONS_UNTIL_LONG_BREAK {
+            anyhow::bail!(
+                "Sessions until long break must be at most {} (got {})",
+                MAX_SESSIONS_UNTIL_LONG_BREAK,
+                self.sessions_until_long_break
+            );
+        }
+        if let Some(minutes) = self.break_durations.iter().find(|minutes| !(1..=MAX_DURATION_MINUTES).contains(minutes)) {
+            anyhow::bail!("Break durations must be between 1 and {} minutes (got {})", MAX_DURATION_MINUTES, minutes);
+        }
+        if self.break_durations.len() > MAX_SESSIONS_UNTIL_LONG_BREAK as usize {
+            anyhow::bail!(
+                "Break durations can list at most {} breaks (got {})",
+                MAX_SESSIONS_UNTIL_LONG_BREAK,
+                self.break_durations.len()
+            );
+        }
         Ok(())
     }
+
+    pub fn warnings(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+
+        if self.long_break_duration < self.short_break_duration {
+            warnings.push(ConfigWarning {
+                message: format!(
+                    "Long break ({} min) is shorter than short break ({} min)",
+                    self.long_break_duration, self.short_break_duration
+                ),
+                hint: "Swap long_break_duration and short_break_duration".to_string(),
+            });
+        }
+        if self.short_break_duration >= self.work_duration {
+            warnings.push(ConfigWarning {
+                message: format!(
+                    "Short break ({} min) is not shorter than work ({} min)",
+                    self.short_break_duration, self.work_duration
+                ),
+                hint: "Durations are in minutes; breaks are usually a fraction of work".to_string(),
+            });
+        }
+        if self.work_duration > 180 {
+            warnings.push(ConfigWarning {
+                message: format!("Work duration of {} min is unusually long", self.work_duration),
+                hint: "Focus sessions of 25-90 minutes work best".to_string(),
+            });
+        }
+        if self.strict_mode && self.work_duration * 60 != strict::CLASSIC_WORK_SECS {
+            warnings.push(ConfigWarning {
+                message: format!("work_duration ({} min) is ignored in strict mode", self.work_duration),
+                hint: format!("Strict mode always runs {}-minute pomodoros", strict::CLASSIC_WORK_SECS / 60),
+            });
+        }
+        if self.sessions_until_long_break > 12 {
+            warnings.push(ConfigWarning {
+                message: format!(
+                    "Long break only every {} sessions",
+                    self.sessions_until_long_break
+                ),
+                hint: "The classic technique uses 4".to_string(),
+            });
+        }
+        if let Some(category) = self
+            .session_categories
+            .iter()
+            .find(|category| category.trim().is_empty() || HISTORY_SESSION_TYPES.contains(&category.as_str()))
+        {
+            warnings.push(ConfigWarning {
+                message: format!("Session category \"{}\" can't be told apart from work and breaks", category),
+                hint: "Name categories something like \"Meeting\" or \"Admin\"".to_string(),
+            });
+        }
+        if let Some(repo) = self.git_repository.as_deref().filter(|repo| crate::git::git_dir(Path::new(repo)).is_none()) {
+            warnings.push(ConfigWarning {
+                message: format!("git_repository \"{}\" isn't a git checkout", repo),
+                hint: "Give the folder that holds .git; its branch can't be offered as the label".to_string(),
+            });
+        }
+        if let Some(e) = self.milestones.iter().find_map(|milestone| Milestone::parse(milestone).err()) {
+            warnings.push(ConfigWarning {
+                message: format!("Milestone {:#}", e),
+                hint: "It's left out; write milestones like \"halfway\", \"75%\" or \"5m\"".to_string(),
+            });
+        }
+        if let Some(color) = self.confetti_colors.iter().find(|color| parse_hex(color).is_none()) {
+            warnings.push(ConfigWarning {
+                message: format!("Confetti color \"{}\" isn't a hex color", color),
+                hint: "Write colors like \"#ef4444\"".to_string(),
+            });
+        }
+        if self.confetti_count > crate::ui::particles::MAX_PARTICLES {
+            warnings.push(ConfigWarning {
+                message: format!("{} confetti is more than the window draws", self.confetti_count),
+                hint: format!("Up to {} are thrown", crate::ui::particles::MAX_PARTICLES),
+            });
+        }
+        if self.duck_audio && self.alarm_mode != AlarmMode::UntilDismissed {
+            warnings.push(ConfigWarning {
+                message: "duck_audio does nothing with alarm_mode = \"once\"".to_string(),
+                hint: "Other apps are turned down while the repeating chime rings; set alarm_mode = \"until_dismissed\"".to_string(),
+            });
+        }
+        if self.session_feed.is_some() && self.encrypt_state {
+            warnings.push(ConfigWarning {
+                message: "The session feed isn't encrypted".to_string(),
+                hint: "Its labels are in plain text even with encrypt_state; unset session_feed to keep them private".to_string(),
+            });
+        }
+        if self.mindfulness_export && !cfg!(feature = "health") {
+            warnings.push(ConfigWarning {
+                message: "mindfulness_export is on, but this build leaves it out".to_string(),
+                hint: "Build with `cargo build --release --features health` to log breaks".to_string(),
+            });
+        }
+        if self.mqtt_broker.as_deref().is_some_and(|broker| broker.starts_with("mqtts://")) {
+            warnings.push(ConfigWarning {
+                message: "MQTT over TLS (mqtts://) is not supported".to_string(),
+                hint: "Use a plain mqtt:// listener on the local network".to_string(),
+            });
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_in_from_the_config() {
+        let template: Template = toml::from_str(
+            "name = \"Deep Work\"\nwork_duration = 50\nshort_break_duration = 10\nsessions_until_long_break = 3",
+        )
+        .unwrap();
+        let config = Config { templates: vec![template.clone()], ..Config::default() };
+        let applied = template.apply(&config);
+        assert_eq!((applied.work_duration, applied.short_break_duration), (50, 10));
+        assert_eq!(applied.sessions_until_long_break, 3);
+        assert_eq!(applied.long_break_duration, config.long_break_duration);
+        assert!(config.validate().is_ok());
+
+        let too_long = Template { name: "Marathon".into(), work_duration: Some(600), ..Template::default() };
+        let config = Config { templates: vec![too_long], ..Config::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn labels_keep_to_the_rules() {
+        let config = Config { label_max_length: 8, label_forbidden_chars: ",;".to_string(), ..Config::default() };
+        assert_eq!(config.clean_label("  Email, then report "), "Email th");
+        assert_eq!(config.clean_label("Fix bug "), "Fix bug");
+        assert_eq!(config.clean_label(";"), "");
+        assert!(Config { label_max_length: 0, ..Config::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn ducking_needs_the_repeating_alarm() {
+        let ducks = |config: Config| config.warnings().iter().any(|warning| warning.message.starts_with("duck_audio"));
+        assert!(ducks(Config { duck_audio: true, ..Config::default() }));
+        assert!(!ducks(Config { duck_audio: true, alarm_mode: AlarmMode::UntilDismissed, ..Config::default() }));
+        assert!(!ducks(Config::default()));
+    }
 }
@@ -0,0 +1,288 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, MAX_DURATION_MINUTES, MAX_SESSIONS_UNTIL_LONG_BREAK};
+use crate::persistence::{Persistence, PASSPHRASE_ENV};
+use crate::sound;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+struct Finding {
+    severity: Severity,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Finding {
+    fn ok(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Ok, message: message.into(), fix: None }
+    }
+
+    fn warning(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, message: message.into(), fix: Some(fix.into()) }
+    }
+
+    fn error(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, message: message.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Runs `pomodoro-timer doctor`, printing findings and returning the process exit code
+pub fn run() -> i32 {
+    let mut findings = Vec::new();
+
+    if let Some(config) = check_config_file(&mut findings) {
+        check_config_values(&config, &mut findings);
+        check_sounds(&config, &mut findings);
+    }
+    check_state_file(&mut findings);
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    for finding in &findings {
+        let tag = match finding.severity {
+            Severity::Ok => "ok",
+            Severity::Warning => {
+                warnings += 1;
+                "warn"
+            }
+            Severity::Error => {
+                errors += 1;
+                "error"
+            }
+        };
+        println!("[{}] {}", tag, finding.message);
+        if let Some(fix) = &finding.fix {
+            println!("       fix: {}", fix);
+        }
+    }
+
+    println!();
+    println!("{} error(s), {} warning(s)", errors, warnings);
+    if errors > 0 { 1 } else { 0 }
+}
+
+// Parse the config file strictly, reporting syntax errors and unknown keys.
+// Returns the parsed config when it can be used for value checks.
+fn check_config_file(findings: &mut Vec<Finding>) -> Option<Config> {
+    let config_path = match Config::config_path() {
+        Ok(path) => path,
+        Err(e) => {
+            findings.push(Finding::error(
+                format!("Cannot locate config file: {}", e),
+                "Set the HOME environment variable",
+            ));
+            return None;
+        }
+    };
+
+    if !config_path.exists() {
+        findings.push(Finding::warning(
+            format!("No config file at {}", config_path.display()),
+            "Launch the app once to run the setup, or defaults will be used",
+        ));
+        return Some(Config::default());
+    }
+
+    let content = match fs::read_to_string(&config_path) {
+        Ok(content) => content,
+        Err(e) => {
+            findings.push(Finding::error(
+                format!("Cannot read {}: {}", config_path.display(), e),
+                "Check the file permissions",
+            ));
+            return None;
+        }
+    };
+
+    check_config_text(&content, &config_path, findings)
+}
+
+fn check_config_text(content: &str, config_path: &Path, findings: &mut Vec<Finding>) -> Option<Config> {
+    let table: toml::Table = match toml::from_str(content) {
+        Ok(table) => table,
+        Err(e) => {
+            findings.push(Finding::error(
+                format!("Config file is not valid TOML: {}", e),
+                format!("Fix the syntax or delete {} to regenerate it", config_path.display()),
+            ));
+            return None;
+        }
+    };
+
+    // Keys the current Config understands, taken from its serialized default.
+    // JSON keeps the unset ones as null, where TOML would leave them out.
+    let known_keys = serde_json::to_value(Config::default())
+        .ok()
+        .and_then(|defaults| defaults.as_object().map(|fields| fields.keys().cloned().collect::<Vec<_>>()))
+        .unwrap_or_default();
+    for key in table.keys().filter(|key| !known_keys.contains(key)) {
+        findings.push(Finding::warning(
+            format!("Unknown config key `{}` is ignored", key),
+            format!("Remove it or check for a typo (known keys: {})", known_keys.join(", ")),
+        ));
+    }
+
+    match toml::from_str::<Config>(content) {
+        Ok(config) => {
+            findings.push(Finding::ok(format!("Config file parsed: {}", config_path.display())));
+            Some(config)
+        }
+        Err(e) => {
+            findings.push(Finding::error(
+                format!("Config values have the wrong type: {}", e),
+                "Durations and counts must be whole numbers, flags must be true/false",
+            ));
+            None
+        }
+    }
+}
+
+fn check_config_values(config: &Config, findings: &mut Vec<Finding>) {
+    if let Err(e) = config.validate() {
+        findings.push(Finding::error(
+            format!("Config validation failed: {}", e),
+            format!(
+                "Durations must be between 1 and {} minutes, sessions_until_long_break between 1 and {}",
+                MAX_DURATION_MINUTES, MAX_SESSIONS_UNTIL_LONG_BREAK
+            ),
+        ));
+        return;
+    }
+
+    let warnings = config.warnings();
+    if warnings.is_empty() {
+        findings.push(Finding::ok("Config values are valid"));
+    }
+    for warning in warnings {
+        findings.push(Finding::warning(warning.message, warning.hint));
+    }
+}
+
+// Focus sound files named in the config, and in its templates. The completion
+// sound is the system sound theme's, so there's no file of ours to check.
+fn check_sounds(config: &Config, findings: &mut Vec<Finding>) {
+    let sounds = config
+        .ambient_sound
+        .iter()
+        .map(|sound| ("ambient_sound".to_string(), sound))
+        .chain(config.templates.iter().filter_map(|template| {
+            let sound = template.ambient_sound.as_ref()?;
+            Some((format!("ambient_sound of template \"{}\"", template.name), sound))
+        }));
+    for (setting, sound) in sounds {
+        match sound::check_sound(sound) {
+            Ok(()) => findings.push(Finding::ok(format!("Focus sound `{}` plays", sound))),
+            Err(e) => findings.push(Finding::warning(
+                format!("Focus sound `{}` in {} can't be played: {:#}", sound, setting, e),
+                match sound::sounds_dir() {
+                    Ok(dir) => format!("Put the file in {} or use brown, pink or white", dir.display()),
+                    Err(_) => "Use brown, pink or white".to_string(),
+                },
+            )),
+        }
+    }
+}
+
+fn check_state_file(findings: &mut Vec<Finding>) {
+    let state_path = match Persistence::state_path() {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+
+    if !state_path.exists() {
+        findings.push(Finding::ok("No saved timer state yet"));
+        return;
+    }
+
+    if Persistence::is_encrypted() {
+        match Persistence::unlock_from_env(false) {
+            Ok(true) => {}
+            Ok(false) => {
+                findings.push(Finding::ok(format!(
+                    "State file is encrypted (set {} to check its contents)",
+                    PASSPHRASE_ENV
+                )));
+                return;
+            }
+            Err(e) => {
+                findings.push(Finding::error(
+                    format!("Cannot decrypt the state file: {:#}", e),
+                    format!("Check the passphrase in {}", PASSPHRASE_ENV),
+                ));
+                return;
+            }
+        }
+    }
+
+    match Persistence::load() {
+        Ok(_) => findings.push(Finding::ok(format!("State file loads: {}", state_path.display()))),
+        Err(e) => findings.push(Finding::error(
+            format!("State file cannot be loaded: {:#}", e),
+            format!("Delete {} to start with a fresh state", state_path.display()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(content: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        if let Some(config) = check_config_text(content, Path::new("config.toml"), &mut findings) {
+            check_config_values(&config, &mut findings);
+            check_sounds(&config, &mut findings);
+        }
+        findings
+    }
+
+    fn problems(findings: &[Finding]) -> Vec<(Severity, &str)> {
+        findings
+            .iter()
+            .filter(|finding| finding.severity != Severity::Ok)
+            .map(|finding| (finding.severity, finding.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reports_broken_configs() {
+        let findings = check("work_duration = 25\nshort_break_duration = ");
+        assert!(matches!(problems(&findings)[..], [(Severity::Error, message)] if message.starts_with("Config file is not valid TOML")));
+
+        let findings = check("work_duration = 25\nwork_durration = 50\n");
+        assert!(matches!(problems(&findings)[..], [(Severity::Warning, "Unknown config key `work_durration` is ignored")]));
+
+        let findings = check("work_duration = 0\n");
+        assert!(matches!(problems(&findings)[..], [(Severity::Error, message)] if message.starts_with("Config validation failed")));
+
+        let findings = check("work_duration = \"long\"\n");
+        assert!(matches!(problems(&findings)[..], [(Severity::Error, message)] if message.starts_with("Config values have the wrong type")));
+    }
+
+    #[test]
+    fn reports_focus_sounds_that_cant_play() {
+        let missing = std::env::temp_dir().join("pomodoro-doctor-test").join("rain.ogg");
+        let findings = check(&format!(
+            "ambient_sound = \"brown\"\n[[templates]]\nname = \"Deep\"\nambient_sound = {:?}\n",
+            missing.display().to_string()
+        ));
+        let found = problems(&findings);
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(found[0].0, Severity::Warning);
+        assert!(found[0].1.contains("in ambient_sound of template \"Deep\" can't be played"));
+        assert!(findings.iter().any(|finding| finding.message == "Focus sound `brown` plays"));
+
+        // A file that's there but isn't audio
+        let junk = std::env::temp_dir().join(format!("pomodoro-doctor-{}.ogg", std::process::id()));
+        fs::write(&junk, "not audio").unwrap();
+        let findings = check(&format!("ambient_sound = {:?}\n", junk.display().to_string()));
+        let _ = fs::remove_file(&junk);
+        assert!(matches!(problems(&findings)[..], [(Severity::Warning, message)] if message.contains("can't be played")));
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+// Other apps play at this share of their volume while the alarm rings
+const DUCK_LEVEL: f32 = 0.2;
+
+// Music players macOS lets us turn down; CoreAudio has no per-app volume
+const PLAYERS: [&str; 2] = ["Music", "Spotify"];
+
+// An app's audio stream and the volume to put back
+enum Stream {
+    SinkInput { id: u32, volumes: Vec<u32> },  // PulseAudio/PipeWire, one volume per channel
+    Player { app: &'static str, volume: u32 },  // A macOS music player, 0 to 100
+}
+
+// Volumes lowered by duck(), until restore() puts them back
+pub struct Ducked(Vec<Stream>);
+
+#[derive(Debug, PartialEq)]
+struct SinkInput {
+    id: u32,
+    pid: Option<u32>,
+    volumes: Vec<u32>,
+}
+
+fn output(command: &mut Command) -> Result<String> {
+    let output = command.output().context("Failed to run the volume command")?;
+    if !output.status.success() {
+        bail!("The volume command failed ({})", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Streams in `pactl list sink-inputs`, with their raw channel volumes
+fn parse_sink_inputs(text: &str) -> Vec<SinkInput> {
+    let mut inputs: Vec<SinkInput> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(id) = line.strip_prefix("Sink Input #") {
+            if let Ok(id) = id.parse() {
+                inputs.push(SinkInput { id, pid: None, volumes: Vec::new() });
+            }
+            continue;
+        }
+        let Some(input) = inputs.last_mut() else {
+            continue;
+        };
+        if let Some(volume) = line.strip_prefix("Volume:") {
+            // front-left: 65536 / 100% / 0.00 dB,   front-right: 65536 / 100% / 0.00 dB
+            input.volumes = volume
+                .split(',')
+                .filter_map(|channel| channel.split_once(':')?.1.split('/').next()?.trim().parse().ok())
+                .collect();
+        } else if let Some(pid) = line.strip_prefix("application.process.id = ") {
+            input.pid = pid.trim_matches('"').parse().ok();
+        }
+    }
+    inputs
+}
+
+fn set_sink_input_volume(id: u32, volumes: &[u32]) -> Result<()> {
+    let mut pactl = Command::new("pactl");
+    pactl.args(["set-sink-input-volume", &id.to_string()]);
+    pactl.args(volumes.iter().map(u32::to_string));
+    output(&mut pactl).map(|_| ())
+}
+
+fn osascript(script: &str) -> Result<String> {
+    Ok(output(Command::new("osascript").args(["-e", script]))?.trim().to_string())
+}
+
+fn duck_players() -> Vec<Stream> {
+    if !cfg!(target_os = "macos") {
+        return Vec::new();
+    }
+    PLAYERS
+        .iter()
+        .filter(|app| osascript(&format!("application \"{}\" is running", app)).is_ok_and(|running| running == "true"))
+        .filter_map(|app| {
+            let volume: u32 = osascript(&format!("tell application \"{}\" to get sound volume", app)).ok()?.parse().ok()?;
+            let ducked = (volume as f32 * DUCK_LEVEL) as u32;
+            osascript(&format!("tell application \"{}\" to set sound volume to {}", app, ducked)).ok()?;
+            Some(Stream::Player { app, volume })
+        })
+        .collect()
+}
+
+// Turn down every other app's audio: each stream on PulseAudio or PipeWire,
+// the music players on macOS
+pub fn duck() -> Result<Ducked> {
+    let mut streams = duck_players();
+    if cfg!(target_os = "linux") {
+        // Untranslated output, since the field names are parsed
+        let listing = output(Command::new("pactl").env("LC_ALL", "C").args(["list", "sink-inputs"]))?;
+        let own_pid = std::process::id();
+        for input in parse_sink_inputs(&listing) {
+            if input.pid == Some(own_pid) || input.volumes.is_empty() {
+                continue;
+            }
+            let ducked: Vec<u32> = input.volumes.iter().map(|volume| (*volume as f32 * DUCK_LEVEL) as u32).collect();
+            // A stream may end in the meantime
+            if set_sink_input_volume(input.id, &ducked).is_ok() {
+                streams.push(Stream::SinkInput { id: input.id, volumes: input.volumes });
+            }
+        }
+    }
+    Ok(Ducked(streams))
+}
+
+impl Ducked {
+    // Streams that ended meanwhile are simply gone, so errors are ignored
+    pub fn restore(self) {
+        for stream in self.0 {
+            match stream {
+                Stream::SinkInput { id, volumes } => {
+                    let _ = set_sink_input_volume(id, &volumes);
+                }
+                Stream::Player { app, volume } => {
+                    let _ = osascript(&format!("tell application \"{}\" to set sound volume to {}", app, volume));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_stream_volumes_from_pactl() {
+        let listing = "Sink Input #42
+	Driver: PipeWire
+	Sink: 55
+	Mute: no
+	Volume: front-left: 65536 / 100% / 0.00 dB,   front-right: 32768 /  50% / -18.06 dB
+	        balance -1.00
+	Properties:
+		application.name = \"Firefox\"
+		application.process.id = \"1234\"
+
+Sink Input #43
+	Volume: mono: 45875 /  70% / -9.29 dB
+";
+        assert_eq!(
+            parse_sink_inputs(listing),
+            vec![
+                SinkInput { id: 42, pid: Some(1234), volumes: vec![65536, 32768] },
+                SinkInput { id: 43, pid: None, volumes: vec![45875] },
+            ]
+        );
+    }
+}
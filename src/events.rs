@@ -0,0 +1,80 @@
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use crate::notifications;
+use crate::state::{SessionInfo, TimerState};
+
+// What happened to the session. Handlers change the state and publish one of these;
+// the UI, persistence, and notifications react to them as subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    Started(TimerState),  // A work or break session began counting down
+    Tick,  // A second passed on a running timer
+    Paused,
+    Resumed,
+    Completed { state: TimerState, label: String },  // Ran to the end; the state is the one that finished
+    Skipped,
+    Stopped,  // Ended early; the next session waits in Idle
+    Reset,  // Restarted from its full length
+    Switched(TimerState),  // Turned from work into a break or back partway; the state is the new session's
+    Milestone(String),  // A work session reached a configured milestone; the text announces it
+    Changed,  // Any other edit: label, queue, mode, history
+}
+
+impl SessionEvent {
+    // Short name for scripts and other integrations
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionEvent::Started(_) => "started",
+            SessionEvent::Tick => "tick",
+            SessionEvent::Paused => "paused",
+            SessionEvent::Resumed => "resumed",
+            SessionEvent::Completed { .. } => "completed",
+            SessionEvent::Skipped => "skipped",
+            SessionEvent::Stopped => "stopped",
+            SessionEvent::Reset => "reset",
+            SessionEvent::Switched(_) => "switched",
+            SessionEvent::Milestone(_) => "milestone",
+            SessionEvent::Changed => "changed",
+        }
+    }
+
+    fn describe(&self) -> Option<String> {
+        match self {
+            SessionEvent::Started(state) => Some(format!("Started {}", state.display_name().to_lowercase())),
+            SessionEvent::Paused => Some("Paused timer".to_string()),
+            SessionEvent::Resumed => Some("Resumed timer".to_string()),
+            SessionEvent::Completed { state, .. } => Some(format!("Completed {}", state.display_name().to_lowercase())),
+            SessionEvent::Skipped => Some("Skipped to the next session".to_string()),
+            SessionEvent::Stopped => Some("Stopped timer".to_string()),
+            SessionEvent::Reset => Some("Reset timer".to_string()),
+            SessionEvent::Switched(state) => Some(format!("Switched to {}", state.display_name().to_lowercase())),
+            SessionEvent::Milestone(text) => Some(text.clone()),
+            SessionEvent::Tick | SessionEvent::Changed => None,
+        }
+    }
+}
+
+// Subscribers run on the publishing thread while the state is locked, so they
+// must not lock it again; anything slow belongs on another thread.
+type Subscriber = Box<dyn Fn(&SessionEvent, &SessionInfo) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self, subscriber: impl Fn(&SessionEvent, &SessionInfo) + Send + Sync + 'static) {
+        self.subscribers.lock().push(Box::new(subscriber));
+    }
+
+    pub fn publish(&self, event: SessionEvent, info: &SessionInfo) {
+        if let Some(message) = event.describe() {
+            notifications::log_info(&message);
+        }
+        for subscriber in self.subscribers.lock().iter() {
+            subscriber(&event, info);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::{CompletedTimer, SessionInfo};
+
+// Newest completed sessions kept in the feed
+pub const FEED_ITEMS: usize = 100;
+
+const TITLE: &str = "Pomodoro sessions";
+const FEED_ID: &str = "tag:pomodoro-timer,2026:sessions";
+
+/// Format of the session feed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// JSON Feed 1.1, with the session's fields under "_pomodoro" in each item
+    Json,
+    /// Atom 1.0
+    Atom,
+}
+
+impl FeedFormat {
+    fn file(&self) -> &'static str {
+        match self {
+            FeedFormat::Json => "sessions.json",
+            FeedFormat::Atom => "sessions.atom",
+        }
+    }
+}
+
+// Where the feed goes: session_feed_path, or the data directory
+pub fn feed_path(config: &Config, format: FeedFormat) -> Result<PathBuf> {
+    match &config.session_feed_path {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(Persistence::data_dir()?.join(format.file())),
+    }
+}
+
+// The newest completed sessions, read-only for other tools to poll: dashboards
+// on a static site, quantified-self pipelines. Sessions are added as they
+// complete rather than read again from the whole history.
+#[derive(Debug, Clone, Default)]
+pub struct SessionFeed {
+    items: VecDeque<CompletedTimer>,  // Oldest first
+    recorded_until: Option<DateTime<Utc>>,  // History entries up to here are in
+}
+
+impl SessionFeed {
+    pub fn from_history(history: &[CompletedTimer]) -> Self {
+        let mut feed = Self::default();
+        feed.catch_up(history);
+        feed
+    }
+
+    // Add the sessions completed since the last call, dropping the oldest past FEED_ITEMS
+    pub fn catch_up(&mut self, history: &[CompletedTimer]) -> bool {
+        let until = self.recorded_until;
+        let new: Vec<&CompletedTimer> =
+            history.iter().filter(|entry| until.is_none_or(|until| entry.completed_at > until)).collect();
+        for entry in &new {
+            self.recorded_until = self.recorded_until.max(Some(entry.completed_at));
+            if !entry.abandoned {
+                self.items.push_back((*entry).clone());
+            }
+        }
+        while self.items.len() > FEED_ITEMS {
+            self.items.pop_front();
+        }
+        !new.is_empty()
+    }
+
+    // Bring the feed up to date with a history that may have been edited,
+    // imported into or pruned. Only the part of the history from the feed's
+    // oldest session on is looked at, and items are replaced one by one when
+    // the same sessions are still listed.
+    pub fn refresh(&mut self, history: &[CompletedTimer]) -> bool {
+        let from = match self.items.front() {
+            Some(oldest) if self.items.len() >= FEED_ITEMS => {
+                history.partition_point(|entry| entry.completed_at < oldest.completed_at)
+            }
+            _ => 0,
+        };
+        let fresh: Vec<&CompletedTimer> = history[from..].iter().filter(|entry| !entry.abandoned).collect();
+        let fresh = &fresh[fresh.len().saturating_sub(FEED_ITEMS)..];
+        self.recorded_until = self.recorded_until.max(history.last().map(|entry| entry.completed_at));
+
+        if fresh.len() != self.items.len() || self.items.iter().zip(fresh).any(|(item, entry)| item.id != entry.id) {
+            self.items = fresh.iter().map(|entry| (*entry).clone()).collect();
+            return true;
+        }
+        let mut changed = false;
+        for (item, entry) in self.items.iter_mut().zip(fresh) {
+            if item != *entry {
+                *item = (*entry).clone();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    // Newest entry first, as feed readers expect
+    fn newest_first(&self) -> impl Iterator<Item = &CompletedTimer> {
+        self.items.iter().rev()
+    }
+
+    fn updated(&self) -> DateTime<Utc> {
+        self.recorded_until.unwrap_or(DateTime::UNIX_EPOCH)
+    }
+
+    pub fn render<Tz: TimeZone>(&self, format: FeedFormat, tz: &Tz) -> String
+    where
+        Tz::Offset: Display,
+    {
+        match format {
+            FeedFormat::Json => self.json_feed(tz),
+            FeedFormat::Atom => self.atom(tz),
+        }
+    }
+
+    fn json_feed<Tz: TimeZone>(&self, tz: &Tz) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let items: Vec<serde_json::Value> = self
+            .newest_first()
+            .map(|entry| {
+                serde_json::json!({
+                    "id": entry.id,
+                    "title": title(entry),
+                    "content_text": summary(entry, tz),
+                    "date_published": entry.completed_at.to_rfc3339(),
+                    "tags": [entry.session_type],
+                    "_pomodoro": {
+                        "session_type": entry.session_type,
+                        "label": entry.label,
+                        "icon": entry.icon,
+                        "duration_secs": entry.duration_secs,
+                        "started_at": started_at(entry).to_rfc3339(),
+                        "completed_at": entry.completed_at.to_rfc3339(),
+                        "task_id": entry.task_id(),
+                    },
+                })
+            })
+            .collect();
+        let feed = serde_json::json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": TITLE,
+            "items": items,
+        });
+        serde_json::to_string_pretty(&feed).unwrap_or_default()
+    }
+
+    fn atom<Tz: TimeZone>(&self, tz: &Tz) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        xml.push_str(&format!("  <title>{}</title>\n", TITLE));
+        xml.push_str(&format!("  <id>{}</id>\n", FEED_ID));
+        xml.push_str(&format!("  <updated>{}</updated>\n", self.updated().to_rfc3339()));
+        xml.push_str("  <author><name>pomodoro-timer</name></author>\n");
+        for entry in self.newest_first() {
+            xml.push_str("  <entry>\n");
+            xml.push_str(&format!("    <id>{}/{}</id>\n", FEED_ID, escape(&entry.id)));
+            xml.push_str(&format!("    <title>{}</title>\n", escape(&title(entry))));
+            xml.push_str(&format!("    <published>{}</published>\n", started_at(entry).to_rfc3339()));
+            xml.push_str(&format!("    <updated>{}</updated>\n", entry.completed_at.to_rfc3339()));
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape(&entry.session_type)));
+            xml.push_str(&format!("    <content type=\"text\">{}</content>\n", escape(&summary(entry, tz))));
+            xml.push_str("  </entry>\n");
+        }
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
+
+// When it first ran, before any pause. Imported and older entries have no
+// segments, so their start is worked back from the length.
+fn started_at(entry: &CompletedTimer) -> DateTime<Utc> {
+    match entry.segments.first() {
+        Some(segment) => segment.start,
+        None => entry.completed_at - Duration::seconds(entry.duration_secs as i64),
+    }
+}
+
+// "Work Session: 📚 Report", or the session type alone without a label
+fn title(entry: &CompletedTimer) -> String {
+    let label = format!("{} {}", entry.icon, entry.label);
+    match label.trim() {
+        "" => entry.session_type.clone(),
+        label => format!("{}: {}", entry.session_type, label),
+    }
+}
+
+// "25 min, 09:50–10:15"
+fn summary<Tz: TimeZone>(entry: &CompletedTimer, tz: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    format!(
+        "{} min, {}–{}",
+        entry.duration_secs.div_ceil(60),
+        started_at(entry).with_timezone(tz).format("%H:%M"),
+        entry.completed_at.with_timezone(tz).format("%H:%M")
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Replace the file in one step, so a tool polling it never reads half a feed
+fn write_feed(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".tmp");
+    std::fs::write(&partial, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    std::fs::rename(&partial, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+// Event bus subscriber that adds sessions to the feed as they complete. The
+// feed is rendered and written on a thread of its own, as subscribers run
+// with the state locked.
+pub struct FeedWriter {
+    feed: Mutex<SessionFeed>,
+    writes: Sender<SessionFeed>,
+}
+
+impl FeedWriter {
+    // None unless session_feed is set. The feed is written once at launch, so
+    // it follows a change of format or path.
+    pub fn spawn(config: &Config, info: &SessionInfo) -> Option<Arc<Self>> {
+        let format = config.session_feed?;
+        let path = feed_path(config, format)
+            .map_err(|e| notifications::log_error(&format!("{:#}", e)))
+            .ok()?;
+        let (writes, pending) = channel::<SessionFeed>();
+        std::thread::Builder::new()
+            .name("write-feed".to_string())
+            .spawn(move || {
+                let mut written = String::new();
+                // Only the latest of a burst gets written
+                while let Ok(mut feed) = pending.recv() {
+                    while let Ok(newer) = pending.try_recv() {
+                        feed = newer;
+                    }
+                    let content = feed.render(format, &chrono::Local);
+                    if content == written {
+                        continue;
+                    }
+                    match write_feed(&path, &content) {
+                        Ok(()) => written = content,
+                        Err(e) => notifications::log_error(&format!("{:#}", e)),
+                    }
+                }
+            })
+            .ok()?;
+        let feed = SessionFeed::from_history(&info.history);
+        let _ = writes.send(feed.clone());
+        Some(Arc::new(Self { feed: Mutex::new(feed), writes }))
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let mut feed = self.feed.lock();
+        let changed = match event {
+            SessionEvent::Completed { .. } | SessionEvent::Skipped | SessionEvent::Switched(_) | SessionEvent::Stopped | SessionEvent::Reset => {
+                feed.catch_up(&info.history)
+            }
+            // The history may have been edited, imported into or pruned
+            SessionEvent::Changed => feed.refresh(&info.history),
+            _ => false,
+        };
+        if changed {
+            let _ = self.writes.send(feed.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{completed, Segment};
+
+    fn entry(id: &str, completed_at: &str, label: &str, abandoned: bool) -> CompletedTimer {
+        CompletedTimer { id: id.to_string(), label: label.to_string(), ..completed(completed_at, 25, "Work Session", abandoned) }
+    }
+
+    #[test]
+    fn lists_completed_sessions_newest_first() {
+        let mut history = vec![
+            entry("a", "2026-10-16T09:25:00Z", "Report", false),
+            entry("b", "2026-10-16T10:00:00Z", "Cut short", true),
+        ];
+        let mut feed = SessionFeed::from_history(&history);
+        history.push(entry("c", "2026-10-16T10:30:00Z", "Q&A <draft>", false));
+        assert!(feed.catch_up(&history));
+        assert!(!feed.catch_up(&history));
+
+        let json: serde_json::Value = serde_json::from_str(&feed.render(FeedFormat::Json, &Utc)).unwrap();
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items.iter().map(|item| item["id"].as_str().unwrap()).collect::<Vec<_>>(), ["c", "a"]);
+        assert_eq!(items[1]["title"], "Work Session: Report");
+        assert_eq!(items[1]["content_text"], "25 min, 09:00–09:25");
+        assert_eq!(items[1]["_pomodoro"]["started_at"], "2026-10-16T09:00:00+00:00");
+
+        let atom = feed.render(FeedFormat::Atom, &Utc);
+        assert!(atom.contains("<updated>2026-10-16T10:30:00+00:00</updated>"));
+        assert!(atom.contains("<title>Work Session: Q&amp;A &lt;draft&gt;</title>"));
+        assert!(!atom.contains("Cut short"));
+    }
+
+    #[test]
+    fn keeps_only_the_newest_sessions() {
+        let start = "2026-10-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let history: Vec<CompletedTimer> = (0..FEED_ITEMS as i64 + 5)
+            .map(|n| CompletedTimer { completed_at: start + Duration::hours(n), ..entry(&n.to_string(), "2026-10-16T00:00:00Z", "", false) })
+            .collect();
+        let feed = SessionFeed::from_history(&history);
+        assert_eq!(feed.items.len(), FEED_ITEMS);
+        assert_eq!(feed.newest_first().last().map(|entry| entry.id.as_str()), Some("5"));
+    }
+
+    #[test]
+    fn updates_edited_sessions_in_place() {
+        let mut history = vec![
+            entry("a", "2026-10-16T09:25:00Z", "Report", false),
+            entry("b", "2026-10-16T10:00:00Z", "Review", false),
+        ];
+        let mut feed = SessionFeed::from_history(&history);
+        assert!(!feed.refresh(&history));
+
+        history[0].label = "Quarterly report".to_string();
+        assert!(feed.refresh(&history));
+        assert_eq!(feed.items[0].label, "Quarterly report");
+
+        history.remove(1);
+        assert!(feed.refresh(&history));
+        assert_eq!(feed.newest_first().map(|entry| entry.id.as_str()).collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn resumed_sessions_start_at_their_first_segment() {
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+        let resumed = CompletedTimer {
+            task_id: "task-1".to_string(),
+            segments: vec![
+                Segment { start: at("2026-10-16T08:30:00Z"), end: at("2026-10-16T08:40:00Z") },
+                Segment { start: at("2026-10-16T09:10:00Z"), end: at("2026-10-16T09:25:00Z") },
+            ],
+            ..entry("a", "2026-10-16T09:25:00Z", "Report", false)
+        };
+        let feed = SessionFeed::from_history(&[resumed, entry("b", "2026-10-16T10:00:00Z", "", false)]);
+
+        let json: serde_json::Value = serde_json::from_str(&feed.render(FeedFormat::Json, &Utc)).unwrap();
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items[1]["_pomodoro"]["started_at"], "2026-10-16T08:30:00+00:00");
+        assert_eq!(items[1]["_pomodoro"]["task_id"], "task-1");
+        assert_eq!(items[0]["_pomodoro"]["task_id"], "b");
+    }
+}
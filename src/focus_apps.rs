@@ -0,0 +1,150 @@
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::events::{EventBus, SessionEvent};
+use crate::git;
+use crate::notifications;
+use crate::state::{SessionInfo, TimerState};
+
+// How often the frontmost app is looked at during a work session
+const SAMPLE_SECS: u32 = 5;
+
+// Between the document and the rest in window titles
+const TITLE_SEPARATORS: [&str; 4] = [" — ", " – ", " - ", " | "];
+
+// Name of the app in front (macOS, Windows, X11). None when it can't be told,
+// e.g. on Wayland where apps can't see the focused window.
+fn frontmost_app() -> Option<String> {
+    active_win_pos_rs::get_active_window()
+        .ok()
+        .map(|window| window.app_name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// Title of the window in front, with its app name. Same limits as frontmost_app.
+fn frontmost_window() -> Option<(String, String)> {
+    active_win_pos_rs::get_active_window().ok().map(|window| (window.title, window.app_name))
+}
+
+// A label from a window title: the document part of "main.rs — project — Code",
+// leaving out the app name. None for titles that are only the app name.
+pub fn label_from_title(title: &str, app: &str) -> Option<String> {
+    let first = TITLE_SEPARATORS
+        .iter()
+        .filter_map(|separator| title.find(separator))
+        .min()
+        .map_or(title, |end| &title[..end]);
+    let label = first.trim().trim_start_matches(['*', '●']).trim();
+    if label.is_empty() || label.eq_ignore_ascii_case(app.trim()) {
+        return None;
+    }
+    Some(label.to_string())
+}
+
+// Suggest a label when a work session starts without one: the git branch of
+// git_repository, or else the window in front. It's looked up off the
+// publishing thread and kept only if the session still has no label by then.
+pub fn spawn_label_suggestions(config: &Config, events: &EventBus, session_info: Arc<Mutex<SessionInfo>>) {
+    if !config.label_from_window && config.git_repository.is_none() {
+        return;
+    }
+    let config = config.clone();
+    events.subscribe(move |event, info| {
+        if *event != SessionEvent::Started(TimerState::Working) || !info.current_label.is_empty() {
+            return;
+        }
+        let id = info.current_id.clone();
+        let session_info = session_info.clone();
+        let config = config.clone();
+        let spawned = std::thread::Builder::new().name("label-suggestion".to_string()).spawn(move || {
+            let label = suggested_label(&config).map(|label| config.clean_label(&label)).unwrap_or_default();
+            let mut info = session_info.lock();
+            if info.current_id == id && info.current_label.is_empty() {
+                info.suggested_label = label;
+            }
+        });
+        if let Err(e) = spawned {
+            notifications::log_error(&format!("Failed to look up a label suggestion: {}", e));
+        }
+    });
+}
+
+fn suggested_label(config: &Config) -> Option<String> {
+    // A missing checkout is reported by the config warnings
+    let branch = config.git_repository.as_deref().and_then(|repo| git::current_branch(Path::new(repo)).ok().flatten());
+    if branch.is_some() || !config.label_from_window {
+        return branch;
+    }
+    frontmost_window()
+        .filter(|(_, app)| !app.to_lowercase().contains("pomodoro"))  // Started from this window
+        .and_then(|(title, app)| label_from_title(&title, &app))
+}
+
+fn is_distracting(app: &str, distracting_apps: &[String]) -> bool {
+    let app = app.to_lowercase();
+    distracting_apps
+        .iter()
+        .map(|pattern| pattern.trim().to_lowercase())
+        .any(|pattern| !pattern.is_empty() && app.contains(&pattern))
+}
+
+// Samples the frontmost app while a work session runs and adds the time to the
+// session, which carries it into history when the session ends
+pub fn spawn_monitor(config: &Config, session_info: Arc<Mutex<SessionInfo>>) {
+    if !config.track_apps {
+        return;
+    }
+    let distracting_apps = config.distracting_apps.clone();
+    let threshold_secs = config.distraction_threshold_minutes * 60;
+
+    let spawned = std::thread::Builder::new().name("app-monitor".to_string()).spawn(move || {
+        let mut unavailable_logged = false;
+        loop {
+            std::thread::sleep(Duration::from_secs(SAMPLE_SECS as u64));
+            if session_info.lock().current_state != TimerState::Working {
+                continue;
+            }
+            // Asking the window system can take a moment, so not under the lock
+            let Some(app) = frontmost_app() else {
+                if !unavailable_logged {
+                    notifications::log_error("Can't tell which app is in front; app time isn't recorded");
+                    unavailable_logged = true;
+                }
+                continue;
+            };
+            let mut info = session_info.lock();
+            // The session may have been paused or finished meanwhile
+            if info.current_state == TimerState::Working {
+                let distracting = is_distracting(&app, &distracting_apps);
+                info.record_app(&app, SAMPLE_SECS, distracting, threshold_secs);
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        notifications::log_error(&format!("Failed to start app tracking: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_distracting_apps_by_part_of_the_name() {
+        let distracting = vec!["slack".to_string(), " Discord ".to_string(), String::new()];
+        assert!(is_distracting("Slack", &distracting));
+        assert!(is_distracting("discord-canary", &distracting));
+        assert!(!is_distracting("Code", &distracting));
+    }
+
+    #[test]
+    fn labels_come_from_the_document_part_of_titles() {
+        assert_eq!(label_from_title("● main.rs — pomodoro-timer — Visual Studio Code", "Code"), Some("main.rs".to_string()));
+        assert_eq!(label_from_title("Quarterly report - Google Docs - Firefox", "firefox"), Some("Quarterly report".to_string()));
+        assert_eq!(label_from_title("Slack", "Slack"), None);
+        assert_eq!(label_from_title("  ", "Terminal"), None);
+    }
+}
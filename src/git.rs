@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+// Git is read from its files rather than run, so nothing has to be installed
+// and a slow repository can't hold up a session start.
+
+// The repository's git directory: `.git` itself, or where a worktree's
+// `.git` file points. None when `repo` isn't a git checkout.
+pub fn git_dir(repo: &Path) -> Option<PathBuf> {
+    let dot_git = repo.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let dir = PathBuf::from(content.strip_prefix("gitdir:")?.trim());
+    Some(if dir.is_absolute() { dir } else { repo.join(dir) })
+}
+
+// The branch named in HEAD's content; None when HEAD is detached
+pub fn branch_from_head(head: &str) -> Option<String> {
+    let branch = head.trim().strip_prefix("ref:")?.trim();
+    let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+    (!branch.is_empty()).then(|| branch.to_string())
+}
+
+// The branch checked out in `repo`, None when HEAD is detached
+pub fn current_branch(repo: &Path) -> Result<Option<String>> {
+    let dir = git_dir(repo).with_context(|| format!("{} is not a git repository", repo.display()))?;
+    let head = std::fs::read_to_string(dir.join("HEAD"))
+        .with_context(|| format!("Failed to read HEAD in {}", dir.display()))?;
+    Ok(branch_from_head(&head))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_branch_from_head() {
+        assert_eq!(branch_from_head("ref: refs/heads/feature/login\n"), Some("feature/login".to_string()));
+        // Detached
+        assert_eq!(branch_from_head("4712e5a0c6b7d2f1e3a9b8c7d6e5f4a3b2c1d0e9\n"), None);
+
+        let repo = std::env::temp_dir().join(format!("pomodoro-git-{}", std::process::id()));
+        std::fs::create_dir_all(repo.join("main/.git")).unwrap();
+        std::fs::write(repo.join("main/.git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        // A worktree's .git is a file pointing at its git directory
+        std::fs::create_dir_all(repo.join("worktree")).unwrap();
+        std::fs::create_dir_all(repo.join("main/.git/worktrees/fix")).unwrap();
+        std::fs::write(repo.join("main/.git/worktrees/fix/HEAD"), "ref: refs/heads/fix-crash\n").unwrap();
+        std::fs::write(repo.join("worktree/.git"), "gitdir: ../main/.git/worktrees/fix\n").unwrap();
+
+        assert_eq!(current_branch(&repo.join("main")).unwrap(), Some("main".to_string()));
+        assert_eq!(current_branch(&repo.join("worktree")).unwrap(), Some("fix-crash".to_string()));
+        assert!(current_branch(&repo).is_err());
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+}
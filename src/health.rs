@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use parking_lot::Mutex;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::{CompletedTimer, SessionInfo};
+use crate::stats::breaks;
+
+// Completed breaks logged as mindfulness minutes. Neither Apple Health nor
+// Google Fit has an API a desktop app can write to, so each break goes into
+// mindfulness.csv in the data directory for an importer app to bring in: one
+// row per stretch it ran, so a pause doesn't count.
+
+const FILE: &str = "mindfulness.csv";
+const HEADER: [&str; 5] = ["start", "end", "minutes", "type", "source"];
+// HKCategoryTypeIdentifierMindfulSession in Apple Health; Google Fit calls it meditation
+const MINDFUL_SESSION: &str = "MindfulSession";
+
+// The rows for one history entry, none unless it's a completed break
+pub fn rows<Tz: TimeZone>(entry: &CompletedTimer, tz: &Tz) -> Vec<[String; 5]>
+where
+    Tz::Offset: Display,
+{
+    if !breaks::is_break(entry) || entry.abandoned {
+        return Vec::new();
+    }
+    // Older entries don't know when they ran, only how long
+    let ran = match entry.segments.as_slice() {
+        [] => vec![(entry.completed_at - Duration::seconds(entry.duration_secs as i64), entry.completed_at)],
+        segments => segments.iter().map(|segment| (segment.start, segment.end)).collect(),
+    };
+    ran.into_iter()
+        .filter(|(start, end)| end > start)
+        .map(|(start, end)| {
+            [
+                start.with_timezone(tz).to_rfc3339(),
+                end.with_timezone(tz).to_rfc3339(),
+                ((end - start).num_seconds() as f64 / 60.0).to_string(),
+                MINDFUL_SESSION.to_string(),
+                entry.session_type.clone(),
+            ]
+        })
+        .collect()
+}
+
+fn append(path: &Path, rows: &[[String; 5]]) -> Result<()> {
+    let new = !path.exists();
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", FILE))?;
+    let mut writer = csv::Writer::from_writer(file);
+    if new {
+        writer.write_record(HEADER).with_context(|| format!("Failed to write {}", FILE))?;
+    }
+    for row in rows {
+        writer.write_record(row).with_context(|| format!("Failed to write {}", FILE))?;
+    }
+    writer.flush().with_context(|| format!("Failed to write {}", FILE))
+}
+
+// Event bus subscriber that appends each break as it completes. Breaks from
+// before it started aren't logged.
+pub struct HealthLog {
+    path: PathBuf,
+    logged_until: Mutex<Option<DateTime<Utc>>>,  // History entries up to here have been looked at
+}
+
+impl HealthLog {
+    // None unless mindfulness_export is on
+    pub fn spawn(config: &Config, info: &SessionInfo) -> Option<Arc<Self>> {
+        if !config.mindfulness_export {
+            return None;
+        }
+        let dir = Persistence::data_dir()
+            .map_err(|e| notifications::log_error(&format!("{:#}", e)))
+            .ok()?;
+        let logged_until = info.history.iter().map(|entry| entry.completed_at).max();
+        Some(Arc::new(Self { path: dir.join(FILE), logged_until: Mutex::new(logged_until) }))
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        if !matches!(event, SessionEvent::Completed { .. }) {
+            return;
+        }
+        let mut logged_until = self.logged_until.lock();
+        let until = *logged_until;
+        let new: Vec<&CompletedTimer> =
+            info.history.iter().filter(|entry| until.is_none_or(|until| entry.completed_at > until)).collect();
+        *logged_until = new.iter().map(|entry| entry.completed_at).max().or(until);
+        let rows: Vec<[String; 5]> = new.iter().flat_map(|entry| rows(entry, &chrono::Local)).collect();
+        if rows.is_empty() {
+            return;
+        }
+        let path = self.path.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = append(&path, &rows) {
+                notifications::log_error(&format!("{:#}", e));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{completed, Segment};
+
+    fn entry(session_type: &str, abandoned: bool) -> CompletedTimer {
+        completed("2026-10-16T10:30:00Z", 5, session_type, abandoned)
+    }
+
+    #[test]
+    fn logs_the_time_completed_breaks_ran() {
+        assert!(rows(&entry("Work Session", false), &Utc).is_empty());
+        assert!(rows(&entry("Short Break", true), &Utc).is_empty());
+        assert_eq!(
+            rows(&entry("Short Break", false), &Utc),
+            [[
+                "2026-10-16T10:25:00+00:00".to_string(),
+                "2026-10-16T10:30:00+00:00".to_string(),
+                "5".to_string(),
+                MINDFUL_SESSION.to_string(),
+                "Short Break".to_string(),
+            ]]
+        );
+
+        // Paused partway: each stretch is a session of its own
+        let at = |time: &str| format!("2026-10-16T{}Z", time).parse::<DateTime<Utc>>().unwrap();
+        let paused = CompletedTimer {
+            segments: vec![Segment { start: at("10:00:00"), end: at("10:02:30") }, Segment { start: at("10:10:00"), end: at("10:12:30") }],
+            ..entry("Long Break", false)
+        };
+        let rows = rows(&paused, &Utc);
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[1][0].as_str(), rows[1][2].as_str()), ("2026-10-16T10:10:00+00:00", "2.5"));
+    }
+}
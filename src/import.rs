@@ -0,0 +1,297 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::{CompletedTimer, HistoryRetention, SessionInfo};
+use crate::stats;
+
+// Column names used by common exports, matched case-insensitively.
+// Toggl: Description, Start date, Start time, End date, End time, Duration (hh:mm:ss)
+// Focus To-Do: Task, Project, Start Time, End Time, Focus Duration (minutes)
+// Pomofocus: date, project, task, minutes
+const LABEL_COLUMNS: &[&str] = &["description", "task", "task name", "title"];
+const PROJECT_COLUMNS: &[&str] = &["project", "project name"];
+const START_DATE_COLUMNS: &[&str] = &["start date", "date"];
+const START_TIME_COLUMNS: &[&str] = &["start time", "start"];
+const END_DATE_COLUMNS: &[&str] = &["end date"];
+const END_TIME_COLUMNS: &[&str] = &["end time", "end"];
+const DURATION_COLUMNS: &[&str] = &["duration", "focus duration", "minutes", "duration (min)", "focus time"];
+
+pub struct ImportSummary {
+    pub added: Vec<CompletedTimer>,  // Records that weren't in the history yet
+    pub duplicates: usize,
+    pub too_old: usize,  // Older than the history keeps, so archived right away
+    pub skipped: usize,
+}
+
+impl ImportSummary {
+    pub fn describe(&self) -> String {
+        let mut text = format!("Imported {} session(s), {} already present", self.added.len(), self.duplicates);
+        if self.too_old > 0 {
+            text.push_str(&format!(", {} skipped as older than the history keeps", self.too_old));
+        }
+        text.push_str(&format!(", {} unreadable row(s)", self.skipped));
+        text
+    }
+}
+
+// Read and parse a CSV export
+pub fn read_csv(path: &Path) -> Result<(Vec<CompletedTimer>, usize)> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_csv(&content, &Local)
+}
+
+// Add imported records to the history, skipping entries already present.
+// The retention applies to them at once, so those it drops are counted apart.
+pub fn merge(info: &mut SessionInfo, records: Vec<CompletedTimer>, skipped: usize) -> ImportSummary {
+    let mut added = Vec::new();
+    let mut duplicates = 0;
+    for record in records {
+        let exists = info
+            .history
+            .iter()
+            .any(|entry| entry.completed_at == record.completed_at && entry.label == record.label);
+        if exists {
+            duplicates += 1;
+        } else {
+            info.history.push(record.clone());
+            added.push(record);
+        }
+    }
+    info.history.sort_by_key(|entry| entry.completed_at);
+    info.prune_history(Utc::now());
+    let before = added.len();
+    added.retain(|record| info.history.iter().any(|entry| entry.id == record.id));
+    ImportSummary { too_old: before - added.len(), added, duplicates, skipped }
+}
+
+// Runs `pomodoro-timer import <file.csv>` against the saved state
+pub fn run(path: &Path, config: &Config) -> Result<ImportSummary> {
+    let (records, skipped) = read_csv(path)?;
+    let mut info = Persistence::load()?;
+    // As the app would: the age limit only once the user agreed to it
+    info.retention = HistoryRetention {
+        limit: (config.history_limit > 0).then_some(config.history_limit as usize),
+        max_age_days: config.history_max_age_days.filter(|days| info.history_max_age_confirmed == Some(*days)),
+    };
+    let summary = merge(&mut info, records, skipped);
+    Persistence::save(&mut info)?;
+    if let Err(e) = stats::summary::add_to_saved(&summary.added) {
+        notifications::log_error(&format!("{:#}", e));
+    }
+    Ok(summary)
+}
+
+// Map CSV rows into work sessions. Returns the records and the number of rows
+// that could not be read (missing times or durations).
+pub fn parse_csv<Tz: TimeZone>(content: &str, tz: &Tz) -> Result<(Vec<CompletedTimer>, usize)> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .context("Failed to read CSV header")?
+        .iter()
+        .map(|header| header.trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    let column = |names: &[&str]| names.iter().find_map(|name| headers.iter().position(|header| header == name));
+    let label_column = column(LABEL_COLUMNS);
+    let project_column = column(PROJECT_COLUMNS);
+    let start_date_column = column(START_DATE_COLUMNS);
+    let start_time_column = column(START_TIME_COLUMNS);
+    let end_date_column = column(END_DATE_COLUMNS);
+    let end_time_column = column(END_TIME_COLUMNS);
+    let duration_column = column(DURATION_COLUMNS);
+
+    if start_date_column.is_none() && start_time_column.is_none() && end_time_column.is_none() {
+        bail!("Unrecognized CSV format: no date or time columns (found: {})", headers.join(", "));
+    }
+    if duration_column.is_none() && end_time_column.is_none() {
+        bail!("Unrecognized CSV format: no duration or end time column (found: {})", headers.join(", "));
+    }
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for row in reader.records() {
+        let row = row.context("Failed to read CSV row")?;
+        let field = |index: Option<usize>| index.and_then(|i| row.get(i)).filter(|value| !value.is_empty());
+
+        let start = parse_datetime(field(start_date_column), field(start_time_column), tz);
+        let end = field(end_time_column)
+            .and_then(|time| parse_datetime(field(end_date_column).or(field(start_date_column)), Some(time), tz));
+        let duration_secs = field(duration_column).and_then(parse_duration).or_else(|| {
+            let secs = (end? - start?).num_seconds();
+            (secs > 0).then_some(secs as u32)
+        });
+        let completed_at = match (end, start, duration_secs) {
+            (Some(end), _, _) => Some(end),
+            (None, Some(start), Some(secs)) => Some(start + Duration::seconds(secs as i64)),
+            _ => None,
+        };
+
+        let (Some(duration_secs), Some(completed_at)) = (duration_secs, completed_at) else {
+            skipped += 1;
+            continue;
+        };
+
+        let label = field(label_column).or(field(project_column)).unwrap_or_default().to_string();
+        records.push(CompletedTimer {
+            id: uuid::Uuid::new_v4().to_string(),
+            label,
+            duration_secs,
+            session_type: "Work Session".to_string(),
+            completed_at,
+            ..CompletedTimer::default()
+        });
+    }
+
+    Ok((records, skipped))
+}
+
+// Accepts "2024-03-01" + "09:30:00", a combined "2024-03-01 09:30", or a
+// date alone (treated as noon, for exports that only record the day)
+fn parse_datetime<Tz: TimeZone>(date: Option<&str>, time: Option<&str>, tz: &Tz) -> Option<DateTime<Utc>> {
+    let naive = match (date, time) {
+        (_, Some(time)) if parse_naive_datetime(time).is_some() => parse_naive_datetime(time)?,
+        (Some(date), Some(time)) => parse_date(date)?.and_time(parse_time(time)?),
+        (Some(date), None) => parse_naive_datetime(date).or_else(|| parse_date(date)?.and_hms_opt(12, 0, 0))?,
+        (None, _) => return None,
+    };
+    Some(tz.from_local_datetime(&naive).earliest()?.with_timezone(&Utc))
+}
+
+fn parse_naive_datetime(value: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M", "%m/%d/%Y %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M %p", "%I:%M:%S %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+}
+
+// "01:25:00" (Toggl) or plain minutes ("25", "25.5")
+fn parse_duration(value: &str) -> Option<u32> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let secs = match parts.as_slice() {
+        [h, m, s] => h.parse::<u32>().ok()? * 3600 + m.parse::<u32>().ok()? * 60 + s.parse::<u32>().ok()?,
+        [m, s] => m.parse::<u32>().ok()? * 60 + s.parse::<u32>().ok()?,
+        [minutes] => (minutes.parse::<f64>().ok()? * 60.0).round() as u32,
+        _ => return None,
+    };
+    (secs > 0).then_some(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+
+    #[test]
+    fn counts_records_older_than_the_history_keeps() {
+        let mut info = SessionInfo::new();
+        info.retention = HistoryRetention { limit: Some(2), max_age_days: None };
+        info.history = vec![completed("2026-03-02T10:00:00Z", 25, "Work Session", false)];
+        let records = vec![
+            CompletedTimer { id: "old".to_string(), ..completed("2026-01-01T10:00:00Z", 25, "Work Session", false) },
+            CompletedTimer { id: "new".to_string(), ..completed("2026-03-03T10:00:00Z", 25, "Work Session", false) },
+            completed("2026-03-02T10:00:00Z", 25, "Work Session", false),
+        ];
+
+        let summary = merge(&mut info, records, 1);
+        let added: Vec<&str> = summary.added.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(added, ["new"]);
+        assert_eq!((summary.duplicates, summary.too_old, summary.skipped), (1, 1, 1));
+        assert_eq!(info.history.len(), 2);
+        assert_eq!(
+            summary.describe(),
+            "Imported 1 session(s), 1 already present, 1 skipped as older than the history keeps, 1 unreadable row(s)"
+        );
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_durations_in_every_format() {
+        assert_eq!(parse_duration("01:25:00"), Some(85 * 60));
+        assert_eq!(parse_duration("25:30"), Some(25 * 60 + 30));
+        assert_eq!(parse_duration("25"), Some(25 * 60));
+        assert_eq!(parse_duration("25.5"), Some(25 * 60 + 30));
+        for unreadable in ["", "0", "00:00:00", "-5", "25 min", "1:2:3:4", "aa:10"] {
+            assert_eq!(parse_duration(unreadable), None, "{:?}", unreadable);
+        }
+    }
+
+    #[test]
+    fn reads_dates_and_times_in_the_given_timezone() {
+        let berlin = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        assert_eq!(parse_datetime(Some("2026-10-16"), Some("09:30:00"), &berlin), Some(utc("2026-10-16T07:30:00Z")));
+        assert_eq!(parse_datetime(Some("2026-10-16"), Some("09:30"), &Utc), Some(utc("2026-10-16T09:30:00Z")));
+        assert_eq!(parse_datetime(Some("10/16/2026"), Some("9:30 PM"), &Utc), Some(utc("2026-10-16T21:30:00Z")));
+        // The whole thing in the time column, whatever the date column says
+        assert_eq!(parse_datetime(Some("ignored"), Some("2026-10-16 09:30"), &berlin), Some(utc("2026-10-16T07:30:00Z")));
+        // A date alone is taken as noon
+        assert_eq!(parse_datetime(Some("16.10.2026"), None, &Utc), Some(utc("2026-10-16T12:00:00Z")));
+        assert_eq!(parse_datetime(None, Some("09:30"), &Utc), None);
+        assert_eq!(parse_datetime(Some("16th October"), Some("09:30"), &Utc), None);
+        assert_eq!(parse_datetime(Some("2026-10-16"), Some("25:00"), &Utc), None);
+    }
+
+    #[test]
+    fn reads_common_exports() {
+        let toggl = "\u{feff}Description,Project,Start date,Start time,End date,End time,Duration\n\
+            Report,Work,2026-10-16,23:40:00,2026-10-17,00:05:00,00:25:00\n";
+        let (records, skipped) = parse_csv(toggl, &Utc).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!((records[0].label.as_str(), records[0].duration_secs), ("Report", 25 * 60));
+        assert_eq!(records[0].completed_at, utc("2026-10-17T00:05:00Z"));
+
+        let focus_todo = "Task,Project,Start Time,End Time,Focus Duration\n\
+            ,Book,2026-10-16 09:00,2026-10-16 09:30,25\n";
+        let (records, _) = parse_csv(focus_todo, &Utc).unwrap();
+        // The project stands in for a missing task, and the focus time for the gap
+        assert_eq!((records[0].label.as_str(), records[0].duration_secs), ("Book", 25 * 60));
+        assert_eq!(records[0].completed_at, utc("2026-10-16T09:30:00Z"));
+
+        let pomofocus = "date,project,task,minutes\n2026-10-16,Client,Email,25\n";
+        let (records, _) = parse_csv(pomofocus, &chrono::FixedOffset::east_opt(3600).unwrap()).unwrap();
+        assert_eq!(records[0].label, "Email");
+        assert_eq!(records[0].completed_at, utc("2026-10-16T11:25:00Z"));
+        assert_eq!(records[0].session_type, "Work Session");
+    }
+
+    #[test]
+    fn skips_rows_it_cannot_read() {
+        let content = "Task,Start Time,End Time,Duration\n\
+            Good,2026-10-16 09:00,,25\n\
+            No date,sometime,,25\n\
+            No length,2026-10-16 10:00,,\n\
+            Backwards,2026-10-16 11:00,2026-10-16 10:00,\n\
+            Short row\n\
+            Worked out,2026-10-16 12:00,2026-10-16 12:50,\n";
+        let (records, skipped) = parse_csv(content, &Utc).unwrap();
+        let read: Vec<(&str, u32)> = records.iter().map(|entry| (entry.label.as_str(), entry.duration_secs)).collect();
+        assert_eq!(read, [("Good", 25 * 60), ("Worked out", 50 * 60)]);
+        assert_eq!(skipped, 4);
+    }
+
+    #[test]
+    fn rejects_files_without_times_or_lengths() {
+        let error = parse_csv("Task,Project\nReport,Work\n", &Utc).unwrap_err();
+        assert!(error.to_string().contains("no date or time columns (found: task, project)"));
+        let error = parse_csv("Task,Date\nReport,2026-10-16\n", &Utc).unwrap_err();
+        assert!(error.to_string().contains("no duration or end time column"));
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use crate::persistence::Persistence;
+
+const FILE: &str = "notes.json";
+
+// Longest note kept, in characters
+pub const MAX_NOTE_CHARS: usize = 500;
+
+// Free-form notes jotted down at the end of a day, one per date. Saved in
+// notes.json in the data directory, encrypted like the state when it is.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Journal {
+    notes: BTreeMap<NaiveDate, String>,
+}
+
+impl Journal {
+    pub fn load() -> Result<Self> {
+        let Some(content) = Persistence::read_private(FILE)? else {
+            return Ok(Self::default());
+        };
+        let notes = serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", FILE))?;
+        Ok(Self { notes })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.notes).context("Failed to serialize notes")?;
+        Persistence::write_private(FILE, &content)
+    }
+
+    pub fn note(&self, date: NaiveDate) -> Option<&str> {
+        self.notes.get(&date).map(String::as_str)
+    }
+
+    // Blank text removes the day's note
+    pub fn set(&mut self, date: NaiveDate, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.notes.remove(&date);
+        } else {
+            self.notes.insert(date, text.chars().take(MAX_NOTE_CHARS).collect());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_one_note_per_day() {
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let mut journal = Journal::default();
+        journal.set(monday, "  Shipped the export \n");
+        assert_eq!(journal.note(monday), Some("Shipped the export"));
+        journal.set(monday, &"x".repeat(MAX_NOTE_CHARS + 10));
+        assert_eq!(journal.note(monday).map(|note| note.len()), Some(MAX_NOTE_CHARS));
+        journal.set(monday, " ");
+        assert_eq!(journal.note(monday), None);
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use futures::channel::mpsc::UnboundedSender;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::{ActiveTimer, SessionInfo, TimerState};
+
+// Team timers on the local network. The leader announces itself over mDNS
+// with its group name; followers in the same group find it, ask for its state
+// over UDP, and run their timer in step with it, so the team breaks together.
+// Labels stay on each machine, only the session and its time left are sent.
+
+const SERVICE_TYPE: &str = "_pomodoro._udp.local.";
+
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(10);  // Followers ask again this often
+const FOLLOWER_TIMEOUT: Duration = Duration::from_secs(35);  // Followers silent this long have left
+const RESYNC_INTERVAL: Duration = Duration::from_secs(30);  // The state goes out again this often while running
+const MAX_MESSAGE: usize = 1024;
+
+// The leader's timer, as followers copy it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupState {
+    pub state: TimerState,
+    pub focus_mode: bool,
+    pub remaining_secs: u32,
+    pub total_secs: u32,
+}
+
+impl GroupState {
+    fn from_info(info: &SessionInfo) -> Self {
+        let timer = info.active_timer();
+        Self {
+            state: info.current_state.clone(),
+            focus_mode: info.is_focus_mode,
+            remaining_secs: timer.remaining_secs,
+            total_secs: timer.total_secs,
+        }
+    }
+
+    pub fn timer(&self) -> ActiveTimer {
+        ActiveTimer { remaining_secs: self.remaining_secs, total_secs: self.total_secs }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Follow { group: String },  // Follower to leader, repeated while following
+    State { group: String, state: GroupState },  // Leader to followers
+}
+
+impl Message {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+
+    fn send(&self, socket: &UdpSocket, to: SocketAddr) {
+        if let Ok(bytes) = serde_json::to_vec(self) {
+            let _ = socket.send_to(&bytes, to);
+        }
+    }
+}
+
+fn group(config: &Config) -> Option<String> {
+    config.lan_group.as_deref().map(str::trim).filter(|group| !group.is_empty()).map(str::to_string)
+}
+
+struct Shared {
+    socket: UdpSocket,
+    group: String,
+    followers: Mutex<HashMap<SocketAddr, Instant>>,  // Last time each follower asked
+    last: Mutex<Option<(GroupState, Instant)>>,  // State last sent, and when
+}
+
+impl Shared {
+    fn send_state(&self, state: &GroupState, to: SocketAddr) {
+        Message::State { group: self.group.clone(), state: state.clone() }.send(&self.socket, to);
+    }
+}
+
+// Event bus subscriber on the leader: sends every change to the followers
+pub struct LanLeader {
+    shared: Arc<Shared>,
+    _mdns: ServiceDaemon,  // Keeps the announcement up
+}
+
+impl LanLeader {
+    // None unless this timer leads a group
+    pub fn spawn(config: &Config) -> Option<Self> {
+        let group = group(config).filter(|_| config.lan_leader)?;
+        match Self::start(group, config.lan_port) {
+            Ok(leader) => Some(leader),
+            Err(e) => {
+                notifications::log_error(&format!("Failed to lead the LAN group: {:#}", e));
+                None
+            }
+        }
+    }
+
+    fn start(group: String, port: u16) -> Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).with_context(|| format!("Failed to listen on UDP port {}", port))?;
+        let mdns = ServiceDaemon::new().context("Failed to start mDNS")?;
+        let id = &uuid::Uuid::new_v4().simple().to_string()[..8];
+        let service = ServiceInfo::new(
+            SERVICE_TYPE,
+            &format!("pomodoro-{}", id),
+            &format!("pomodoro-{}.local.", id),
+            (),
+            port,
+            &[("group", group.as_str())][..],
+        )
+        .context("Failed to describe the mDNS service")?
+        .enable_addr_auto();
+        mdns.register(service).context("Failed to announce the group over mDNS")?;
+
+        let shared = Arc::new(Shared {
+            socket: socket.try_clone().context("Failed to share the UDP socket")?,
+            group,
+            followers: Mutex::new(HashMap::new()),
+            last: Mutex::new(None),
+        });
+        let worker = shared.clone();
+        std::thread::Builder::new()
+            .name("lan-leader".to_string())
+            .spawn(move || listen(&worker))
+            .context("Failed to start the LAN thread")?;
+        notifications::log_info(&format!("Leading LAN group \"{}\" on UDP port {}", shared.group, port));
+        Ok(Self { shared, _mdns: mdns })
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let state = GroupState::from_info(info);
+        let mut last = self.shared.last.lock();
+        // Ticks only go out now and then, so followers that drifted catch up
+        if *event == SessionEvent::Tick && last.as_ref().is_some_and(|(_, sent)| sent.elapsed() < RESYNC_INTERVAL) {
+            return;
+        }
+        if *event != SessionEvent::Tick && last.as_ref().is_some_and(|(sent, _)| *sent == state) {
+            return;
+        }
+        *last = Some((state.clone(), Instant::now()));
+        drop(last);
+
+        let mut followers = self.shared.followers.lock();
+        followers.retain(|_, asked| asked.elapsed() < FOLLOWER_TIMEOUT);
+        for follower in followers.keys() {
+            self.shared.send_state(&state, *follower);
+        }
+    }
+}
+
+// Leader thread: takes note of followers and sends newcomers the state right away
+fn listen(shared: &Shared) {
+    let mut buffer = [0; MAX_MESSAGE];
+    loop {
+        let Ok((len, from)) = shared.socket.recv_from(&mut buffer) else {
+            continue;
+        };
+        let Some(Message::Follow { group }) = Message::parse(&buffer[..len]) else {
+            continue;
+        };
+        if group != shared.group {
+            continue;
+        }
+        if shared.followers.lock().insert(from, Instant::now()).is_none() {
+            notifications::log_info(&format!("{} follows the LAN group", from.ip()));
+            if let Some((state, _)) = shared.last.lock().clone() {
+                shared.send_state(&state, from);
+            }
+        }
+    }
+}
+
+// Follows the group's leader, once one turns up, and sends its state to
+// `states`. Does nothing unless this timer is in a group without leading it.
+pub fn spawn_follower(config: &Config, states: UnboundedSender<GroupState>) {
+    let Some(group) = group(config).filter(|_| !config.lan_leader) else {
+        return;
+    };
+    let started = ServiceDaemon::new().context("Failed to start mDNS").and_then(|mdns| {
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).context("Failed to open a UDP socket")?;
+        socket.set_read_timeout(Some(Duration::from_secs(1))).context("Failed to set the UDP timeout")?;
+        let thread_group = group.clone();
+        std::thread::Builder::new()
+            .name("lan-follower".to_string())
+            .spawn(move || follow(mdns, socket, thread_group, states))
+            .context("Failed to start the LAN thread")
+    });
+    match started {
+        Ok(_) => notifications::log_info(&format!("Looking for the leader of LAN group \"{}\"", group)),
+        Err(e) => notifications::log_error(&format!("Failed to follow the LAN group: {:#}", e)),
+    }
+}
+
+fn follow(mdns: ServiceDaemon, socket: UdpSocket, group: String, states: UnboundedSender<GroupState>) {
+    let browse = match mdns.browse(SERVICE_TYPE) {
+        Ok(browse) => browse,
+        Err(e) => {
+            notifications::log_error(&format!("Failed to look for the LAN group: {}", e));
+            return;
+        }
+    };
+    let mut leader: Option<(String, SocketAddr)> = None;  // mDNS name and address
+    let mut asked: Option<Instant> = None;
+    let mut buffer = [0; MAX_MESSAGE];
+    loop {
+        while let Ok(event) = browse.try_recv() {
+            match event {
+                ServiceEvent::ServiceResolved(service) if service.get_property_val_str("group") == Some(group.as_str()) => {
+                    let Some(ip) = service.get_addresses_v4().into_iter().next().copied() else {
+                        continue;
+                    };
+                    let address = SocketAddr::from((ip, service.get_port()));
+                    if leader.as_ref().is_none_or(|(_, known)| *known != address) {
+                        notifications::log_info(&format!("Following the LAN group leader at {}", address));
+                        asked = None;
+                    }
+                    leader = Some((service.get_fullname().to_string(), address));
+                }
+                ServiceEvent::ServiceRemoved(_, name) if leader.as_ref().is_some_and(|(known, _)| *known == name) => {
+                    notifications::log_info("The LAN group leader went away");
+                    leader = None;
+                }
+                _ => {}
+            }
+        }
+
+        let Some((_, address)) = leader.clone() else {
+            std::thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+        if asked.is_none_or(|asked| asked.elapsed() >= FOLLOW_INTERVAL) {
+            Message::Follow { group: group.clone() }.send(&socket, address);
+            asked = Some(Instant::now());
+        }
+        // Times out after a second, to keep up with mDNS
+        let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+            continue;
+        };
+        if from.ip() != address.ip() {
+            continue;
+        }
+        if let Some(Message::State { group: from_group, state }) = Message::parse(&buffer[..len]) {
+            if from_group == group && states.unbounded_send(state).is_err() {
+                return;  // The app is gone
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_group_messages() {
+        let follow = Message::parse(br#"{"type":"follow","group":"office"}"#);
+        assert_eq!(follow, Some(Message::Follow { group: "office".to_string() }));
+
+        let state = Message::State {
+            group: "office".to_string(),
+            state: GroupState { state: TimerState::ShortBreak, focus_mode: false, remaining_secs: 240, total_secs: 300 },
+        };
+        let bytes = serde_json::to_vec(&state).unwrap();
+        assert_eq!(Message::parse(&bytes), Some(state));
+
+        assert_eq!(Message::parse(b"not json"), None);
+        assert_eq!(Message::parse(br#"{"type":"shout","group":"office"}"#), None);
+    }
+
+    #[test]
+    fn only_group_members_take_part() {
+        let config = Config { lan_group: Some("  ".to_string()), ..Config::default() };
+        assert_eq!(group(&config), None);
+        let config = Config { lan_group: Some(" office ".to_string()), ..Config::default() };
+        assert_eq!(group(&config).as_deref(), Some("office"));
+    }
+}
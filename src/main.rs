@@ -1,62 +1,321 @@
 use gpui::*;
+use futures::StreamExt;
 
 mod app;
+mod blocker;
+mod calendar;
+mod clock;
+mod commit_hook;
 mod config;
+mod doctor;
+mod ducking;
+mod events;
+mod feed;
+mod focus_apps;
+mod git;
+#[cfg(feature = "health")]
+mod health;
+mod import;
+mod journal;
+mod lan;
+mod milestones;
+mod mqtt;
 mod notifications;
 mod persistence;
+mod placement;
+mod plugins;
+mod power;
+mod project;
+mod quick_timer;
+mod recovery;
+mod remote;
+mod schedule;
+mod scripts;
+mod sound;
 mod state;
+mod stats;
+mod status;
+mod strict;
 mod theme;
 mod timer;
 mod ui;
+mod url_scheme;
 
-use app::{PomodoroApp, QuitApp};
+use app::{About, CopyLabel, FocusNext, FocusPrev, OpenSettings, PasteLabel, PomodoroApp, Present, QuitApp, ResetTimer, SkipSession, ToggleTimer};
 use config::Config;
+use persistence::{Persistence, PASSPHRASE_ENV};
+use ui::{Mirror, Onboarding, Unlock};
+use url_scheme::IncomingUrl;
 
 fn main() {
+    // Command-line subcommands run without opening a window
+    let mut launch_url = None;
+    let mut present = false;
+    let mut project_dir = None;
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        // A pomodoro:// link: handled by the running timer, or by this one once it's open
+        Some(url) if url.starts_with(url_scheme::SCHEME) => match url_scheme::forward(url) {
+            Some(Ok(result)) => {
+                println!("{}", result);
+                std::process::exit(0);
+            }
+            Some(Err(message)) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+            None => launch_url = Some(url.to_string()),
+        },
+        Some("doctor") => std::process::exit(doctor::run()),
+        Some("test-notification") => std::process::exit(test_notification()),
+        Some("import") => std::process::exit(import_history()),
+        Some("prepare-commit-msg") => std::process::exit(commit_hook::run(&std::env::args().skip(2).collect::<Vec<_>>())),
+        Some("status") => std::process::exit(status::run(std::env::args().skip(2).any(|arg| arg == "--json"))),
+        Some("dump-state") => std::process::exit(status::run_dump()),
+        Some("--present") => present = true,
+        Some("--project") => project_dir = Some(std::path::PathBuf::from(std::env::args().nth(2).unwrap_or_else(|| ".".to_string()))),
+        Some("--mirror") => {
+            run_mirror();
+            return;
+        }
+        Some(other) => {
+            eprintln!("Unknown command: {}", other);
+            eprintln!("Usage: pomodoro-timer [doctor | test-notification | import <file.csv> | status [--json] | dump-state | --project [dir] | prepare-commit-msg <file> | prepare-commit-msg --install | --mirror | --present | pomodoro://<action>]");
+            std::process::exit(2);
+        }
+    }
+
+    // First run: nothing saved yet, so let the user pick settings before creating the config
+    let is_first_run = !Config::exists() && !Persistence::has_saved_state();
+
     // Load configuration
-    let config = match Config::load() {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            eprintln!("Failed to load configuration: {}", e);
-            eprintln!("Using default configuration...");
-            Config::default()
+    let mut config = if is_first_run {
+        Config::default()
+    } else {
+        match Config::load() {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                eprintln!("Failed to load configuration: {}", e);
+                eprintln!("Using default configuration...");
+                Config::default()
+            }
         }
     };
 
-    // Validate configuration
+    // Validate configuration; problems are also shown in the window at startup
+    let mut notices = Vec::new();
     if let Err(e) = config.validate() {
         eprintln!("Configuration validation failed: {}", e);
-        eprintln!("\nPlease check your config file at: ~/.config/pomodoro-timer/config.toml");
-        std::process::exit(1);
+        if let Ok(path) = Config::config_path() {
+            eprintln!("Please check your config file at: {}", path.display());
+        }
+        eprintln!("Using default configuration...");
+        notices.push(format!("{}. Using defaults; run `pomodoro-timer doctor`.", e));
+        config = Config::default();
+    }
+    // Projects' .pomodoro.toml plans, offered next to the templates
+    let (project, problems) = project::add_to(&mut config, project_dir.as_deref());
+    for problem in problems {
+        eprintln!("[WARN] {}", problem);
+        notices.push(problem);
+    }
+    for warning in config.warnings() {
+        eprintln!("[WARN] {} ({})", warning.message, warning.hint);
+        notices.push(warning.message);
     }
 
-    Application::new().run(move |cx| {
-        // Bind only quit shortcut globally
+    // An encrypted state needs the passphrase before anything is loaded or saved
+    let needs_unlock = if config.encrypt_state || Persistence::is_encrypted() {
+        match Persistence::unlock_from_env(config.encrypt_state) {
+            Ok(unlocked) => !unlocked,
+            Err(e) => {
+                eprintln!("Failed to unlock state with {}: {:#}", PASSPHRASE_ENV, e);
+                true
+            }
+        }
+    } else {
+        false
+    };
+
+    // Links arrive from the OS (macOS), from other processes, or on the command line
+    let (url_tx, mut url_rx) = futures::channel::mpsc::unbounded::<IncomingUrl>();
+    if let Some(url) = launch_url {
+        let _ = url_tx.unbounded_send(IncomingUrl { url, reply: None });
+    }
+    // Before the event loop starts, as it may have to ask other programs
+    let spot = placement::locate(config.display.as_deref());
+    let app = Application::new();
+    let os_urls = url_tx.clone();
+    app.on_open_urls(move |urls| {
+        for url in urls {
+            let _ = os_urls.unbounded_send(IncomingUrl { url, reply: None });
+        }
+    });
+
+    app.run(move |cx| {
+        // Bind quit and focus navigation shortcuts globally
         cx.bind_keys([
             KeyBinding::new("cmd-q", QuitApp, None),
+            KeyBinding::new("tab", FocusNext, None),
+            KeyBinding::new("shift-tab", FocusPrev, None),
+            KeyBinding::new("cmd-,", OpenSettings, None),
+            // Cmd on macOS, Ctrl elsewhere
+            KeyBinding::new("cmd-v", PasteLabel, None),
+            KeyBinding::new("ctrl-v", PasteLabel, None),
+            KeyBinding::new("cmd-c", CopyLabel, None),
+            KeyBinding::new("ctrl-c", CopyLabel, None),
         ]);
+        // Quit works from the menu even without a window
+        cx.on_action(|_: &QuitApp, cx| cx.quit());
 
-        // Other shortcuts will be bound contextually in render to respect edit mode
-        // Set window size and position - compact square
-        let window_size = size(px(240.0), px(240.0));
-        let centered_bounds = Bounds::centered(None, window_size, cx);
-
-        // Open the main window as floating popup
-        cx.open_window(
-            WindowOptions {
-                window_bounds: Some(WindowBounds::Windowed(centered_bounds)),
-                titlebar: None,
-                window_decorations: Some(WindowDecorations::Client),
-                kind: WindowKind::PopUp, // Floating window
-                is_movable: true,
-                is_resizable: false,
-                focus: true,
-                show: true,
-                app_id: Some("pomodoro-timer".to_string()),
-                ..Default::default()
+        // Application menu bar (macOS)
+        cx.set_menus(vec![
+            Menu {
+                name: "Pomodoro Timer".into(),
+                items: vec![
+                    MenuItem::action("About Pomodoro Timer", About),
+                    MenuItem::separator(),
+                    MenuItem::action("Preferences…", OpenSettings),
+                    MenuItem::separator(),
+                    MenuItem::os_submenu("Services", SystemMenuType::Services),
+                    MenuItem::separator(),
+                    MenuItem::action("Quit Pomodoro Timer", QuitApp),
+                ],
             },
-            |_window, cx| cx.new(|cx| PomodoroApp::new(config.clone(), cx)),
-        )
-        .expect("Failed to open window");
+            Menu {
+                name: "Timer".into(),
+                items: vec![
+                    MenuItem::action("Start/Pause", ToggleTimer),
+                    MenuItem::action("Skip", SkipSession),
+                    MenuItem::action("Reset", ResetTimer),
+                    MenuItem::separator(),
+                    MenuItem::action("Presentation Mode", Present),
+                ],
+            },
+        ]);
+
+        // Other shortcuts will be bound contextually in render to respect edit mode
+        // Open the main window as a floating popup on the configured display
+        let window_options = placement::window_options(&config, spot, cx);
+
+        if is_first_run {
+            cx.open_window(window_options, |_window, cx| {
+                cx.new(|_cx| Onboarding::new(config.clone()))
+            })
+            .expect("Failed to open window");
+        } else if needs_unlock {
+            cx.open_window(window_options, |_window, cx| {
+                cx.new(|cx| Unlock::new(config.clone(), notices.clone(), cx))
+            })
+            .expect("Failed to open window");
+        } else {
+            let window = cx
+                .open_window(window_options, |window, cx| {
+                    cx.new(|cx| PomodoroApp::new(config.clone(), notices.clone(), window, cx))
+                })
+                .expect("Failed to open window");
+            if let Some(name) = project.clone() {
+                let _ = window.update(cx, |app, _window, cx| app.handle_open_project(name, cx));
+            }
+            if present {
+                let _ = window.update(cx, |app, _window, cx| app.handle_present(cx));
+            }
+        }
+
+        url_scheme::listen(url_tx.clone());
+        cx.spawn(async move |cx| {
+            while let Some(incoming) = url_rx.next().await {
+                if cx.update(|cx| open_url(incoming, cx)).is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    });
+}
+
+// `pomodoro-timer --mirror`: a display-only window following the timer that's
+// already open, e.g. on a second monitor. Nothing is loaded or saved here.
+fn run_mirror() {
+    let config = if Config::exists() {
+        Config::load().unwrap_or_default()
+    } else {
+        Config::default()
+    };
+
+    let spot = placement::locate(config.display.as_deref());
+    Application::new().run(move |cx| {
+        cx.bind_keys([KeyBinding::new("cmd-q", QuitApp, None)]);
+        cx.on_action(|_: &QuitApp, cx| cx.quit());
+        cx.on_window_closed(|cx| cx.quit()).detach();
+
+        let window_options = placement::mirror_window_options(&config, spot, cx);
+        cx.open_window(window_options, |_window, cx| cx.new(|cx| Mirror::new(config.clone(), cx)))
+            .expect("Failed to open window");
+    });
+}
+
+// Passes a pomodoro:// link to the timer window
+fn open_url(incoming: IncomingUrl, cx: &mut App) {
+    let app = cx.windows().into_iter().find_map(|window| {
+        window.update(cx, |root, _window, _cx| root.downcast::<PomodoroApp>().ok()).ok().flatten()
     });
+    let result = match app {
+        Some(app) => app.update(cx, |app, cx| app.handle_url(&incoming.url, cx)),
+        None => Err("The timer is locked or still being set up".to_string()),
+    };
+    if let Some(reply) = incoming.reply {
+        let _ = reply.send(result);
+    }
+}
+
+// `pomodoro-timer import <file.csv>`: add sessions exported from another app to the history
+fn import_history() -> i32 {
+    let Some(path) = std::env::args().nth(2) else {
+        eprintln!("Usage: pomodoro-timer import <file.csv>");
+        return 2;
+    };
+
+    let config = if Config::exists() {
+        Config::load().unwrap_or_default()
+    } else {
+        Config::default()
+    };
+    if let Err(e) = Persistence::unlock_from_env(config.encrypt_state) {
+        eprintln!("Failed to unlock state with {}: {:#}", PASSPHRASE_ENV, e);
+        return 1;
+    }
+
+    match import::run(std::path::Path::new(&path), &config) {
+        Ok(summary) => {
+            println!("{}", summary.describe());
+            0
+        }
+        Err(e) => {
+            eprintln!("Import failed: {:#}", e);
+            1
+        }
+    }
+}
+
+// `pomodoro-timer test-notification`: send a sample completion notification and report the outcome
+fn test_notification() -> i32 {
+    // Don't create a config file here, that would skip the first-run setup
+    let config = if Config::exists() {
+        Config::load().unwrap_or_default()
+    } else {
+        Config::default()
+    };
+
+    notifications::probe_capabilities();
+    match notifications::send_test_notification(&config) {
+        Ok(()) => {
+            println!("Test notification sent. If nothing appeared, check your system notification settings.");
+            0
+        }
+        Err(e) => {
+            eprintln!("Test notification failed: {}", e);
+            1
+        }
+    }
 }
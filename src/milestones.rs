@@ -0,0 +1,99 @@
+use anyhow::{bail, Context, Result};
+
+use crate::state::ActiveTimer;
+
+// A point in a work session worth announcing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Milestone {
+    Done(u32),  // This percent of the session has passed: "50%", or "halfway"
+    Left(u32),  // This many seconds remain: "5m"
+}
+
+impl Milestone {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_lowercase();
+        let milestone = if text == "halfway" {
+            Milestone::Done(50)
+        } else if let Some(percent) = text.strip_suffix('%') {
+            Milestone::Done(percent.trim().parse().with_context(|| format!("\"{}\" isn't a percentage", text))?)
+        } else if let Some(minutes) = text.strip_suffix('m') {
+            let minutes: u32 = minutes.trim().parse().with_context(|| format!("\"{}\" isn't a number of minutes", text))?;
+            Milestone::Left(minutes * 60)
+        } else {
+            bail!("\"{}\" isn't a milestone; write \"halfway\", \"75%\" or \"5m\"", text);
+        };
+        match milestone {
+            Milestone::Done(percent) if !(1..100).contains(&percent) => bail!("{}% is not during the session", percent),
+            Milestone::Left(0) => bail!("0m is the end of the session"),
+            milestone => Ok(milestone),
+        }
+    }
+
+    fn reached(&self, timer: &ActiveTimer) -> bool {
+        match *self {
+            // A session shorter than this has nothing left to announce
+            Milestone::Left(secs) => timer.remaining_secs <= secs && timer.total_secs > secs,
+            Milestone::Done(percent) => (timer.total_secs - timer.remaining_secs) * 100 >= timer.total_secs * percent,
+        }
+    }
+
+    // Seconds left when it comes, to tell which of several came last
+    fn remaining_at(&self, timer: &ActiveTimer) -> u32 {
+        match *self {
+            Milestone::Left(secs) => secs,
+            Milestone::Done(percent) => timer.total_secs - timer.total_secs * percent / 100,
+        }
+    }
+
+    pub fn announcement(&self) -> String {
+        match *self {
+            Milestone::Done(50) => "Halfway there".to_string(),
+            Milestone::Done(percent) => format!("{}% done", percent),
+            Milestone::Left(60) => "1 minute left".to_string(),
+            Milestone::Left(secs) => format!("{} minutes left", secs / 60),
+        }
+    }
+}
+
+// The announcement for milestones `timer` has reached since they were last
+// checked, noting them in `announced` (indexes into `milestones`). Several
+// reached at once, e.g. after the computer slept, give only the latest one.
+// Unreadable milestones are skipped; Config::warnings reports them.
+pub fn due(milestones: &[String], timer: &ActiveTimer, announced: &mut Vec<usize>) -> Option<String> {
+    let reached: Vec<(usize, Milestone)> = milestones
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !announced.contains(index))
+        .filter_map(|(index, text)| Some((index, Milestone::parse(text).ok()?)))
+        .filter(|(_, milestone)| milestone.reached(timer))
+        .collect();
+    announced.extend(reached.iter().map(|(index, _)| index));
+    reached.iter().map(|(_, milestone)| milestone).min_by_key(|milestone| milestone.remaining_at(timer)).map(Milestone::announcement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_each_milestone_once() {
+        assert_eq!(Milestone::parse(" Halfway ").unwrap(), Milestone::Done(50));
+        assert_eq!(Milestone::parse("5m").unwrap(), Milestone::Left(300));
+        assert!(Milestone::parse("100%").is_err());
+        assert!(Milestone::parse("soon").is_err());
+
+        let milestones = vec!["halfway".to_string(), "5m".to_string(), "1m".to_string(), "soon".to_string()];
+        let mut announced = Vec::new();
+        let at = |remaining_secs| ActiveTimer { remaining_secs, total_secs: 1500 };
+        assert_eq!(due(&milestones, &at(800), &mut announced), None);
+        assert_eq!(due(&milestones, &at(750), &mut announced).as_deref(), Some("Halfway there"));
+        assert_eq!(due(&milestones, &at(749), &mut announced), None);
+        // Asleep through two of them
+        assert_eq!(due(&milestones, &at(30), &mut announced).as_deref(), Some("1 minute left"));
+        assert_eq!(announced, [0, 1, 2]);
+
+        // A 4-minute session has no "5 minutes left"
+        let mut announced = Vec::new();
+        assert_eq!(due(&milestones[1..2], &ActiveTimer { remaining_secs: 200, total_secs: 240 }, &mut announced), None);
+    }
+}
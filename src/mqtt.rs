@@ -0,0 +1,301 @@
+use futures::channel::mpsc::UnboundedSender;
+use parking_lot::Mutex;
+use rumqttc::{Client, Connection, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::{SessionInfo, TimerState};
+
+// Publishes the timer state to an MQTT broker for home automation.
+// QoS 0 over plain TCP, which is what local brokers like Mosquitto accept by default.
+
+// Topic Home Assistant announces its restarts on, and the prefix it discovers entities under
+const HOME_ASSISTANT_STATUS: &str = "homeassistant/status";
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+const QUEUE_SIZE: usize = 64;  // Messages waiting for the connection thread
+
+// Where and how to connect, taken from the config
+#[derive(Debug, Clone)]
+struct BrokerSettings {
+    host: String,
+    port: u16,
+    prefix: String,
+    username: Option<String>,
+    password: Option<String>,
+    discovery: bool,  // Announce entities to Home Assistant
+}
+
+impl BrokerSettings {
+    fn from_config(config: &Config) -> Option<Self> {
+        let broker = config.mqtt_broker.as_deref()?.trim();
+        let host = broker.strip_prefix("mqtt://").unwrap_or(broker).trim_end_matches('/');
+        if host.is_empty() || broker.starts_with("mqtts://") {
+            return None;
+        }
+        let (host, port) = match host.rsplit_once(':').map(|(name, port)| (name, port.parse::<u16>())) {
+            Some((name, Ok(port))) => (name, port),
+            _ => (host, DEFAULT_PORT),
+        };
+        Some(Self {
+            host: host.to_string(),
+            port,
+            prefix: config.mqtt_topic_prefix.trim_end_matches('/').to_string(),
+            username: config.mqtt_username.clone(),
+            password: config.mqtt_password.clone(),
+            discovery: config.mqtt_discovery,
+        })
+    }
+
+    fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+
+    // Id of this timer in Home Assistant, from the topic prefix
+    fn node_id(&self) -> String {
+        self.prefix.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+    }
+}
+
+// Sent by the broker on the command topic: turn the Home Assistant switch on or off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MqttCommand {
+    SetRunning(bool),
+}
+
+struct Message {
+    topic: String,
+    payload: String,
+    retain: bool,
+}
+
+// Event bus subscriber: publishes <prefix>/state (retained) on every change and
+// once a minute while running, and <prefix>/event with each event's name
+pub struct MqttPublisher {
+    settings: BrokerSettings,
+    client: Client,
+    last_state: Arc<Mutex<Option<String>>>,  // Sent again after reconnecting
+    sessions_until_long_break: u32,
+    last_shown: Mutex<Option<(TimerState, u32)>>,  // State and remaining minutes last published
+}
+
+impl MqttPublisher {
+    // Starts the connection thread, or returns None when no broker is configured.
+    // Commands received from the broker are sent to `commands`.
+    pub fn spawn(config: &Config, commands: UnboundedSender<MqttCommand>) -> Option<Self> {
+        let settings = BrokerSettings::from_config(config)?;
+        let client_id = format!("pomodoro-timer-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let mut options = MqttOptions::new(client_id, settings.host.clone(), settings.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        // Retained, so subscribers see when the app goes away
+        options.set_last_will(LastWill::new(settings.topic("status"), "offline", QoS::AtMostOnce, true));
+        if let Some(username) = &settings.username {
+            options.set_credentials(username.clone(), settings.password.clone().unwrap_or_default());
+        }
+
+        let (client, connection) = Client::new(options, QUEUE_SIZE);
+        let last_state = Arc::new(Mutex::new(None));
+        let (worker_settings, worker_client, worker_state) = (settings.clone(), client.clone(), last_state.clone());
+        std::thread::Builder::new()
+            .name("mqtt".to_string())
+            .spawn(move || run(worker_settings, worker_client, connection, worker_state, commands))
+            .ok()?;
+        Some(Self {
+            settings,
+            client,
+            last_state,
+            sessions_until_long_break: config.cycle_length(),
+            last_shown: Mutex::new(None),
+        })
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let shown = (info.current_state.clone(), info.active_timer().remaining_secs.div_ceil(60));
+        {
+            let mut last_shown = self.last_shown.lock();
+            // Ticks only matter when the minute changes
+            if *event == SessionEvent::Tick && last_shown.as_ref() == Some(&shown) {
+                return;
+            }
+            *last_shown = Some(shown);
+        }
+
+        let state = info.status_json(notifications::is_private(), self.sessions_until_long_break).to_string();
+        *self.last_state.lock() = Some(state.clone());
+        self.publish("state", state, true);
+        if *event != SessionEvent::Tick {
+            self.publish("event", event.name().to_string(), false);
+        }
+    }
+
+    fn publish(&self, name: &str, payload: String, retain: bool) {
+        // A full queue means the broker is unreachable; the state goes out again on reconnect
+        let _ = self.client.try_publish(self.settings.topic(name), QoS::AtMostOnce, retain, payload);
+    }
+}
+
+// Entities Home Assistant creates for the timer: its state, the minutes left,
+// and a switch that starts and pauses it
+fn discovery_messages(settings: &BrokerSettings) -> Vec<Message> {
+    let node_id = settings.node_id();
+    let device = json!({
+        "identifiers": [node_id],
+        "name": "Pomodoro Timer",
+        "manufacturer": "pomodoro-timer",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let entity = |component: &str, object_id: &str, name: &str, extra: serde_json::Value| {
+        let mut config = json!({
+            "name": name,
+            "unique_id": format!("{}_{}", node_id, object_id),
+            "state_topic": settings.topic("state"),
+            "availability_topic": settings.topic("status"),
+            "device": device,
+        });
+        if let (Some(config), Some(extra)) = (config.as_object_mut(), extra.as_object()) {
+            config.extend(extra.clone());
+        }
+        Message {
+            topic: format!("{}/{}/{}/{}/config", DISCOVERY_PREFIX, component, node_id, object_id),
+            payload: config.to_string(),
+            retain: true,
+        }
+    };
+
+    vec![
+        entity("sensor", "state", "State", json!({
+            "value_template": "{{ value_json.state }}",
+            "json_attributes_topic": settings.topic("state"),
+            "icon": "mdi:timer-outline",
+        })),
+        entity("sensor", "remaining", "Remaining", json!({
+            "value_template": "{{ value_json.remaining_minutes }}",
+            "unit_of_measurement": "min",
+            "icon": "mdi:timer-sand",
+        })),
+        entity("switch", "running", "Running", json!({
+            "command_topic": settings.topic("set/running"),
+            "value_template": "{{ 'ON' if value_json.running else 'OFF' }}",
+            "icon": "mdi:play-pause",
+        })),
+    ]
+}
+
+// Connection loop: rumqttc reconnects whenever the connection is polled again,
+// so failures just wait a growing delay. Messages queued while disconnected are
+// dropped, and the latest state is sent once connected.
+fn run(
+    settings: BrokerSettings,
+    client: Client,
+    mut connection: Connection,
+    last_state: Arc<Mutex<Option<String>>>,
+    commands: UnboundedSender<MqttCommand>,
+) {
+    let mut delay = Duration::from_secs(1);
+    while let Ok(notification) = connection.recv() {
+        match notification {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                notifications::log_info(&format!("Connected to MQTT broker {}", settings.address()));
+                delay = Duration::from_secs(1);
+                announce(&settings, &client, &last_state);
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let payload = String::from_utf8_lossy(&publish.payload);
+                handle_message(&settings, &client, &publish.topic, payload.trim(), &commands);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                notifications::log_error(&format!("MQTT broker {}: {}", settings.address(), e));
+                connection.eventloop.clean();
+                connection.eventloop.pending.clear();
+                std::thread::sleep(delay);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+// Sent on every (re)connect: availability, discovery, subscriptions, and the current state
+fn announce(settings: &BrokerSettings, client: &Client, last_state: &Mutex<Option<String>>) {
+    let mut messages = vec![Message { topic: settings.topic("status"), payload: "online".to_string(), retain: true }];
+    let mut topics = vec![settings.topic("set/running")];
+    if settings.discovery {
+        messages.extend(discovery_messages(settings));
+        topics.push(HOME_ASSISTANT_STATUS.to_string());
+    }
+    if let Some(state) = last_state.lock().clone() {
+        messages.push(Message { topic: settings.topic("state"), payload: state, retain: true });
+    }
+    for topic in topics {
+        let _ = client.try_subscribe(topic, QoS::AtMostOnce);
+    }
+    send(client, messages);
+}
+
+// Commands from Home Assistant, and its restarts, after which it needs the entities again
+fn handle_message(settings: &BrokerSettings, client: &Client, topic: &str, payload: &str, commands: &UnboundedSender<MqttCommand>) {
+    if topic == settings.topic("set/running") {
+        match payload {
+            "ON" => {
+                let _ = commands.unbounded_send(MqttCommand::SetRunning(true));
+            }
+            "OFF" => {
+                let _ = commands.unbounded_send(MqttCommand::SetRunning(false));
+            }
+            other => notifications::log_error(&format!("Unknown MQTT command \"{}\" on {}", other, topic)),
+        }
+    } else if topic == HOME_ASSISTANT_STATUS && payload == "online" && settings.discovery {
+        send(client, discovery_messages(settings));
+    }
+}
+
+fn send(client: &Client, messages: Vec<Message>) {
+    for message in messages {
+        let _ = client.try_publish(message.topic, QoS::AtMostOnce, message.retain, message.payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_entities_to_home_assistant() {
+        let config = Config { mqtt_broker: Some("broker.lan".to_string()), mqtt_topic_prefix: "desk/pomodoro".to_string(), ..Config::default() };
+        let messages = discovery_messages(&BrokerSettings::from_config(&config).unwrap());
+
+        let topics: Vec<_> = messages.iter().map(|message| message.topic.as_str()).collect();
+        assert_eq!(topics, [
+            "homeassistant/sensor/desk_pomodoro/state/config",
+            "homeassistant/sensor/desk_pomodoro/remaining/config",
+            "homeassistant/switch/desk_pomodoro/running/config",
+        ]);
+        let switch: serde_json::Value = serde_json::from_str(&messages[2].payload).unwrap();
+        assert_eq!(switch["command_topic"], "desk/pomodoro/set/running");
+        assert_eq!(switch["state_topic"], "desk/pomodoro/state");
+        assert!(messages.iter().all(|message| message.retain));
+    }
+
+    #[test]
+    fn reads_the_broker_from_config() {
+        let config = Config { mqtt_broker: Some("mqtt://broker.lan/".to_string()), ..Config::default() };
+        let settings = BrokerSettings::from_config(&config).unwrap();
+        assert_eq!((settings.host.as_str(), settings.port), ("broker.lan", 1883));
+        assert_eq!(settings.topic("state"), "pomodoro/state");
+
+        let config = Config { mqtt_broker: Some("10.0.0.2:1884".to_string()), ..Config::default() };
+        assert_eq!(BrokerSettings::from_config(&config).unwrap().address(), "10.0.0.2:1884");
+        assert!(BrokerSettings::from_config(&Config::default()).is_none());
+    }
+}
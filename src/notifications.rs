@@ -1,48 +1,445 @@
-use notify_rust::Notification;
+use anyhow::Result;
+use notify_rust::{Notification, Timeout};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
-pub fn notify_work_complete() {
-    log_info("Sending work complete notification...");
-    match Notification::new()
-        .summary("Work Session Complete!")
-        .body("Time for a break. Great job!")
-        .timeout(5000)
-        .sound_name("message-new-instant")  // System notification sound
-        .show() {
-            Ok(_) => log_info("Work complete notification sent successfully"),
-            Err(e) => log_error(&format!("Failed to send work complete notification: {}", e)),
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::state::{shown_label, SessionInfo, TimerState};
+
+// System notification sound, named the way each platform's notification service expects
+#[cfg(all(unix, not(target_os = "macos")))]
+const COMPLETION_SOUND: &str = "message-new-instant";  // freedesktop sound theme
+#[cfg(target_os = "macos")]
+const COMPLETION_SOUND: &str = "Glass";
+#[cfg(windows)]
+const COMPLETION_SOUND: &str = "Reminder";
+
+// What the notification server can do, probed once at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationCapabilities {
+    pub server: String,
+    pub sound: bool,
+    pub actions: bool,
+    pub persistence: bool,
+}
+
+static CAPABILITIES: OnceLock<NotificationCapabilities> = OnceLock::new();
+
+// Privacy mode: notifications leave out labels
+static PRIVATE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_private(private: bool) {
+    PRIVATE.store(private, Ordering::Relaxed);
+}
+
+pub fn is_private() -> bool {
+    PRIVATE.load(Ordering::Relaxed)
+}
+
+// In a meeting: session notifications wait until it's over
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// Server id of the live progress notification, reused so updates replace it in place
+static PROGRESS_ID: Mutex<Option<u32>> = Mutex::new(None);
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detect_capabilities() -> Result<NotificationCapabilities> {
+    let info = notify_rust::get_server_information()?;
+    let capabilities = notify_rust::get_capabilities()?;
+    let has = |name: &str| capabilities.iter().any(|c| c == name);
+    Ok(NotificationCapabilities {
+        server: format!("{} {}", info.name, info.version),
+        sound: has("sound"),
+        actions: has("actions"),
+        persistence: has("persistence"),
+    })
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn detect_capabilities() -> Result<NotificationCapabilities> {
+    // Notification Center and Windows toasts play sounds and keep history natively
+    Ok(NotificationCapabilities {
+        server: if cfg!(target_os = "macos") { "Notification Center" } else { "Windows" }.to_string(),
+        sound: true,
+        actions: true,
+        persistence: true,
+    })
+}
+
+// Probe the notification server and remember the result. Blocks on D-Bus, so call it off the UI thread.
+pub fn probe_capabilities() {
+    match detect_capabilities() {
+        Ok(capabilities) => {
+            log_info(&format!(
+                "Notification server: {} (sound: {}, actions: {}, persistence: {})",
+                capabilities.server, capabilities.sound, capabilities.actions, capabilities.persistence
+            ));
+            if !capabilities.sound {
+                log_info("Notification server has no sound support, completion sounds will be played by the app");
+            }
+            let _ = CAPABILITIES.set(capabilities);
+        }
+        Err(e) => log_error(&format!("Failed to query notification server: {}", e)),
+    }
+}
+
+pub fn capabilities() -> Option<&'static NotificationCapabilities> {
+    CAPABILITIES.get()
+}
+
+pub fn notify_work_complete(config: &Config, label: &str) -> Result<()> {
+    let body = match shown_label(label, is_private()).as_str() {
+        "" => "Time for a break. Great job!".to_string(),
+        label => format!("{} done. Time for a break. Great job!", label),
+    };
+    show_completion(config, "Work Session Complete!", &body, "work complete")
+}
+
+pub fn notify_break_complete(config: &Config) -> Result<()> {
+    show_completion(config, "Break Complete!", "Ready to focus again?", "break complete")
+}
+
+pub fn notify_long_break_complete(config: &Config) -> Result<()> {
+    show_completion(
+        config,
+        "Long Break Complete!",
+        "You've completed a full Pomodoro cycle. Well done!",
+        "long break complete",
+    )
+}
+
+// Message sent by a user script or plugin
+pub fn notify_script(config: &Config, message: &str) -> Result<()> {
+    if !config.enable_notifications {
+        log_info("Notifications are disabled in config");
+        return Ok(());
+    }
+    show_completion(config, "Pomodoro Timer", message, "script")
+}
+
+// Sent when the meeting that paused the timer ends
+pub fn notify_meeting_over(config: &Config, label: &str) -> Result<()> {
+    if !config.enable_notifications {
+        log_info("Notifications are disabled in config");
+        return Ok(());
+    }
+    let body = match shown_label(label, is_private()).as_str() {
+        "" => "Resume your session when you're ready.".to_string(),
+        label => format!("Resume {} when you're ready.", label),
+    };
+    show_completion(config, "Meeting Over", &body, "meeting over")
+}
+
+// Sent once a day when only one more work session fits before the hard stop
+pub fn notify_wind_down(config: &Config, hard_stop: &str) -> Result<()> {
+    if !config.enable_notifications {
+        log_info("Notifications are disabled in config");
+        return Ok(());
+    }
+    let body = format!("The workday ends at {}. Time to wrap up.", hard_stop);
+    show_completion(config, "Winding Down", &body, "wind-down")
+}
+
+// A quick timer ran out. It rings like a completion, but leaves the session's
+// live progress notification alone.
+pub fn notify_quick_timer(config: &Config, name: &str, minutes: u32) -> Result<()> {
+    if !config.enable_notifications {
+        log_info("Notifications are disabled in config");
+        return Ok(());
+    }
+    let body = format!("Your {}-minute timer is up.", minutes);
+    show_alert(config, name, &body, "quick timer", false)
+}
+
+// A milestone in a work session: quiet and short-lived, the session isn't over
+pub fn notify_milestone(text: &str) -> Result<()> {
+    notify_quietly("Pomodoro Timer", text, "milestone")
+}
+
+// Sent at most once a day while few breaks are being taken
+pub fn notify_break_nudge(config: &Config, percent: u32) -> Result<()> {
+    if !config.enable_notifications {
+        log_info("Notifications are disabled in config");
+        return Ok(());
+    }
+    let body = format!("You took {}% of your breaks in full over the last week. Resting keeps the focus sharp.", percent);
+    notify_quietly("Time to Rest?", &body, "break nudge")
+}
+
+// A short notification without the completion sound
+fn notify_quietly(summary: &str, body: &str, kind: &str) -> Result<()> {
+    log_info(&format!("Sending {} notification...", kind));
+    Notification::new().summary(summary).body(body).timeout(5000).show()?;
+    Ok(())
+}
+
+// Read text out loud with the system's speech: say on macOS, spd-say or
+// espeak on Linux, the speech synthesizer through PowerShell on Windows
+pub fn speak(text: &str) {
+    #[cfg(target_os = "macos")]
+    let commands = [("say", vec![text.to_string()])];
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let commands = [("spd-say", vec![text.to_string()]), ("espeak", vec![text.to_string()])];
+    #[cfg(windows)]
+    let commands = [(
+        "powershell",
+        vec![
+            "-NoProfile".to_string(),
+            "-Command".to_string(),
+            format!(
+                "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+                text.replace('\'', "''")
+            ),
+        ],
+    )];
+    let spoken = commands.iter().any(|(program, args)| {
+        std::process::Command::new(program).args(args).status().is_ok_and(|status| status.success())
+    });
+    if !spoken {
+        log_error("Can't speak milestones: no speech command found");
+    }
+}
+
+fn show_completion(config: &Config, summary: &str, body: &str, kind: &str) -> Result<()> {
+    show_alert(config, summary, body, kind, true)
+}
+
+fn show_alert(config: &Config, summary: &str, body: &str, kind: &str, replaces_progress: bool) -> Result<()> {
+    log_info(&format!("Sending {} notification...", kind));
+
+    // Servers without sound support ignore the sound hint, so play it ourselves instead
+    let play_sound_in_app = capabilities().is_some_and(|c| !c.sound);
+
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body);
+    // Replace the live progress notification rather than stacking a second one
+    if let Some(id) = PROGRESS_ID.lock().take_if(|_| replaces_progress) {
+        notification.id(id);
+    }
+    if config.persistent_notifications {
+        // Critical notifications are not auto-dismissed and show over fullscreen apps
+        notification.timeout(Timeout::Never);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        notification.urgency(notify_rust::Urgency::Critical);
+    } else {
+        notification.timeout(5000);
+    }
+    if !play_sound_in_app {
+        notification.sound_name(COMPLETION_SOUND);
+    }
+
+    let result = match notification.show() {
+        Ok(_) => {
+            log_info(&format!("{} notification sent successfully", kind));
+            Ok(())
         }
+        Err(e) => {
+            log_error(&format!("Failed to send {} notification: {}", kind, e));
+            Err(e.into())
+        }
+    };
+
+    if play_sound_in_app {
+        play_completion_sound();
+    }
+    result
 }
 
-pub fn notify_break_complete() {
-    log_info("Sending break complete notification...");
-    match Notification::new()
-        .summary("Break Complete!")
-        .body("Ready to focus again?")
-        .timeout(5000)
-        .sound_name("message-new-instant")  // System notification sound
-        .show() {
-            Ok(_) => log_info("Break complete notification sent successfully"),
-            Err(e) => log_error(&format!("Failed to send break complete notification: {}", e)),
+// Event bus subscriber: completion notifications and the live progress notification
+pub struct Notifier {
+    config: Config,
+    progress: ProgressNotifier,
+}
+
+impl Notifier {
+    pub fn new(config: Config) -> Self {
+        Self { config, progress: ProgressNotifier::default() }
+    }
+
+    pub fn on_event(&mut self, event: &SessionEvent, info: &SessionInfo) {
+        if is_quiet() {
+            return;
         }
+        if let SessionEvent::Completed { state, label } = event {
+            // The completion notification replaces the progress one
+            self.progress = ProgressNotifier::default();
+            if !self.config.enable_notifications {
+                log_info("Notifications are disabled in config");
+                return;
+            }
+            let _ = match state {
+                TimerState::Working => notify_work_complete(&self.config, label),
+                TimerState::ShortBreak => notify_break_complete(&self.config),
+                TimerState::LongBreak => notify_long_break_complete(&self.config),
+                _ => Ok(()),
+            };
+        } else if let SessionEvent::Milestone(text) = event {
+            let (text, notify, speak) = (text.clone(), self.config.enable_notifications, self.config.speak_milestones);
+            // Notification servers and speech can both block
+            std::thread::spawn(move || {
+                if notify {
+                    let _ = notify_milestone(&text).inspect_err(|e| log_error(&format!("Failed to send milestone notification: {}", e)));
+                }
+                if speak {
+                    self::speak(&text);
+                }
+            });
+        } else if self.config.progress_notifications {
+            if let Some((body, expire)) = self.progress.update(info) {
+                // Talking to the notification server can block
+                std::thread::spawn(move || show_progress(&body, expire));
+            }
+        }
+    }
+}
+
+// Decides when the live progress notification needs refreshing: once a minute and on state changes
+#[derive(Default)]
+pub struct ProgressNotifier {
+    last_shown: Option<(TimerState, u32)>,
 }
 
-pub fn notify_long_break_complete() {
-    log_info("Sending long break complete notification...");
-    match Notification::new()
-        .summary("Long Break Complete!")
-        .body("You've completed a full Pomodoro cycle. Well done!")
-        .timeout(5000)
-        .sound_name("message-new-instant")  // System notification sound
-        .show() {
-            Ok(_) => log_info("Long break complete notification sent successfully"),
-            Err(e) => log_error(&format!("Failed to send long break complete notification: {}", e)),
+impl ProgressNotifier {
+    // Returns the new notification body, or None when nothing changed.
+    // The bool is true when the notification should expire (the timer went idle).
+    pub fn update(&mut self, info: &SessionInfo) -> Option<(String, bool)> {
+        let mode = if info.is_focus_mode { "Focus" } else { "Rest" };
+
+        if info.current_state == TimerState::Idle {
+            return self
+                .last_shown
+                .take()
+                .map(|_| (format!("{} — stopped", mode), true));
+        }
+
+        let shown = (info.current_state.clone(), info.active_timer().remaining_secs / 60);
+        if self.last_shown.as_ref() == Some(&shown) {
+            return None;
         }
+        self.last_shown = Some(shown);
+
+        // Name the task while focusing, unless privacy mode hides it
+        let title = match shown_label(&info.current_label, is_private()) {
+            label if info.is_focus_mode && !label.is_empty() => label,
+            _ => mode.to_string(),
+        };
+        let body = if info.current_state.is_running() {
+            format!("{} — {} remaining", title, info.format_time())
+        } else {
+            format!("{} — paused at {}", title, info.format_time())
+        };
+        Some((body, false))
+    }
 }
 
+// Show or update the single live progress notification (XDG servers only)
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn show_progress(body: &str, expire: bool) {
+    use notify_rust::{Hint, Urgency};
+
+    // Out of the lock while the server answers, which can take a while
+    let progress_id = PROGRESS_ID.lock().take();
+    let mut notification = Notification::new();
+    notification
+        .summary("Pomodoro Timer")
+        .body(body)
+        .urgency(Urgency::Low)
+        .hint(Hint::Transient(true));
+    notification.timeout(if expire { Timeout::Milliseconds(3000) } else { Timeout::Never });
+    if let Some(id) = progress_id {
+        notification.id(id);
+    }
+
+    match notification.show() {
+        Ok(handle) => *PROGRESS_ID.lock() = if expire { None } else { Some(handle.id()) },
+        Err(e) => {
+            // Still showing, as far as we know
+            *PROGRESS_ID.lock() = progress_id;
+            log_error(&format!("Failed to update progress notification: {}", e));
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn show_progress(_body: &str, _expire: bool) {
+    // Other platforms cannot replace a notification in place
+}
+
+// Play the completion sound through libcanberra, which uses the same sound theme as the server would
+fn play_completion_sound() {
+    std::thread::spawn(|| {
+        let status = std::process::Command::new("canberra-gtk-play")
+            .arg(format!("--id={}", COMPLETION_SOUND))
+            .status();
+        if let Err(e) = status {
+            log_error(&format!("Failed to play completion sound: {}", e));
+        }
+    });
+}
+
+// Sends the work-complete notification on demand so users can check their setup
+pub fn send_test_notification(config: &Config) -> Result<(), String> {
+    notify_work_complete(config, "").map_err(|e| {
+        format!(
+            "{}. Check that a notification daemon is running and the app is allowed to post notifications.",
+            e
+        )
+    })
+}
+
+// Lines kept for the diagnostics panel
+pub const LOG_LINES: usize = 200;
+
+// The newest log lines, oldest first, so a bug report doesn't need a terminal
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
 pub fn log_info(message: &str) {
     eprintln!("[INFO] {}", message);
+    remember("INFO", message);
 }
 
 pub fn log_error(message: &str) {
     eprintln!("[ERROR] {}", message);
+    remember("ERROR", message);
+}
+
+fn remember(level: &str, message: &str) {
+    let line = format!("{} [{}] {}", chrono::Local::now().format("%H:%M:%S"), level, message);
+    let mut log = RECENT_LOG.lock();
+    if log.len() == LOG_LINES {
+        log.pop_front();
+    }
+    log.push_back(line);
+}
+
+pub fn recent_log() -> Vec<String> {
+    RECENT_LOG.lock().iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_log_lines() {
+        for n in 0..LOG_LINES + 10 {
+            log_info(&format!("line {}", n));
+        }
+        log_error("Failed to save");
+        let log = recent_log();
+        assert_eq!(log.len(), LOG_LINES);
+        assert!(log.iter().any(|line| line.ends_with(" [ERROR] Failed to save")));
+        assert!(!log.iter().any(|line| line.ends_with("] line 10")));
+    }
 }
@@ -1,50 +1,777 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{Local, NaiveDate, TimeZone};
+use parking_lot::{Condvar, Mutex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::state::SessionInfo;
+use crate::events::{EventBus, SessionEvent};
+use crate::notifications;
+use crate::power;
+use crate::state::{CompletedTimer, SessionInfo};
+
+// Environment variable that unlocks an encrypted state without the startup prompt
+pub const PASSPHRASE_ENV: &str = "POMODORO_TIMER_PASSPHRASE";
+
+// Key derived from the passphrase, kept for the life of the process
+struct StateKey {
+    salt: [u8; 16],
+    key: [u8; 32],
+    encrypt_on_save: bool,  // False when encryption was turned off: read once, then save in plain text
+}
+
+static STATE_KEY: Mutex<Option<StateKey>> = Mutex::new(None);
+
+// Finished sessions live in one file per local day, sessions/2024-06-01.json,
+// and state.json holds the rest. These are the day files as this process last
+// read or wrote them (plain contents, and whether encrypted), so a save only
+// touches the days that changed.
+static DAY_FILES: Mutex<BTreeMap<NaiveDate, (String, bool)>> = Mutex::new(BTreeMap::new());
+
+// What a save writes: state.json, and each day's sessions before encryption
+struct Snapshot {
+    state: String,
+    days: BTreeMap<NaiveDate, String>,
+    ids: HashSet<String>,  // Of every session in the history
+}
+
+// On-disk format of an encrypted state.json
+#[derive(Serialize, Deserialize)]
+struct EncryptedState {
+    version: u32,
+    #[serde(default)]
+    generation: u64,  // Left readable, so a write can be checked without the key
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// The generation at the top of either format of state.json
+#[derive(Deserialize)]
+struct Stamp {
+    #[serde(default)]
+    generation: u64,
+}
 
 pub struct Persistence;
 
 impl Persistence {
     pub fn data_dir() -> Result<PathBuf> {
+        // Tests work in a folder of their own
+        #[cfg(test)]
+        if let Some(dir) = tests::DATA_DIR.lock().clone() {
+            return Ok(dir);
+        }
+        Self::user_data_dir()
+    }
+
+    #[cfg(not(windows))]
+    fn user_data_dir() -> Result<PathBuf> {
         let home = std::env::var("HOME").context("HOME environment variable not set")?;
         Ok(PathBuf::from(home).join(".local/share/pomodoro-timer"))
     }
 
+    // Roams with the profile, next to the config
+    #[cfg(windows)]
+    fn user_data_dir() -> Result<PathBuf> {
+        let app_data = std::env::var("APPDATA").context("APPDATA environment variable not set")?;
+        Ok(PathBuf::from(app_data).join("pomodoro-timer"))
+    }
+
     pub fn state_path() -> Result<PathBuf> {
         Ok(Self::data_dir()?.join("state.json"))
     }
 
+    pub fn sessions_dir() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("sessions"))
+    }
+
+    fn day_path(day: NaiveDate) -> Result<PathBuf> {
+        Ok(Self::sessions_dir()?.join(format!("{}.json", day.format("%Y-%m-%d"))))
+    }
+
+    // Where sessions dropped from the history by the retention are kept
+    fn archive_path(day: NaiveDate) -> Result<PathBuf> {
+        Ok(Self::sessions_dir()?.join("archive").join(format!("{}.json", day.format("%Y-%m-%d"))))
+    }
+
+    pub fn has_saved_state() -> bool {
+        Self::state_path().map(|path| path.exists()).unwrap_or(false)
+    }
+
+    pub fn is_encrypted() -> bool {
+        Self::read_encrypted().ok().flatten().is_some()
+    }
+
+    // Derive the key from the passphrase and check it against the saved state.
+    // With `encrypt` off, an encrypted state is still readable but saved in plain text.
+    pub fn unlock(passphrase: &str, encrypt: bool) -> Result<()> {
+        let (salt, existing) = match Self::read_encrypted()? {
+            Some(envelope) => (decode_hex(&envelope.salt)?, Some(envelope)),
+            None => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                (salt, None)
+            }
+        };
+
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| anyhow!("Failed to derive key: {}", e))?;
+
+        if let Some(envelope) = existing {
+            decrypt(&key, &envelope)?;
+        }
+
+        *STATE_KEY.lock() = Some(StateKey { salt, key, encrypt_on_save: encrypt });
+        Ok(())
+    }
+
+    // Unlock with the passphrase from the environment, if one is set
+    pub fn unlock_from_env(encrypt: bool) -> Result<bool> {
+        match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => Self::unlock(&passphrase, encrypt).map(|()| true),
+            Err(_) => Ok(false),
+        }
+    }
+
     pub fn load() -> Result<SessionInfo> {
         let state_path = Self::state_path()?;
 
+        // The history in the day files outlives a deleted state.json
         if !state_path.exists() {
-            return Ok(SessionInfo::new());
+            let mut session_info = SessionInfo::new();
+            session_info.history = Self::load_days()?;
+            return Ok(session_info);
         }
 
         let content = fs::read_to_string(&state_path)
             .context("Failed to read state file")?;
+        let (content, _) = open(content)?;
 
-        let session_info: SessionInfo = serde_json::from_str(&content)
+        let mut session_info: SessionInfo = serde_json::from_str(&content)
             .context("Failed to parse state file")?;
 
+        // A state.json from before the day files still has the history in it;
+        // the next save moves it out
+        if session_info.history.is_empty() {
+            session_info.history = Self::load_days()?;
+        } else {
+            notifications::log_info("Moving the history out of state.json into day files");
+            DAY_FILES.lock().clear();
+        }
+
         Ok(session_info)
     }
 
-    pub fn save(session_info: &SessionInfo) -> Result<()> {
-        let data_dir = Self::data_dir()?;
-        fs::create_dir_all(&data_dir)
-            .context("Failed to create data directory")?;
+    // All the sessions in the day files, oldest first
+    fn load_days() -> Result<Vec<CompletedTimer>> {
+        let sessions_dir = Self::sessions_dir()?;
+        if !sessions_dir.exists() {
+            DAY_FILES.lock().clear();
+            return Ok(Vec::new());
+        }
+
+        let mut history = Vec::new();
+        let mut day_files = BTreeMap::new();
+        for entry in fs::read_dir(&sessions_dir).context("Failed to read the sessions folder")? {
+            let path = entry.context("Failed to read the sessions folder")?.path();
+            let day = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|name| NaiveDate::parse_from_str(name, "%Y-%m-%d").ok());
+            let Some(day) = day else {
+                continue;
+            };
+            let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let (content, encrypted) = open(content).with_context(|| format!("Failed to open {}", path.display()))?;
+            let entries: Vec<CompletedTimer> =
+                serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+            let plain = serde_json::to_string_pretty(&entries).context("Failed to serialize sessions")?;
+            day_files.insert(day, (plain, encrypted));
+            history.extend(entries);
+        }
+        history.sort_by_key(|entry| entry.completed_at);
+        *DAY_FILES.lock() = day_files;
+        Ok(history)
+    }
 
+    // Save a state loaded from disk, unless another process has saved since
+    pub fn save(session_info: &mut SessionInfo) -> Result<()> {
+        let _lock = Self::lock()?;
+        if Self::saved_by_another(session_info)? {
+            bail!("The timer state was changed by another process meanwhile; try again");
+        }
+        session_info.generation += 1;
+        Self::write(&Self::encode(session_info)?)
+    }
+
+    fn lock_path() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("state.lock"))
+    }
+
+    // Held while checking the generation and writing, so two processes can't
+    // interleave. Released when the file is dropped.
+    fn lock() -> Result<fs::File> {
+        fs::create_dir_all(Self::data_dir()?).context("Failed to create data directory")?;
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::lock_path()?)
+            .context("Failed to open the state lock")?;
+        file.lock().context("Failed to lock the state file")?;
+        Ok(file)
+    }
+
+    // Whether state.json has been saved by someone else since this state was
+    // loaded or last saved. A missing or unreadable file is fair game.
+    fn saved_by_another(session_info: &SessionInfo) -> Result<bool> {
         let state_path = Self::state_path()?;
+        if !state_path.exists() {
+            return Ok(false);
+        }
+        let content = fs::read_to_string(&state_path).context("Failed to read state file")?;
+        Ok(serde_json::from_str::<Stamp>(&content).is_ok_and(|stamp| stamp.generation != session_info.generation))
+    }
+
+    // File contents for the state, encrypted when a key is set, and the history by day
+    fn encode(session_info: &SessionInfo) -> Result<Snapshot> {
         let content = serde_json::to_string_pretty(session_info)
             .context("Failed to serialize state")?;
+        let state = match STATE_KEY.lock().as_ref().filter(|state_key| state_key.encrypt_on_save) {
+            Some(state_key) => serde_json::to_string_pretty(&encrypt(state_key, &content, session_info.generation)?)
+                .context("Failed to serialize encrypted state")?,
+            None => content,
+        };
+
+        let days = by_day(&session_info.history, &Local)
+            .into_iter()
+            .map(|(day, entries)| Ok((day, serde_json::to_string_pretty(&entries).context("Failed to serialize sessions")?)))
+            .collect::<Result<_>>()?;
+        let ids = session_info.history.iter().map(|entry| entry.id.clone()).collect();
+        Ok(Snapshot { state, days, ids })
+    }
+
+    // Day files first, then state.json, whose new generation marks the save done.
+    // Sessions the retention has since dropped from the history go to the
+    // archive before their day file is rewritten or removed.
+    fn write(snapshot: &Snapshot) -> Result<()> {
+        let data_dir = Self::data_dir()?;
+        fs::create_dir_all(&data_dir)
+            .context("Failed to create data directory")?;
+
+        let key = STATE_KEY.lock();
+        let state_key = key.as_ref().filter(|state_key| state_key.encrypt_on_save);
+        let mut day_files = DAY_FILES.lock();
+        if !snapshot.days.is_empty() {
+            fs::create_dir_all(Self::sessions_dir()?).context("Failed to create the sessions folder")?;
+        }
+        for (day, (plain, _)) in day_files.iter() {
+            // Unchanged, so nothing was dropped from it
+            if snapshot.days.get(day) == Some(plain) {
+                continue;
+            }
+            let entries: Vec<CompletedTimer> = serde_json::from_str(plain).context("Failed to parse sessions")?;
+            let dropped: Vec<CompletedTimer> = entries.into_iter().filter(|entry| !snapshot.ids.contains(&entry.id)).collect();
+            if !dropped.is_empty() {
+                Self::archive(*day, dropped, key.as_ref())?;
+            }
+        }
+        for (day, content) in &snapshot.days {
+            let file = (content.clone(), state_key.is_some());
+            if day_files.get(day) == Some(&file) {
+                continue;
+            }
+            let written = match state_key {
+                Some(state_key) => serde_json::to_string_pretty(&encrypt(state_key, content, 0)?)
+                    .context("Failed to serialize encrypted sessions")?,
+                None => content.clone(),
+            };
+            write_atomic(&Self::day_path(*day)?, &written).with_context(|| format!("Failed to write the sessions of {}", day))?;
+            day_files.insert(*day, file);
+        }
+        let dropped: Vec<NaiveDate> = day_files.keys().filter(|day| !snapshot.days.contains_key(day)).copied().collect();
+        for day in dropped {
+            match fs::remove_file(Self::day_path(day)?) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("Failed to remove the sessions of {}", day));
+                }
+                _ => {
+                    day_files.remove(&day);
+                }
+            }
+        }
 
-        fs::write(&state_path, content)
+        let state_path = Self::state_path()?;
+        write_atomic(&state_path, &snapshot.state)
             .context("Failed to write state file")?;
 
         Ok(())
     }
 
+    // Add sessions to the day's archive file, encrypted like the day files
+    fn archive(day: NaiveDate, dropped: Vec<CompletedTimer>, key: Option<&StateKey>) -> Result<()> {
+        let path = Self::archive_path(day)?;
+        let mut entries: Vec<CompletedTimer> = if path.exists() {
+            let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let (content, _) = open_with(content, key).with_context(|| format!("Failed to open {}", path.display()))?;
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        // Already there when a save was cut short after archiving
+        entries.retain(|entry| !dropped.iter().any(|archived| archived.id == entry.id));
+        entries.extend(dropped);
+        entries.sort_by_key(|entry| entry.completed_at);
+
+        let content = serde_json::to_string_pretty(&entries).context("Failed to serialize sessions")?;
+        let written = match key.filter(|state_key| state_key.encrypt_on_save) {
+            Some(state_key) => serde_json::to_string_pretty(&encrypt(state_key, &content, 0)?)
+                .context("Failed to serialize encrypted sessions")?,
+            None => content,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create the archive folder")?;
+        }
+        write_atomic(&path, &written).with_context(|| format!("Failed to archive the sessions of {}", day))
+    }
+
+    // Write a file in the data directory that may hold labels, encrypted like
+    // the state when it is. Waits only briefly for the key, for the panic hook.
+    pub fn write_private(file: &str, content: &str) -> Result<()> {
+        let written = {
+            let state_key = STATE_KEY
+                .try_lock_for(Duration::from_millis(100))
+                .context("The state key is in use")?;
+            match state_key.as_ref().filter(|state_key| state_key.encrypt_on_save) {
+                Some(state_key) => serde_json::to_string_pretty(&encrypt(state_key, content, 0)?)
+                    .with_context(|| format!("Failed to serialize encrypted {}", file))?,
+                None => content.to_string(),
+            }
+        };
+        fs::create_dir_all(Self::data_dir()?).context("Failed to create data directory")?;
+        write_atomic(&Self::data_dir()?.join(file), &written).with_context(|| format!("Failed to write {}", file))
+    }
+
+    // Plain contents of a file written by write_private, None when there is none
+    pub fn read_private(file: &str) -> Result<Option<String>> {
+        let path = Self::data_dir()?.join(file);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+        open(content).map(|(content, _)| Some(content))
+    }
+
+    fn read_encrypted() -> Result<Option<EncryptedState>> {
+        let state_path = Self::state_path()?;
+        if !state_path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&state_path)
+            .context("Failed to read state file")?;
+        Ok(serde_json::from_str(&content).ok())
+    }
+
+}
+
+// How long after a change the state is written. Edits are saved almost right
+// away; the countdown alone only needs saving now and then, and less often
+// while battery_saver is in effect.
+const SAVE_DELAY: Duration = Duration::from_millis(300);
+const TICK_SAVE_DELAY: Duration = Duration::from_secs(5);
+const BATTERY_TICK_SAVE_DELAY: Duration = Duration::from_secs(60);
+
+// Writes the state on a background thread once it has changed. A burst of
+// changes becomes one write, and nothing is written while the state is unchanged.
+// When another process saved the state in the meantime, such as an import or
+// a second timer, its state is loaded in place of this one instead.
+#[derive(Clone)]
+pub struct StateWriter {
+    session_info: Arc<Mutex<SessionInfo>>,
+    shared: Arc<WriterShared>,
+    events: EventBus,  // Told when the state was swapped for the one on disk
+}
+
+struct WriterShared {
+    due: Mutex<Option<Instant>>,  // When the pending write is due, None when nothing changed
+    wake: Condvar,
+    writing: Mutex<()>,  // Keeps a flush and the background write from overlapping
+    reloading: AtomicBool,  // Set while announcing a reload, which needs no write of its own
+}
+
+impl StateWriter {
+    pub fn spawn(session_info: Arc<Mutex<SessionInfo>>, events: EventBus) -> Self {
+        let writer = Self::new(session_info, events);
+        let worker = writer.clone();
+        std::thread::Builder::new()
+            .name("state-writer".to_string())
+            .spawn(move || worker.run())
+            .expect("Failed to start state writer thread");
+        writer
+    }
+
+    fn new(session_info: Arc<Mutex<SessionInfo>>, events: EventBus) -> Self {
+        Self {
+            session_info,
+            shared: Arc::new(WriterShared {
+                due: Mutex::new(None),
+                wake: Condvar::new(),
+                writing: Mutex::new(()),
+                reloading: AtomicBool::new(false),
+            }),
+            events,
+        }
+    }
+
+    // Event bus subscriber: schedule a write for the change
+    pub fn on_event(&self, event: &SessionEvent) {
+        // Writing the state just loaded would make the other process reload it in turn
+        if self.shared.reloading.load(Ordering::Relaxed) {
+            return;
+        }
+        self.mark_dirty(match event {
+            SessionEvent::Tick if power::saving() => BATTERY_TICK_SAVE_DELAY,
+            SessionEvent::Tick => TICK_SAVE_DELAY,
+            _ => SAVE_DELAY,
+        });
+    }
+
+    fn mark_dirty(&self, delay: Duration) {
+        let at = Instant::now() + delay;
+        let mut due = self.shared.due.lock();
+        if due.is_none_or(|due| at < due) {
+            *due = Some(at);
+            self.shared.wake.notify_one();
+        }
+    }
+
+    // Write pending changes right away, e.g. before quitting
+    pub fn flush(&self) {
+        let pending = self.shared.due.lock().take();
+        if pending.is_some() {
+            self.write();
+        }
+    }
+
+    fn run(&self) {
+        loop {
+            let mut due = self.shared.due.lock();
+            match *due {
+                None => self.shared.wake.wait(&mut due),
+                Some(at) if Instant::now() < at => {
+                    self.shared.wake.wait_until(&mut due, at);
+                }
+                Some(_) => {
+                    *due = None;
+                    drop(due);
+                    self.write();
+                }
+            }
+        }
+    }
+
+    fn write(&self) {
+        let _writing = self.shared.writing.lock();
+        if let Err(e) = self.try_write() {
+            notifications::log_error(&format!("Failed to save state: {:#}", e));
+        }
+    }
+
+    fn try_write(&self) -> Result<()> {
+        let _lock = Persistence::lock()?;
+        // Only serializing happens under the state lock, the disk write does not
+        let snapshot = {
+            let mut info = self.session_info.lock();
+            if Persistence::saved_by_another(&info)? {
+                match Persistence::load() {
+                    Ok(mut newer) => {
+                        notifications::log_info("Another process saved the timer state, switching to it");
+                        newer.retention = info.retention;
+                        *info = newer;
+                        // Publishers hold the state lock, so no other event gets in meanwhile
+                        self.shared.reloading.store(true, Ordering::Relaxed);
+                        self.events.publish(SessionEvent::Changed, &info);
+                        self.shared.reloading.store(false, Ordering::Relaxed);
+                        return Ok(());
+                    }
+                    // Nothing better to switch to
+                    Err(e) => notifications::log_error(&format!("Overwriting state saved by another process: {:#}", e)),
+                }
+            }
+            info.generation += 1;
+            Persistence::encode(&info)
+        };
+        let written = snapshot.and_then(|snapshot| Persistence::write(&snapshot));
+        if written.is_err() {
+            // Not on disk, so the next write is still checked against the old one
+            self.session_info.lock().generation -= 1;
+        }
+        written
+    }
+}
+
+// Write to a temporary file and rename it over the old one, so a process
+// reading at the same time sees the old contents or the new, never half of them
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+// History entries grouped by the day they ended on in `tz`
+fn by_day<'a, Tz: TimeZone>(history: &'a [CompletedTimer], tz: &Tz) -> BTreeMap<NaiveDate, Vec<&'a CompletedTimer>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&CompletedTimer>> = BTreeMap::new();
+    for entry in history {
+        days.entry(entry.completed_at.with_timezone(tz).date_naive()).or_default().push(entry);
+    }
+    days
+}
+
+// Plain contents of a state or day file, and whether they were encrypted
+fn open(content: String) -> Result<(String, bool)> {
+    open_with(content, STATE_KEY.lock().as_ref())
+}
+
+fn open_with(content: String, key: Option<&StateKey>) -> Result<(String, bool)> {
+    match serde_json::from_str::<EncryptedState>(&content) {
+        Ok(envelope) => {
+            let state_key =
+                key.with_context(|| format!("State file is encrypted; enter the passphrase at startup or set {}", PASSPHRASE_ENV))?;
+            Ok((decrypt(&state_key.key, &envelope)?, true))
+        }
+        Err(_) => Ok((content, false)),
+    }
+}
+
+fn encrypt(state_key: &StateKey, plaintext: &str, generation: u64) -> Result<EncryptedState> {
+    let cipher = ChaCha20Poly1305::new(&Key::from(state_key.key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| anyhow!("Failed to encrypt state"))?;
+
+    Ok(EncryptedState {
+        version: 1,
+        generation,
+        salt: encode_hex(&state_key.salt),
+        nonce: encode_hex(&nonce),
+        ciphertext: encode_hex(&ciphertext),
+    })
+}
+
+fn decrypt(key: &[u8; 32], envelope: &EncryptedState) -> Result<String> {
+    if envelope.version != 1 {
+        bail!("Unsupported encrypted state version {}", envelope.version);
+    }
+    let nonce: [u8; 12] = decode_hex(&envelope.nonce)?;
+    let ciphertext = decode_hex_vec(&envelope.ciphertext)?;
+
+    let cipher = ChaCha20Poly1305::new(&Key::from(*key));
+    let plaintext = cipher
+        .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow!("Wrong passphrase or damaged state file"))?;
+    String::from_utf8(plaintext).context("Decrypted state is not valid UTF-8")
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex_vec(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        bail!("Invalid hex in encrypted state");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).context("Invalid hex in encrypted state"))
+        .collect()
+}
+
+fn decode_hex<const N: usize>(hex: &str) -> Result<[u8; N]> {
+    decode_hex_vec(hex)?
+        .try_into()
+        .map_err(|_| anyhow!("Unexpected field length in encrypted state"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+    use chrono::{FixedOffset, Utc};
+
+    // Set while a test has a data directory of its own
+    pub(super) static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    // The day files cache is shared, so tests that write take turns
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    struct Scratch {
+        dir: PathBuf,
+        _serial: parking_lot::MutexGuard<'static, ()>,
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            *DATA_DIR.lock() = None;
+            DAY_FILES.lock().clear();
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    // An empty data directory for the rest of the test
+    fn scratch(name: &str) -> Scratch {
+        let serial = SERIAL.lock();
+        let dir = std::env::temp_dir().join(format!("pomodoro-persistence-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        *DATA_DIR.lock() = Some(dir.clone());
+        DAY_FILES.lock().clear();
+        Scratch { dir, _serial: serial }
+    }
+
+    fn entry(id: &str, completed_at: &str) -> CompletedTimer {
+        CompletedTimer { id: id.to_string(), ..completed(completed_at, 25, "Work Session", false) }
+    }
+
+    fn ids(history: &[CompletedTimer]) -> Vec<&str> {
+        history.iter().map(|entry| entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn history_goes_into_local_days() {
+        let history = vec![entry("a", "2026-03-02T10:00:00Z"), entry("b", "2026-03-02T23:30:00Z"), entry("c", "2026-03-03T08:00:00Z")];
+        let days = by_day(&history, &Utc);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[&NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()].len(), 2);
+
+        // Late evening in UTC is the next morning further east
+        let days = by_day(&history, &FixedOffset::east_opt(2 * 3600).unwrap());
+        let ids: Vec<&str> = days[&NaiveDate::from_ymd_opt(2026, 3, 3).unwrap()].iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c"]);
+    }
+
+    #[test]
+    fn history_stays_out_of_state_json() {
+        let mut info = SessionInfo::new();
+        info.history.push(entry("a", "2026-03-02T10:00:00Z"));
+        let json = serde_json::to_value(&info).unwrap();
+        assert!(json.get("history").is_none());
+
+        // Older state files with the history in them still read
+        let mut json = json;
+        json["history"] = serde_json::to_value(&info.history).unwrap();
+        let older: SessionInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(older.history.len(), 1);
+    }
+
+    #[test]
+    fn pruned_sessions_move_to_the_archive() {
+        let scratch = scratch("pruned");
+        let mut info = SessionInfo::new();
+        info.history = vec![entry("a", "2026-03-01T12:00:00Z"), entry("b", "2026-03-02T12:00:00Z"), entry("c", "2026-03-02T12:30:00Z")];
+        Persistence::save(&mut info).unwrap();
+
+        info.retention.limit = Some(1);
+        assert_eq!(info.prune_history(Utc::now()), 2);
+        Persistence::save(&mut info).unwrap();
+        assert_eq!(ids(&Persistence::load().unwrap().history), ["c"]);
+
+        let mut archived: Vec<CompletedTimer> = fs::read_dir(scratch.dir.join("sessions/archive"))
+            .unwrap()
+            .flat_map(|file| serde_json::from_str::<Vec<CompletedTimer>>(&fs::read_to_string(file.unwrap().path()).unwrap()).unwrap())
+            .collect();
+        archived.sort_by_key(|entry| entry.completed_at);
+        assert_eq!(ids(&archived), ["a", "b"]);
+
+        // Saving again leaves the archive as it is
+        Persistence::save(&mut info).unwrap();
+        assert_eq!(fs::read_dir(scratch.dir.join("sessions/archive")).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn a_stale_copy_does_not_overwrite_newer_state() {
+        let _scratch = scratch("generation");
+        let mut first = SessionInfo::new();
+        Persistence::save(&mut first).unwrap();
+        let mut second = Persistence::load().unwrap();
+        assert_eq!(second.generation, first.generation);
+
+        first.current_label = "Newer".to_string();
+        Persistence::save(&mut first).unwrap();
+        second.current_label = "Stale".to_string();
+        assert!(Persistence::save(&mut second).is_err());
+        assert_eq!(Persistence::load().unwrap().current_label, "Newer");
+
+        // Once reloaded it can save again
+        let mut second = Persistence::load().unwrap();
+        second.current_label = "Later".to_string();
+        Persistence::save(&mut second).unwrap();
+        assert_eq!(Persistence::load().unwrap().generation, first.generation + 1);
+    }
+
+    #[test]
+    fn the_lock_keeps_writers_apart() {
+        let _scratch = scratch("lock");
+        let held = Persistence::lock().unwrap();
+        let other = fs::OpenOptions::new().write(true).open(Persistence::lock_path().unwrap()).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(held);
+        assert!(other.try_lock().is_ok());
+    }
+
+    #[test]
+    fn the_writer_switches_to_state_saved_by_another_process() {
+        let _scratch = scratch("reload");
+        let mut saved = SessionInfo::new();
+        Persistence::save(&mut saved).unwrap();
+        let writer = StateWriter::new(Arc::new(Mutex::new(saved.clone())), EventBus::default());
+
+        let mut other = Persistence::load().unwrap();
+        other.current_label = "From elsewhere".to_string();
+        Persistence::save(&mut other).unwrap();
+
+        writer.try_write().unwrap();
+        let info = writer.session_info.lock();
+        assert_eq!(info.current_label, "From elsewhere");
+        assert_eq!(info.generation, other.generation);
+        drop(info);
+
+        // Its next write goes on top of the newer state
+        writer.try_write().unwrap();
+        assert_eq!(Persistence::load().unwrap().generation, other.generation + 1);
+    }
+
+    #[test]
+    fn readers_never_see_half_a_file() {
+        let _scratch = scratch("concurrent");
+        let mut info = SessionInfo::new();
+        info.history.push(entry("a", "2026-03-02T12:00:00Z"));
+        Persistence::save(&mut info).unwrap();
+
+        let writer = std::thread::spawn(move || {
+            for round in 0..50 {
+                // Big enough that a write takes more than one go
+                info.current_label = format!("{}", round).repeat(20_000);
+                info.history[0].label = info.current_label.clone();
+                Persistence::save(&mut info).unwrap();
+            }
+        });
+        while !writer.is_finished() {
+            let loaded = Persistence::load().unwrap();
+            assert_eq!(loaded.history.len(), 1);
+        }
+        writer.join().unwrap();
+    }
 }
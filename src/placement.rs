@@ -0,0 +1,399 @@
+use gpui::*;
+use std::rc::Rc;
+
+use crate::config::Config;
+use crate::notifications;
+use crate::ui::detached::SavedBounds;
+
+// Size of the main window in logical pixels; gpui scales it for each display's DPI
+const WINDOW_SIZE: f32 = 240.0;
+
+// Display picked by the `display` config: a number counting from 1 in the
+// system's order, a display's UUID, or "cursor" or "focused" for the one with
+// the mouse pointer or the window in front, found beforehand with `locate`.
+// Falls back to the main display.
+fn preferred_display(config: &Config, spot: Option<Spot>, cx: &App) -> Option<Rc<dyn PlatformDisplay>> {
+    find_display(config.display.as_deref(), spot, cx)
+}
+
+fn find_display(wanted: Option<&str>, spot: Option<Spot>, cx: &App) -> Option<Rc<dyn PlatformDisplay>> {
+    let Some(wanted) = wanted else {
+        return cx.primary_display();
+    };
+    let displays = cx.displays();
+    let found = match wanted {
+        "cursor" | "focused" => {
+            let areas = display_areas(cx);
+            let main = cx.primary_display().map(|display| display.bounds());
+            let at = spot.and_then(|spot| spot.resolve(&areas, main));
+            let found = at.and_then(|at| displays.iter().find(|display| display.bounds().contains(&at)).cloned());
+            if found.is_none() {
+                notifications::log_error(&format!("Couldn't tell which display has the {}, using the main display", wanted));
+            }
+            return found.or_else(|| cx.primary_display());
+        }
+        _ => match wanted.parse::<usize>() {
+            Ok(number) => number.checked_sub(1).and_then(|index| displays.get(index)).cloned(),
+            Err(_) => displays
+                .iter()
+                .find(|display| display.uuid().is_ok_and(|uuid| uuid.to_string().eq_ignore_ascii_case(wanted)))
+                .cloned(),
+        },
+    };
+    if found.is_none() {
+        notifications::log_error(&format!(
+            "Display \"{}\" not found ({} connected), using the main display",
+            wanted,
+            displays.len()
+        ));
+    }
+    found.or_else(|| cx.primary_display())
+}
+
+// A place on the desktop as the window system tells it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spot {
+    // In gpui's own coordinates
+    Logical(Point<Pixels>),
+    // AppKit's, counting up from the bottom of the main display
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    FromBottom(Point<Pixels>),
+    // X11 device pixels on a desktop of `desktop` device pixels, which gpui
+    // scales down to fit its displays
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    Device { at: Point<Pixels>, desktop: Size<Pixels> },
+}
+
+impl Spot {
+    // In gpui's coordinates, given the connected displays and the main one
+    fn resolve(self, areas: &[Bounds<Pixels>], main: Option<Bounds<Pixels>>) -> Option<Point<Pixels>> {
+        match self {
+            Spot::Logical(at) => Some(at),
+            Spot::FromBottom(at) => Some(point(at.x, main?.size.height - at.y)),
+            Spot::Device { at, desktop } => {
+                let left = areas.iter().map(|area| f32::from(area.left())).reduce(f32::min)?;
+                let right = areas.iter().map(|area| f32::from(area.right())).reduce(f32::max)?;
+                let scale = f32::from(desktop.width) / (right - left);
+                (scale > 0.0).then(|| point(px(f32::from(at.x) / scale), px(f32::from(at.y) / scale)))
+            }
+        }
+    }
+}
+
+// Whether the display config needs `locate` before a window opens
+pub fn needs_lookup(wanted: Option<&str>) -> bool {
+    matches!(wanted, Some("cursor" | "focused"))
+}
+
+// Where the mouse pointer or the window in front is, for the "cursor" and
+// "focused" displays. This asks the window system, often through another
+// program, so it's run before the app starts or off the UI thread.
+pub fn locate(wanted: Option<&str>) -> Option<Spot> {
+    match wanted? {
+        "cursor" => cursor_position(),
+        "focused" => focused_window_center(),
+        _ => None,
+    }
+}
+
+// Middle of the window in front (macOS, Windows, X11; not Wayland)
+fn focused_window_center() -> Option<Spot> {
+    let position = active_win_pos_rs::get_active_window().ok()?.position;
+    let center = point(px((position.x + position.width / 2.0) as f32), px((position.y + position.height / 2.0) as f32));
+    #[cfg(target_os = "linux")]
+    let spot = x11_spot(center);
+    #[cfg(windows)]
+    let spot = Spot::Logical(windows_logical(center));
+    #[cfg(not(any(target_os = "linux", windows)))]
+    let spot = Spot::Logical(center);
+    Some(spot)
+}
+
+// The first two numbers in a tool's answer: "1280, 720" from hyprctl,
+// "X=1280\nY=720\n..." from xdotool
+#[cfg(any(target_os = "linux", target_os = "macos", test))]
+fn parse_point(text: &str) -> Option<Point<Pixels>> {
+    let mut numbers = text
+        .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .filter_map(|part| part.parse::<f32>().ok());
+    Some(point(px(numbers.next()?), px(numbers.next()?)))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// A point in X11 device pixels. Without xdotool to give the desktop's size it's
+// taken as it is, which is right unless the display is scaled.
+#[cfg(target_os = "linux")]
+fn x11_spot(at: Point<Pixels>) -> Spot {
+    match command_output("xdotool", &["getdisplaygeometry"]).and_then(|text| parse_point(&text)) {
+        Some(desktop) => Spot::Device { at, desktop: size(desktop.x, desktop.y) },
+        None => Spot::Logical(at),
+    }
+}
+
+// Where the mouse pointer is on the desktop. gpui only knows it inside its own
+// windows, so the window system is asked: Hyprland or X11 (with xdotool) on Linux.
+#[cfg(target_os = "linux")]
+fn cursor_position() -> Option<Spot> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        return parse_point(&command_output("hyprctl", &["cursorpos"])?).map(Spot::Logical);
+    }
+    let at = parse_point(&command_output("xdotool", &["getmouselocation", "--shell"])?)?;
+    Some(x11_spot(at))
+}
+
+#[cfg(target_os = "macos")]
+fn cursor_position() -> Option<Spot> {
+    let script = "ObjC.import('AppKit'); const at = $.NSEvent.mouseLocation; at.x + ',' + at.y";
+    parse_point(&command_output("osascript", &["-l", "JavaScript", "-e", script])?).map(Spot::FromBottom)
+}
+
+#[cfg(windows)]
+fn cursor_position() -> Option<Spot> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    let mut at = POINT::default();
+    unsafe { GetCursorPos(&mut at) }.ok()?;
+    Some(Spot::Logical(windows_logical(point(px(at.x as f32), px(at.y as f32)))))
+}
+
+// Device pixels divided by the scale of the monitor they're on, as gpui does
+#[cfg(windows)]
+fn windows_logical(at: Point<Pixels>) -> Point<Pixels> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::{MonitorFromPoint, MONITOR_DEFAULTTONEAREST};
+    use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    let device = POINT { x: f32::from(at.x) as i32, y: f32::from(at.y) as i32 };
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    let monitor = unsafe { MonitorFromPoint(device, MONITOR_DEFAULTTONEAREST) };
+    if unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) }.is_err() || dpi_x == 0 {
+        return at;
+    }
+    let scale = dpi_x as f32 / 96.0;
+    point(px(f32::from(at.x) / scale), px(f32::from(at.y) / scale))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn cursor_position() -> Option<Spot> {
+    None
+}
+
+// Options for the main window: a compact floating square centered on the preferred display
+pub fn window_options(config: &Config, spot: Option<Spot>, cx: &App) -> WindowOptions {
+    let display = preferred_display(config, spot, cx);
+    let bounds = match &display {
+        Some(display) => {
+            // Never larger than the display, for small or heavily scaled screens
+            let area = display.bounds();
+            let window_size = size(px(WINDOW_SIZE), px(WINDOW_SIZE)).min(&area.size);
+            Bounds::centered_at(area.center(), window_size)
+        }
+        None => Bounds::centered(None, size(px(WINDOW_SIZE), px(WINDOW_SIZE)), cx),
+    };
+
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(bounds)),
+        display_id: display.map(|display| display.id()),
+        titlebar: None,
+        window_decorations: Some(WindowDecorations::Client),
+        kind: WindowKind::PopUp, // Floating window
+        // See-through where the timer fades out with auto_hide
+        window_background: if config.auto_hide { WindowBackgroundAppearance::Transparent } else { WindowBackgroundAppearance::Opaque },
+        is_movable: true,
+        is_resizable: false,
+        focus: true,
+        show: true,
+        app_id: Some("pomodoro-timer".to_string()),
+        ..Default::default()
+    }
+}
+
+// Options for a mirror window: an ordinary window that can be resized or made
+// fullscreen on a second monitor or a wall display
+pub fn mirror_window_options(config: &Config, spot: Option<Spot>, cx: &App) -> WindowOptions {
+    let display = preferred_display(config, spot, cx);
+    let window_size = size(px(WINDOW_SIZE * 2.0), px(WINDOW_SIZE * 1.5));
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(Bounds::centered(
+            display.as_ref().map(|display| display.id()),
+            window_size,
+            cx,
+        ))),
+        display_id: display.map(|display| display.id()),
+        titlebar: Some(TitlebarOptions { title: Some("Pomodoro Timer".into()), ..Default::default() }),
+        is_resizable: true,
+        focus: true,
+        show: true,
+        app_id: Some("pomodoro-timer-mirror".to_string()),
+        ..Default::default()
+    }
+}
+
+// Options for a panel popped out of the timer: an ordinary window where it
+// was last time, pulled fully onto that display, or centered on the timer's
+// display when the one it was on is gone. `saved_place` tells which.
+pub fn detached_window_options(config: &Config, title: &str, saved: Option<SavedBounds>, spot: Option<Spot>, cx: &App) -> WindowOptions {
+    let bounds = saved_place(saved, cx).unwrap_or_else(|| {
+        let display = preferred_display(config, spot, cx);
+        Bounds::centered(display.as_ref().map(|display| display.id()), size(px(WINDOW_SIZE * 1.4), px(WINDOW_SIZE * 1.8)), cx)
+    });
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(bounds)),
+        titlebar: Some(TitlebarOptions { title: Some(format!("{} - Pomodoro Timer", title).into()), ..Default::default() }),
+        is_resizable: true,
+        focus: true,
+        show: true,
+        app_id: Some("pomodoro-timer".to_string()),
+        ..Default::default()
+    }
+}
+
+// Options for the presentation window: fullscreen on the `presentation_display`,
+// or on the timer's own display when that isn't set
+pub fn presentation_window_options(config: &Config, spot: Option<Spot>, cx: &App) -> WindowOptions {
+    let display = find_display(presentation_display(config), spot, cx);
+    let bounds = match &display {
+        Some(display) => display.bounds(),
+        None => Bounds::centered(None, size(px(WINDOW_SIZE * 4.0), px(WINDOW_SIZE * 3.0)), cx),
+    };
+    WindowOptions {
+        window_bounds: Some(WindowBounds::Fullscreen(bounds)),
+        display_id: display.map(|display| display.id()),
+        titlebar: Some(TitlebarOptions { title: Some("Pomodoro Timer".into()), ..Default::default() }),
+        focus: true,
+        show: true,
+        app_id: Some("pomodoro-timer-presentation".to_string()),
+        ..Default::default()
+    }
+}
+
+// The display config the presentation goes by
+pub fn presentation_display(config: &Config) -> Option<&str> {
+    config.presentation_display.as_deref().or(config.display.as_deref())
+}
+
+// A detached panel's saved place, when it's still on a connected display
+pub fn saved_place(saved: Option<SavedBounds>, cx: &App) -> Option<Bounds<Pixels>> {
+    saved.and_then(|saved| clamp_to_displays(saved.to_bounds(), cx))
+}
+
+// The displays connected, to notice one coming or going
+pub fn display_ids(cx: &App) -> Vec<DisplayId> {
+    cx.displays().iter().map(|display| display.id()).collect()
+}
+
+// The bounds moved, and shrunk if need be, to lie wholly on the display most of
+// them are on. None when they're on no connected display at all.
+pub fn clamp_to_displays(bounds: Bounds<Pixels>, cx: &App) -> Option<Bounds<Pixels>> {
+    clamp_onto(bounds, &display_areas(cx))
+}
+
+fn display_areas(cx: &App) -> Vec<Bounds<Pixels>> {
+    cx.displays().iter().map(|display| display.bounds()).collect()
+}
+
+fn clamp_onto(bounds: Bounds<Pixels>, areas: &[Bounds<Pixels>]) -> Option<Bounds<Pixels>> {
+    let area = areas
+        .iter()
+        .map(|area| (*area, area.intersect(&bounds).size))
+        .filter(|(_, overlap)| overlap.width > px(0.0) && overlap.height > px(0.0))
+        .max_by(|(_, a), (_, b)| (f32::from(a.width) * f32::from(a.height)).total_cmp(&(f32::from(b.width) * f32::from(b.height))))?
+        .0;
+    Some(clamp_into(bounds, area))
+}
+
+fn clamp_into(bounds: Bounds<Pixels>, area: Bounds<Pixels>) -> Bounds<Pixels> {
+    let size = bounds.size.min(&area.size);
+    let x = bounds.origin.x.clamp(area.origin.x, area.origin.x + area.size.width - size.width);
+    let y = bounds.origin.y.clamp(area.origin.y, area.origin.y + area.size.height - size.height);
+    Bounds::new(point(x, y), size)
+}
+
+// Whether enough of the window is on a connected display to grab it
+pub fn is_reachable(bounds: Bounds<Pixels>, cx: &App) -> bool {
+    reachable_on(bounds, &display_areas(cx))
+}
+
+fn reachable_on(bounds: Bounds<Pixels>, areas: &[Bounds<Pixels>]) -> bool {
+    let grab = px(WINDOW_SIZE / 4.0);
+    areas.iter().any(|area| {
+        let visible = area.intersect(&bounds);
+        visible.size.width >= grab && visible.size.height >= grab
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // Not a glob: gpui has a test attribute of its own
+    use super::{clamp_into, clamp_onto, parse_point, reachable_on, Spot};
+    use gpui::{point, px, size, Bounds};
+
+    #[test]
+    fn reads_the_cursor_from_window_system_tools() {
+        assert_eq!(parse_point("1280, 720\n"), Some(point(px(1280.0), px(720.0))));
+        assert_eq!(parse_point("X=1280\nY=720\nSCREEN=0\nWINDOW=4194313\n"), Some(point(px(1280.0), px(720.0))));
+        // A display left of the main one
+        assert_eq!(parse_point("-1920.5,300"), Some(point(px(-1920.5), px(300.0))));
+        assert_eq!(parse_point("No cursor\n"), None);
+    }
+
+    #[test]
+    fn pulls_windows_onto_the_display() {
+        let display = Bounds::new(point(px(1920.0), px(0.0)), size(px(1280.0), px(800.0)));
+        // Hanging off the right and bottom edges
+        let window = Bounds::new(point(px(3000.0), px(700.0)), size(px(340.0), px(430.0)));
+        assert_eq!(clamp_into(window, display), Bounds::new(point(px(2860.0), px(370.0)), size(px(340.0), px(430.0))));
+        // Already on it
+        let window = Bounds::new(point(px(2000.0), px(100.0)), size(px(340.0), px(430.0)));
+        assert_eq!(clamp_into(window, display), window);
+        // Taller than the display
+        let window = Bounds::new(point(px(2000.0), px(-50.0)), size(px(340.0), px(900.0)));
+        assert_eq!(clamp_into(window, display), Bounds::new(point(px(2000.0), px(0.0)), size(px(340.0), px(800.0))));
+    }
+
+    #[test]
+    fn pulls_saved_places_back_on_screen() {
+        let laptop = Bounds::new(point(px(0.0), px(0.0)), size(px(1440.0), px(900.0)));
+        let monitor = Bounds::new(point(px(1440.0), px(0.0)), size(px(2560.0), px(1440.0)));
+        let displays = [laptop, monitor];
+        // Mostly on the monitor, so it goes wholly onto the monitor
+        let window = Bounds::new(point(px(1300.0), px(100.0)), size(px(400.0), px(500.0)));
+        assert_eq!(clamp_onto(window, &displays), Some(Bounds::new(point(px(1440.0), px(100.0)), size(px(400.0), px(500.0)))));
+        // Saved when the monitor was on, now only the laptop is
+        let window = Bounds::new(point(px(2000.0), px(200.0)), size(px(400.0), px(500.0)));
+        assert_eq!(clamp_onto(window, &[laptop]), None);
+        // Below a display that has since shrunk
+        let window = Bounds::new(point(px(100.0), px(700.0)), size(px(400.0), px(500.0)));
+        assert_eq!(clamp_onto(window, &[laptop]), Some(Bounds::new(point(px(100.0), px(400.0)), size(px(400.0), px(500.0)))));
+        assert_eq!(clamp_onto(window, &[]), None);
+    }
+
+    #[test]
+    fn tells_stranded_windows() {
+        let laptop = Bounds::new(point(px(0.0), px(0.0)), size(px(1440.0), px(900.0)));
+        assert!(reachable_on(Bounds::new(point(px(100.0), px(100.0)), size(px(340.0), px(430.0))), &[laptop]));
+        // Only a sliver left on the laptop after the monitor to its right went away
+        assert!(!reachable_on(Bounds::new(point(px(1430.0), px(100.0)), size(px(340.0), px(430.0))), &[laptop]));
+        assert!(!reachable_on(Bounds::new(point(px(1500.0), px(100.0)), size(px(340.0), px(430.0))), &[laptop]));
+    }
+
+    #[test]
+    fn scales_device_pixels_to_the_displays() {
+        let laptop = Bounds::new(point(px(0.0), px(0.0)), size(px(1440.0), px(900.0)));
+        let monitor = Bounds::new(point(px(1440.0), px(0.0)), size(px(1920.0), px(1080.0)));
+        // A 2x desktop: 6720 device pixels across for 3360 of gpui's
+        let spot = Spot::Device { at: point(px(4000.0), px(600.0)), desktop: size(px(6720.0), px(2160.0)) };
+        assert_eq!(spot.resolve(&[laptop, monitor], Some(laptop)), Some(point(px(2000.0), px(300.0))));
+        assert_eq!(spot.resolve(&[], Some(laptop)), None);
+        // AppKit counts up from the bottom of the main display
+        let spot = Spot::FromBottom(point(px(100.0), px(800.0)));
+        assert_eq!(spot.resolve(&[laptop], Some(laptop)), Some(point(px(100.0), px(100.0))));
+        assert_eq!(Spot::Logical(point(px(5.0), px(5.0))).resolve(&[], None), Some(point(px(5.0), px(5.0))));
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::{Context, Result};
+use futures::channel::mpsc::UnboundedSender;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Engine, Store};
+use wasmtime_wasi::{IoView, WasiCtx, WasiCtxBuilder, WasiView};
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::SessionInfo;
+
+// Generated from wit/plugin.wit
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "plugin",
+    });
+}
+
+use bindings::pomodoro::plugin::{host, types};
+use bindings::Plugin;
+use types::SessionEvent as PluginEvent;
+
+// Fuel for one call into a plugin, so a plugin stuck in a loop traps
+// instead of hanging the plugin thread
+const FUEL_PER_CALL: u64 = 50_000_000;
+
+// What plugins ask the app to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginCommand {
+    Notify(String),
+    SetBadge { plugin: String, text: String },  // Empty text removes the badge
+}
+
+pub fn plugins_dir() -> Result<PathBuf> {
+    Ok(Config::config_dir()?.join("plugins"))
+}
+
+// WebAssembly components in the plugins directory, in name order
+pub fn find_plugins() -> Vec<PathBuf> {
+    let Ok(entries) = plugins_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wasm"))
+        .collect();
+    plugins.sort();
+    plugins
+}
+
+// Whether a plugin with these subscriptions gets an event
+fn wants(subscriptions: &[String], event: &str) -> bool {
+    if subscriptions.is_empty() {
+        event != "tick"
+    } else {
+        subscriptions.iter().any(|name| name == event)
+    }
+}
+
+// Everything a plugin instance can reach through the host interface
+struct PluginState {
+    name: String,
+    storage: BTreeMap<String, String>,
+    storage_path: Option<PathBuf>,
+    commands: UnboundedSender<PluginCommand>,
+    wasi: WasiCtx,
+    table: ResourceTable,
+}
+
+impl PluginState {
+    fn new(name: String, commands: UnboundedSender<PluginCommand>) -> Self {
+        let storage_path = Persistence::data_dir().ok().map(|dir| dir.join("plugins").join(format!("{}.json", name)));
+        let storage = storage_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            name,
+            storage,
+            storage_path,
+            commands,
+            // Plugins get clocks and randomness, plus stderr for debugging;
+            // no files, network, or environment
+            wasi: WasiCtxBuilder::new().inherit_stderr().build(),
+            table: ResourceTable::new(),
+        }
+    }
+
+    fn save_storage(&self) -> Result<()> {
+        let Some(path) = &self.storage_path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.storage)?)?;
+        Ok(())
+    }
+}
+
+impl types::Host for PluginState {}
+
+impl host::Host for PluginState {
+    fn notify(&mut self, message: String) {
+        if !message.trim().is_empty() {
+            let _ = self.commands.unbounded_send(PluginCommand::Notify(message.trim().to_string()));
+        }
+    }
+
+    fn set_badge(&mut self, text: String) {
+        let _ = self.commands.unbounded_send(PluginCommand::SetBadge { plugin: self.name.clone(), text: text.trim().to_string() });
+    }
+
+    fn storage_get(&mut self, key: String) -> Option<String> {
+        self.storage.get(&key).cloned()
+    }
+
+    fn storage_set(&mut self, key: String, value: String) {
+        self.storage.insert(key, value);
+        if let Err(e) = self.save_storage() {
+            notifications::log_error(&format!("Plugin {}: failed to save storage: {:#}", self.name, e));
+        }
+    }
+
+    fn log(&mut self, message: String) {
+        notifications::log_info(&format!("Plugin {}: {}", self.name, message));
+    }
+}
+
+impl IoView for PluginState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for PluginState {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+struct LoadedPlugin {
+    store: Store<PluginState>,
+    bindings: Plugin,
+    subscriptions: Vec<String>,
+}
+
+impl LoadedPlugin {
+    fn load(engine: &Engine, linker: &Linker<PluginState>, path: &Path, commands: UnboundedSender<PluginCommand>) -> Result<Self> {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let component = Component::from_file(engine, path)?;
+        let mut store = Store::new(engine, PluginState::new(name, commands));
+        store.set_fuel(FUEL_PER_CALL)?;
+        let bindings = Plugin::instantiate(&mut store, &component, linker)?;
+        store.set_fuel(FUEL_PER_CALL)?;
+        let subscriptions = bindings.call_subscriptions(&mut store)?;
+        Ok(Self { store, bindings, subscriptions })
+    }
+
+    fn on_event(&mut self, event: &PluginEvent) -> Result<()> {
+        if !wants(&self.subscriptions, &event.name) {
+            return Ok(());
+        }
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        self.bindings.call_on_event(&mut self.store, event)
+    }
+}
+
+fn plugin_linker(engine: &Engine) -> Result<Linker<PluginState>> {
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+    Plugin::add_to_linker(&mut linker, |state: &mut PluginState| state)?;
+    Ok(linker)
+}
+
+// Event bus subscriber: loads the plugins on their own thread, since
+// compiling them takes a moment, and hands them the events they subscribe to
+pub struct PluginHost {
+    events: Option<mpsc::Sender<PluginEvent>>,
+}
+
+impl PluginHost {
+    pub fn spawn(plugins: Vec<PathBuf>, commands: UnboundedSender<PluginCommand>) -> Self {
+        if plugins.is_empty() {
+            return Self { events: None };
+        }
+
+        let (tx, rx) = mpsc::channel::<PluginEvent>();
+        std::thread::spawn(move || {
+            let mut config = wasmtime::Config::new();
+            config.consume_fuel(true);
+            let setup = wasmtime::Engine::new(&config)
+                .and_then(|engine| plugin_linker(&engine).map(|linker| (engine, linker)))
+                .context("Failed to set up the plugin runtime");
+            let (engine, linker) = match setup {
+                Ok(setup) => setup,
+                Err(e) => {
+                    notifications::log_error(&format!("{:#}", e));
+                    return;
+                }
+            };
+
+            let mut loaded: Vec<(PathBuf, LoadedPlugin)> = plugins
+                .into_iter()
+                .filter_map(|path| match LoadedPlugin::load(&engine, &linker, &path, commands.clone()) {
+                    Ok(plugin) => {
+                        notifications::log_info(&format!("Loaded plugin {}", path.display()));
+                        Some((path, plugin))
+                    }
+                    Err(e) => {
+                        notifications::log_error(&format!("Plugin {} failed to load: {:#}", path.display(), e));
+                        None
+                    }
+                })
+                .collect();
+
+            for event in rx {
+                // A plugin that traps can't be called again, so it's unloaded
+                loaded.retain_mut(|(path, plugin)| match plugin.on_event(&event) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        notifications::log_error(&format!("Plugin {} failed and was unloaded: {:#}", path.display(), e));
+                        false
+                    }
+                });
+            }
+        });
+        Self { events: Some(tx) }
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let Some(events) = &self.events else { return };
+        if matches!(event, SessionEvent::Changed) {
+            return;
+        }
+        let finished = match event {
+            SessionEvent::Completed { state, .. } => Some(state.display_name().to_string()),
+            _ => None,
+        };
+        let _ = events.send(PluginEvent {
+            name: event.name().to_string(),
+            finished,
+            state: info.current_state.display_name().to_string(),
+            mode: if info.is_focus_mode { "focus" } else { "rest" }.to_string(),
+            label: info.current_label.clone(),
+            remaining_secs: info.active_timer().remaining_secs,
+            total_secs: info.active_timer().total_secs,
+            completed_sessions: info.completed_sessions,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriptions_pick_the_events() {
+        assert!(wants(&[], "completed"));
+        assert!(!wants(&[], "tick"));
+
+        let subscriptions = vec!["completed".to_string(), "tick".to_string()];
+        assert!(wants(&subscriptions, "tick"));
+        assert!(!wants(&subscriptions, "started"));
+    }
+
+    // A plugin written by hand in the component text format: it subscribes
+    // to everything and shows the session's label as its badge
+    const LABEL_BADGE_PLUGIN: &str = r#"
+(component
+  (import "pomodoro:plugin/types@0.1.0" (instance $types
+    (type $ev (record (field "name" string) (field "finished" (option string)) (field "state" string) (field "mode" string) (field "label" string) (field "remaining-secs" u32) (field "total-secs" u32) (field "completed-sessions" u32)))
+    (export "session-event" (type (eq $ev)))))
+  (alias export $types "session-event" (type $event))
+  (import "pomodoro:plugin/host@0.1.0" (instance $host
+    (export "set-badge" (func (param "text" string)))))
+  (core module $Libc
+    (memory (export "memory") 1)
+    (global $bump (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $r i32)
+      global.get $bump
+      local.set $r
+      global.get $bump
+      local.get 3
+      i32.add
+      global.set $bump
+      local.get $r))
+  (core instance $libc (instantiate $Libc))
+  (core func $set_badge (canon lower (func $host "set-badge") (memory $libc "memory")))
+  (core module $Main
+    (import "libc" "memory" (memory 1))
+    (import "host" "set-badge" (func $set_badge (param i32 i32)))
+    (data (i32.const 16) "hello")
+    (func (export "subscriptions") (result i32)
+      (i32.store (i32.const 32) (i32.const 0))
+      (i32.store (i32.const 36) (i32.const 0))
+      i32.const 32)
+    (func (export "on-event") (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32)
+      ;; The label is the fifth field, flattened to params 9 and 10
+      (call $set_badge (local.get 9) (local.get 10))))
+  (core instance $main (instantiate $Main
+    (with "libc" (instance $libc))
+    (with "host" (instance (export "set-badge" (func $set_badge))))))
+  (func (export "subscriptions") (result (list string))
+    (canon lift (core func $main "subscriptions") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (func (export "on-event") (param "event" $event)
+    (canon lift (core func $main "on-event") (memory $libc "memory") (realloc (func $libc "realloc"))))
+)
+"#;
+
+    #[test]
+    fn plugins_get_events_and_set_badges() {
+        let dir = std::env::temp_dir().join(format!("pomodoro-plugins-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("badge.wasm");
+        fs::write(&path, LABEL_BADGE_PLUGIN).unwrap();
+
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).unwrap();
+        let linker = plugin_linker(&engine).unwrap();
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        let mut plugin = LoadedPlugin::load(&engine, &linker, &path, tx).unwrap();
+        assert!(plugin.subscriptions.is_empty());
+        let event = PluginEvent {
+            name: "started".to_string(),
+            finished: None,
+            state: "Working".to_string(),
+            mode: "focus".to_string(),
+            label: "write report".to_string(),
+            remaining_secs: 1500,
+            total_secs: 1500,
+            completed_sessions: 2,
+        };
+        plugin.on_event(&event).unwrap();
+        assert_eq!(
+            rx.try_next().unwrap(),
+            Some(PluginCommand::SetBadge { plugin: "badge".to_string(), text: "write report".to_string() })
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use futures::channel::mpsc::UnboundedSender;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::config::{BatterySaver, Config};
+use crate::notifications;
+
+// How often the power source is looked at with battery_saver = "auto"
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+// Whether battery_saver is in effect right now. Read wherever the timer can go
+// easier: animations, how often the countdown is saved, the focus sound.
+static SAVING: AtomicBool = AtomicBool::new(false);
+
+pub fn saving() -> bool {
+    SAVING.load(Ordering::Relaxed)
+}
+
+// Put battery_saver into effect, and keep following the power source with
+// "auto". Each change is sent on, starting with the first.
+pub fn spawn_watcher(config: &Config, changes: UnboundedSender<bool>) {
+    match config.battery_saver {
+        BatterySaver::Off => {}
+        BatterySaver::Always => {
+            SAVING.store(true, Ordering::Relaxed);
+            let _ = changes.unbounded_send(true);
+        }
+        BatterySaver::Auto => {
+            let watcher = std::thread::Builder::new().name("power".to_string()).spawn(move || loop {
+                // Unknown, such as on a desktop without a battery, counts as plugged in
+                let on_battery = on_battery().unwrap_or(false);
+                if SAVING.swap(on_battery, Ordering::Relaxed) != on_battery {
+                    notifications::log_info(if on_battery { "On battery, saving power" } else { "Plugged in" });
+                    if changes.unbounded_send(on_battery).is_err() {
+                        break;
+                    }
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            });
+            if let Err(e) = watcher {
+                notifications::log_error(&format!("Failed to watch the power source: {}", e));
+            }
+        }
+    }
+}
+
+// What one of the supplies under /sys/class/power_supply says of itself
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Supply {
+    kind: String,  // "Mains", "Battery", "USB"...
+    online: String,  // "1" for a charger plugged in
+    status: String,  // "Charging", "Discharging", "Full"...
+    scope: String,  // "Device" for a mouse's or a headset's battery
+}
+
+// Whether the computer runs on battery; None when it can't be told
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> Option<bool> {
+    let read = |path: &std::path::Path, file: &str| std::fs::read_to_string(path.join(file)).unwrap_or_default().trim().to_string();
+    let supplies: Vec<Supply> = std::fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .map(|supply| {
+            let path = supply.path();
+            Supply { kind: read(&path, "type"), online: read(&path, "online"), status: read(&path, "status"), scope: read(&path, "scope") }
+        })
+        .collect();
+    from_supplies(&supplies)
+}
+
+// On mains when a charger is online, on battery when the computer's own
+// battery is discharging
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn from_supplies(supplies: &[Supply]) -> Option<bool> {
+    if supplies.iter().any(|supply| supply.kind == "Mains" && supply.online == "1") {
+        return Some(false);
+    }
+    let mut batteries = supplies.iter().filter(|supply| supply.kind == "Battery" && supply.scope != "Device").peekable();
+    batteries.peek()?;
+    Some(batteries.any(|battery| battery.status == "Discharging"))
+}
+
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    from_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+// "Now drawing from 'Battery Power'" on the first line of `pmset -g batt`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn from_pmset(text: &str) -> Option<bool> {
+    let source = text.lines().next()?.split('\'').nth(1)?;
+    Some(source == "Battery Power")
+}
+
+#[cfg(windows)]
+pub fn on_battery() -> Option<bool> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    from_line_status(status.ACLineStatus)
+}
+
+// ACLineStatus of SYSTEM_POWER_STATUS: 0 offline, 1 online, 255 unknown
+#[cfg_attr(not(windows), allow(dead_code))]
+fn from_line_status(status: u8) -> Option<bool> {
+    match status {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn on_battery() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(kind: &str, online: &str, status: &str) -> Supply {
+        Supply { kind: kind.to_string(), online: online.to_string(), status: status.to_string(), scope: String::new() }
+    }
+
+    #[test]
+    fn tells_battery_from_mains() {
+        assert_eq!(from_supplies(&[supply("Mains", "0", ""), supply("Battery", "", "Discharging")]), Some(true));
+        assert_eq!(from_supplies(&[supply("Mains", "1", ""), supply("Battery", "", "Charging")]), Some(false));
+        assert_eq!(from_supplies(&[supply("Battery", "", "Full")]), Some(false));
+        // A desktop, with only a wireless mouse's battery
+        let mouse = Supply { scope: "Device".to_string(), ..supply("Battery", "", "Discharging") };
+        assert_eq!(from_supplies(&[]), None);
+        assert_eq!(from_supplies(&[mouse]), None);
+
+        assert_eq!(from_pmset("Now drawing from 'Battery Power'\n -InternalBattery-0\t82%; discharging"), Some(true));
+        assert_eq!(from_pmset("Now drawing from 'AC Power'\n"), Some(false));
+        assert_eq!(from_pmset(""), None);
+
+        assert_eq!(from_line_status(0), Some(true));
+        assert_eq!(from_line_status(1), Some(false));
+        assert_eq!(from_line_status(255), None);
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::config::{Config, Template};
+
+// Per-project plan, in the project's folder
+pub const FILE: &str = ".pomodoro.toml";
+
+// The plan in `dir`'s .pomodoro.toml: a template, named after the folder unless
+// it names itself
+pub fn load(dir: &Path) -> Result<Template> {
+    let path = dir.join(FILE);
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut template: Template = toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    if template.name.trim().is_empty() {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        template.name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| "Project".to_string());
+    }
+    Ok(template)
+}
+
+// Offer the config's projects, and the one opened from the command line, in the
+// timer menu. Returns the opened one's name, and what went wrong with the rest.
+pub fn add_to(config: &mut Config, opened: Option<&Path>) -> (Option<String>, Vec<String>) {
+    let mut problems = Vec::new();
+    let mut opened_name = None;
+    let dirs = config.projects.clone();
+    for (dir, is_opened) in dirs.iter().map(|dir| (Path::new(dir), false)).chain(opened.map(|dir| (dir, true))) {
+        let added = load(dir).and_then(|template| {
+            let name = template.name.clone();
+            // Opened again from the list
+            if is_opened && config.project_templates.iter().any(|project| project.name == name) {
+                return Ok(name);
+            }
+            config.add_project(template).with_context(|| format!("In {}", dir.join(FILE).display()))?;
+            Ok(name)
+        });
+        match added {
+            Ok(name) if is_opened => opened_name = Some(name),
+            Ok(_) => {}
+            Err(e) => problems.push(format!("{:#}", e)),
+        }
+    }
+    (opened_name, problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projects_join_the_templates() {
+        let root = std::env::temp_dir().join(format!("pomodoro-projects-{}", std::process::id()));
+        let (client, slow, broken) = (root.join("client-site"), root.join("thesis"), root.join("broken"));
+        for dir in [&client, &slow, &broken] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(client.join(FILE), "work_duration = 50\nlabels = [\"Review PRs\"]\n").unwrap();
+        std::fs::write(slow.join(FILE), "name = \"Thesis\"\nwork_duration = 90\n").unwrap();
+        std::fs::write(broken.join(FILE), "work_duration = 0\n").unwrap();
+
+        let mut config = Config {
+            projects: vec![client.display().to_string(), broken.display().to_string()],
+            ..Config::default()
+        };
+        let (opened, problems) = add_to(&mut config, Some(&slow));
+        assert_eq!(opened.as_deref(), Some("Thesis"));
+        assert_eq!(problems.len(), 1);
+        let names: Vec<&str> = config.all_templates().map(|template| template.name.as_str()).collect();
+        assert_eq!(names, ["client-site", "Thesis"]);
+        let client = config.template("client-site").unwrap().apply(&config);
+        assert_eq!(client.work_duration, 50);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::QuickTimerPreset;
+use crate::state::ActiveTimer;
+
+// A one-off countdown beside the pomodoro cycle, such as tea or a standup.
+// It runs whatever the timer is doing and never shows up in history or stats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickTimer {
+    pub name: String,
+    pub minutes: u32,
+    pub ends_at: DateTime<Utc>,  // Kept by the clock, so a suspend doesn't hold it back
+}
+
+impl QuickTimer {
+    pub fn start(preset: &QuickTimerPreset, now: DateTime<Utc>) -> Self {
+        Self {
+            name: preset.name.clone(),
+            minutes: preset.duration,
+            ends_at: now + Duration::minutes(preset.duration as i64),
+        }
+    }
+
+    pub fn remaining_secs(&self, now: DateTime<Utc>) -> u32 {
+        // Round up, so it reads 00:00 only once it's over
+        let millis = (self.ends_at - now).num_milliseconds().max(0);
+        ((millis + 999) / 1000) as u32
+    }
+
+    pub fn is_over(&self, now: DateTime<Utc>) -> bool {
+        self.remaining_secs(now) == 0
+    }
+
+    // Shown in its lane under the timer: "Tea 02:14"
+    pub fn text(&self, now: DateTime<Utc>) -> String {
+        let countdown = ActiveTimer { remaining_secs: self.remaining_secs(now), total_secs: self.minutes * 60 };
+        format!("{} {}", self.name, countdown.format())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_down_by_the_clock() {
+        let now = "2026-10-16T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let tea = QuickTimer::start(&QuickTimerPreset { name: "Tea".to_string(), duration: 3 }, now);
+        assert_eq!(tea.text(now), "Tea 03:00");
+        assert_eq!(tea.text(now + Duration::milliseconds(45_500)), "Tea 02:15");
+        assert!(!tea.is_over(now + Duration::milliseconds(179_900)));
+        assert!(tea.is_over(now + Duration::minutes(3)));
+        assert_eq!(tea.remaining_secs(now + Duration::hours(1)), 0);
+    }
+}
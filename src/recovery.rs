@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::{CompletedTimer, SessionInfo};
+
+const FILE: &str = "recovery.json";
+
+// History entries kept with the session, in case they ended after the last save
+const RECENT_ENTRIES: usize = 10;
+
+// The session as it was when the app crashed, saved by the panic hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recovery {
+    pub crashed_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,  // When the running session would have ended
+    pub session: SessionInfo,
+    #[serde(default)]
+    pub recent: Vec<CompletedTimer>,
+}
+
+impl Recovery {
+    fn new(session: SessionInfo, recent: Vec<CompletedTimer>, crashed_at: DateTime<Utc>) -> Self {
+        let ends_at = session.ends_at();
+        Self { crashed_at, ends_at, session, recent }
+    }
+
+    // Put the crashed session in place of the current one, keeping the history
+    // and adding the entries that ended after it was last saved
+    pub fn restore_into(self, info: &mut SessionInfo) {
+        let newest = info.history.last().map(|entry| entry.completed_at);
+        let mut session = self.session;
+        session.history = std::mem::take(&mut info.history);
+        session.history.extend(self.recent.into_iter().filter(|entry| newest.is_none_or(|newest| entry.completed_at > newest)));
+        session.history_index = None;
+        session.generation = info.generation;
+        session.history_max_age_confirmed = info.history_max_age_confirmed;
+        session.retention = info.retention;
+        *info = session;
+    }
+
+    // The recovery left by a crash, removed so it's only offered once
+    pub fn take() -> Option<Self> {
+        let content = Persistence::read_private(FILE)
+            .inspect_err(|e| notifications::log_error(&format!("Failed to read the crash recovery: {:#}", e)))
+            .ok()
+            .flatten()?;
+        Self::clear();
+        serde_json::from_str(&content)
+            .inspect_err(|e| notifications::log_error(&format!("Failed to parse the crash recovery: {}", e)))
+            .ok()
+    }
+
+    // Also on a clean quit: a panic on another thread doesn't always bring the app down
+    pub fn clear() {
+        if let Ok(path) = Persistence::data_dir().map(|dir| dir.join(FILE)) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Event bus subscriber that keeps a copy of the session as of the last event,
+// for a panic hook to save. The hook can't take the state lock: the thread
+// that panicked may be holding it.
+pub struct CrashGuard {
+    latest: Arc<Mutex<Option<Snapshot>>>,
+}
+
+struct Snapshot {
+    session: SessionInfo,  // Without the history
+    recent: Vec<CompletedTimer>,
+}
+
+impl CrashGuard {
+    pub fn install(info: &SessionInfo) -> Self {
+        let guard = Self { latest: Arc::new(Mutex::new(None)) };
+        guard.on_event(&SessionEvent::Changed, info);
+
+        let latest = guard.latest.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic| {
+            let latest = latest.try_lock_for(std::time::Duration::from_millis(100));
+            if let Some(snapshot) = latest.as_ref().and_then(|latest| latest.as_ref()) {
+                let recovery = Recovery::new(snapshot.session.clone(), snapshot.recent.clone(), Utc::now());
+                let saved = serde_json::to_string(&recovery)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| Persistence::write_private(FILE, &content));
+                match saved {
+                    Ok(()) => notifications::log_info("Saved the running session for the next launch"),
+                    Err(e) => notifications::log_error(&format!("Failed to save the running session: {:#}", e)),
+                }
+            }
+            previous(panic);
+        }));
+        guard
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let mut latest = self.latest.lock();
+        match latest.as_mut() {
+            // The countdown is all a tick moves, and copying the history every second is a waste
+            Some(Snapshot { session, .. }) if *event == SessionEvent::Tick => {
+                session.focus = info.focus.clone();
+                session.rest = info.rest.clone();
+                session.elapsed_secs = info.elapsed_secs;
+                session.last_updated = info.last_updated;
+            }
+            _ => {
+                let mut session = info.clone();
+                let recent = session.history.split_off(session.history.len().saturating_sub(RECENT_ENTRIES));
+                session.history.clear();
+                *latest = Some(Snapshot { session, recent });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::state::{ActiveTimer, TimerState};
+
+    #[test]
+    fn restores_the_crashed_session() {
+        let mut crashed = SessionInfo::new();
+        crashed.add_to_history("a".into(), String::new(), 1500, "Work Session".into(), false);
+        crashed.add_to_history("b".into(), String::new(), 300, "Short Break".into(), false);
+        crashed.current_state = TimerState::Working;
+        crashed.current_label = "Report".to_string();
+        crashed.focus = ActiveTimer { remaining_secs: 600, total_secs: 1500 };
+        let recent = std::mem::take(&mut crashed.history);
+        let recovery = Recovery::new(crashed.clone(), recent.clone(), Utc::now());
+        assert_eq!(recovery.ends_at, Some(crashed.last_updated + Duration::minutes(10)));
+
+        // The last save had only the first entry
+        let mut info = SessionInfo::new();
+        info.history.push(recent[0].clone());
+        info.generation = 7;
+        recovery.restore_into(&mut info);
+        assert_eq!(info.current_label, "Report");
+        assert_eq!(info.focus.remaining_secs, 600);
+        assert_eq!(info.history.iter().map(|entry| entry.id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(info.generation, 7);
+    }
+}
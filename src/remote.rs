@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Result};
+use futures::channel::mpsc::UnboundedSender;
+use parking_lot::Mutex;
+use std::fs;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::state::SessionInfo;
+
+// Control page for phones, served to anyone on the local network with the token
+const PAGE: &str = include_str!("remote.html");
+
+// What the phone asks the timer to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoteCommand {
+    Toggle,
+    Skip,
+}
+
+// What a request is asking for
+#[derive(Debug, PartialEq)]
+enum Route {
+    Page,
+    Status,
+    Command(RemoteCommand),
+    Forbidden,
+    NotFound,
+}
+
+fn route(method: &Method, url: &str, token: &str) -> Route {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let authorized = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| key == "token" && value == token);
+    if !authorized {
+        return Route::Forbidden;
+    }
+    match (method, path) {
+        (Method::Get, "/") => Route::Page,
+        (Method::Get, "/status") => Route::Status,
+        (Method::Post, "/toggle") => Route::Command(RemoteCommand::Toggle),
+        (Method::Post, "/skip") => Route::Command(RemoteCommand::Skip),
+        _ => Route::NotFound,
+    }
+}
+
+// Token in the remote's URL. Kept between runs so a bookmarked page keeps working;
+// delete the file to lock out phones that have it.
+fn remote_token() -> Result<String> {
+    let path = Persistence::data_dir()?.join("remote-token");
+    if let Ok(token) = fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, &token)?;
+    Ok(token)
+}
+
+// Address phones on the same network can reach this machine at. Connecting a
+// UDP socket sends nothing; it only picks the interface with the default route.
+fn lan_address() -> String {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+// Event bus subscriber that keeps the status the page shows, and the HTTP
+// server thread answering the page
+pub struct WebRemote {
+    url: String,
+    status: Arc<Mutex<String>>,
+    sessions_until_long_break: u32,
+}
+
+impl WebRemote {
+    // Starts the server, or returns None when the remote is off or can't start
+    pub fn spawn(config: &Config, commands: UnboundedSender<RemoteCommand>) -> Option<Self> {
+        if !config.web_remote {
+            return None;
+        }
+        match Self::start(config, commands) {
+            Ok(remote) => {
+                notifications::log_info(&format!("Phone remote at {}", remote.url));
+                Some(remote)
+            }
+            Err(e) => {
+                notifications::log_error(&format!("Failed to start the phone remote: {:#}", e));
+                None
+            }
+        }
+    }
+
+    fn start(config: &Config, commands: UnboundedSender<RemoteCommand>) -> Result<Self> {
+        let port = config.web_remote_port;
+        let token = remote_token()?;
+        let server = Server::http((Ipv4Addr::UNSPECIFIED, port)).map_err(|e| anyhow!("port {}: {}", port, e))?;
+        let url = format!("http://{}:{}/?token={}", lan_address(), port, token);
+        let status = Arc::new(Mutex::new(String::from("{}")));
+
+        let shared_status = status.clone();
+        std::thread::Builder::new().name("web-remote".to_string()).spawn(move || {
+            for request in server.incoming_requests() {
+                let route = route(request.method(), request.url(), &token);
+                if let Route::Command(command) = route {
+                    let _ = commands.unbounded_send(command);
+                }
+                let status = shared_status.lock().clone();
+                if let Err(e) = respond(request, route, status) {
+                    notifications::log_error(&format!("Phone remote: {}", e));
+                }
+            }
+        })?;
+        Ok(Self { url, status, sessions_until_long_break: config.cycle_length() })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn on_event(&self, _event: &SessionEvent, info: &SessionInfo) {
+        *self.status.lock() = info.status_json(notifications::is_private(), self.sessions_until_long_break).to_string();
+    }
+}
+
+fn respond(request: Request, route: Route, status: String) -> std::io::Result<()> {
+    let content_type = |value: &str| Header::from_bytes("Content-Type", value).expect("valid header");
+    match route {
+        Route::Page => request.respond(Response::from_string(PAGE).with_header(content_type("text/html; charset=utf-8"))),
+        // Commands are carried out on the UI thread, so this is the state just
+        // before; the page's next poll picks up the change
+        Route::Status | Route::Command(_) => {
+            request.respond(Response::from_string(status).with_header(content_type("application/json")))
+        }
+        Route::Forbidden => request.respond(Response::from_string("Forbidden").with_status_code(403)),
+        Route::NotFound => request.respond(Response::from_string("Not found").with_status_code(404)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_need_the_token() {
+        let token = "abc123";
+        assert_eq!(route(&Method::Get, "/?token=abc123", token), Route::Page);
+        assert_eq!(route(&Method::Get, "/status?v=2&token=abc123", token), Route::Status);
+        assert_eq!(route(&Method::Post, "/toggle?token=abc123", token), Route::Command(RemoteCommand::Toggle));
+        assert_eq!(route(&Method::Post, "/skip?token=abc123", token), Route::Command(RemoteCommand::Skip));
+        assert_eq!(route(&Method::Get, "/skip?token=abc123", token), Route::NotFound);
+
+        assert_eq!(route(&Method::Get, "/", token), Route::Forbidden);
+        assert_eq!(route(&Method::Post, "/toggle?token=abc", token), Route::Forbidden);
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Weekday};
+
+use crate::config::Config;
+
+// When auto_start_work may start a work session by itself: hours for each
+// weekday, and dates off such as holidays. Weekdays missing from a non-empty
+// schedule are days off; an empty schedule allows any time. A hard stop ends
+// every workday, with no session started that would run past it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkSchedule {
+    hours: Vec<(Weekday, NaiveTime, NaiveTime)>,
+    days_off: Vec<NaiveDate>,
+    hard_stop: Option<NaiveTime>,
+}
+
+fn parse_hours(day: Weekday, text: &str) -> Result<Vec<(Weekday, NaiveTime, NaiveTime)>> {
+    if text.trim().eq_ignore_ascii_case("off") {
+        return Ok(Vec::new());
+    }
+    text.split(',')
+        .map(|range| {
+            let (start, end) = range
+                .split_once('-')
+                .with_context(|| format!("Work hours \"{}\" should look like 09:00-17:30", range.trim()))?;
+            let time = |text: &str| {
+                NaiveTime::parse_from_str(text.trim(), "%H:%M")
+                    .with_context(|| format!("\"{}\" is not a time like 09:00", text.trim()))
+            };
+            let (start, end) = (time(start)?, time(end)?);
+            if end <= start {
+                bail!("Work hours {}-{} end before they start", start.format("%H:%M"), end.format("%H:%M"));
+            }
+            Ok((day, start, end))
+        })
+        .collect()
+}
+
+impl WorkSchedule {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut hours = Vec::new();
+        for (day, text) in &config.work_hours {
+            let weekday = day
+                .parse::<Weekday>()
+                .map_err(|_| anyhow::anyhow!("\"{}\" in work_hours is not a day of the week", day))?;
+            hours.extend(parse_hours(weekday, text)?);
+        }
+        // A table of nothing but "off" days still means a schedule
+        let has_schedule = !config.work_hours.is_empty();
+        if has_schedule && hours.is_empty() {
+            bail!("work_hours has no working days");
+        }
+
+        let days_off = config
+            .days_off
+            .iter()
+            .map(|date| {
+                NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                    .with_context(|| format!("Day off \"{}\" should be a date like 2026-12-25", date))
+            })
+            .collect::<Result<_>>()?;
+
+        let hard_stop = config
+            .hard_stop
+            .as_deref()
+            .map(|time| {
+                NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                    .with_context(|| format!("Hard stop \"{}\" should be a time like 17:30", time))
+            })
+            .transpose()?;
+        Ok(Self { hours, days_off, hard_stop })
+    }
+
+    pub fn allows(&self, now: DateTime<Local>) -> bool {
+        if self.days_off.contains(&now.date_naive()) {
+            return false;
+        }
+        self.hours.is_empty()
+            || self
+                .hours
+                .iter()
+                .any(|(day, start, end)| *day == now.weekday() && (*start..*end).contains(&now.time()))
+    }
+
+    // Where the next stretch of work hours begins after `now`, within a week.
+    // None without a schedule, since any time is then already allowed.
+    pub fn next_start(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..=7)
+            .filter_map(|days| now.date_naive().checked_add_signed(Duration::days(days)))
+            .filter(|date| !self.days_off.contains(date))
+            .flat_map(|date| {
+                self.hours
+                    .iter()
+                    .filter(move |(day, _, _)| *day == date.weekday())
+                    .filter_map(move |(_, start, _)| date.and_time(*start).and_local_timezone(Local).earliest())
+            })
+            .filter(|start| *start > now)
+            .min()
+    }
+
+    pub fn has_hard_stop(&self) -> bool {
+        self.hard_stop.is_some()
+    }
+
+    // A date in days_off, or a weekday the schedule leaves out
+    fn is_day_off(&self, date: NaiveDate) -> bool {
+        self.days_off.contains(&date) || (!self.hours.is_empty() && self.hours.iter().all(|(day, _, _)| *day != date.weekday()))
+    }
+
+    // Time until today's hard stop: None without one or on a day off, zero once it has passed
+    pub fn workday_left(&self, now: DateTime<Local>) -> Option<Duration> {
+        let hard_stop = self.hard_stop?;
+        if self.is_day_off(now.date_naive()) {
+            return None;
+        }
+        Some((hard_stop - now.time()).max(Duration::zero()))
+    }
+
+    // Whether a session this long would still be running at the hard stop
+    pub fn runs_past_hard_stop(&self, now: DateTime<Local>, secs: u32) -> bool {
+        self.workday_left(now).is_some_and(|left| left < Duration::seconds(secs as i64))
+    }
+}
+
+// "2h 05m left today" under the timer, until the hard stop passes
+pub fn workday_text(left: Duration) -> String {
+    let minutes = (left.num_seconds() + 59) / 60;
+    match minutes {
+        0 => "Workday over".to_string(),
+        1..=59 => format!("{} min left today", minutes),
+        _ => format!("{}h {:02}m left today", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    fn at(text: &str) -> DateTime<Local> {
+        Local.from_local_datetime(&chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn allows_work_hours_only() {
+        let config = Config {
+            work_hours: BTreeMap::from([
+                ("mon".to_string(), "09:00-12:00, 13:00-17:30".to_string()),
+                ("friday".to_string(), "09:00-15:00".to_string()),
+                ("sat".to_string(), "off".to_string()),
+            ]),
+            days_off: vec!["2026-10-19".to_string()],
+            ..Config::default()
+        };
+        let schedule = WorkSchedule::from_config(&config).unwrap();
+        assert!(schedule.allows(at("2026-10-12 09:00")));  // Monday
+        assert!(!schedule.allows(at("2026-10-12 12:30")));  // Lunch
+        assert!(!schedule.allows(at("2026-10-12 17:30")));
+        assert!(!schedule.allows(at("2026-10-13 10:00")));  // Tuesday isn't listed
+        assert!(schedule.allows(at("2026-10-16 14:59")));
+        assert!(!schedule.allows(at("2026-10-17 10:00")));  // Saturday is off
+        assert!(!schedule.allows(at("2026-10-19 10:00")));  // A Monday off
+
+        assert!(WorkSchedule::default().allows(at("2026-10-17 23:00")));
+
+        assert_eq!(schedule.next_start(at("2026-10-12 12:30")), Some(at("2026-10-12 13:00")));
+        // Over the weekend and the Monday off, to Friday
+        assert_eq!(schedule.next_start(at("2026-10-12 18:00")), Some(at("2026-10-16 09:00")));
+        assert_eq!(schedule.next_start(at("2026-10-16 16:00")), Some(at("2026-10-23 09:00")));
+        assert_eq!(WorkSchedule::default().next_start(at("2026-10-12 18:00")), None);
+    }
+
+    #[test]
+    fn rejects_unclear_schedules() {
+        let with_hours = |day: &str, hours: &str| Config {
+            work_hours: BTreeMap::from([(day.to_string(), hours.to_string())]),
+            ..Config::default()
+        };
+        assert!(WorkSchedule::from_config(&with_hours("mon", "17:00-09:00")).is_err());
+        assert!(WorkSchedule::from_config(&with_hours("mon", "9am-5pm")).is_err());
+        assert!(WorkSchedule::from_config(&with_hours("someday", "09:00-17:00")).is_err());
+        assert!(WorkSchedule::from_config(&with_hours("sun", "off")).is_err());
+        let holiday = Config { days_off: vec!["25/12/2026".to_string()], ..Config::default() };
+        assert!(WorkSchedule::from_config(&holiday).is_err());
+        let hard_stop = Config { hard_stop: Some("5:30pm".to_string()), ..Config::default() };
+        assert!(WorkSchedule::from_config(&hard_stop).is_err());
+    }
+
+    #[test]
+    fn counts_down_to_the_hard_stop() {
+        let config = Config { hard_stop: Some("17:30".to_string()), ..Config::default() };
+        let schedule = WorkSchedule::from_config(&config).unwrap();
+        assert_eq!(schedule.workday_left(at("2026-10-12 15:00")), Some(Duration::minutes(150)));
+        assert_eq!(schedule.workday_left(at("2026-10-12 18:00")), Some(Duration::zero()));
+        assert!(!schedule.runs_past_hard_stop(at("2026-10-12 17:05"), 25 * 60));
+        assert!(schedule.runs_past_hard_stop(at("2026-10-12 17:06"), 25 * 60));
+
+        assert_eq!(WorkSchedule::default().workday_left(at("2026-10-12 15:00")), None);
+
+        // No workday to end on a day off, or a weekday without work hours
+        let config = Config {
+            hard_stop: Some("17:30".to_string()),
+            work_hours: BTreeMap::from([("mon".to_string(), "09:00-17:00".to_string())]),
+            days_off: vec!["2026-10-19".to_string()],
+            ..Config::default()
+        };
+        let schedule = WorkSchedule::from_config(&config).unwrap();
+        assert_eq!(schedule.workday_left(at("2026-10-12 15:00")), Some(Duration::minutes(150)));
+        assert_eq!(schedule.workday_left(at("2026-10-19 15:00")), None);
+        assert_eq!(schedule.workday_left(at("2026-10-17 15:00")), None);
+        assert!(!schedule.runs_past_hard_stop(at("2026-10-19 17:06"), 25 * 60));
+
+        assert_eq!(workday_text(Duration::minutes(125)), "2h 05m left today");
+        assert_eq!(workday_text(Duration::seconds(61)), "2 min left today");
+        assert_eq!(workday_text(Duration::zero()), "Workday over");
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use chrono::Timelike;
+use futures::channel::mpsc::UnboundedSender;
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::SessionInfo;
+
+// Operations one hook call may take before it is stopped, so a runaway loop
+// in a script can't hang the script thread
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+// What a script can ask the app to do through its API functions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptCommand {
+    Notify(String),  // notify(message)
+    SetLabel(String),  // set_label(text), "" clears it
+    SetDuration(u32),  // set_duration(minutes): new length of the current session
+}
+
+pub fn scripts_dir() -> Result<PathBuf> {
+    Ok(Config::config_dir()?.join("scripts"))
+}
+
+// Rhai scripts in the scripts directory, in name order
+pub fn find_scripts() -> Vec<PathBuf> {
+    let Ok(entries) = scripts_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+    let mut scripts: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "rhai"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+// Engine with the script API; commands go straight to the app's channel
+fn script_engine(commands: UnboundedSender<ScriptCommand>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| notifications::log_info(&format!("Script: {}", text)));
+
+    let tx = commands.clone();
+    engine.register_fn("notify", move |message: &str| {
+        if !message.trim().is_empty() {
+            let _ = tx.unbounded_send(ScriptCommand::Notify(message.trim().to_string()));
+        }
+    });
+    let tx = commands.clone();
+    engine.register_fn("set_label", move |label: &str| {
+        let _ = tx.unbounded_send(ScriptCommand::SetLabel(label.trim().to_string()));
+    });
+    engine.register_fn("set_duration", move |minutes: i64| {
+        if let Some(minutes) = u32::try_from(minutes).ok().filter(|minutes| *minutes > 0) {
+            let _ = commands.unbounded_send(ScriptCommand::SetDuration(minutes));
+        }
+    });
+    engine
+}
+
+// A script's on_event(event) hook, if it defines one
+fn run_hook(engine: &Engine, ast: &AST, event: &serde_json::Value) -> Result<()> {
+    if !ast.iter_functions().any(|function| function.name == "on_event" && function.params.len() == 1) {
+        return Ok(());
+    }
+    let event: Dynamic = rhai::serde::to_dynamic(event).map_err(|e| anyhow!("{}", e))?;
+    engine
+        .call_fn::<Dynamic>(&mut Scope::new(), ast, "on_event", (event,))
+        .map(|_| ())
+        .map_err(|e| anyhow!("{}", e))
+}
+
+// Event bus subscriber: hands session events to the scripts' on_event hooks.
+// Scripts run on their own thread so a slow one never holds up the UI.
+pub struct ScriptRunner {
+    events: Option<mpsc::Sender<serde_json::Value>>,
+}
+
+impl ScriptRunner {
+    pub fn new(scripts: Vec<PathBuf>, commands: UnboundedSender<ScriptCommand>) -> Self {
+        let engine = script_engine(commands);
+        let loaded: Vec<(PathBuf, AST)> = scripts
+            .into_iter()
+            .filter_map(|path| match engine.compile_file(path.clone()) {
+                Ok(ast) => {
+                    notifications::log_info(&format!("Loaded script {}", path.display()));
+                    Some((path, ast))
+                }
+                Err(e) => {
+                    notifications::log_error(&format!("Script {} failed to load: {}", path.display(), e));
+                    None
+                }
+            })
+            .collect();
+        if loaded.is_empty() {
+            return Self { events: None };
+        }
+
+        let (tx, rx) = mpsc::channel::<serde_json::Value>();
+        std::thread::spawn(move || {
+            for event in rx {
+                for (path, ast) in &loaded {
+                    if let Err(e) = run_hook(&engine, ast, &event) {
+                        notifications::log_error(&format!("Script {} failed: {:#}", path.display(), e));
+                    }
+                }
+            }
+        });
+        Self { events: Some(tx) }
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        // Ticks would run scripts every second, and edits include the ones scripts make
+        let Some(events) = &self.events else { return };
+        if matches!(event, SessionEvent::Tick | SessionEvent::Changed) {
+            return;
+        }
+        let _ = events.send(event_json(event, info));
+    }
+}
+
+fn event_json(event: &SessionEvent, info: &SessionInfo) -> serde_json::Value {
+    let finished = match event {
+        SessionEvent::Completed { state, .. } => Some(state.display_name()),
+        _ => None,
+    };
+    json!({
+        "event": event.name(),
+        "finished": finished,
+        "state": info.current_state.display_name(),
+        "mode": if info.is_focus_mode { "focus" } else { "rest" },
+        "label": info.current_label,
+        "remaining_secs": info.active_timer().remaining_secs,
+        "total_secs": info.active_timer().total_secs,
+        "session": info.current_session,
+        "completed_sessions": info.completed_sessions,
+        "hour": chrono::Local::now().hour(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_send_commands_from_their_hook() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        let engine = script_engine(tx);
+        let ast = engine
+            .compile(
+                r#"
+                fn on_event(event) {
+                    if event.event != "completed" { return; }
+                    notify("Finished " + event.label);
+                    set_label("");
+                    set_duration(50);
+                    set_duration(0);
+                }
+                "#,
+            )
+            .unwrap();
+
+        run_hook(&engine, &ast, &json!({ "event": "started", "label": "report" })).unwrap();
+        assert!(rx.try_next().is_err());
+
+        run_hook(&engine, &ast, &json!({ "event": "completed", "label": "report" })).unwrap();
+        assert_eq!(rx.try_next().unwrap(), Some(ScriptCommand::Notify("Finished report".to_string())));
+        assert_eq!(rx.try_next().unwrap(), Some(ScriptCommand::SetLabel(String::new())));
+        assert_eq!(rx.try_next().unwrap(), Some(ScriptCommand::SetDuration(50)));
+        assert!(rx.try_next().is_err());
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let (tx, _rx) = futures::channel::mpsc::unbounded();
+        let engine = script_engine(tx);
+        let ast = engine.compile("fn on_event(event) { loop {} }").unwrap();
+        assert!(run_hook(&engine, &ast, &json!({ "event": "started" })).is_err());
+
+        // Scripts without a hook are fine
+        let ast = engine.compile("let x = 1;").unwrap();
+        assert!(run_hook(&engine, &ast, &json!({ "event": "started" })).is_ok());
+    }
+}
@@ -0,0 +1,409 @@
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use rodio::buffer::SamplesBuffer;
+use rodio::source::noise::{Brownian, Pink, WhiteUniform};
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source};
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::ducking::{self, Ducked};
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::{SessionInfo, TimerState};
+
+// Focus sounds made on the fly, so there's always something to pick
+pub const GENERATED_SOUNDS: [(&str, &str); 3] = [("brown", "Brown noise"), ("pink", "Pink noise"), ("white", "White noise")];
+
+// Files the focus sound can be picked from
+const AUDIO_EXTENSIONS: [&str; 5] = ["ogg", "mp3", "flac", "wav", "m4a"];
+
+// Sample rate of the generated noise and chime
+const NOISE_SAMPLE_RATE: u32 = 44_100;
+
+// Loudness of the alarm chime, whatever the focus sound's volume
+const ALARM_VOLUME: f32 = 0.8;
+
+// Loudness of the final minute's tick, kept soft
+const TICK_VOLUME: f32 = 0.25;
+
+// Work sessions tick through their last this many seconds, with tick_sound
+const TICK_SECS: u32 = 60;
+
+// The focus sound fades in when work starts and out when it stops
+const FADE_IN: Duration = Duration::from_secs(2);
+const FADE_OUT: Duration = Duration::from_secs(4);
+const FADE_STEP: Duration = Duration::from_millis(50);
+
+pub fn sounds_dir() -> Result<PathBuf> {
+    Ok(Config::config_dir()?.join("sounds"))
+}
+
+// Focus sounds to choose from: the generated noises, then audio files in the
+// sounds folder by name (rain.ogg, cafe.mp3, ...)
+pub fn available_sounds() -> Vec<String> {
+    let mut files: Vec<String> = sounds_dir()
+        .and_then(|dir| Ok(std::fs::read_dir(dir)?))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .and_then(|extension| extension.to_str())
+                        .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+                })
+                .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    GENERATED_SOUNDS.iter().map(|(name, _)| name.to_string()).chain(files).collect()
+}
+
+// "Brown noise", or the file name without its extension ("rain")
+pub fn sound_name(sound: &str) -> String {
+    match GENERATED_SOUNDS.iter().find(|(name, _)| *name == sound) {
+        Some((_, title)) => title.to_string(),
+        None => std::path::Path::new(sound)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(sound)
+            .to_string(),
+    }
+}
+
+// The sound after `current` in the settings' list, with Off after the last
+fn next_sound(current: Option<&str>, sounds: &[String]) -> Option<String> {
+    match current.and_then(|current| sounds.iter().position(|sound| sound == current)) {
+        Some(index) => sounds.get(index + 1).cloned(),
+        None if current.is_some() => None,
+        None => sounds.first().cloned(),
+    }
+}
+
+pub fn cycle_sound(current: Option<&str>) -> Option<String> {
+    next_sound(current, &available_sounds())
+}
+
+// A generated noise, a file in the sounds folder, or a path to an audio file
+fn open_sound(sound: &str) -> Result<Box<dyn Source + Send>> {
+    let source: Box<dyn Source + Send> = match sound {
+        "brown" => Box::new(Brownian::new(NOISE_SAMPLE_RATE)),
+        "pink" => Box::new(Pink::new(NOISE_SAMPLE_RATE)),
+        "white" => Box::new(WhiteUniform::new(NOISE_SAMPLE_RATE).amplify(0.3)),
+        file => {
+            let path = if file.contains(std::path::MAIN_SEPARATOR) { PathBuf::from(file) } else { sounds_dir()?.join(file) };
+            let reader = std::io::BufReader::new(File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?);
+            Box::new(Decoder::new_looped(reader).with_context(|| format!("Can't play {}", path.display()))?)
+        }
+    };
+    Ok(source)
+}
+
+// Whether the sound can be played: the file is there and decodes. For `doctor`.
+pub fn check_sound(sound: &str) -> Result<()> {
+    open_sound(sound).map(|_| ())
+}
+
+// Two falling notes and a pause, repeated by the alarm
+fn chime() -> SamplesBuffer {
+    let note = |frequency: f32, secs: f32| {
+        (0..(NOISE_SAMPLE_RATE as f32 * secs) as usize).map(move |i| {
+            let t = i as f32 / NOISE_SAMPLE_RATE as f32;
+            // Bell-like: loud at the strike, dying away
+            (t * frequency * std::f32::consts::TAU).sin() * (-t * 6.0).exp() * 0.5
+        })
+    };
+    let silence = std::iter::repeat_n(0.0, NOISE_SAMPLE_RATE as usize);
+    SamplesBuffer::new(1, NOISE_SAMPLE_RATE, note(880.0, 0.4).chain(note(660.0, 0.6)).chain(silence).collect::<Vec<f32>>())
+}
+
+// A short click, like a clock's
+fn tick() -> SamplesBuffer {
+    let samples = (0..NOISE_SAMPLE_RATE as usize / 40)
+        .map(|i| {
+            let t = i as f32 / NOISE_SAMPLE_RATE as f32;
+            (t * 2_000.0 * std::f32::consts::TAU).sin() * (-t * 200.0).exp()
+        })
+        .collect::<Vec<f32>>();
+    SamplesBuffer::new(1, NOISE_SAMPLE_RATE, samples)
+}
+
+// Whether a second of this session ticks: the last minute of running work
+fn ticks(info: &SessionInfo) -> bool {
+    info.current_state == TimerState::Working && (1..=TICK_SECS).contains(&info.focus.remaining_secs)
+}
+
+enum AudioCommand {
+    Ambient(Option<String>),  // Play this focus sound, or fade it out
+    Volume(f32),
+    Alarm(bool),  // Start or stop the repeating chime
+    Tick,
+}
+
+// Playing focus sound and where its volume is headed
+struct Ambient {
+    sound: String,
+    sink: Sink,
+    target: f32,
+}
+
+// Output device and the sounds playing on it. Lives on its own thread, since
+// audio streams can't move between threads on every platform.
+struct AudioThread {
+    stream: Option<OutputStream>,  // Opened on first use
+    ambient: Option<Ambient>,
+    volume: f32,
+    alarm: Option<Sink>,
+    duck_audio: bool,
+    ducked: Arc<Mutex<Option<Ducked>>>,  // Other apps' volumes while the alarm rings
+}
+
+impl AudioThread {
+    fn run(mut self, commands: Receiver<AudioCommand>) {
+        loop {
+            // Wake up often only while a fade is under way
+            let command = if self.is_fading() {
+                match commands.recv_timeout(FADE_STEP) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                }
+            };
+            match command {
+                Some(AudioCommand::Ambient(sound)) => self.set_ambient(sound),
+                Some(AudioCommand::Alarm(on)) => self.set_alarm(on),
+                Some(AudioCommand::Tick) => match self.output() {
+                    Ok(stream) => stream.mixer().add(tick().amplify(TICK_VOLUME)),
+                    Err(e) => notifications::log_error(&format!("Failed to play the tick: {:#}", e)),
+                },
+                Some(AudioCommand::Volume(volume)) => {
+                    self.volume = volume;
+                    if let Some(ambient) = self.ambient.as_mut().filter(|ambient| ambient.target > 0.0) {
+                        ambient.target = volume;
+                        ambient.sink.set_volume(volume);
+                    }
+                }
+                None => {}
+            }
+            self.fade();
+        }
+    }
+
+    fn output(&mut self) -> Result<&OutputStream> {
+        if self.stream.is_none() {
+            let mut stream = OutputStreamBuilder::open_default_stream().context("No audio output")?;
+            stream.log_on_drop(false);
+            self.stream = Some(stream);
+        }
+        Ok(self.stream.as_ref().expect("stream was just opened"))
+    }
+
+    fn set_ambient(&mut self, sound: Option<String>) {
+        match (sound, self.ambient.as_mut()) {
+            // Already playing, maybe fading out: fade back in
+            (Some(sound), Some(ambient)) if ambient.sound == sound => ambient.target = self.volume,
+            (Some(sound), _) => {
+                let sink = match open_sound(&sound).and_then(|source| Ok((source, self.output()?))) {
+                    Ok((source, stream)) => {
+                        let sink = Sink::connect_new(stream.mixer());
+                        sink.set_volume(0.0);
+                        sink.append(source);
+                        sink
+                    }
+                    Err(e) => {
+                        notifications::log_error(&format!("Failed to play focus sound: {:#}", e));
+                        return;
+                    }
+                };
+                self.ambient = Some(Ambient { sound, sink, target: self.volume });
+            }
+            (None, Some(ambient)) => ambient.target = 0.0,
+            (None, None) => {}
+        }
+    }
+
+    fn set_alarm(&mut self, on: bool) {
+        if !on {
+            if let Some(alarm) = self.alarm.take() {
+                alarm.stop();
+            }
+            if let Some(ducked) = self.ducked.lock().take() {
+                ducked.restore();
+            }
+            return;
+        }
+        if self.alarm.is_some() {
+            return;
+        }
+        if self.duck_audio {
+            match ducking::duck() {
+                Ok(ducked) => *self.ducked.lock() = Some(ducked),
+                Err(e) => notifications::log_error(&format!("Failed to turn other apps down: {:#}", e)),
+            }
+        }
+        match self.output() {
+            Ok(stream) => {
+                let sink = Sink::connect_new(stream.mixer());
+                sink.set_volume(ALARM_VOLUME);
+                sink.append(chime().repeat_infinite());
+                self.alarm = Some(sink);
+            }
+            Err(e) => notifications::log_error(&format!("Failed to play the alarm: {:#}", e)),
+        }
+    }
+
+    fn is_fading(&self) -> bool {
+        self.ambient.as_ref().is_some_and(|ambient| ambient.sink.volume() != ambient.target)
+    }
+
+    // Move the volume one step towards its target; a sound faded to silence stops
+    fn fade(&mut self) {
+        let Some(ambient) = self.ambient.as_mut() else {
+            return;
+        };
+        let (current, target) = (ambient.sink.volume(), ambient.target);
+        let length = if target > current { FADE_IN } else { FADE_OUT };
+        let step = self.volume.max(0.01) * FADE_STEP.as_secs_f32() / length.as_secs_f32();
+        let volume = if target > current { (current + step).min(target) } else { (current - step).max(target) };
+        ambient.sink.set_volume(volume);
+        if volume == 0.0 && target == 0.0 {
+            ambient.sink.stop();
+            self.ambient = None;
+        }
+    }
+}
+
+// Event bus subscriber that plays the focus sound while a work session counts
+// down, fading it out for breaks and pauses. With tick_sound it also ticks
+// through the session's last minute.
+pub struct SoundPlayer {
+    commands: Sender<AudioCommand>,
+    tick_sound: bool,
+    ducked: Arc<Mutex<Option<Ducked>>>,
+    ambient_sound: Mutex<Option<String>>,
+    playing: Mutex<bool>,
+    quiet: Mutex<bool>,  // Focus sound held back to save the battery
+}
+
+impl SoundPlayer {
+    pub fn spawn(config: &Config) -> Self {
+        let (commands, receiver) = channel();
+        let volume = config.ambient_volume.min(100) as f32 / 100.0;
+        let ducked = Arc::new(Mutex::new(None));
+        let audio = AudioThread {
+            stream: None,
+            ambient: None,
+            volume,
+            alarm: None,
+            duck_audio: config.duck_audio,
+            ducked: ducked.clone(),
+        };
+        if let Err(e) = std::thread::Builder::new().name("audio".to_string()).spawn(move || audio.run(receiver)) {
+            notifications::log_error(&format!("Failed to start audio: {}", e));
+        }
+        Self {
+            commands,
+            tick_sound: config.tick_sound,
+            ducked,
+            ambient_sound: Mutex::new(config.ambient_sound.clone()), playing: Mutex::new(false),
+            quiet: Mutex::new(false),
+        }
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        if *event == SessionEvent::Tick {
+            if self.tick_sound && ticks(info) {
+                let _ = self.commands.send(AudioCommand::Tick);
+            }
+            return;
+        }
+        let working = info.current_state == TimerState::Working;
+        let mut playing = self.playing.lock();
+        if *playing != working {
+            *playing = working;
+            let _ = self.commands.send(AudioCommand::Ambient(self.focus_sound().filter(|_| working)));
+        }
+    }
+
+    // The focus sound to play during work, None while it's kept quiet
+    fn focus_sound(&self) -> Option<String> {
+        self.ambient_sound.lock().clone().filter(|_| !*self.quiet.lock())
+    }
+
+    // Switch the focus sound from the settings; it plays right away if work is under way
+    pub fn set_ambient_sound(&self, sound: Option<String>) {
+        *self.ambient_sound.lock() = sound;
+        if *self.playing.lock() {
+            let _ = self.commands.send(AudioCommand::Ambient(self.focus_sound()));
+        }
+    }
+
+    // Hold the focus sound back, or let it play again, as battery_saver comes and goes
+    pub fn set_quiet(&self, quiet: bool) {
+        *self.quiet.lock() = quiet;
+        if *self.playing.lock() {
+            let _ = self.commands.send(AudioCommand::Ambient(self.focus_sound()));
+        }
+    }
+
+    pub fn set_alarm(&self, on: bool) {
+        let _ = self.commands.send(AudioCommand::Alarm(on));
+    }
+
+    pub fn set_volume(&self, percent: u32) {
+        let _ = self.commands.send(AudioCommand::Volume(percent.min(100) as f32 / 100.0));
+    }
+
+    // Give other apps their volume back before the app quits. Runs right
+    // here, since the audio thread won't get the chance.
+    pub fn restore(&self) {
+        if let Some(ducked) = self.ducked.lock().take() {
+            ducked.restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_through_sounds_and_off() {
+        let sounds = vec!["brown".to_string(), "pink".to_string(), "rain.ogg".to_string()];
+        assert_eq!(next_sound(None, &sounds).as_deref(), Some("brown"));
+        assert_eq!(next_sound(Some("pink"), &sounds).as_deref(), Some("rain.ogg"));
+        assert_eq!(next_sound(Some("rain.ogg"), &sounds), None);
+        // A sound that is gone, e.g. a deleted file, turns the sound off
+        assert_eq!(next_sound(Some("cafe.mp3"), &sounds), None);
+
+        assert_eq!(sound_name("brown"), "Brown noise");
+        assert_eq!(sound_name("rain.ogg"), "rain");
+    }
+
+    #[test]
+    fn ticks_through_the_last_minute_of_work() {
+        let mut info = SessionInfo { current_state: TimerState::Working, ..SessionInfo::default() };
+        info.focus.remaining_secs = 61;
+        assert!(!ticks(&info));
+        info.focus.remaining_secs = 60;
+        assert!(ticks(&info));
+        info.focus.remaining_secs = 1;
+        assert!(ticks(&info));
+
+        // Breaks and paused work stay silent
+        info.current_state = TimerState::WorkPaused;
+        assert!(!ticks(&info));
+        info.current_state = TimerState::ShortBreak;
+        assert!(!ticks(&info));
+    }
+}
@@ -1,14 +1,195 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::strict::StrictLog;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct CompletedTimer {
     pub id: String,  // Unique ID for this timer session
     pub label: String,
     pub duration_secs: u32,
     pub session_type: String,  // "Work", "Short Break", "Long Break"
     pub completed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub abandoned: bool,  // Stopped before the timer ran out
+    #[serde(default)]
+    pub switched: bool,  // Cut short by switching to a break or back to work, which isn't abandoning it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<AppUsage>,  // Frontmost apps during a work session, when track_apps is on
+    #[serde(default)]
+    pub distracted: bool,  // Distracting apps were in front longer than the threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub break_preset: Option<String>,  // Break preset picked for a break, e.g. "Walk"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub task_id: String,  // Id of the first session of the task this one continues, empty when it's the first
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub icon: String,  // Emoji picked for the label, e.g. "📚"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<Segment>,  // When it ran, from each start or resume to the next pause; empty for imported and older entries
+}
+
+impl CompletedTimer {
+    // Sessions of the same task share this
+    pub fn task_id(&self) -> &str {
+        if self.task_id.is_empty() { &self.id } else { &self.task_id }
+    }
+
+    // The timer ran out, rather than the session being abandoned or switched away from
+    pub fn ran_out(&self) -> bool {
+        !self.abandoned && !self.switched
+    }
+}
+
+// History entry for tests, ending at `completed_at` (RFC 3339). Anything else
+// is set with struct update syntax: `CompletedTimer { label, ..completed(...) }`.
+#[cfg(test)]
+pub(crate) fn completed(completed_at: &str, duration_mins: u32, session_type: &str, abandoned: bool) -> CompletedTimer {
+    CompletedTimer {
+        duration_secs: duration_mins * 60,
+        session_type: session_type.to_string(),
+        completed_at: completed_at.parse::<DateTime<Utc>>().unwrap(),
+        abandoned,
+        ..CompletedTimer::default()
+    }
+}
+
+// A stretch of a session that ran without a pause
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+// Time one application spent in front during a work session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppUsage {
+    pub app: String,
+    pub secs: u32,
+    #[serde(default)]
+    pub distracting: bool,  // Matched distracting_apps when it was sampled
+}
+
+// Upcoming label and how many work sessions it should get
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedLabel {
+    pub label: String,
+    pub count: u32,
+}
+
+// Queued sessions that began on one day. With what's still queued, that's
+// the day's plan, for comparing with what got done.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayPlan {
+    pub date: Option<NaiveDate>,
+    pub started: Vec<QueuedLabel>,
+}
+
+impl DayPlan {
+    fn record(&mut self, label: &str, today: NaiveDate) {
+        if self.date != Some(today) {
+            self.date = Some(today);
+            self.started.clear();
+        }
+        match self.started.iter_mut().find(|entry| entry.label == label) {
+            Some(entry) => entry.count += 1,
+            None => self.started.push(QueuedLabel { label: label.to_string(), count: 1 }),
+        }
+    }
+}
+
+// Focus time since the last long break, counted apart from current_session so
+// sessions started by hand outside the cycle count too
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FocusRun {
+    pub secs: u32,
+    pub counted_at: Option<DateTime<Utc>>,  // When focus time was last added
+    pub long_break: bool,  // The next break was made a long one for it
+}
+
+impl FocusRun {
+    // Add focus time at `now`. A gap of `rest_secs` or more since the last was
+    // as good as a long break, so the count starts over.
+    pub fn add(&mut self, secs: u32, now: DateTime<Utc>, rest_secs: u32) {
+        if self.counted_at.is_some_and(|at| now - at >= Duration::seconds((rest_secs + secs) as i64)) {
+            *self = FocusRun::default();
+        }
+        self.secs += secs;
+        self.counted_at = Some(now);
+    }
+}
+
+// Session types recorded in history, as written by TimerState::display_name
+pub const HISTORY_SESSION_TYPES: [&str; 3] = ["Work Session", "Short Break", "Long Break"];
+
+// How far back a history search looks
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HistoryRange {
+    #[default]
+    All,
+    Today,
+    Week,
+    Month,
+}
+
+impl HistoryRange {
+    pub fn next(&self) -> Self {
+        match self {
+            HistoryRange::All => HistoryRange::Today,
+            HistoryRange::Today => HistoryRange::Week,
+            HistoryRange::Week => HistoryRange::Month,
+            HistoryRange::Month => HistoryRange::All,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HistoryRange::All => "All time",
+            HistoryRange::Today => "Today",
+            HistoryRange::Week => "7 days",
+            HistoryRange::Month => "30 days",
+        }
+    }
+
+    // Earliest completion time included, or None for no limit
+    pub fn start(&self, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+        let start_of_today = now.date_naive().and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()?;
+        match self {
+            HistoryRange::All => None,
+            HistoryRange::Today => Some(start_of_today.with_timezone(&Utc)),
+            HistoryRange::Week => Some((start_of_today - Duration::days(6)).with_timezone(&Utc)),
+            HistoryRange::Month => Some((start_of_today - Duration::days(29)).with_timezone(&Utc)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    pub query: String,                // Case-insensitive label substring
+    pub session_type: Option<String>, // One of HISTORY_SESSION_TYPES, None for all
+    pub range: HistoryRange,
+}
+
+impl HistoryFilter {
+    pub fn next_session_type(&mut self) {
+        // Cycle All -> each type -> All
+        let position = self
+            .session_type
+            .as_deref()
+            .and_then(|current| HISTORY_SESSION_TYPES.iter().position(|t| *t == current));
+        self.session_type = match position {
+            None => Some(HISTORY_SESSION_TYPES[0].to_string()),
+            Some(i) => HISTORY_SESSION_TYPES.get(i + 1).map(|t| t.to_string()),
+        };
+    }
+
+    pub fn matches(&self, entry: &CompletedTimer, since: Option<DateTime<Utc>>) -> bool {
+        let query = self.query.trim().to_lowercase();
+        let matches_text = |text: &str| text.to_lowercase().contains(&query);
+        (query.is_empty() || matches_text(&entry.label) || entry.break_preset.as_deref().is_some_and(matches_text))
+            && self.session_type.as_ref().is_none_or(|t| *t == entry.session_type)
+            && since.is_none_or(|since| entry.completed_at >= since)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -61,59 +242,363 @@ impl TimerState {
         }
     }
 
+    pub fn status_text(&self) -> &str {
+        match self {
+            TimerState::Idle => "ready",
+            TimerState::Working | TimerState::ShortBreak | TimerState::LongBreak => "running",
+            TimerState::WorkPaused | TimerState::BreakPaused | TimerState::LongBreakPaused => "paused",
+        }
+    }
+}
+
+// Countdown for one mode. Focus and rest each keep their own, so switching
+// tabs doesn't lose the other's progress.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredTimer")]
+pub struct ActiveTimer {
+    pub remaining_secs: u32,
+    pub total_secs: u32,  // Full length of the session, for the progress bar
+}
+
+// Older state files stored only the remaining seconds
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredTimer {
+    Timer { remaining_secs: u32, total_secs: u32 },
+    Seconds(u32),
+}
+
+impl From<StoredTimer> for ActiveTimer {
+    fn from(stored: StoredTimer) -> Self {
+        match stored {
+            StoredTimer::Timer { remaining_secs, total_secs } => Self { remaining_secs, total_secs },
+            StoredTimer::Seconds(secs) => Self::new(secs),
+        }
+    }
+}
+
+impl ActiveTimer {
+    pub fn new(total_secs: u32) -> Self {
+        Self { remaining_secs: total_secs, total_secs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining_secs == 0
+    }
+
+    pub fn format(&self) -> String {
+        format!("{:02}:{:02}", self.remaining_secs / 60, self.remaining_secs % 60)
+    }
+
+    // Change the session's length, keeping the time already spent
+    pub fn set_total(&mut self, total_secs: u32) {
+        let spent = self.total_secs.saturating_sub(self.remaining_secs);
+        self.total_secs = total_secs;
+        self.remaining_secs = total_secs.saturating_sub(spent);
+    }
+
+    // Fraction of the session done, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.total_secs == 0 {
+            return 0.0;
+        }
+        1.0 - self.remaining_secs.min(self.total_secs) as f32 / self.total_secs as f32
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionInfo {
     pub current_state: TimerState,
-    pub time_remaining_secs: u32,
-    pub rest_time_remaining_secs: u32, // Separate timer for rest mode
+    #[serde(alias = "time_remaining_secs")]
+    pub focus: ActiveTimer,
+    #[serde(alias = "rest_time_remaining_secs")]
+    pub rest: ActiveTimer,
     pub is_focus_mode: bool,       // true = focus/work, false = rest/break
     pub current_session: u32,      // Current session number (1-4)
     pub completed_sessions: u32,   // Total completed today
     pub last_updated: DateTime<Utc>,
     pub current_id: String,        // Unique ID for current timer session
     pub current_label: String,     // Label for current timer
-    pub history: Vec<CompletedTimer>, // History of completed timers
+    #[serde(default)]
+    pub current_icon: String,      // Emoji shown before the label, empty for none
+    #[serde(default)]
+    pub current_category: Option<String>,  // Session category the current work session was started as
+    #[serde(default)]
+    pub suggested_label: String,  // From the window in front at the start, used if no label is typed
+    #[serde(default, skip_serializing)]
+    pub history: Vec<CompletedTimer>, // History of completed timers, saved in day files (see persistence.rs)
     pub history_index: Option<usize>, // Current index when browsing history (None = current timer)
     #[serde(default)]
     pub show_celebration: bool,    // True when timer just completed (breathing effect)
+    #[serde(default)]
+    pub label_queue: Vec<QueuedLabel>, // Labels for upcoming work sessions
+    #[serde(default)]
+    pub elapsed_secs: u32,         // Seconds the current session has been running
+    #[serde(default)]
+    pub current_apps: Vec<AppUsage>, // Frontmost apps so far in the current work session
+    #[serde(default)]
+    pub current_segments: Vec<Segment>,  // When the current session has run so far
+    #[serde(default)]
+    pub current_distracted: bool,  // The current session went over the distraction threshold
+    #[serde(default)]
+    pub template: Option<String>,  // Name of the picked session template, None for the config's own plan
+    #[serde(default)]
+    pub current_break_preset: Option<String>,  // Break preset picked for the current break
+    #[serde(default)]
+    pub continued_task: Option<String>,  // Task picked in history for the current session to continue
+    #[serde(default)]
+    pub plan: DayPlan,  // Queued sessions begun today, see plan_for
+    #[serde(default)]
+    pub focus_run: FocusRun,  // Focus since the last long break
+    #[serde(default)]
+    pub break_streak: u32,  // Work sessions completed in a row this cycle, for break_durations
+    #[serde(default)]
+    pub announced_milestones: Vec<usize>,  // Config milestones already announced in this work session, by index
+    #[serde(default)]
+    pub strict: StrictLog,  // Pomodoros completed and voided in strict mode
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,  // The next session is armed to start by itself then
+    #[serde(default)]
+    pub generation: u64,  // Times the state was saved, to notice saves by another process
+    #[serde(default)]
+    pub history_max_age_confirmed: Option<u32>,  // history_max_age_days the user agreed to prune at
+    #[serde(skip)]
+    pub retention: HistoryRetention,  // From the config at startup
+}
+
+// A running session saved longer ago than this was left by an app that quit
+// or crashed; while running, the state is saved every few seconds
+const STALE_AFTER_SECS: i64 = 60;
+
+// How much history add_to_history keeps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryRetention {
+    pub limit: Option<usize>,  // Most recent entries kept, None for all
+    pub max_age_days: Option<u32>,  // Entries older than this are dropped, once confirmed
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self { limit: Some(50), max_age_days: None }
+    }
 }
 
 impl SessionInfo {
     pub fn new() -> Self {
         Self {
             current_state: TimerState::Idle,
-            time_remaining_secs: 0,
-            rest_time_remaining_secs: 0,
+            focus: ActiveTimer::default(),
+            rest: ActiveTimer::default(),
             is_focus_mode: true,  // Default to focus mode
             current_session: 1,
             completed_sessions: 0,
             last_updated: Utc::now(),
             current_id: Uuid::new_v4().to_string(),
             current_label: String::new(),
+            current_icon: String::new(),
+            current_category: None,
+            suggested_label: String::new(),
             history: Vec::new(),
             history_index: None,
             show_celebration: false,
+            label_queue: Vec::new(),
+            elapsed_secs: 0,
+            current_apps: Vec::new(),
+            current_segments: Vec::new(),
+            current_distracted: false,
+            template: None,
+            current_break_preset: None,
+            continued_task: None,
+            plan: DayPlan::default(),
+            focus_run: FocusRun::default(),
+            break_streak: 0,
+            strict: StrictLog::default(),
+            announced_milestones: Vec::new(),
+            starts_at: None,
+            generation: 0,
+            history_max_age_confirmed: None,
+            retention: HistoryRetention::default(),
         }
     }
 
-    pub fn add_to_history(&mut self, id: String, label: String, duration_secs: u32, session_type: String) {
+    // The icon was picked for the old label, so it goes when the label changes
+    pub fn set_label(&mut self, label: String) {
+        if self.current_label != label {
+            self.current_label = label;
+            self.current_icon.clear();
+        }
+    }
+
+    pub fn enqueue_label(&mut self, label: String) {
+        // Queuing the same label twice in a row just adds a session to it
+        match self.label_queue.last_mut() {
+            Some(last) if last.label == label => last.count += 1,
+            _ => self.label_queue.push(QueuedLabel { label, count: 1 }),
+        }
+    }
+
+    pub fn take_next_queued_label(&mut self) -> Option<String> {
+        let next = self.label_queue.first_mut()?;
+        let label = next.label.clone();
+        next.count = next.count.saturating_sub(1);
+        if next.count == 0 {
+            self.label_queue.remove(0);
+        }
+        self.plan.record(&label, Local::now().date_naive());
+        Some(label)
+    }
+
+    // Sessions planned for `today` per label: the queued ones that began today
+    // and the ones still in the queue
+    pub fn plan_for(&self, today: NaiveDate) -> Vec<QueuedLabel> {
+        let started = if self.plan.date == Some(today) { self.plan.started.as_slice() } else { &[] };
+        let mut plan: Vec<QueuedLabel> = Vec::new();
+        for entry in started.iter().chain(&self.label_queue) {
+            match plan.iter_mut().find(|planned| planned.label == entry.label) {
+                Some(planned) => planned.count += entry.count,
+                None => plan.push(entry.clone()),
+            }
+        }
+        plan
+    }
+
+    pub fn add_to_history(&mut self, id: String, label: String, duration_secs: u32, session_type: String, abandoned: bool) {
+        self.add_entry(id, label, duration_secs, session_type, abandoned, false);
+    }
+
+    fn add_entry(&mut self, id: String, label: String, duration_secs: u32, session_type: String, abandoned: bool, switched: bool) {
+        // A work session nobody labelled takes the suggested label
+        let suggested = std::mem::take(&mut self.suggested_label);
+        let label = if label.is_empty() && session_type == TimerState::Working.display_name() { suggested } else { label };
+        // A work session started in a category is recorded as that category, once
+        let session_type = match self.current_category.take_if(|_| session_type == TimerState::Working.display_name()) {
+            Some(category) => category,
+            None => session_type,
+        };
+        let task_id = self.task_of(&label, &session_type).unwrap_or_default();
         self.history.push(CompletedTimer {
             id,
             label,
             duration_secs,
             session_type,
             completed_at: Utc::now(),
+            abandoned,
+            switched,
+            apps: std::mem::take(&mut self.current_apps),
+            distracted: std::mem::take(&mut self.current_distracted),
+            break_preset: self.current_break_preset.take(),
+            task_id,
+            icon: self.current_icon.clone(),
+            segments: std::mem::take(&mut self.current_segments),
         });
-        // Keep only last 50 timers
-        if self.history.len() > 50 {
-            self.history.remove(0);
-        }
+        self.prune_history(Utc::now());
+        self.announced_milestones.clear();
         // Generate new ID for next session
         self.current_id = Uuid::new_v4().to_string();
+        self.elapsed_secs = 0;
     }
 
+    // Task a finished session belongs to: the one continued from history, or
+    // the last work session's when this one has the same label. None starts a task.
+    fn task_of(&mut self, label: &str, session_type: &str) -> Option<String> {
+        if session_type != TimerState::Working.display_name() {
+            return None;
+        }
+        if let Some(task) = self.continued_task.take() {
+            return Some(task);
+        }
+        let last_work = self.history.iter().rev().find(|entry| entry.session_type == session_type)?;
+        (!label.is_empty() && last_work.label == label).then(|| last_work.task_id().to_string())
+    }
+
+    // Seconds since a running session was last saved, when the app must have
+    // been closed in between
+    pub fn stale_secs(&self, now: DateTime<Utc>) -> Option<u32> {
+        let gap = (now - self.last_updated).num_seconds();
+        (self.current_state.is_running() && gap > STALE_AFTER_SECS).then_some(gap as u32)
+    }
+
+    // Entries that ended more than `days` ago
+    pub fn older_than(&self, days: u32, now: DateTime<Utc>) -> usize {
+        let cutoff = now - Duration::days(days as i64);
+        self.history.iter().filter(|entry| entry.completed_at < cutoff).count()
+    }
+
+    // Drop history past the retention limits. Returns how many entries went.
+    pub fn prune_history(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.history.len();
+        if let Some(days) = self.retention.max_age_days {
+            let cutoff = now - Duration::days(days as i64);
+            self.history.retain(|entry| entry.completed_at >= cutoff);
+        }
+        if let Some(limit) = self.retention.limit {
+            let excess = self.history.len().saturating_sub(limit);
+            self.history.drain(..excess);
+        }
+        let pruned = before - self.history.len();
+        if pruned > 0 {
+            self.history_index = None;
+        }
+        pruned
+    }
+
+    // Add a sample of the frontmost app to the current session. Once distracting
+    // apps add up to more than `threshold_secs` the session is flagged.
+    pub fn record_app(&mut self, app: &str, secs: u32, distracting: bool, threshold_secs: u32) {
+        match self.current_apps.iter_mut().find(|usage| usage.app == app) {
+            Some(usage) => usage.secs += secs,
+            None => self.current_apps.push(AppUsage { app: app.to_string(), secs, distracting }),
+        }
+        let distracted_secs: u32 = self.current_apps.iter().filter(|usage| usage.distracting).map(|usage| usage.secs).sum();
+        if distracted_secs > threshold_secs {
+            self.current_distracted = true;
+        }
+    }
+
+    // Record a started but unfinished session as abandoned, so resets still
+    // count towards the completion rate
+    pub fn abandon_current(&mut self) {
+        if self.current_state == TimerState::Idle || self.elapsed_secs == 0 {
+            return;
+        }
+        let session_type = self.current_state.display_name().to_string();
+        let (id, label, elapsed) = (self.current_id.clone(), self.current_label.clone(), self.elapsed_secs);
+        self.add_to_history(id, label, elapsed, session_type, true);
+    }
+
+    // Record the part of the current session run before switching to a break
+    // or back to work. It's kept as it is, not abandoned.
+    pub fn switch_current(&mut self) {
+        if self.current_state == TimerState::Idle || self.elapsed_secs == 0 {
+            return;
+        }
+        let session_type = self.current_state.display_name().to_string();
+        let (id, label, elapsed) = (self.current_id.clone(), self.current_label.clone(), self.elapsed_secs);
+        self.add_entry(id, label, elapsed, session_type, false, true);
+    }
+
+    // Note that the current session ran from `start` to `end`, carrying on the
+    // last segment when nothing paused it in between
+    pub fn add_run(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) {
+        match self.current_segments.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => self.current_segments.push(Segment { start, end }),
+        }
+    }
+
+    // When the current session first ran: its first run, or the start before
+    // the first tick has recorded one
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        let first_run = self.current_segments.first().map(|segment| segment.start);
+        first_run.or_else(|| self.current_state.is_running().then_some(self.last_updated))
+    }
+
+    // When the running session will end if it isn't paused; None while it isn't running
+    pub fn ends_at(&self) -> Option<DateTime<Utc>> {
+        self.current_state
+            .is_running()
+            .then(|| self.last_updated + Duration::seconds(self.active_timer().remaining_secs as i64))
+    }
 
     pub fn exit_history(&mut self) {
         self.history_index = None;
@@ -131,22 +616,158 @@ impl SessionInfo {
         });
     }
 
-    pub fn get_active_time(&self) -> u32 {
-        // Use is_focus_mode to determine which timer to show
+    // Step towards newer entries, leaving history after the newest
+    pub fn navigate_history_next(&mut self) {
+        self.history_index = match self.history_index {
+            Some(i) if i + 1 < self.history.len() => Some(i + 1),
+            _ => None,
+        };
+    }
+
+    // Indices of history entries matching the filter, newest first
+    pub fn search_history(&self, filter: &HistoryFilter) -> Vec<usize> {
+        let since = filter.range.start(Local::now());
+        (0..self.history.len())
+            .rev()
+            .filter(|&index| filter.matches(&self.history[index], since))
+            .collect()
+    }
+
+    // The countdown for the current mode
+    pub fn active_timer(&self) -> &ActiveTimer {
         if self.is_focus_mode {
-            self.time_remaining_secs
+            &self.focus
         } else {
-            self.rest_time_remaining_secs
+            &self.rest
+        }
+    }
+
+    pub fn active_timer_mut(&mut self) -> &mut ActiveTimer {
+        if self.is_focus_mode {
+            &mut self.focus
+        } else {
+            &mut self.rest
         }
     }
 
     pub fn format_time(&self) -> String {
-        let time = self.get_active_time();
-        let minutes = time / 60;
-        let seconds = time % 60;
-        format!("{:02}:{:02}", minutes, seconds)
+        self.active_timer().format()
+    }
+
+    // What the timer view shows, copied out without the history. Labels are
+    // hidden behind the privacy stand-in when `private` is set.
+    pub fn timer_view(&self, private: bool) -> TimerView {
+        let history_entry = self.history_index.and_then(|index| {
+            let mut entry = self.history.get(index)?.clone();
+            entry.label = shown_label(&entry.label, private);
+            Some((index, self.history.len(), entry))
+        });
+        TimerView {
+            current_state: self.current_state.clone(),
+            is_focus_mode: self.is_focus_mode,
+            time_text: self.format_time(),
+            remaining_secs: self.active_timer().remaining_secs,
+            progress: self.active_timer().progress(),
+            current_label: shown_label(&self.current_label, private),
+            suggested_label: shown_label(&self.suggested_label, private),
+            current_icon: if private { String::new() } else { self.current_icon.clone() },
+            queued_sessions: self.label_queue.iter().map(|entry| entry.count).sum(),
+            show_celebration: self.show_celebration,
+            starts_at: self.starts_at.map(|at| start_text(at.with_timezone(&Local), Local::now().date_naive())),
+            current_session: self.current_session,
+            started_at: self.started_at().map(|at| at.with_timezone(&Local).format("%H:%M").to_string()),
+            ends_at: self.ends_at().map(|at| at.with_timezone(&Local).format("%H:%M").to_string()),
+            history_entry,
+        }
+    }
+
+    // Work sessions that ran to the end on `now`'s day
+    pub fn completed_today(&self, now: DateTime<Local>) -> usize {
+        self.history
+            .iter()
+            .filter(|entry| entry.session_type == "Work Session" && entry.ran_out())
+            .filter(|entry| entry.completed_at.with_timezone(&Local).date_naive() == now.date_naive())
+            .count()
+    }
+
+    // The timer as JSON, for MQTT, the phone remote, and `pomodoro-timer status`
+    pub fn status_json(&self, private: bool, sessions_until_long_break: u32) -> serde_json::Value {
+        let timer = self.active_timer();
+        serde_json::json!({
+            "state": self.current_state.display_name(),
+            "mode": if self.is_focus_mode { "focus" } else { "rest" },
+            "running": self.current_state.is_running(),
+            "label": shown_label(&self.current_label, private),
+            "time_text": self.format_time(),
+            "remaining_secs": timer.remaining_secs,
+            "remaining_minutes": timer.remaining_secs.div_ceil(60),
+            "total_secs": timer.total_secs,
+            "session": self.current_session,
+            "sessions_until_long_break": sessions_until_long_break,
+            "completed_sessions": self.completed_sessions,
+            "today_completed": self.completed_today(Local::now()),
+        })
     }
 
+    // Spoken summary of the timer, announced to screen readers when it changes
+    pub fn announcement(&self, private: bool) -> String {
+        let mode = if self.is_focus_mode { "Focus" } else { "Rest" };
+        let mut text = format!("Pomodoro Timer: {} {}", mode, self.current_state.status_text());
+        if !self.current_label.is_empty() && !private {
+            text.push_str(&format!(" - {}", self.current_label));
+        }
+        text
+    }
+}
+
+// When an armed session starts: the time today, with the date on another day
+fn start_text(at: DateTime<Local>, today: NaiveDate) -> String {
+    if at.date_naive() == today {
+        at.format("%H:%M").to_string()
+    } else {
+        at.format("%a %-d %b %H:%M").to_string()
+    }
+}
+
+// Snapshot of the session for the timer view
+#[derive(Debug, Clone)]
+pub struct TimerView {
+    pub current_state: TimerState,
+    pub is_focus_mode: bool,
+    pub time_text: String,
+    pub remaining_secs: u32,
+    pub progress: f32,  // Fraction of the session done
+    pub current_label: String,
+    pub suggested_label: String,  // Shown in place of an empty label, masked like it
+    pub current_icon: String,  // Empty in privacy mode, like the label
+    pub queued_sessions: u32,  // Work sessions waiting in the label queue
+    pub show_celebration: bool,
+    pub starts_at: Option<String>,  // "HH:MM", or "Fri 17 Oct 09:00" on another day, while the next session is armed
+    pub current_session: u32,
+    pub started_at: Option<String>,  // "HH:MM" the current session first ran
+    pub ends_at: Option<String>,  // "HH:MM" it will end, while it runs
+    pub history_entry: Option<(usize, usize, CompletedTimer)>,  // Entry being browsed: index, history length, entry
+}
+
+// Stand-in shown instead of labels while privacy mode is on
+pub const PRIVATE_LABEL: &str = "Focus";
+
+// A label as shown on screen and in notifications
+pub fn shown_label(label: &str, private: bool) -> String {
+    if private && !label.is_empty() {
+        PRIVATE_LABEL.to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+// Text being typed, masked in privacy mode so the length is all that shows
+pub fn shown_input(text: &str, private: bool) -> String {
+    if private {
+        "•".repeat(text.chars().count())
+    } else {
+        text.to_string()
+    }
 }
 
 impl Default for SessionInfo {
@@ -154,3 +775,206 @@ impl Default for SessionInfo {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_countdowns_from_older_state_files() {
+        let mut json = serde_json::to_value(SessionInfo::new()).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("focus");
+        fields.remove("rest");
+        fields.insert("time_remaining_secs".into(), 600.into());
+        fields.insert("rest_time_remaining_secs".into(), 120.into());
+
+        let info: SessionInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(info.focus, ActiveTimer::new(600));
+        assert_eq!(info.rest, ActiveTimer::new(120));
+    }
+
+    #[test]
+    fn progress_follows_the_active_mode() {
+        let mut info = SessionInfo::new();
+        info.focus = ActiveTimer { remaining_secs: 1500, total_secs: 1500 };
+        info.rest = ActiveTimer { remaining_secs: 150, total_secs: 300 };
+
+        info.is_focus_mode = false;
+        assert_eq!(info.format_time(), "02:30");
+        assert_eq!(info.active_timer().progress(), 0.5);
+    }
+
+    #[test]
+    fn knows_when_the_session_started_and_ends() {
+        let at = |secs: i64| "2026-10-16T14:00:00Z".parse::<DateTime<Utc>>().unwrap() + Duration::seconds(secs);
+        let mut info = SessionInfo::new();
+        assert_eq!((info.started_at(), info.ends_at()), (None, None));
+
+        info.current_state = TimerState::Working;
+        info.focus = ActiveTimer::new(1500);
+        info.last_updated = at(0);
+        assert_eq!((info.started_at(), info.ends_at()), (Some(at(0)), Some(at(1500))));
+
+        // Paused after a minute: it started then, and has no end until it's resumed
+        info.add_run(at(0), at(60));
+        info.focus.remaining_secs = 1440;
+        info.current_state = TimerState::WorkPaused;
+        info.last_updated = at(120);
+        assert_eq!((info.started_at(), info.ends_at()), (Some(at(0)), None));
+        info.current_state = TimerState::Working;
+        assert_eq!(info.ends_at(), Some(at(1560)));
+    }
+
+    #[test]
+    fn counts_work_sessions_finished_today() {
+        let mut info = SessionInfo::new();
+        info.add_to_history("1".into(), String::new(), 1500, "Work Session".into(), false);
+        info.add_to_history("2".into(), String::new(), 300, "Short Break".into(), false);
+        info.add_to_history("3".into(), String::new(), 600, "Work Session".into(), true);
+        info.add_to_history("4".into(), String::new(), 1500, "Work Session".into(), false);
+        info.history[0].completed_at -= Duration::days(1);
+
+        assert_eq!(info.completed_today(Local::now()), 1);
+    }
+
+    #[test]
+    fn flags_sessions_spent_in_distracting_apps() {
+        let mut info = SessionInfo::new();
+        info.record_app("Code", 300, false, 120);
+        info.record_app("Slack", 60, true, 120);
+        info.record_app("Slack", 60, true, 120);
+        assert!(!info.current_distracted);
+        info.record_app("Slack", 5, true, 120);
+        assert!(info.current_distracted);
+
+        info.add_to_history("1".into(), String::new(), 1500, "Work Session".into(), false);
+        let entry = &info.history[0];
+        assert!(entry.distracted);
+        assert_eq!(entry.apps[1], AppUsage { app: "Slack".to_string(), secs: 125, distracting: true });
+        assert!(info.current_apps.is_empty() && !info.current_distracted);
+    }
+
+    #[test]
+    fn break_presets_are_kept_with_their_break() {
+        let mut info = SessionInfo::new();
+        info.current_break_preset = Some("Walk".to_string());
+        info.add_to_history("1".into(), String::new(), 900, "Short Break".into(), false);
+        info.add_to_history("2".into(), String::new(), 1500, "Work Session".into(), false);
+        assert_eq!(info.history[0].break_preset.as_deref(), Some("Walk"));
+        assert_eq!(info.history[1].break_preset, None);
+
+        let filter = HistoryFilter { query: "walk".to_string(), ..HistoryFilter::default() };
+        assert!(filter.matches(&info.history[0], None));
+        assert!(!filter.matches(&info.history[1], None));
+    }
+
+    #[test]
+    fn prunes_history_by_count_and_age() {
+        let mut info = SessionInfo::new();
+        for id in 0..55 {
+            info.add_to_history(id.to_string(), String::new(), 1500, "Work Session".into(), false);
+        }
+        assert_eq!(info.history.len(), 50);
+        assert_eq!(info.history[0].id, "5");
+
+        info.retention.limit = None;
+        info.add_to_history("55".into(), String::new(), 1500, "Work Session".into(), false);
+        assert_eq!(info.history.len(), 51);
+
+        let now = Utc::now();
+        info.history[0].completed_at = now - Duration::days(40);
+        info.history[1].completed_at = now - Duration::days(31);
+        assert_eq!(info.older_than(30, now), 2);
+        info.retention.max_age_days = Some(30);
+        assert_eq!(info.prune_history(now), 2);
+        assert_eq!(info.history[0].id, "7");
+    }
+
+    #[test]
+    fn chains_sessions_of_the_same_task() {
+        let mut info = SessionInfo::new();
+        info.add_to_history("a".into(), "Report".into(), 1500, "Work Session".into(), false);
+        info.add_to_history("b".into(), "Report".into(), 300, "Short Break".into(), false);
+        info.add_to_history("c".into(), "Report".into(), 1500, "Work Session".into(), false);
+        info.add_to_history("d".into(), "Email".into(), 1500, "Work Session".into(), false);
+        info.continued_task = Some("a".into());
+        info.add_to_history("e".into(), "Report v2".into(), 1500, "Work Session".into(), false);
+        info.add_to_history("f".into(), String::new(), 1500, "Work Session".into(), false);
+        info.add_to_history("g".into(), String::new(), 1500, "Work Session".into(), false);
+
+        let tasks: Vec<&str> = info.history.iter().map(|entry| entry.task_id()).collect();
+        assert_eq!(tasks, ["a", "b", "a", "d", "a", "f", "g"]);
+        assert_eq!(info.continued_task, None);
+    }
+
+    #[test]
+    fn running_session_left_unsaved_is_stale() {
+        let mut info = SessionInfo::new();
+        let now = info.last_updated + Duration::minutes(90);
+        assert_eq!(info.stale_secs(now), None);
+        info.current_state = TimerState::Working;
+        assert_eq!(info.stale_secs(now), Some(90 * 60));
+        assert_eq!(info.stale_secs(info.last_updated + Duration::seconds(5)), None);
+        info.current_state = TimerState::WorkPaused;
+        assert_eq!(info.stale_secs(now), None);
+    }
+
+    #[test]
+    fn category_is_recorded_as_the_session_type() {
+        let mut info = SessionInfo::new();
+        info.current_category = Some("Meeting".to_string());
+        info.add_to_history("1".into(), String::new(), 300, "Short Break".into(), false);
+        info.add_to_history("2".into(), String::new(), 1500, "Work Session".into(), false);
+        info.add_to_history("3".into(), String::new(), 1500, "Work Session".into(), false);
+        let types: Vec<&str> = info.history.iter().map(|entry| entry.session_type.as_str()).collect();
+        assert_eq!(types, ["Short Break", "Meeting", "Work Session"]);
+    }
+
+    #[test]
+    fn suggested_label_names_an_unlabelled_work_session() {
+        let mut info = SessionInfo::new();
+        info.suggested_label = "main.rs".to_string();
+        info.add_to_history("1".into(), "Typed".into(), 1500, "Work Session".into(), false);
+        info.suggested_label = "main.rs".to_string();
+        info.add_to_history("2".into(), String::new(), 1500, "Work Session".into(), false);
+        info.add_to_history("3".into(), String::new(), 300, "Short Break".into(), false);
+        let labels: Vec<&str> = info.history.iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["Typed", "main.rs", ""]);
+        assert!(info.suggested_label.is_empty());
+    }
+
+    #[test]
+    fn a_new_label_drops_the_icon() {
+        let mut info = SessionInfo::new();
+        info.set_label("Report".into());
+        info.current_icon = "📝".to_string();
+        info.set_label("Report".into());
+        assert_eq!(info.current_icon, "📝");
+        info.set_label("Email".into());
+        assert!(info.current_icon.is_empty());
+    }
+
+    #[test]
+    fn plan_keeps_queued_sessions_once_begun() {
+        let mut info = SessionInfo::new();
+        info.enqueue_label("Report".into());
+        info.enqueue_label("Report".into());
+        info.enqueue_label("Email".into());
+        assert_eq!(info.take_next_queued_label(), Some("Report".to_string()));
+
+        let today = Local::now().date_naive();
+        let planned = |info: &SessionInfo, day| info.plan_for(day).iter().map(|entry| (entry.label.clone(), entry.count)).collect::<Vec<_>>();
+        assert_eq!(planned(&info, today), vec![("Report".to_string(), 2), ("Email".to_string(), 1)]);
+        // Only what's still queued carries over to another day
+        assert_eq!(planned(&info, today + Duration::days(1)), vec![("Report".to_string(), 1), ("Email".to_string(), 1)]);
+    }
+
+    #[test]
+    fn armed_starts_show_the_date_on_another_day() {
+        use chrono::TimeZone;
+        let at = Local.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap();
+        assert_eq!(start_text(at, at.date_naive()), "09:00");
+        assert_eq!(start_text(at, at.date_naive() - Duration::days(1)), "Fri 16 Oct 09:00");
+    }
+}
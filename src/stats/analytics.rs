@@ -0,0 +1,155 @@
+use chrono::NaiveDate;
+
+use super::summary::StatsSummary;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Analytics {
+    pub average_session_secs: u32, // Mean length of completed work sessions
+    pub completed: usize,          // Work sessions that ran to the end
+    pub abandoned: usize,          // Work sessions stopped early
+    pub hour_histogram: [u32; 24], // Completed work sessions by starting hour
+    pub longest_streak_days: u32,  // Most consecutive days with a completed work session
+}
+
+impl Analytics {
+    // Work sessions only; breaks don't count towards productivity numbers
+    pub fn compute(summary: &StatsSummary) -> Self {
+        let days = summary.days.values();
+        let completed: u32 = days.clone().map(|day| day.completed).sum();
+        let completed_secs: u64 = days.clone().map(|day| day.completed_secs as u64).sum();
+        let average_session_secs = if completed == 0 { 0 } else { (completed_secs / completed as u64) as u32 };
+
+        let worked: Vec<NaiveDate> = summary.days.iter().filter(|(_, day)| day.completed > 0).map(|(date, _)| *date).collect();
+
+        Self {
+            average_session_secs,
+            completed: completed as usize,
+            abandoned: days.map(|day| day.abandoned as usize).sum(),
+            hour_histogram: summary.hour_histogram,
+            longest_streak_days: longest_streak(&worked),
+        }
+    }
+
+    // Share of work sessions that were completed, None without any
+    pub fn completion_rate(&self) -> Option<f32> {
+        let total = self.completed + self.abandoned;
+        (total > 0).then(|| self.completed as f32 / total as f32)
+    }
+
+    // Hour of day with the most completed sessions (earliest on ties)
+    pub fn most_productive_hour(&self) -> Option<u32> {
+        let max = *self.hour_histogram.iter().max()?;
+        if max == 0 {
+            return None;
+        }
+        self.hour_histogram.iter().position(|&count| count == max).map(|hour| hour as u32)
+    }
+}
+
+// Longest run of consecutive dates in a sorted, deduplicated list
+fn longest_streak(days: &[NaiveDate]) -> u32 {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days {
+        current = match previous {
+            Some(prev) if prev.succ_opt() == Some(*day) => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(*day);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{completed, CompletedTimer};
+    use chrono::{TimeZone, Utc};
+
+    const WORK_SESSION: &str = "Work Session";
+
+    fn compute<Tz: TimeZone>(history: &[CompletedTimer], tz: &Tz) -> Analytics {
+        Analytics::compute(&StatsSummary::from_history(history, tz))
+    }
+
+    #[test]
+    fn empty_history() {
+        let analytics = compute(&[], &Utc);
+        assert_eq!(analytics, Analytics::default());
+        assert_eq!(analytics.completion_rate(), None);
+        assert_eq!(analytics.most_productive_hour(), None);
+    }
+
+    #[test]
+    fn average_and_completion_rate_ignore_breaks() {
+        let history = vec![
+            completed("2026-03-02T09:25:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-02T09:30:00Z", 5, "Short Break", false),
+            completed("2026-03-02T10:15:00Z", 35, WORK_SESSION, false),
+            completed("2026-03-02T10:25:00Z", 4, WORK_SESSION, true),
+            completed("2026-03-02T11:00:00Z", 15, "Long Break", true),
+        ];
+        let analytics = compute(&history, &Utc);
+
+        assert_eq!(analytics.average_session_secs, 30 * 60);
+        assert_eq!(analytics.completed, 2);
+        assert_eq!(analytics.abandoned, 1);
+        assert_eq!(analytics.completion_rate(), Some(2.0 / 3.0));
+    }
+
+    #[test]
+    fn histogram_uses_start_hour() {
+        let history = vec![
+            // Started at 09:50, finished at 10:15
+            completed("2026-03-02T10:15:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-02T14:30:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-03T14:40:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-03T16:40:00Z", 25, WORK_SESSION, true),
+        ];
+        let analytics = compute(&history, &Utc);
+
+        assert_eq!(analytics.hour_histogram[9], 1);
+        assert_eq!(analytics.hour_histogram[10], 0);
+        assert_eq!(analytics.hour_histogram[14], 2);
+        assert_eq!(analytics.hour_histogram[16], 0);
+        assert_eq!(analytics.most_productive_hour(), Some(14));
+    }
+
+    #[test]
+    fn histogram_follows_timezone() {
+        let history = vec![completed("2026-03-02T10:30:00Z", 25, WORK_SESSION, false)];
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let analytics = compute(&history, &tz);
+
+        assert_eq!(analytics.most_productive_hour(), Some(12));
+    }
+
+    #[test]
+    fn longest_streak_counts_consecutive_days() {
+        let history = vec![
+            completed("2026-03-01T09:00:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-02T09:00:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-02T15:00:00Z", 25, WORK_SESSION, false),
+            // Abandoned sessions and breaks don't keep a streak alive
+            completed("2026-03-03T09:00:00Z", 25, WORK_SESSION, true),
+            completed("2026-03-03T09:30:00Z", 5, "Short Break", false),
+            completed("2026-03-05T09:00:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-06T09:00:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-07T09:00:00Z", 25, WORK_SESSION, false),
+        ];
+        let analytics = compute(&history, &Utc);
+
+        assert_eq!(analytics.longest_streak_days, 3);
+    }
+
+    #[test]
+    fn streak_spans_month_boundary() {
+        let history = vec![
+            completed("2026-02-28T09:00:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-01T09:00:00Z", 25, WORK_SESSION, false),
+        ];
+        assert_eq!(compute(&history, &Utc).longest_streak_days, 2);
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::summary::StatsSummary;
+use crate::state::{CompletedTimer, TimerState};
+
+// Work started again within this of the last one ending skipped the break;
+// after a longer gap the break was taken away from the timer
+const SKIPPED_GAP_SECS: i64 = 5 * 60;
+
+// Days the compliance covers, and how many breaks it needs before it nudges
+const COMPLIANCE_DAYS: i64 = 7;
+pub const NUDGE_MIN_BREAKS: u32 = 5;
+
+// How the breaks due after work sessions went
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BreakCounts {
+    pub taken: u32,  // Ran to the end
+    pub shortened: u32,  // Stopped or skipped partway
+    pub skipped: u32,  // Went straight on to more work
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakOutcome {
+    Taken,
+    Shortened,
+    Skipped,
+}
+
+impl BreakCounts {
+    pub fn record(&mut self, outcome: BreakOutcome) {
+        match outcome {
+            BreakOutcome::Taken => self.taken += 1,
+            BreakOutcome::Shortened => self.shortened += 1,
+            BreakOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    pub fn due(&self) -> u32 {
+        self.taken + self.shortened + self.skipped
+    }
+
+    // Share of the breaks taken in full, None when none were due
+    pub fn percent_taken(&self) -> Option<u32> {
+        (self.due() > 0).then(|| self.taken * 100 / self.due())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.due() == 0
+    }
+}
+
+// What became of the break due after a work session that ended at `awaited`,
+// told from the entry that came next. None when no break was due, or the gap
+// before more work was a break of its own.
+pub fn outcome(awaited: Option<DateTime<Utc>>, next: &CompletedTimer) -> Option<BreakOutcome> {
+    let awaited = awaited?;
+    if is_break(next) {
+        return Some(if next.ran_out() { BreakOutcome::Taken } else { BreakOutcome::Shortened });
+    }
+    let started = next.completed_at - Duration::seconds(next.duration_secs as i64);
+    (started - awaited < Duration::seconds(SKIPPED_GAP_SECS)).then_some(BreakOutcome::Skipped)
+}
+
+pub fn is_break(entry: &CompletedTimer) -> bool {
+    entry.session_type == TimerState::ShortBreak.display_name() || entry.session_type == TimerState::LongBreak.display_name()
+}
+
+// The breaks of the COMPLIANCE_DAYS up to `today`
+pub fn recent(summary: &StatsSummary, today: NaiveDate) -> BreakCounts {
+    let mut counts = BreakCounts::default();
+    for (_, day) in summary.days_since(Some(today - Duration::days(COMPLIANCE_DAYS - 1))) {
+        counts.taken += day.breaks.taken;
+        counts.shortened += day.breaks.shortened;
+        counts.skipped += day.breaks.skipped;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+
+    #[test]
+    fn tells_how_breaks_went() {
+        let history = vec![
+            completed("2026-10-12T09:25:00Z", 25, "Work Session", false),
+            completed("2026-10-12T09:30:00Z", 5, "Short Break", false),
+            completed("2026-10-12T09:55:00Z", 25, "Work Session", false),
+            completed("2026-10-12T09:57:00Z", 2, "Short Break", true),
+            completed("2026-10-12T10:22:00Z", 25, "Work Session", false),
+            // Straight on
+            completed("2026-10-12T10:47:00Z", 25, "Work Session", false),
+            // Back after lunch, away from the timer
+            completed("2026-10-12T13:25:00Z", 25, "Work Session", false),
+        ];
+        let summary = StatsSummary::from_history(&history, &Utc);
+        let counts = recent(&summary, NaiveDate::from_ymd_opt(2026, 10, 14).unwrap());
+        assert_eq!(counts, BreakCounts { taken: 1, shortened: 1, skipped: 1 });
+        assert_eq!(counts.percent_taken(), Some(33));
+        // A week on, they've aged out
+        assert!(recent(&summary, NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()).is_empty());
+    }
+}
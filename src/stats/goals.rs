@@ -0,0 +1,112 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use super::report::week_start;
+use super::summary::StatsSummary;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoalPeriod {
+    Week,
+    Month,
+}
+
+impl GoalPeriod {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            GoalPeriod::Week => "This week",
+            GoalPeriod::Month => "This month",
+        }
+    }
+
+    // First day of the period `today` is in, and how many days it has
+    fn span(&self, today: NaiveDate) -> (NaiveDate, i64) {
+        match self {
+            GoalPeriod::Week => (week_start(today), 7),
+            GoalPeriod::Month => {
+                let first = today.with_day(1).unwrap_or(today);
+                let next = first.checked_add_months(chrono::Months::new(1)).unwrap_or(first);
+                (first, (next - first).num_days())
+            }
+        }
+    }
+}
+
+// Focus time so far against a goal for the week or month
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub period: GoalPeriod,
+    pub goal_secs: u32,
+    pub focus_secs: u32,
+    pub expected_secs: u32,  // Where an even pace would be by the start of today
+}
+
+impl GoalProgress {
+    // None when no goal is set for the period
+    pub fn compute(summary: &StatsSummary, period: GoalPeriod, goal_hours: u32, today: NaiveDate) -> Option<Self> {
+        if goal_hours == 0 {
+            return None;
+        }
+        let (first, days) = period.span(today);
+        let focus_secs = summary
+            .days
+            .range(first..first + Duration::days(days))
+            .map(|(_, day)| day.focus_secs)
+            .sum();
+        let goal_secs = goal_hours * 3600;
+        let days_done = (today - first).num_days();
+        Some(Self { period, goal_secs, focus_secs, expected_secs: (goal_secs as i64 * days_done / days) as u32 })
+    }
+
+    pub fn fraction(&self) -> f32 {
+        (self.focus_secs as f32 / self.goal_secs as f32).min(1.0)
+    }
+
+    // "On track", or how many work sessions of `pomodoro_secs` ahead or behind
+    pub fn pace_text(&self, pomodoro_secs: u32) -> String {
+        if self.focus_secs >= self.goal_secs {
+            return "Goal reached".to_string();
+        }
+        let pomodoros = |secs: u32| secs.div_ceil(pomodoro_secs.max(1));
+        let count = |n: u32| if n == 1 { "1 pomodoro".to_string() } else { format!("{} pomodoros", n) };
+        if self.focus_secs < self.expected_secs {
+            format!("Behind by {}", count(pomodoros(self.expected_secs - self.focus_secs)))
+        } else if self.focus_secs - self.expected_secs >= pomodoro_secs.max(1) {
+            format!("Ahead by {}", count((self.focus_secs - self.expected_secs) / pomodoro_secs.max(1)))
+        } else {
+            "On track".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{completed, CompletedTimer};
+    use chrono::Utc;
+
+    fn entry(completed_at: &str, duration_mins: u32) -> CompletedTimer {
+        completed(completed_at, duration_mins, "Work Session", false)
+    }
+
+    #[test]
+    fn paces_the_week_and_month() {
+        // Four hours over Monday and Tuesday, and some from the month before
+        let history: Vec<CompletedTimer> = ["2026-09-30T10:00:00Z", "2026-10-12T10:00:00Z", "2026-10-13T10:00:00Z"]
+            .iter()
+            .flat_map(|at| std::iter::repeat_n(entry(at, 60), 2))
+            .collect();
+        let summary = StatsSummary::from_history(&history, &Utc);
+        let thursday = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+
+        // 14 hours a week is 6 by Thursday
+        let week = GoalProgress::compute(&summary, GoalPeriod::Week, 14, thursday).unwrap();
+        assert_eq!((week.focus_secs, week.expected_secs), (4 * 3600, 6 * 3600));
+        assert_eq!(week.pace_text(25 * 60), "Behind by 5 pomodoros");
+        assert_eq!(GoalProgress::compute(&summary, GoalPeriod::Week, 2, thursday).unwrap().pace_text(1500), "Goal reached");
+
+        // 31 hours in October is 14 by the 15th
+        let month = GoalProgress::compute(&summary, GoalPeriod::Month, 31, thursday).unwrap();
+        assert_eq!(month.expected_secs, 14 * 3600);
+        assert_eq!(GoalProgress::compute(&summary, GoalPeriod::Month, 5, NaiveDate::from_ymd_opt(2026, 10, 2).unwrap()).unwrap().pace_text(3600), "Ahead by 3 pomodoros");
+        assert_eq!(GoalProgress::compute(&summary, GoalPeriod::Month, 0, thursday), None);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use super::summary::{DaySummary, LabelDay, StatsSummary};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelTotal {
+    pub label: String,
+    pub focus_secs: u32, // All focus time, including abandoned sessions
+    pub sessions: u32,   // Completed work sessions
+}
+
+// Focus time per label from the given day on, most time first.
+// Labels not containing the query (case-insensitive) are left out.
+pub fn by_label(summary: &StatsSummary, since: Option<NaiveDate>, query: &str) -> Vec<LabelTotal> {
+    add_up(summary, since, query, |day| &day.labels)
+}
+
+// The same per label emoji, for sessions that had one
+pub fn by_icon(summary: &StatsSummary, since: Option<NaiveDate>) -> Vec<LabelTotal> {
+    add_up(summary, since, "", |day| &day.icons)
+}
+
+// Time per session category, after the deep work they're kept apart from.
+// Empty when no session in range had a category.
+pub fn by_category(summary: &StatsSummary, since: Option<NaiveDate>) -> Vec<LabelTotal> {
+    let categories = add_up(summary, since, "", |day| &day.categories);
+    if categories.is_empty() {
+        return categories;
+    }
+    let deep_work = summary.days_since(since).fold(LabelTotal { label: "Deep work".to_string(), focus_secs: 0, sessions: 0 }, |mut total, (_, day)| {
+        total.focus_secs += day.focus_secs;
+        total.sessions += day.completed;
+        total
+    });
+    std::iter::once(deep_work).chain(categories).collect()
+}
+
+fn add_up(
+    summary: &StatsSummary,
+    since: Option<NaiveDate>,
+    query: &str,
+    groups: impl Fn(&DaySummary) -> &BTreeMap<String, LabelDay>,
+) -> Vec<LabelTotal> {
+    let query = query.trim().to_lowercase();
+    let mut totals: Vec<LabelTotal> = Vec::new();
+
+    for (_, day) in summary.days_since(since) {
+        for (label, day_total) in groups(day) {
+            if !label.to_lowercase().contains(&query) {
+                continue;
+            }
+            let index = match totals.iter().position(|total| total.label == *label) {
+                Some(index) => index,
+                None => {
+                    totals.push(LabelTotal { label: label.clone(), focus_secs: 0, sessions: 0 });
+                    totals.len() - 1
+                }
+            };
+            totals[index].focus_secs += day_total.focus_secs;
+            totals[index].sessions += day_total.sessions;
+        }
+    }
+
+    totals.sort_by(|a, b| b.focus_secs.cmp(&a.focus_secs).then_with(|| a.label.cmp(&b.label)));
+    totals
+}
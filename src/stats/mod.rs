@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Sender};
+
+use crate::notifications;
+use crate::persistence::Persistence;
+
+pub mod analytics;
+pub mod breaks;
+pub mod goals;
+pub mod labels;
+pub mod plan;
+pub mod report;
+pub mod summary;
+pub mod tasks;
+pub mod timeline;
+pub mod usage;
+
+fn cache_path(file: &str) -> Result<PathBuf> {
+    Ok(Persistence::data_dir()?.join(file))
+}
+
+// A cache saved by a writer below, None before the first save
+fn load_cache<T: DeserializeOwned>(file: &str) -> Result<Option<T>> {
+    let path = cache_path(file)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+    serde_json::from_str(&content).map(Some).with_context(|| format!("Failed to parse {}", file))
+}
+
+fn save_cache<T: Serialize>(file: &str, cache: &T) -> Result<()> {
+    let content = serde_json::to_string(cache).with_context(|| format!("Failed to serialize {}", file))?;
+    std::fs::create_dir_all(Persistence::data_dir()?).context("Failed to create data directory")?;
+    std::fs::write(cache_path(file)?, content).with_context(|| format!("Failed to write {}", file))
+}
+
+// Saves the caches sent to it into `file` in the data directory, off the UI
+// thread. Only the latest of a burst gets written.
+fn spawn_cache_writer<T: Serialize + Send + 'static>(file: &'static str) -> Option<Sender<T>> {
+    let (writes, pending) = channel::<T>();
+    std::thread::Builder::new()
+        .name(format!("write-{}", file))
+        .spawn(move || {
+            while let Ok(mut cache) = pending.recv() {
+                while let Ok(newer) = pending.try_recv() {
+                    cache = newer;
+                }
+                if let Err(e) = save_cache(file, &cache) {
+                    notifications::log_error(&format!("{:#}", e));
+                }
+            }
+        })
+        .ok()?;
+    Some(writes)
+}
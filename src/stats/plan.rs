@@ -0,0 +1,102 @@
+use chrono::{NaiveDate, TimeZone};
+
+use crate::state::{CompletedTimer, QueuedLabel};
+
+const WORK_SESSION: &str = "Work Session";
+
+// One label's planned pomodoros against the ones completed on the day
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanLine {
+    pub label: String,
+    pub planned: u32,  // Zero for unplanned work
+    pub done: u32,
+}
+
+impl PlanLine {
+    fn outcome(&self) -> String {
+        match self.done.cmp(&self.planned) {
+            std::cmp::Ordering::Equal => "On plan".to_string(),
+            std::cmp::Ordering::Greater => format!("Overran by {}", self.done - self.planned),
+            std::cmp::Ordering::Less => format!("{} short", self.planned - self.done),
+        }
+    }
+}
+
+// The plan in its order, then unplanned labels that got completed sessions on `date`
+pub fn compare<Tz: TimeZone>(plan: &[QueuedLabel], history: &[CompletedTimer], date: NaiveDate, tz: &Tz) -> Vec<PlanLine> {
+    let mut lines: Vec<PlanLine> =
+        plan.iter().map(|entry| PlanLine { label: entry.label.clone(), planned: entry.count, done: 0 }).collect();
+    let done = history.iter().filter(|entry| {
+        entry.session_type == WORK_SESSION && entry.ran_out() && entry.completed_at.with_timezone(tz).date_naive() == date
+    });
+    for entry in done {
+        match lines.iter_mut().find(|line| line.label == entry.label) {
+            Some(line) => line.done += 1,
+            None => lines.push(PlanLine { label: entry.label.clone(), planned: 0, done: 1 }),
+        }
+    }
+    lines
+}
+
+// Markdown table of the planned work, and a list of the unplanned
+pub fn markdown(lines: &[PlanLine], date: NaiveDate) -> String {
+    let label = |line: &PlanLine| if line.label.is_empty() { "Untitled".to_string() } else { line.label.replace('|', "\\|") };
+    let mut report = format!("# Planned vs actual, {}\n\n", date.format("%Y-%m-%d"));
+    let (planned, unplanned): (Vec<&PlanLine>, Vec<&PlanLine>) = lines.iter().partition(|line| line.planned > 0);
+    if planned.is_empty() {
+        report.push_str("Nothing was planned in the queue.\n");
+    } else {
+        report.push_str("| Task | Planned | Done | |\n|---|---|---|---|\n");
+        for line in &planned {
+            report.push_str(&format!("| {} | {} | {} | {} |\n", label(line), line.planned, line.done, line.outcome()));
+        }
+    }
+    if !unplanned.is_empty() {
+        report.push_str("\n## Unplanned\n\n");
+        for line in unplanned {
+            report.push_str(&format!("- {}: {} pomodoro(s)\n", label(line), line.done));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+    use chrono::Utc;
+
+    fn entry(completed_at: &str, label: &str, abandoned: bool) -> CompletedTimer {
+        CompletedTimer { label: label.to_string(), ..completed(completed_at, 25, WORK_SESSION, abandoned) }
+    }
+
+    #[test]
+    fn compares_the_plan_with_the_day() {
+        let plan = vec![
+            QueuedLabel { label: "Report".to_string(), count: 2 },
+            QueuedLabel { label: "Email".to_string(), count: 1 },
+            QueuedLabel { label: "Review".to_string(), count: 2 },
+        ];
+        let history = vec![
+            entry("2026-10-15T09:00:00Z", "Report", false),  // The day before
+            entry("2026-10-16T09:00:00Z", "Report", false),
+            entry("2026-10-16T09:30:00Z", "Report", false),
+            entry("2026-10-16T10:00:00Z", "Email", false),
+            entry("2026-10-16T10:30:00Z", "Email", false),
+            entry("2026-10-16T11:00:00Z", "Review", true),
+            entry("2026-10-16T11:30:00Z", "Standup", false),
+        ];
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let lines = compare(&plan, &history, date, &Utc);
+        assert_eq!(
+            markdown(&lines, date),
+            "# Planned vs actual, 2026-10-16\n\n\
+             | Task | Planned | Done | |\n|---|---|---|---|\n\
+             | Report | 2 | 2 | On plan |\n\
+             | Email | 1 | 2 | Overran by 1 |\n\
+             | Review | 2 | 0 | 2 short |\n\n\
+             ## Unplanned\n\n\
+             - Standup: 1 pomodoro(s)\n"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, NaiveDate};
+use std::path::PathBuf;
+
+use super::summary::StatsSummary;
+use crate::journal::Journal;
+use crate::persistence::Persistence;
+
+// The Monday of `date`'s week
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn hours(secs: u32) -> String {
+    let minutes = secs / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+// Markdown report of the week starting `monday`: focus time per day and label,
+// with each day's note. Days with neither are left out.
+pub fn weekly(summary: &StatsSummary, journal: &Journal, monday: NaiveDate) -> String {
+    let days: Vec<NaiveDate> = (0..7).map(|offset| monday + Duration::days(offset)).collect();
+    let (focus_secs, completed) = days
+        .iter()
+        .filter_map(|date| summary.days.get(date))
+        .fold((0, 0), |(secs, completed), day| (secs + day.focus_secs, completed + day.completed));
+
+    let mut report = format!("# Week of {}\n\n", monday.format("%Y-%m-%d"));
+    report.push_str(&format!("**Focus:** {} in {} completed session(s)\n", hours(focus_secs), completed));
+    for date in days {
+        let day = summary.days.get(&date);
+        let note = journal.note(date);
+        if day.is_none() && note.is_none() {
+            continue;
+        }
+        report.push_str(&format!("\n## {}\n\n", date.format("%A %-d %B")));
+        if let Some(day) = day {
+            report.push_str(&format!("{} focused, {} completed, {} abandoned\n\n", hours(day.focus_secs), day.completed, day.abandoned));
+            let mut labels: Vec<_> = day.labels.iter().collect();
+            labels.sort_by(|a, b| b.1.focus_secs.cmp(&a.1.focus_secs).then_with(|| a.0.cmp(b.0)));
+            for (label, total) in labels {
+                let label = if label.is_empty() { "Untitled" } else { label };
+                report.push_str(&format!("- {}: {} ({})\n", label, hours(total.focus_secs), total.sessions));
+            }
+        }
+        if let Some(note) = note {
+            if day.is_some() {
+                report.push('\n');
+            }
+            report.push_str(&format!("> {}\n", note.replace('\n', "\n> ")));
+        }
+    }
+    report
+}
+
+// Write a report as `name`.md into reports/ in the data directory
+pub fn save(name: &str, report: &str) -> Result<PathBuf> {
+    let dir = Persistence::data_dir()?.join("reports");
+    std::fs::create_dir_all(&dir).context("Failed to create reports directory")?;
+    let path = dir.join(format!("{}.md", name));
+    std::fs::write(&path, report).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{completed, CompletedTimer};
+    use chrono::Utc;
+
+    fn entry(completed_at: &str, duration_mins: u32, label: &str) -> CompletedTimer {
+        CompletedTimer { label: label.to_string(), ..completed(completed_at, duration_mins, "Work Session", false) }
+    }
+
+    #[test]
+    fn reports_the_week_with_notes() {
+        let history = vec![
+            entry("2026-10-12T10:00:00Z", 25, "Report"),
+            entry("2026-10-12T11:00:00Z", 50, "Email"),
+            // The week after
+            entry("2026-10-19T10:00:00Z", 25, "Report"),
+        ];
+        let summary = StatsSummary::from_history(&history, &Utc);
+        let mut journal = Journal::default();
+        let wednesday = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        journal.set(wednesday, "Sick day");
+        let monday = week_start(wednesday);
+        assert_eq!(monday, NaiveDate::from_ymd_opt(2026, 10, 12).unwrap());
+
+        let report = weekly(&summary, &journal, monday);
+        assert_eq!(
+            report,
+            "# Week of 2026-10-12\n\n\
+             **Focus:** 1h 15m in 2 completed session(s)\n\n\
+             ## Monday 12 October\n\n\
+             1h 15m focused, 2 completed, 0 abandoned\n\n\
+             - Email: 0h 50m (1)\n\
+             - Report: 0h 25m (1)\n\n\
+             ## Wednesday 14 October\n\n\
+             > Sick day\n"
+        );
+    }
+}
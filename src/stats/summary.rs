@@ -0,0 +1,285 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Timelike, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use super::breaks::{self, BreakCounts};
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::{CompletedTimer, SessionInfo, HISTORY_SESSION_TYPES};
+
+const WORK_SESSION: &str = "Work Session";
+const FILE: &str = "stats-summary.json";
+
+// Work time for one label on one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabelDay {
+    pub focus_secs: u32,  // All focus time, including abandoned sessions
+    pub sessions: u32,  // Completed work sessions
+}
+
+// Totals for one day's work sessions; weeks and other ranges add up days
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaySummary {
+    pub focus_secs: u32,  // All focus time, including abandoned sessions
+    pub completed: u32,
+    pub completed_secs: u32,  // Length of the completed sessions, for the average
+    pub abandoned: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, LabelDay>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub icons: BTreeMap<String, LabelDay>,  // Same again by label emoji, for sessions that had one
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<String, LabelDay>,  // Sessions in a category, which the rest leaves out
+    #[serde(default)]
+    pub breaks: BreakCounts,  // The breaks due after work sessions, by the day they came up
+}
+
+// Work-session totals per day, added to as sessions end so the stats panel
+// doesn't go through the whole history each time it opens. Saved in
+// stats-summary.json; with an encrypted state it stays in memory instead,
+// since it holds labels.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub days: BTreeMap<NaiveDate, DaySummary>,  // By local date of completion
+    pub hour_histogram: [u32; 24],  // Completed work sessions by starting hour
+    recorded_until: Option<DateTime<Utc>>,  // History entries up to here are counted
+    #[serde(default)]
+    break_awaited: Option<DateTime<Utc>>,  // End of the last work session counted, while its break is due
+}
+
+impl StatsSummary {
+    pub fn from_history<Tz: TimeZone>(history: &[CompletedTimer], tz: &Tz) -> Self {
+        let mut summary = Self::default();
+        summary.catch_up(history, tz);
+        summary
+    }
+
+    // Count the history entries that ended since the last call
+    pub fn catch_up<Tz: TimeZone>(&mut self, history: &[CompletedTimer], tz: &Tz) -> bool {
+        let until = self.recorded_until;
+        let new: Vec<&CompletedTimer> =
+            history.iter().filter(|entry| until.is_none_or(|until| entry.completed_at > until)).collect();
+        for entry in &new {
+            self.add(entry, tz);
+            self.count_break(entry, tz);
+        }
+        !new.is_empty()
+    }
+
+    // Count entries merged into the history from before what's counted, such as
+    // imported ones. Newer ones are left to catch_up.
+    pub fn add_earlier<Tz: TimeZone>(&mut self, entries: &[CompletedTimer], tz: &Tz) -> bool {
+        let Some(until) = self.recorded_until else {
+            return false;
+        };
+        let earlier: Vec<&CompletedTimer> = entries.iter().filter(|entry| entry.completed_at <= until).collect();
+        for entry in &earlier {
+            self.add(entry, tz);
+        }
+        !earlier.is_empty()
+    }
+
+    fn add<Tz: TimeZone>(&mut self, entry: &CompletedTimer, tz: &Tz) {
+        self.recorded_until = self.recorded_until.max(Some(entry.completed_at));
+        let date = entry.completed_at.with_timezone(tz).date_naive();
+        if !HISTORY_SESSION_TYPES.contains(&entry.session_type.as_str()) {
+            let category = self.days.entry(date).or_default().categories.entry(entry.session_type.clone()).or_default();
+            category.focus_secs += entry.duration_secs;
+            category.sessions += u32::from(entry.ran_out());
+            return;
+        }
+        if entry.session_type != WORK_SESSION {
+            return;
+        }
+        let day = self.days.entry(date).or_default();
+        day.focus_secs += entry.duration_secs;
+        let mut groups = vec![day.labels.entry(entry.label.clone()).or_default()];
+        if !entry.icon.is_empty() {
+            groups.push(day.icons.entry(entry.icon.clone()).or_default());
+        }
+        for group in &mut groups {
+            group.focus_secs += entry.duration_secs;
+        }
+        if entry.abandoned {
+            day.abandoned += 1;
+            return;
+        }
+        // Its time counts, but it's neither completed nor abandoned
+        if entry.switched {
+            return;
+        }
+        for group in groups {
+            group.sessions += 1;
+        }
+        day.completed += 1;
+        day.completed_secs += entry.duration_secs;
+        let started = entry.completed_at - chrono::Duration::seconds(entry.duration_secs as i64);
+        self.hour_histogram[started.with_timezone(tz).hour() as usize] += 1;
+    }
+
+    // Breaks are told from the order of sessions, so imported ones, which are
+    // only work, don't count
+    fn count_break<Tz: TimeZone>(&mut self, entry: &CompletedTimer, tz: &Tz) {
+        if let Some(outcome) = breaks::outcome(self.break_awaited, entry) {
+            let date = entry.completed_at.with_timezone(tz).date_naive();
+            self.days.entry(date).or_default().breaks.record(outcome);
+        }
+        self.break_awaited = (!breaks::is_break(entry) && !entry.abandoned).then_some(entry.completed_at);
+    }
+
+    // Days from `since` on, or all of them
+    pub fn days_since(&self, since: Option<NaiveDate>) -> impl Iterator<Item = (&NaiveDate, &DaySummary)> {
+        self.days.range(since.unwrap_or(NaiveDate::MIN)..)
+    }
+}
+
+// Event bus subscriber that keeps the summary up to date and saves it off the UI
+// thread. It also nudges when few breaks are being taken.
+pub struct StatsRecorder {
+    summary: Mutex<StatsSummary>,
+    writes: Option<Sender<StatsSummary>>,  // None when the summary isn't saved
+    config: Config,
+    nudged_on: Mutex<Option<NaiveDate>>,
+}
+
+impl StatsRecorder {
+    // Picks up the saved summary, or starts from the history there is
+    pub fn spawn(config: &Config, info: &SessionInfo) -> Arc<Self> {
+        let saved = if config.encrypt_state {
+            // Left from before encryption was turned on
+            if let Ok(path) = super::cache_path(FILE) {
+                let _ = std::fs::remove_file(path);
+            }
+            None
+        } else {
+            super::load_cache::<StatsSummary>(FILE).unwrap_or_else(|e| {
+                notifications::log_error(&format!("{:#}, counting again from history", e));
+                None
+            })
+        };
+        let (summary, changed) = match saved {
+            Some(mut summary) => {
+                let changed = summary.catch_up(&info.history, &chrono::Local);
+                (summary, changed)
+            }
+            None => (StatsSummary::from_history(&info.history, &chrono::Local), true),
+        };
+        let writes = if config.encrypt_state { None } else { super::spawn_cache_writer(FILE) };
+        let recorder = Self { summary: Mutex::new(summary), writes, config: config.clone(), nudged_on: Mutex::new(None) };
+        if changed {
+            recorder.save(&recorder.summary.lock());
+        }
+        Arc::new(recorder)
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        if !matches!(
+            event,
+            SessionEvent::Completed { .. } | SessionEvent::Skipped | SessionEvent::Switched(_) | SessionEvent::Stopped | SessionEvent::Reset | SessionEvent::Changed
+        ) {
+            return;
+        }
+        let mut summary = self.summary.lock();
+        if summary.catch_up(&info.history, &chrono::Local) {
+            self.save(&summary);
+            self.nudge_for_breaks(&summary);
+        }
+    }
+
+    fn nudge_for_breaks(&self, summary: &StatsSummary) {
+        let today = chrono::Local::now().date_naive();
+        let counts = breaks::recent(summary, today);
+        let Some(percent) = counts.percent_taken() else {
+            return;
+        };
+        let mut nudged_on = self.nudged_on.lock();
+        if percent >= self.config.break_nudge_below
+            || counts.due() < breaks::NUDGE_MIN_BREAKS
+            || *nudged_on == Some(today)
+            || notifications::is_quiet()
+        {
+            return;
+        }
+        *nudged_on = Some(today);
+        let config = self.config.clone();
+        // Talking to the notification server can block
+        std::thread::spawn(move || notifications::notify_break_nudge(&config, percent));
+    }
+
+    // Count entries an import merged into the history
+    pub fn add_imported(&self, entries: &[CompletedTimer]) {
+        let mut summary = self.summary.lock();
+        if summary.add_earlier(entries, &chrono::Local) {
+            self.save(&summary);
+        }
+    }
+
+    pub fn read<R>(&self, read: impl FnOnce(&StatsSummary) -> R) -> R {
+        read(&self.summary.lock())
+    }
+
+    fn save(&self, summary: &StatsSummary) {
+        if let Some(writes) = &self.writes {
+            let _ = writes.send(summary.clone());
+        }
+    }
+}
+
+// Count entries imported while the app isn't running into the saved summary
+pub fn add_to_saved(entries: &[CompletedTimer]) -> anyhow::Result<()> {
+    // Without one, it's built from the history at the next launch
+    let Some(mut summary) = super::load_cache::<StatsSummary>(FILE)? else {
+        return Ok(());
+    };
+    if !summary.add_earlier(entries, &chrono::Local) {
+        return Ok(());
+    }
+    super::save_cache(FILE, &summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+
+    fn entry(completed_at: &str, duration_mins: u32, label: &str, abandoned: bool) -> CompletedTimer {
+        CompletedTimer { label: label.to_string(), ..completed(completed_at, duration_mins, WORK_SESSION, abandoned) }
+    }
+
+    #[test]
+    fn adds_up_days_as_sessions_end() {
+        let mut history = vec![
+            CompletedTimer { icon: "📚".to_string(), ..entry("2026-03-02T10:15:00Z", 25, "Report", false) },
+            entry("2026-03-02T11:00:00Z", 10, "Report", true),
+            CompletedTimer { session_type: "Meeting".to_string(), ..entry("2026-03-02T12:00:00Z", 30, "Standup", false) },
+        ];
+        let mut summary = StatsSummary::from_history(&history, &Utc);
+        history.push(entry("2026-03-03T09:25:00Z", 25, "Email", false));
+        assert!(summary.catch_up(&history, &Utc));
+        assert!(!summary.catch_up(&history, &Utc));
+
+        let monday = &summary.days[&NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()];
+        assert_eq!((monday.focus_secs, monday.completed, monday.abandoned), (35 * 60, 1, 1));
+        assert_eq!(monday.labels["Report"], LabelDay { focus_secs: 35 * 60, sessions: 1 });
+        assert_eq!(monday.icons["📚"], LabelDay { focus_secs: 25 * 60, sessions: 1 });
+        // A meeting is kept apart from deep work
+        assert_eq!(monday.categories["Meeting"], LabelDay { focus_secs: 30 * 60, sessions: 1 });
+        assert_eq!(summary.hour_histogram[9], 2);
+        assert_eq!(summary.days_since(NaiveDate::from_ymd_opt(2026, 3, 3)).count(), 1);
+
+        // The whole history at once comes out the same
+        assert_eq!(StatsSummary::from_history(&history, &Utc), summary);
+
+        // An import from before is counted; catch_up finds the newer entries itself
+        let imported = vec![entry("2026-03-01T15:00:00Z", 25, "Report", false), entry("2026-03-04T15:00:00Z", 25, "Email", false)];
+        assert!(summary.add_earlier(&imported, &Utc));
+        assert_eq!(summary.days.len(), 3);
+        history.extend(imported);
+        assert!(summary.catch_up(&history, &Utc));
+        assert_eq!(summary.days.len(), 4);
+    }
+}
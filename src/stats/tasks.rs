@@ -0,0 +1,80 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+use crate::state::{CompletedTimer, TimerState};
+
+// Work on one task across its sessions, e.g. "7 pomodoros over 3 days"
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskTotal {
+    pub label: String,  // From the latest session
+    pub pomodoros: u32,  // Completed work sessions
+    pub focus_secs: u32,  // All focus time, including abandoned sessions
+    pub days: u32,  // Days with work on it
+    pub last_worked: DateTime<Utc>,
+}
+
+// Tasks in the history with more than one work session, most recently worked on first
+pub fn by_task<Tz: TimeZone>(history: &[CompletedTimer], tz: &Tz) -> Vec<TaskTotal> {
+    let work = TimerState::Working.display_name();
+    let mut tasks: Vec<(&str, TaskTotal, Vec<NaiveDate>)> = Vec::new();
+
+    for entry in history.iter().filter(|entry| entry.session_type == work) {
+        let index = match tasks.iter().position(|(task_id, _, _)| *task_id == entry.task_id()) {
+            Some(index) => index,
+            None => {
+                let total = TaskTotal { label: String::new(), pomodoros: 0, focus_secs: 0, days: 0, last_worked: entry.completed_at };
+                tasks.push((entry.task_id(), total, Vec::new()));
+                tasks.len() - 1
+            }
+        };
+        let (_, total, days) = &mut tasks[index];
+        total.label = entry.label.clone();
+        total.focus_secs += entry.duration_secs;
+        total.pomodoros += u32::from(entry.ran_out());
+        total.last_worked = total.last_worked.max(entry.completed_at);
+        let day = entry.completed_at.with_timezone(tz).date_naive();
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+
+    let mut totals: Vec<TaskTotal> = tasks
+        .into_iter()
+        .map(|(_, total, days)| TaskTotal { days: days.len() as u32, ..total })
+        .filter(|total| total.pomodoros > 1)
+        .collect();
+    totals.sort_by_key(|total| std::cmp::Reverse(total.last_worked));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+
+    fn entry(id: &str, task_id: &str, completed_at: &str, label: &str, abandoned: bool) -> CompletedTimer {
+        CompletedTimer {
+            id: id.to_string(),
+            label: label.to_string(),
+            task_id: task_id.to_string(),
+            ..completed(completed_at, 25, "Work Session", abandoned)
+        }
+    }
+
+    #[test]
+    fn adds_up_tasks_across_days() {
+        let history = vec![
+            entry("a", "", "2026-03-02T10:00:00Z", "Report", false),
+            entry("b", "a", "2026-03-02T10:30:00Z", "Report", false),
+            entry("c", "", "2026-03-02T11:00:00Z", "Email", false),
+            entry("d", "a", "2026-03-03T09:00:00Z", "Report", true),
+            entry("e", "a", "2026-03-04T09:00:00Z", "Report draft 2", false),
+        ];
+        let totals = by_task(&history, &Utc);
+
+        // A single session isn't worth a rollup
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].label, "Report draft 2");
+        assert_eq!((totals[0].pomodoros, totals[0].days), (3, 3));
+        assert_eq!(totals[0].focus_secs, 4 * 1500);
+    }
+}
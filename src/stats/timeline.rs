@@ -0,0 +1,78 @@
+use chrono::{DateTime, Duration, Local, Timelike};
+
+use crate::state::CompletedTimer;
+
+// One session on the timeline, positioned as fractions of the visible span
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineBlock {
+    pub offset: f32,
+    pub width: f32,
+    pub session_type: String,
+    pub abandoned: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub blocks: Vec<TimelineBlock>,
+    pub focus_secs: u32,
+    pub work_sessions: usize,
+}
+
+// Lay out today's sessions from their completion times and durations.
+// The span runs from the hour of the first session to the hour after the
+// last one (or now), so gaps between blocks show idle time.
+pub fn today(history: &[CompletedTimer], now: DateTime<Local>) -> Option<Timeline> {
+    let sessions: Vec<(DateTime<Local>, DateTime<Local>, &CompletedTimer)> = history
+        .iter()
+        .map(|entry| {
+            let end = entry.completed_at.with_timezone(&Local);
+            // Pauses put the start further back than the duration
+            let start = match entry.segments.first() {
+                Some(segment) => segment.start.with_timezone(&Local),
+                None => end - Duration::seconds(entry.duration_secs as i64),
+            };
+            (start, end, entry)
+        })
+        .filter(|(_, end, _)| end.date_naive() == now.date_naive())
+        .collect();
+
+    let first_start = sessions.iter().map(|(start, _, _)| *start).min()?;
+    let last_end = sessions.iter().map(|(_, end, _)| *end).max()?.max(now);
+
+    let start = start_of_hour(first_start);
+    let end = start_of_hour(last_end) + Duration::hours(1);
+    let span = (end - start).num_seconds() as f32;
+
+    let work: Vec<_> = sessions.iter().filter(|(_, _, entry)| entry.session_type == "Work Session").collect();
+    let focus_secs = work.iter().map(|(_, _, entry)| entry.duration_secs).sum();
+    let work_sessions = work.len();
+
+    // A session paused partway shows as the stretches it ran
+    let blocks = sessions
+        .into_iter()
+        .flat_map(|(session_start, session_end, entry)| {
+            let runs: Vec<(DateTime<Local>, DateTime<Local>)> = if entry.segments.is_empty() {
+                vec![(session_start, session_end)]
+            } else {
+                entry.segments.iter().map(|segment| (segment.start.with_timezone(&Local), segment.end.with_timezone(&Local))).collect()
+            };
+            runs.into_iter().map(move |(run_start, run_end)| TimelineBlock {
+                offset: (run_start - start).num_seconds().max(0) as f32 / span,
+                width: (run_end - run_start).num_seconds() as f32 / span,
+                session_type: entry.session_type.clone(),
+                abandoned: entry.abandoned,
+            })
+        })
+        .collect();
+
+    Some(Timeline { start, end, blocks, focus_secs, work_sessions })
+}
+
+fn start_of_hour(time: DateTime<Local>) -> DateTime<Local> {
+    time.with_minute(0)
+        .and_then(|t| t.with_second(0))
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(time)
+}
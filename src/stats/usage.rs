@@ -0,0 +1,148 @@
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc, Weekday};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::events::SessionEvent;
+use crate::notifications;
+use crate::state::{CompletedTimer, SessionInfo, TimerState};
+
+const WORK_SESSION: &str = "Work Session";
+const FILE: &str = "usage.json";
+
+// How the timer gets used, kept in usage.json only when usage_stats is on and
+// never sent anywhere. Counted as sessions end, so the stats panel shows it
+// without going through the history.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub by_weekday_hour: [[u32; 24]; 7],  // Completed work sessions by starting weekday (Monday first) and hour
+    pub work_sessions: u32,  // Work sessions completed or abandoned
+    pub interruptions: u32,  // Pauses and abandoned sessions during work
+    recorded_until: Option<DateTime<Utc>>,  // History entries up to here are counted
+}
+
+impl UsageStats {
+    // Count the history entries that ended since the last call
+    pub fn catch_up<Tz: TimeZone>(&mut self, history: &[CompletedTimer], tz: &Tz) -> bool {
+        let new: Vec<&CompletedTimer> = history
+            .iter()
+            .filter(|entry| self.recorded_until.is_none_or(|until| entry.completed_at > until))
+            .collect();
+        for entry in &new {
+            self.recorded_until = self.recorded_until.max(Some(entry.completed_at));
+            if entry.session_type != WORK_SESSION {
+                continue;
+            }
+            self.work_sessions += 1;
+            if !entry.ran_out() {
+                self.interruptions += 1;
+                continue;
+            }
+            let started = (entry.completed_at - chrono::Duration::seconds(entry.duration_secs as i64)).with_timezone(tz);
+            self.by_weekday_hour[started.weekday().num_days_from_monday() as usize][started.hour() as usize] += 1;
+        }
+        !new.is_empty()
+    }
+
+    pub fn average_interruptions(&self) -> Option<f32> {
+        (self.work_sessions > 0).then(|| self.interruptions as f32 / self.work_sessions as f32)
+    }
+
+    // Weekday and hour with the most completed sessions (earliest on ties)
+    pub fn busiest(&self) -> Option<(Weekday, u32)> {
+        let mut busiest: Option<(Weekday, u32, u32)> = None;
+        for (day, hours) in self.by_weekday_hour.iter().enumerate() {
+            for (hour, &count) in hours.iter().enumerate() {
+                if count > busiest.map_or(0, |(_, _, most)| most) {
+                    busiest = Some((Weekday::try_from(day as u8).ok()?, hour as u32, count));
+                }
+            }
+        }
+        busiest.map(|(day, hour, _)| (day, hour))
+    }
+
+    pub fn most_in_an_hour(&self) -> u32 {
+        self.by_weekday_hour.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
+
+// Event bus subscriber that keeps the usage stats up to date and writes them
+// off the UI thread
+pub struct UsageRecorder {
+    usage: Mutex<UsageStats>,
+    writes: Sender<UsageStats>,
+}
+
+impl UsageRecorder {
+    // None unless usage_stats is on. The first run counts the history there is.
+    pub fn spawn(config: &Config, info: &SessionInfo) -> Option<Arc<Self>> {
+        if !config.usage_stats {
+            return None;
+        }
+        let mut usage = super::load_cache::<UsageStats>(FILE).unwrap_or_else(|e| {
+            notifications::log_error(&format!("{:#}, counting again from history", e));
+            None
+        });
+        let first_run = usage.is_none();
+        let usage = usage.get_or_insert_with(UsageStats::default);
+        let writes = super::spawn_cache_writer(FILE)?;
+        if usage.catch_up(&info.history, &chrono::Local) || first_run {
+            let _ = writes.send(usage.clone());
+        }
+        Some(Arc::new(Self { usage: Mutex::new(usage.clone()), writes }))
+    }
+
+    pub fn on_event(&self, event: &SessionEvent, info: &SessionInfo) {
+        let mut usage = self.usage.lock();
+        let changed = match event {
+            SessionEvent::Paused if info.current_state == TimerState::WorkPaused => {
+                usage.interruptions += 1;
+                true
+            }
+            SessionEvent::Completed { .. } | SessionEvent::Skipped | SessionEvent::Switched(_) | SessionEvent::Stopped | SessionEvent::Reset | SessionEvent::Changed => {
+                usage.catch_up(&info.history, &chrono::Local)
+            }
+            _ => false,
+        };
+        if changed {
+            let _ = self.writes.send(usage.clone());
+        }
+    }
+
+    pub fn usage(&self) -> UsageStats {
+        self.usage.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::completed;
+
+    #[test]
+    fn counts_each_session_once() {
+        let mut history = vec![
+            // Monday, started 09:50
+            completed("2026-03-02T10:15:00Z", 25, WORK_SESSION, false),
+            completed("2026-03-02T10:20:00Z", 5, "Short Break", false),
+            completed("2026-03-02T10:30:00Z", 8, WORK_SESSION, true),
+        ];
+        let mut usage = UsageStats::default();
+        assert!(usage.catch_up(&history, &Utc));
+        assert!(!usage.catch_up(&history, &Utc));
+
+        // Wednesday, started 14:05
+        history.push(completed("2026-03-04T14:30:00Z", 25, WORK_SESSION, false));
+        assert!(usage.catch_up(&history, &Utc));
+
+        assert_eq!(usage.work_sessions, 3);
+        assert_eq!(usage.interruptions, 1);
+        assert_eq!(usage.by_weekday_hour[0][9], 1);
+        assert_eq!(usage.by_weekday_hour[2][14], 1);
+        assert_eq!(usage.busiest(), Some((Weekday::Mon, 9)));
+        assert_eq!(usage.average_interruptions(), Some(1.0 / 3.0));
+        assert_eq!(UsageStats::default().average_interruptions(), None);
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::state::SessionInfo;
+
+// Strict classic mode keeps to the technique as written: a pomodoro is 25
+// minutes and can't be divided, so one that is paused, stopped, skipped or
+// reset is void and never counts. An interruption has to be logged, and it
+// voids the pomodoro it broke. The timer calls in here at those points; the
+// state machine itself is the same in both modes.
+
+pub const CLASSIC_WORK_SECS: u32 = 25 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interruption {
+    Internal,  // The worker's own: a thought, a craving, an urge to check mail
+    External,  // Someone else's: a call, a colleague at the desk
+}
+
+impl Interruption {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interruption::Internal => "internal",
+            Interruption::External => "external",
+        }
+    }
+}
+
+// How one day's strict pomodoros went
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StrictDay {
+    pub completed: u32,
+    pub voided: u32,  // Including the interrupted ones
+    pub internal: u32,
+    pub external: u32,
+}
+
+impl StrictDay {
+    pub fn is_empty(&self) -> bool {
+        *self == StrictDay::default()
+    }
+}
+
+// Strict-mode stats, kept apart from history, which never sees a void pomodoro
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StrictLog {
+    pub days: BTreeMap<NaiveDate, StrictDay>,  // By local date
+}
+
+impl StrictLog {
+    pub fn day(&self, date: NaiveDate) -> StrictDay {
+        self.days.get(&date).cloned().unwrap_or_default()
+    }
+
+    pub fn complete(&mut self, date: NaiveDate) {
+        self.days.entry(date).or_default().completed += 1;
+    }
+}
+
+// Whether ending the current session early voids it: a work session that has begun
+pub fn voids(info: &SessionInfo) -> bool {
+    info.current_state.is_work() && info.elapsed_secs > 0
+}
+
+// Drop the current work session as a void pomodoro, leaving nothing in history,
+// and log the interruption that broke it, if one did
+pub fn void(info: &mut SessionInfo, date: NaiveDate, interruption: Option<Interruption>) {
+    let day = info.strict.days.entry(date).or_default();
+    day.voided += 1;
+    match interruption {
+        Some(Interruption::Internal) => day.internal += 1,
+        Some(Interruption::External) => day.external += 1,
+        None => {}
+    }
+    // What add_to_history would have used up
+    info.suggested_label.clear();
+    info.current_category = None;
+    info.continued_task = None;
+    info.current_apps.clear();
+    info.current_segments.clear();
+    info.current_distracted = false;
+    info.announced_milestones.clear();
+    info.current_id = Uuid::new_v4().to_string();
+    info.elapsed_secs = 0;
+}
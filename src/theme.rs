@@ -1,4 +1,35 @@
+use chrono::{Local, NaiveTime, Timelike};
 use gpui::*;
+use serde::{Deserialize, Serialize};
+
+// With theme = "time" the window is light from 07:00 to 19:00 and dark
+// otherwise, fading from one to the other over the half hour before each
+const LIGHT_FROM_MINUTE: f32 = 7.0 * 60.0;
+const DARK_FROM_MINUTE: f32 = 19.0 * 60.0;
+const FADE_MINUTES: f32 = 30.0;
+
+// How dark the time-of-day theme is at `time`, from 0.0 (light) to 1.0 (dark)
+pub fn darkness_at(time: NaiveTime) -> f32 {
+    let minute = time.hour() as f32 * 60.0 + time.minute() as f32 + time.second() as f32 / 60.0;
+    if (LIGHT_FROM_MINUTE - FADE_MINUTES..LIGHT_FROM_MINUTE).contains(&minute) {
+        (LIGHT_FROM_MINUTE - minute) / FADE_MINUTES
+    } else if (DARK_FROM_MINUTE - FADE_MINUTES..DARK_FROM_MINUTE).contains(&minute) {
+        1.0 - (DARK_FROM_MINUTE - minute) / FADE_MINUTES
+    } else if (LIGHT_FROM_MINUTE..DARK_FROM_MINUTE).contains(&minute) {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+// A "#rrggbb" color from the config file
+pub fn parse_hex(text: &str) -> Option<Hsla> {
+    let digits = text.trim().strip_prefix('#')?;
+    if digits.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok().map(|color| rgb(color).into())
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ThemeMode {
@@ -15,6 +46,47 @@ impl ThemeMode {
     }
 }
 
+// Theme choice stored in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+    Time,  // By time of day, see darkness_at
+}
+
+impl ThemePreference {
+    pub fn resolve(&self, appearance: WindowAppearance) -> ThemeMode {
+        match self {
+            ThemePreference::System => ThemeMode::from_appearance(appearance),
+            ThemePreference::Light => ThemeMode::Light,
+            ThemePreference::Dark => ThemeMode::Dark,
+            ThemePreference::Time if darkness_at(Local::now().time()) < 0.5 => ThemeMode::Light,
+            ThemePreference::Time => ThemeMode::Dark,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ThemePreference::System => ThemePreference::Light,
+            ThemePreference::Light => ThemePreference::Dark,
+            ThemePreference::Dark => ThemePreference::Time,
+            ThemePreference::Time => ThemePreference::System,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+            ThemePreference::Time => "Time of day",
+        }
+    }
+}
+
 pub struct Theme {
     pub background: Hsla,
     pub foreground: Hsla,
@@ -23,6 +95,12 @@ pub struct Theme {
     pub muted_foreground: Hsla,
     pub secondary: Hsla,
     pub secondary_foreground: Hsla,
+    pub focus_ring: Hsla,
+    pub warning_background: Hsla,
+    pub warning_foreground: Hsla,
+    pub work: Hsla,
+    pub short_break: Hsla,
+    pub long_break: Hsla,
 }
 
 impl Theme {
@@ -35,6 +113,12 @@ impl Theme {
             muted_foreground: rgb(0x6b7280).into(),
             secondary: rgb(0xe5e7eb).into(),
             secondary_foreground: rgb(0x374151).into(),
+            focus_ring: rgb(0x3b82f6).into(),
+            warning_background: rgb(0xfef3c7).into(),
+            warning_foreground: rgb(0x92400e).into(),
+            work: rgb(0xef4444).into(),
+            short_break: rgb(0x22c55e).into(),
+            long_break: rgb(0x3b82f6).into(),
         }
     }
 
@@ -47,6 +131,21 @@ impl Theme {
             muted_foreground: rgb(0x9ca3af).into(),
             secondary: rgb(0x4b5563).into(),
             secondary_foreground: rgb(0xe5e7eb).into(),
+            focus_ring: rgb(0x60a5fa).into(),
+            warning_background: rgb(0x78350f).into(),
+            warning_foreground: rgb(0xfef3c7).into(),
+            work: rgb(0xf87171).into(),
+            short_break: rgb(0x4ade80).into(),
+            long_break: rgb(0x60a5fa).into(),
+        }
+    }
+
+    // Color for a session type as recorded in history
+    pub fn session_color(&self, session_type: &str) -> Hsla {
+        match session_type {
+            "Short Break" => self.short_break,
+            "Long Break" => self.long_break,
+            _ => self.work,
         }
     }
 
@@ -56,4 +155,53 @@ impl Theme {
             ThemeMode::Dark => Self::dark(),
         }
     }
+
+    // The theme to draw with right now
+    pub fn current(preference: ThemePreference, appearance: WindowAppearance) -> Self {
+        match preference {
+            ThemePreference::Time => Self::mixed(darkness_at(Local::now().time())),
+            _ => Self::from_mode(preference.resolve(appearance)),
+        }
+    }
+
+    // Light and dark mixed, `darkness` of the way towards dark
+    fn mixed(darkness: f32) -> Self {
+        let (light, dark) = (Self::light(), Self::dark());
+        let mix = |light: Hsla, dark: Hsla| light.blend(dark.opacity(darkness));
+        Self {
+            background: mix(light.background, dark.background),
+            foreground: mix(light.foreground, dark.foreground),
+            border: mix(light.border, dark.border),
+            muted_background: mix(light.muted_background, dark.muted_background),
+            muted_foreground: mix(light.muted_foreground, dark.muted_foreground),
+            secondary: mix(light.secondary, dark.secondary),
+            secondary_foreground: mix(light.secondary_foreground, dark.secondary_foreground),
+            focus_ring: mix(light.focus_ring, dark.focus_ring),
+            warning_background: mix(light.warning_background, dark.warning_background),
+            warning_foreground: mix(light.warning_foreground, dark.warning_foreground),
+            work: mix(light.work, dark.work),
+            short_break: mix(light.short_break, dark.short_break),
+            long_break: mix(light.long_break, dark.long_break),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{darkness_at, NaiveTime};
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn fades_into_day_and_night() {
+        assert_eq!(darkness_at(at(3, 0)), 1.0);
+        assert_eq!(darkness_at(at(6, 45)), 0.5);
+        assert_eq!(darkness_at(at(7, 0)), 0.0);
+        assert_eq!(darkness_at(at(12, 0)), 0.0);
+        assert_eq!(darkness_at(at(18, 45)), 0.5);
+        assert_eq!(darkness_at(at(19, 0)), 1.0);
+        assert_eq!(darkness_at(at(23, 59)), 1.0);
+    }
 }
@@ -1,19 +1,88 @@
+use crate::clock::Clock;
 use crate::config::Config;
-use crate::state::{SessionInfo, TimerState};
-use chrono::Utc;
+use crate::events::SessionEvent;
+use crate::milestones;
+use crate::schedule::WorkSchedule;
+use crate::state::{ActiveTimer, FocusRun, SessionInfo, TimerState};
+use crate::strict::{self, Interruption};
+use chrono::{DateTime, Duration, Local, Utc};
 use std::sync::Arc;
 use parking_lot::Mutex;
 
+// How far behind or ahead a following timer may run before it is moved
+const FOLLOW_SLACK_SECS: u32 = 2;
+
+// How well the countdown keeps time since launch, shown under About in settings
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TickStats {
+    pub ticks: u32,  // Ticks while a session ran, after the first
+    pub total_interval_ms: i64,  // Time between those ticks and the one before
+    pub longest_interval_ms: i64,  // Such as across a suspend
+    pub tick_drift_ms: i64,  // How far a countdown of one second per tick would be off by now
+    pub drift_ms: i64,  // Wall-clock time the countdown hadn't counted yet at the last tick
+    pub worst_drift_ms: i64,
+    last_call: Option<DateTime<Utc>>,  // Previous tick while running
+}
+
+impl TickStats {
+    pub fn mean_interval_ms(&self) -> Option<i64> {
+        (self.ticks > 0).then(|| self.total_interval_ms / self.ticks as i64)
+    }
+
+    fn record(&mut self, now: DateTime<Utc>, last_tick: DateTime<Utc>) {
+        if let Some(previous) = self.last_call.replace(now) {
+            let interval_ms = (now - previous).num_milliseconds();
+            self.ticks += 1;
+            self.total_interval_ms += interval_ms;
+            self.longest_interval_ms = self.longest_interval_ms.max(interval_ms);
+            self.tick_drift_ms += interval_ms - 1000;
+        }
+        self.drift_ms = (now - last_tick).num_milliseconds();
+        self.worst_drift_ms = self.worst_drift_ms.max(self.drift_ms.abs());
+    }
+}
+
 pub struct Timer {
     session_info: Arc<Mutex<SessionInfo>>,
-    config: Config,
+    config: Mutex<Config>,  // Durations can change when a template is picked
+    schedule: WorkSchedule,  // When auto_start_work may start a session
+    clock: Arc<dyn Clock>,
+    last_tick: Mutex<Option<DateTime<Utc>>>,  // Time the countdown was last advanced to, None when stopped
+    tick_stats: Mutex<TickStats>,
 }
 
 impl Timer {
-    pub fn new(session_info: Arc<Mutex<SessionInfo>>, config: Config) -> Self {
+    pub fn new(session_info: Arc<Mutex<SessionInfo>>, config: Config, clock: Arc<dyn Clock>) -> Self {
+        // The config was validated at startup
+        let schedule = WorkSchedule::from_config(&config).unwrap_or_default();
         Self {
             session_info,
-            config,
+            config: Mutex::new(config),
+            schedule,
+            clock,
+            last_tick: Mutex::new(None),
+            tick_stats: Mutex::new(TickStats::default()),
+        }
+    }
+
+    pub fn tick_stats(&self) -> TickStats {
+        *self.tick_stats.lock()
+    }
+
+    // Use these durations from the next session on
+    pub fn set_config(&self, config: Config) {
+        *self.config.lock() = config;
+    }
+
+    // Load any countdown that has run out with its next session at full length,
+    // so an idle timer shows what Start will run instead of 00:00
+    pub fn preload(&self) {
+        let mut info = self.session_info.lock();
+        if info.focus.is_empty() {
+            info.focus = ActiveTimer::new(self.config.lock().work_duration_secs());
+        }
+        if info.rest.is_empty() {
+            info.rest = ActiveTimer::new(self.break_duration_secs(&info));
         }
     }
 
@@ -21,11 +90,17 @@ impl Timer {
         let mut info = self.session_info.lock();
         info.current_state = TimerState::Working;
         info.is_focus_mode = true;
+        // Pick up the next queued label when none is set
+        if info.current_label.is_empty() {
+            if let Some(label) = info.take_next_queued_label() {
+                info.set_label(label);
+            }
+        }
         // Initialize work timer if not already set
-        if info.time_remaining_secs == 0 {
-            info.time_remaining_secs = self.config.work_duration_secs();
+        if info.focus.is_empty() {
+            info.focus = ActiveTimer::new(self.config.lock().work_duration_secs());
         }
-        info.last_updated = Utc::now();
+        self.mark_started(&mut info);
     }
 
     pub async fn start_short_break(&self) {
@@ -33,18 +108,48 @@ impl Timer {
         info.current_state = TimerState::ShortBreak;
         info.is_focus_mode = false;
         // Initialize rest timer if not already set
-        if info.rest_time_remaining_secs == 0 {
-            info.rest_time_remaining_secs = self.config.short_break_duration_secs();
+        if info.rest.is_empty() {
+            info.rest = ActiveTimer::new(self.config.lock().short_break_duration_secs());
         }
-        info.last_updated = Utc::now();
+        self.mark_started(&mut info);
     }
 
+    // Start the break that is due: long after the last work session of a cycle, short otherwise
+    pub async fn start_break(&self) {
+        let mut info = self.session_info.lock();
+        info.current_state = if self.long_break_due(&info) { TimerState::LongBreak } else { TimerState::ShortBreak };
+        info.is_focus_mode = false;
+        if info.rest.is_empty() {
+            info.rest = ActiveTimer::new(self.break_duration_secs(&info));
+        }
+        self.mark_started(&mut info);
+    }
 
+    // Have the next session start by itself at `at`, shown as "Starting at
+    // HH:MM" until then. Only an idle timer can be armed.
+    pub fn arm(&self, at: DateTime<Utc>) -> Option<SessionEvent> {
+        let mut info = self.session_info.lock();
+        if info.current_state != TimerState::Idle {
+            return None;
+        }
+        info.starts_at = Some(at);
+        Some(SessionEvent::Changed)
+    }
+
+    pub fn disarm(&self) -> Option<SessionEvent> {
+        self.session_info.lock().starts_at.take().map(|_| SessionEvent::Changed)
+    }
+
+    // A strict-mode pomodoro doesn't pause; see interrupt
     pub async fn pause(&self) {
         let mut info = self.session_info.lock();
+        if self.strict_work(&info) {
+            return;
+        }
         if let Some(paused_state) = info.current_state.pause() {
             info.current_state = paused_state;
-            info.last_updated = Utc::now();
+            info.last_updated = self.clock.now();
+            *self.last_tick.lock() = None;
         }
     }
 
@@ -52,26 +157,1033 @@ impl Timer {
         let mut info = self.session_info.lock();
         if let Some(resumed_state) = info.current_state.resume() {
             info.current_state = resumed_state;
-            info.last_updated = Utc::now();
+            self.mark_started(&mut info);
         }
     }
 
-    pub async fn reset(&self) {
+    // End the session, keeping the time spent in history, and wait in Idle with
+    // the next session of the same mode loaded at its configured length
+    pub async fn stop(&self) {
+        let mut info = self.session_info.lock();
+        self.abandon(&mut info);
+        // A long break cut short still settles the focus run
+        if info.current_state.resume().unwrap_or(info.current_state.clone()) == TimerState::LongBreak {
+            info.focus_run = FocusRun::default();
+        }
+        self.go_idle(&mut info);
+    }
+
+    // Void a running strict-mode pomodoro for the interruption that broke it,
+    // and stop as stop does. None when there's no such pomodoro.
+    pub fn interrupt(&self, interruption: Interruption) -> Option<SessionEvent> {
         let mut info = self.session_info.lock();
+        if !self.strict_work(&info) {
+            return None;
+        }
+        info.break_streak = 0;
+        strict::void(&mut info, self.today(), Some(interruption));
+        self.go_idle(&mut info);
+        Some(SessionEvent::Stopped)
+    }
 
-        // Reset only the current timer based on current state
-        if info.current_state.is_work() || info.current_state == TimerState::Idle {
-            // Reset focus timer
-            info.time_remaining_secs = self.config.work_duration_secs();
+    // Wait with the next session of the same mode loaded at its configured length
+    fn go_idle(&self, info: &mut SessionInfo) {
+        if info.is_focus_mode {
+            info.focus = ActiveTimer::new(self.config.lock().work_duration_secs());
         } else {
-            // Reset rest timer
-            info.rest_time_remaining_secs = self.config.short_break_duration_secs();
+            info.rest = ActiveTimer::new(self.break_duration_secs(info));
         }
 
-        // Set to Idle but stay in same mode (focus/rest)
         info.current_state = TimerState::Idle;
-        info.last_updated = Utc::now();
+        info.starts_at = None;
+        info.elapsed_secs = 0;
+        info.last_updated = self.clock.now();
+        *self.last_tick.lock() = None;
+    }
+
+    // Go back to the first work session of a cycle at the current durations,
+    // with nothing running. Time already spent is kept in history as abandoned.
+    pub fn restart_cycle(&self) {
+        let mut info = self.session_info.lock();
+        self.abandon(&mut info);
+        info.current_state = TimerState::Idle;
+        info.is_focus_mode = true;
+        info.current_session = 1;
+        info.break_streak = 0;
+        info.elapsed_secs = 0;
+        info.show_celebration = false;
+        info.starts_at = None;
+        info.focus = ActiveTimer::new(self.config.lock().work_duration_secs());
+        info.rest = ActiveTimer::new(self.break_duration_secs(&info));
+        info.last_updated = self.clock.now();
+        *self.last_tick.lock() = None;
+    }
+
+    // Restart the current session from its full length, keeping it running or paused
+    pub async fn reset(&self) {
+        let mut info = self.session_info.lock();
+        self.abandon(&mut info);
+
+        let total_secs = match info.active_timer().total_secs {
+            0 if info.is_focus_mode => self.config.lock().work_duration_secs(),
+            0 => self.break_duration_secs(&info),
+            total_secs => total_secs,
+        };
+        *info.active_timer_mut() = ActiveTimer::new(total_secs);
+        info.elapsed_secs = 0;
+
+        if info.current_state.is_running() {
+            self.mark_started(&mut info);
+        } else {
+            info.last_updated = self.clock.now();
+            *self.last_tick.lock() = None;
+        }
+    }
+
+    // Run in step with a timer elsewhere: its session, with its time left.
+    // Returns what changed, or None when this timer is already within a couple
+    // of seconds of it. A different session this one was in is abandoned.
+    pub fn follow(&self, state: TimerState, focus_mode: bool, timer: ActiveTimer) -> Option<SessionEvent> {
+        let mut info = self.session_info.lock();
+        // Pausing or resuming stays in the same session
+        let session = |state: &TimerState| state.resume().unwrap_or(state.clone());
+        let same_session = session(&info.current_state) == session(&state) && info.is_focus_mode == focus_mode;
+        let in_step = info.active_timer().remaining_secs.abs_diff(timer.remaining_secs) <= FOLLOW_SLACK_SECS;
+        if same_session && info.current_state == state && in_step {
+            return None;
+        }
+        // A session about to end here finishes by itself, rather than being cut short
+        let ending = info.current_state.is_running() && info.active_timer().remaining_secs <= FOLLOW_SLACK_SECS;
+        if !same_session && ending {
+            return None;
+        }
+
+        if !same_session {
+            self.abandon(&mut info);
+            info.elapsed_secs = 0;
+            info.show_celebration = false;
+        }
+        let was_running = info.current_state.is_running();
+        info.current_state = state.clone();
+        info.is_focus_mode = focus_mode;
+        *info.active_timer_mut() = timer;
+        if state.is_running() {
+            self.mark_started(&mut info);
+        } else {
+            info.last_updated = self.clock.now();
+            *self.last_tick.lock() = None;
+        }
+        Some(match (was_running, state.is_running()) {
+            (_, true) if !same_session => SessionEvent::Started(state),
+            (false, true) => SessionEvent::Resumed,
+            (true, false) if same_session => SessionEvent::Paused,
+            _ => SessionEvent::Changed,
+        })
+    }
+
+    // Resume a paused session as if it had run for `secs` more, finishing it
+    // when that takes it past its end. For time the app wasn't running.
+    pub fn fast_forward(&self, secs: u32) -> Vec<SessionEvent> {
+        let mut info = self.session_info.lock();
+        let Some(resumed) = info.current_state.resume() else {
+            return Vec::new();
+        };
+        info.current_state = resumed;
+        let timer = info.active_timer_mut();
+        let step = secs.min(timer.remaining_secs);
+        timer.remaining_secs -= step;
+        let finished = timer.is_empty();
+        info.elapsed_secs += step;
+        // From when it was last seen running
+        let from = info.last_updated;
+        info.add_run(from, from + Duration::seconds(step as i64));
+
+        if finished {
+            *self.last_tick.lock() = None;
+            info.last_updated = self.clock.now();
+            self.complete(&mut info)
+        } else {
+            self.mark_started(&mut info);
+            vec![SessionEvent::Resumed]
+        }
+    }
+
+    // Advance a running countdown to the clock's current time and return what
+    // happened, for the caller to publish. A long gap, such as a suspend, is
+    // applied at once and can finish the session.
+    pub fn tick(&self) -> Vec<SessionEvent> {
+        let now = self.clock.now();
+        let mut info = self.session_info.lock();
+        let mut last_tick = self.last_tick.lock();
+
+        if !info.current_state.is_running() {
+            *last_tick = None;
+            self.tick_stats.lock().last_call = None;
+            drop(last_tick);
+            // An armed session starts once its time comes
+            if info.current_state == TimerState::Idle && info.starts_at.is_some_and(|at| at <= now) {
+                return vec![self.start_next(&mut info)];
+            }
+            return Vec::new();
+        }
+        // A timer restored already running starts counting from here
+        let Some(since) = *last_tick else {
+            *last_tick = Some(now);
+            return Vec::new();
+        };
+        let elapsed = (now - since).num_seconds().max(0) as u32;
+        let counted_to = since + Duration::seconds(elapsed as i64);
+        self.tick_stats.lock().record(now, counted_to);
+        if elapsed == 0 {
+            return Vec::new();
+        }
+        *last_tick = Some(counted_to);
+        drop(last_tick);
+
+        let timer = info.active_timer_mut();
+        let step = elapsed.min(timer.remaining_secs);
+        timer.remaining_secs -= step;
+        let finished = timer.is_empty();
+        info.elapsed_secs += step;
+        info.add_run(since, since + Duration::seconds(step as i64));
+        info.last_updated = now;
+        if info.current_state == TimerState::Working {
+            let rest_secs = self.config.lock().long_break_duration_secs();
+            info.focus_run.add(step, now, rest_secs);
+        }
+
+        if finished {
+            return self.complete(&mut info);
+        }
+        let mut events = vec![SessionEvent::Tick];
+        if info.current_state == TimerState::Working {
+            let timer = info.focus.clone();
+            if let Some(text) = milestones::due(&self.config.lock().milestones, &timer, &mut info.announced_milestones) {
+                events.push(SessionEvent::Milestone(text));
+            }
+        }
+        events
+    }
+
+    // End the current session early and move on to the next one in the cycle.
+    // Time already spent is kept in history as abandoned. Returns Started when
+    // the next session starts by itself.
+    pub async fn skip_to_next(&self) -> Option<SessionEvent> {
+        let mut info = self.session_info.lock();
+        self.abandon(&mut info);
+        let skipped = match info.current_state.clone() {
+            TimerState::Idle if info.is_focus_mode => TimerState::Working,
+            TimerState::Idle if self.long_break_due(&info) => TimerState::LongBreak,
+            TimerState::Idle => TimerState::ShortBreak,
+            state => state.resume().unwrap_or(state),
+        };
+        // As does skipping one that hasn't started
+        if skipped.is_work() {
+            info.break_streak = 0;
+        }
+        info.elapsed_secs = 0;
+        info.show_celebration = false;
+        *self.last_tick.lock() = None;
+        self.advance(&mut info, &skipped)
+    }
+
+    // Record the finished session and move on to the next one in the cycle
+    fn complete(&self, info: &mut SessionInfo) -> Vec<SessionEvent> {
+        let finished = info.current_state.clone();
+        let (id, label, elapsed) = (info.current_id.clone(), info.current_label.clone(), info.elapsed_secs);
+        info.add_to_history(id, label.clone(), elapsed, finished.display_name().to_string(), false);
+        // Which may be the suggested one
+        let label = info.history.last().map_or(label, |entry| entry.label.clone());
+        if finished.is_work() {
+            info.completed_sessions += 1;
+            info.break_streak += 1;
+            if self.config.lock().strict_mode {
+                let today = self.today();
+                info.strict.complete(today);
+            }
+        }
+        let mut events = vec![SessionEvent::Completed { state: finished.clone(), label }];
+        match self.advance(info, &finished) {
+            Some(started) => events.push(started),
+            // Enable celebration breathing effect while waiting for the next session
+            None => info.show_celebration = true,
+        }
+        events
+    }
+
+    // Load the session that follows `finished` and start it if the config says so
+    fn advance(&self, info: &mut SessionInfo, finished: &TimerState) -> Option<SessionEvent> {
+        let auto_start = if finished.is_work() {
+            // The next work session takes its label from the queue
+            if let Some(label) = info.take_next_queued_label() {
+                crate::notifications::log_info(&format!("Next label from queue: {}", label));
+                info.set_label(label);
+            }
+            self.load_break(info);
+            self.config.lock().auto_start_breaks
+        } else {
+            self.load_work(info, finished);
+            let now = self.clock.now().with_timezone(&Local);
+            let in_hours = self.schedule.allows(now);
+            let before_stop = !self.schedule.runs_past_hard_stop(now, info.focus.total_secs);
+            let auto_start_work = self.config.lock().auto_start_work;
+            if auto_start_work && !in_hours {
+                crate::notifications::log_info("Outside work hours, not starting the next work session");
+            } else if auto_start_work && !before_stop {
+                crate::notifications::log_info("The workday ends before another session would, not starting it");
+            }
+            // Held back sessions wait for the work hours to come round again
+            if auto_start_work && !(in_hours && before_stop) {
+                info.starts_at = self.schedule.next_start(now).map(|start| start.with_timezone(&Utc));
+            }
+            auto_start_work && in_hours && before_stop
+        };
+
+        if !auto_start {
+            info.current_state = TimerState::Idle;
+            return None;
+        }
+        Some(self.start_next(info))
+    }
+
+    // Load the break due after a work session
+    fn load_break(&self, info: &mut SessionInfo) {
+        info.is_focus_mode = false;
+        if self.focus_run_over(info) && self.config.lock().force_long_break {
+            crate::notifications::log_info("Long focus run, taking a long break");
+            info.focus_run.long_break = true;
+        }
+        info.rest = ActiveTimer::new(self.break_duration_secs(info));
+    }
+
+    // Load the work session after the `finished` break
+    fn load_work(&self, info: &mut SessionInfo, finished: &TimerState) {
+        // A preset picked for a break skipped before it ran doesn't carry over
+        info.current_break_preset = None;
+        // A long break ends the cycle, and the focus run that earned it, however it ended
+        if *finished == TimerState::LongBreak {
+            info.current_session = 1;
+            info.break_streak = 0;
+            info.focus_run = FocusRun::default();
+        } else {
+            info.current_session += 1;
+        }
+        info.is_focus_mode = true;
+        info.focus = ActiveTimer::new(self.config.lock().work_duration_secs());
+    }
+
+    // Turn the session into the other kind on the spot, such as work into a
+    // break when an urgent call comes in. The part already spent is kept in
+    // history as a session of its own, and the new one runs from the start
+    // straight away, or waits paused if this one was paused.
+    pub fn switch_session(&self) -> Option<SessionEvent> {
+        let mut info = self.session_info.lock();
+        let from = info.current_state.clone();
+        if from == TimerState::Idle {
+            return None;
+        }
+        // Not abandoned or voided, even in strict mode: the time was spent
+        if from.is_work() && info.elapsed_secs > 0 {
+            info.break_streak = 0;
+        }
+        info.switch_current();
+        info.elapsed_secs = 0;
+        info.show_celebration = false;
+        let to = if from.is_work() {
+            // The label stays for the work to come back to
+            self.load_break(&mut info);
+            if self.long_break_due(&info) { TimerState::LongBreak } else { TimerState::ShortBreak }
+        } else {
+            self.load_work(&mut info, &from.resume().unwrap_or(from.clone()));
+            TimerState::Working
+        };
+        if from.is_running() {
+            info.current_state = to.clone();
+            self.mark_started(&mut info);
+        } else {
+            info.current_state = to.pause().unwrap_or(to.clone());
+            info.last_updated = self.clock.now();
+            *self.last_tick.lock() = None;
+        }
+        Some(SessionEvent::Switched(to))
+    }
+
+    // Start the session that's loaded next, work or break
+    fn start_next(&self, info: &mut SessionInfo) -> SessionEvent {
+        info.show_celebration = false;
+        if info.is_focus_mode && info.current_label.is_empty() {
+            if let Some(label) = info.take_next_queued_label() {
+                info.set_label(label);
+            }
+        }
+        info.current_state = if info.is_focus_mode {
+            TimerState::Working
+        } else if self.long_break_due(info) {
+            TimerState::LongBreak
+        } else {
+            TimerState::ShortBreak
+        };
+        self.mark_started(info);
+        SessionEvent::Started(info.current_state.clone())
     }
 
+    // End the current session early: as abandoned, kept in history, or in
+    // strict mode as a void pomodoro when it's work
+    pub fn abandon(&self, info: &mut SessionInfo) {
+        // Breaks grow only with work sessions completed in a row
+        if info.current_state.is_work() && info.elapsed_secs > 0 {
+            info.break_streak = 0;
+        }
+        if self.config.lock().strict_mode && strict::voids(info) {
+            crate::notifications::log_info("Pomodoro voided");
+            strict::void(info, self.today(), None);
+        } else {
+            info.abandon_current();
+        }
+    }
+
+    // A strict-mode pomodoro is running, which can only be voided, not paused
+    pub fn strict_work(&self, info: &SessionInfo) -> bool {
+        self.config.lock().strict_mode && info.current_state == TimerState::Working
+    }
+
+    fn today(&self) -> chrono::NaiveDate {
+        self.clock.now().with_timezone(&Local).date_naive()
+    }
+
+    fn mark_started(&self, info: &mut SessionInfo) {
+        let now = self.clock.now();
+        info.last_updated = now;
+        info.starts_at = None;
+        *self.last_tick.lock() = Some(now);
+    }
 
+    // With break_durations the cycle follows the streak of completed sessions,
+    // otherwise the session count
+    fn long_break_due(&self, info: &SessionInfo) -> bool {
+        let config = self.config.lock();
+        let position = if config.break_durations.is_empty() { info.current_session } else { info.break_streak };
+        position >= config.cycle_length() || info.focus_run.long_break
+    }
+
+    // Focus since the last long break has reached long_break_after_focus_minutes
+    fn focus_run_over(&self, info: &SessionInfo) -> bool {
+        let threshold_secs = self.config.lock().long_break_after_focus_minutes * 60;
+        threshold_secs > 0 && info.focus_run.secs >= threshold_secs
+    }
+
+    // A short break is due or running after a long focus run, and the config
+    // leaves it to the user to make it long
+    pub fn long_break_suggested(&self, info: &SessionInfo) -> bool {
+        let short_break = matches!(info.current_state, TimerState::ShortBreak | TimerState::BreakPaused)
+            || (info.current_state == TimerState::Idle && !info.is_focus_mode && !self.long_break_due(info));
+        short_break && self.focus_run_over(info) && !self.config.lock().force_long_break
+    }
+
+    // Make the loaded or running short break a long one, counting the time it has run
+    pub fn take_long_break(&self) -> Option<SessionEvent> {
+        let mut info = self.session_info.lock();
+        if !self.long_break_suggested(&info) {
+            return None;
+        }
+        info.focus_run.long_break = true;
+        let total_secs = self.config.lock().long_break_duration_secs();
+        let spent = info.rest.total_secs - info.rest.remaining_secs;
+        info.rest = ActiveTimer { remaining_secs: total_secs.saturating_sub(spent).max(1), total_secs };
+        info.current_state = match info.current_state {
+            TimerState::ShortBreak => TimerState::LongBreak,
+            TimerState::BreakPaused => TimerState::LongBreakPaused,
+            ref state => state.clone(),
+        };
+        Some(SessionEvent::Changed)
+    }
+
+    // The length of the break due next, grown with the streak when break_durations is set
+    fn break_duration_secs(&self, info: &SessionInfo) -> u32 {
+        let long = self.long_break_due(info);
+        let config = self.config.lock();
+        match config.grown_break_secs(info.break_streak) {
+            Some(secs) if !info.focus_run.long_break => secs,
+            _ if long => config.long_break_duration_secs(),
+            _ => config.short_break_duration_secs(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::state::Segment;
+    use futures::executor::block_on;
+    use chrono::TimeZone;
+
+    fn timer(config: Config) -> (Timer, Arc<ManualClock>, Arc<Mutex<SessionInfo>>) {
+        let clock = Arc::new(ManualClock::new());
+        let session_info = Arc::new(Mutex::new(SessionInfo::new()));
+        let timer = Timer::new(session_info.clone(), config, clock.clone());
+        (timer, clock, session_info)
+    }
+
+    // Run the current session to its end, one second per tick
+    fn run_to_end(timer: &Timer, clock: &ManualClock) -> Vec<SessionEvent> {
+        loop {
+            clock.advance(1);
+            let events = timer.tick();
+            if events.iter().any(|event| matches!(event, SessionEvent::Completed { .. })) {
+                return events;
+            }
+        }
+    }
+
+    #[test]
+    fn counts_down_with_the_clock() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+
+        clock.advance(60);
+        assert_eq!(timer.tick(), vec![SessionEvent::Tick]);
+        assert_eq!(info.lock().focus.remaining_secs, 24 * 60);
+        assert_eq!(info.lock().elapsed_secs, 60);
+
+        // Nothing happens until a whole second has passed
+        assert!(timer.tick().is_empty());
+    }
+
+    #[test]
+    fn reports_tick_timing() {
+        let (timer, clock, _info) = timer(Config::default());
+        block_on(timer.start_work());
+        for _ in 0..3 {
+            clock.advance_millis(1100);
+            timer.tick();
+        }
+        let stats = timer.tick_stats();
+        // The first tick has nothing before it to measure from
+        assert_eq!(stats.ticks, 2);
+        assert_eq!(stats.mean_interval_ms(), Some(1100));
+        assert_eq!(stats.tick_drift_ms, 200);
+        // 3.3 s have gone by and 3 were counted
+        assert_eq!(stats.drift_ms, 300);
+        assert_eq!(stats.worst_drift_ms, 300);
+    }
+
+    #[test]
+    fn long_focus_runs_earn_a_long_break() {
+        let config = Config { long_break_after_focus_minutes: 50, ..Config::default() };
+        let (timer, clock, info) = timer(config.clone());
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        assert!(!timer.long_break_suggested(&info.lock()));
+
+        // Straight into another work session, by hand; the cycle still says short break
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        assert_eq!(info.lock().current_session, 1);
+        assert_eq!(info.lock().focus_run.secs, 50 * 60);
+        assert!(timer.long_break_suggested(&info.lock()));
+        assert_eq!(timer.take_long_break(), Some(SessionEvent::Changed));
+        assert_eq!(info.lock().rest.total_secs, config.long_break_duration_secs());
+
+        block_on(timer.start_break());
+        assert_eq!(info.lock().current_state, TimerState::LongBreak);
+        run_to_end(&timer, &clock);
+        assert_eq!(info.lock().focus_run, FocusRun::default());
+    }
+
+    #[test]
+    fn a_skipped_long_break_ends_the_focus_run() {
+        let config = Config { force_long_break: true, long_break_after_focus_minutes: 25, ..Config::default() };
+        let (timer, clock, info) = timer(config.clone());
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        block_on(timer.start_break());
+        assert_eq!(info.lock().current_state, TimerState::LongBreak);
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.skip_to_next());
+        assert_eq!(info.lock().focus_run, FocusRun::default());
+
+        // The next break is a short one again
+        block_on(timer.start_work());
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.skip_to_next());
+        assert!(!info.lock().focus_run.long_break);
+        assert_eq!(info.lock().rest.total_secs, config.short_break_duration_secs());
+    }
+
+    #[test]
+    fn a_stopped_long_break_ends_the_focus_run() {
+        let (timer, clock, info) =
+            timer(Config { force_long_break: true, long_break_after_focus_minutes: 25, ..Config::default() });
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        block_on(timer.start_break());
+        assert_eq!(info.lock().current_state, TimerState::LongBreak);
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.stop());
+        assert_eq!(info.lock().focus_run, FocusRun::default());
+        assert!(!timer.long_break_suggested(&info.lock()));
+    }
+
+    #[test]
+    fn forced_long_break_needs_no_answer() {
+        let (timer, clock, info) =
+            timer(Config { force_long_break: true, long_break_after_focus_minutes: 25, ..Config::default() });
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        assert!(!timer.long_break_suggested(&info.lock()));
+        block_on(timer.start_break());
+        assert_eq!(info.lock().current_state, TimerState::LongBreak);
+    }
+
+    #[test]
+    fn paused_timer_does_not_count() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        block_on(timer.pause());
+
+        clock.advance(300);
+        assert!(timer.tick().is_empty());
+        block_on(timer.resume());
+        clock.advance(1);
+        timer.tick();
+        assert_eq!(info.lock().focus.remaining_secs, 25 * 60 - 1);
+    }
+
+    #[test]
+    fn fast_forward_counts_time_away() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        block_on(timer.pause());
+        assert_eq!(timer.fast_forward(10 * 60), vec![SessionEvent::Resumed]);
+        assert_eq!(info.lock().focus.remaining_secs, 15 * 60);
+        clock.advance(1);
+        assert_eq!(timer.tick(), vec![SessionEvent::Tick]);
+
+        // Past the end, the session is done
+        block_on(timer.pause());
+        let events = timer.fast_forward(60 * 60);
+        assert_eq!(events, vec![SessionEvent::Completed { state: TimerState::Working, label: String::new() }]);
+        let info = info.lock();
+        assert_eq!(info.history[0].duration_secs, 25 * 60);
+        assert!(!info.history[0].abandoned);
+        assert_eq!(info.current_state, TimerState::Idle);
+
+        // Only a paused session moves
+        drop(info);
+        assert!(timer.fast_forward(60).is_empty());
+    }
+
+    #[test]
+    fn records_when_the_session_ran() {
+        let (timer, clock, info) = timer(Config::default());
+        let started = clock.now();
+        block_on(timer.start_work());
+        for _ in 0..60 {
+            clock.advance(1);
+            timer.tick();
+        }
+        block_on(timer.pause());
+        clock.advance(30);
+        timer.tick();
+        block_on(timer.resume());
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.stop());
+
+        let at = |secs| started + Duration::seconds(secs);
+        let info = info.lock();
+        assert_eq!(
+            info.history[0].segments,
+            vec![Segment { start: at(0), end: at(60) }, Segment { start: at(90), end: at(150) }]
+        );
+        assert!(info.current_segments.is_empty());
+    }
+
+    #[test]
+    fn a_pause_pushes_the_end_back() {
+        let (timer, clock, info) = timer(Config::default());
+        let started = clock.now();
+        block_on(timer.start_work());
+        for _ in 0..60 {
+            clock.advance(1);
+            timer.tick();
+        }
+        assert_eq!(info.lock().ends_at(), Some(started + Duration::minutes(25)));
+
+        block_on(timer.pause());
+        clock.advance(90);
+        timer.tick();
+        assert_eq!(info.lock().ends_at(), None);
+        block_on(timer.resume());
+        assert_eq!(info.lock().ends_at(), Some(started + Duration::seconds(25 * 60 + 90)));
+    }
+
+    #[test]
+    fn fourth_work_session_is_followed_by_a_long_break() {
+        let config = Config::default();
+        let (timer, clock, info) = timer(config.clone());
+
+        for session in 1..=4 {
+            block_on(timer.start_work());
+            let events = run_to_end(&timer, &clock);
+            assert_eq!(events[0], SessionEvent::Completed { state: TimerState::Working, label: String::new() });
+
+            block_on(timer.start_break());
+            let expected = if session == 4 { TimerState::LongBreak } else { TimerState::ShortBreak };
+            assert_eq!(info.lock().current_state, expected, "break after session {}", session);
+            let expected_secs = if session == 4 { config.long_break_duration_secs() } else { config.short_break_duration_secs() };
+            assert_eq!(info.lock().rest.remaining_secs, expected_secs);
+            run_to_end(&timer, &clock);
+        }
+
+        let info = info.lock();
+        assert_eq!(info.completed_sessions, 4);
+        assert_eq!(info.current_session, 1);
+        assert_eq!(info.history.len(), 8);
+        assert!(info.is_focus_mode);
+    }
+
+    #[test]
+    fn breaks_grow_with_sessions_completed_in_a_row() {
+        let config = Config { break_durations: vec![5, 5, 10, 20], ..Config::default() };
+        let (timer, clock, info) = timer(config);
+        let mut breaks = Vec::new();
+        for _ in 0..3 {
+            block_on(timer.start_work());
+            run_to_end(&timer, &clock);
+            let info = info.lock();
+            breaks.push((info.rest.total_secs / 60, timer.long_break_due(&info)));
+            drop(info);
+            block_on(timer.start_break());
+            run_to_end(&timer, &clock);
+        }
+        assert_eq!(breaks, vec![(5, false), (5, false), (10, false)]);
+
+        // Skipping work starts over
+        block_on(timer.skip_to_next());
+        assert_eq!(info.lock().rest.total_secs, 5 * 60);
+        block_on(timer.skip_to_next());
+        for _ in 0..4 {
+            block_on(timer.start_work());
+            run_to_end(&timer, &clock);
+            if info.lock().break_streak < 4 {
+                block_on(timer.skip_to_next());
+            }
+        }
+        // The last one is the long break, and ends the cycle
+        assert_eq!(info.lock().rest.total_secs, 20 * 60);
+        block_on(timer.start_break());
+        assert_eq!(info.lock().current_state, TimerState::LongBreak);
+        run_to_end(&timer, &clock);
+        assert_eq!(info.lock().break_streak, 0);
+    }
+
+    #[test]
+    fn switches_between_work_and_break_partway() {
+        let (timer, clock, info) = timer(Config::default());
+        assert_eq!(timer.switch_session(), None);
+        info.lock().current_label = "Report".to_string();
+        block_on(timer.start_work());
+        clock.advance(600);
+        timer.tick();
+
+        // A call comes in
+        assert_eq!(timer.switch_session(), Some(SessionEvent::Switched(TimerState::ShortBreak)));
+        {
+            let info = info.lock();
+            assert_eq!(info.current_state, TimerState::ShortBreak);
+            assert_eq!(info.rest.remaining_secs, 5 * 60);
+            assert_eq!(info.current_label, "Report");
+        }
+        clock.advance(1);
+        assert_eq!(timer.tick(), vec![SessionEvent::Tick]);
+
+        // Back to work while paused stays paused
+        clock.advance(119);
+        timer.tick();
+        block_on(timer.pause());
+        assert_eq!(timer.switch_session(), Some(SessionEvent::Switched(TimerState::Working)));
+        let info = info.lock();
+        assert_eq!(info.current_state, TimerState::WorkPaused);
+        assert_eq!(info.focus.remaining_secs, 25 * 60);
+        let logged: Vec<_> = info.history.iter().map(|entry| (entry.session_type.as_str(), entry.duration_secs)).collect();
+        assert_eq!(logged, vec![("Work Session", 600), ("Short Break", 120)]);
+        assert!(info.history.iter().all(|entry| entry.switched && !entry.abandoned));
+    }
+
+    #[test]
+    fn strict_mode_keeps_a_switched_pomodoro() {
+        let config = Config { strict_mode: true, ..Config::default() };
+        let (timer, clock, info) = timer(config);
+        block_on(timer.start_work());
+        clock.advance(600);
+        timer.tick();
+
+        assert_eq!(timer.switch_session(), Some(SessionEvent::Switched(TimerState::ShortBreak)));
+        let info = info.lock();
+        let logged: Vec<_> = info.history.iter().map(|entry| (entry.duration_secs, entry.abandoned, entry.switched)).collect();
+        assert_eq!(logged, vec![(600, false, true)]);
+        assert_eq!(info.completed_today(clock.now().with_timezone(&Local)), 0);
+    }
+
+    #[test]
+    fn completed_session_waits_without_auto_start() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        let events = run_to_end(&timer, &clock);
+
+        assert_eq!(events.len(), 1);
+        let info = info.lock();
+        assert_eq!(info.current_state, TimerState::Idle);
+        assert!(!info.is_focus_mode);
+        assert!(info.show_celebration);
+    }
+
+    #[test]
+    fn auto_start_runs_the_next_session() {
+        let config = Config { auto_start_breaks: true, auto_start_work: true, ..Config::default() };
+        let (timer, clock, info) = timer(config);
+        block_on(timer.start_work());
+
+        let events = run_to_end(&timer, &clock);
+        assert_eq!(events[1], SessionEvent::Started(TimerState::ShortBreak));
+        assert_eq!(info.lock().current_state, TimerState::ShortBreak);
+
+        let events = run_to_end(&timer, &clock);
+        assert_eq!(events[1], SessionEvent::Started(TimerState::Working));
+        assert_eq!(info.lock().current_session, 2);
+    }
+
+    #[test]
+    fn auto_start_waits_for_work_hours() {
+        let work_hours = std::collections::BTreeMap::from([("fri".to_string(), "09:00-18:00".to_string())]);
+        let config = Config { auto_start_work: true, work_hours, ..Config::default() };
+        let (timer, clock, info) = timer(config);
+
+        // A break ending at 23:00 on a Friday leaves the next session waiting
+        let evening = Local.with_ymd_and_hms(2026, 10, 16, 22, 55, 0).unwrap();
+        clock.set(evening.with_timezone(&Utc));
+        block_on(timer.start_break());
+        let events = run_to_end(&timer, &clock);
+        assert_eq!(events.len(), 1);
+        assert_eq!(info.lock().current_state, TimerState::Idle);
+        // It's armed for the next Friday morning
+        let next_friday = Local.with_ymd_and_hms(2026, 10, 23, 9, 0, 0).unwrap();
+        assert_eq!(info.lock().starts_at, Some(next_friday.with_timezone(&Utc)));
+
+        let morning = Local.with_ymd_and_hms(2026, 10, 16, 10, 0, 0).unwrap();
+        clock.set(morning.with_timezone(&Utc));
+        block_on(timer.start_break());
+        let events = run_to_end(&timer, &clock);
+        assert_eq!(events[1], SessionEvent::Started(TimerState::Working));
+    }
+
+    #[test]
+    fn armed_session_starts_on_time() {
+        let (timer, clock, info) = timer(Config::default());
+        let at = clock.now() + Duration::minutes(10);
+        assert_eq!(timer.arm(at), Some(SessionEvent::Changed));
+        assert!(info.lock().timer_view(false).starts_at.is_some());
+
+        clock.advance(9 * 60);
+        assert!(timer.tick().is_empty());
+        clock.advance(60);
+        assert_eq!(timer.tick(), vec![SessionEvent::Started(TimerState::Working)]);
+        assert_eq!(info.lock().starts_at, None);
+        // A running timer can't be armed, and there's nothing left to cancel
+        assert_eq!(timer.arm(at), None);
+        assert_eq!(timer.disarm(), None);
+
+        block_on(timer.stop());
+        timer.arm(at);
+        assert_eq!(timer.disarm(), Some(SessionEvent::Changed));
+        clock.advance(60);
+        assert!(timer.tick().is_empty());
+    }
+
+    #[test]
+    fn suspend_fast_forwards_the_countdown() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+
+        // Ten minutes asleep count as ten minutes of work
+        clock.advance(10 * 60);
+        timer.tick();
+        assert_eq!(info.lock().focus.remaining_secs, 15 * 60);
+
+        // Sleeping past the end finishes the session with its full length
+        clock.advance(60 * 60);
+        let events = timer.tick();
+        assert!(matches!(events[0], SessionEvent::Completed { .. }));
+        let info = info.lock();
+        assert_eq!(info.history.last().map(|entry| entry.duration_secs), Some(25 * 60));
+        assert_eq!(info.current_state, TimerState::Idle);
+    }
+
+    #[test]
+    fn skip_moves_through_the_cycle() {
+        let config = Config { sessions_until_long_break: 2, ..Config::default() };
+        let (timer, clock, info) = timer(config.clone());
+        block_on(timer.start_work());
+        clock.advance(60);
+        timer.tick();
+
+        // Skipping work keeps the minute spent and preloads the break
+        assert_eq!(block_on(timer.skip_to_next()), None);
+        {
+            let info = info.lock();
+            assert_eq!(info.current_state, TimerState::Idle);
+            assert!(!info.is_focus_mode);
+            assert_eq!(info.rest.remaining_secs, config.short_break_duration_secs());
+            assert_eq!(info.history.last().map(|entry| (entry.duration_secs, entry.abandoned)), Some((60, true)));
+            assert_eq!(info.completed_sessions, 0);
+        }
+
+        // Skipping the idle break moves on to the second work session, whose break is long
+        block_on(timer.skip_to_next());
+        assert!(info.lock().is_focus_mode);
+        assert_eq!(info.lock().current_session, 2);
+        block_on(timer.skip_to_next());
+        assert_eq!(info.lock().rest.remaining_secs, config.long_break_duration_secs());
+        block_on(timer.skip_to_next());
+        assert_eq!(info.lock().current_session, 1);
+        assert_eq!(info.lock().history.len(), 1);
+    }
+
+    #[test]
+    fn stop_ends_the_session_and_reset_restarts_it() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        clock.advance(90);
+        timer.tick();
+
+        // Reset keeps the session going from the top
+        block_on(timer.reset());
+        {
+            let info = info.lock();
+            assert_eq!(info.current_state, TimerState::Working);
+            assert_eq!(info.focus.remaining_secs, 25 * 60);
+            assert_eq!(info.elapsed_secs, 0);
+        }
+        clock.advance(1);
+        assert_eq!(timer.tick(), vec![SessionEvent::Tick]);
+
+        // Stop leaves a fresh work session waiting
+        clock.advance(59);
+        timer.tick();
+        block_on(timer.stop());
+        let info = info.lock();
+        assert_eq!(info.current_state, TimerState::Idle);
+        assert_eq!(info.focus.remaining_secs, 25 * 60);
+        let logged: Vec<_> = info.history.iter().map(|entry| (entry.duration_secs, entry.abandoned)).collect();
+        assert_eq!(logged, vec![(90, true), (60, true)]);
+    }
+
+    #[test]
+    fn strict_mode_voids_broken_pomodoros() {
+        let config = Config { strict_mode: true, work_duration: 50, ..Config::default() };
+        let (timer, clock, info) = timer(config);
+        block_on(timer.start_work());
+        assert_eq!(info.lock().focus.total_secs, 25 * 60);
+        clock.advance(60);
+        timer.tick();
+
+        // No pausing, only an interruption that voids it
+        block_on(timer.pause());
+        assert_eq!(info.lock().current_state, TimerState::Working);
+        assert_eq!(timer.interrupt(Interruption::External), Some(SessionEvent::Stopped));
+        assert_eq!(info.lock().current_state, TimerState::Idle);
+
+        // Skipped partway, void too
+        block_on(timer.start_work());
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.skip_to_next());
+        assert!(info.lock().history.is_empty());
+
+        // Breaks end early as usual, and a whole pomodoro counts
+        block_on(timer.start_break());
+        clock.advance(60);
+        timer.tick();
+        block_on(timer.skip_to_next());
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        let info = info.lock();
+        let logged: Vec<_> = info.history.iter().map(|entry| (entry.session_type.as_str(), entry.abandoned)).collect();
+        assert_eq!(logged, vec![("Short Break", true), ("Work Session", false)]);
+        let today = info.strict.day(clock.now().with_timezone(&Local).date_naive());
+        assert_eq!(today, strict::StrictDay { completed: 1, voided: 2, internal: 0, external: 1 });
+    }
+
+    #[test]
+    fn idle_timer_shows_the_upcoming_session() {
+        let config = Config { sessions_until_long_break: 1, ..Config::default() };
+        let (timer, _clock, info) = timer(config.clone());
+        timer.preload();
+
+        let view = info.lock().timer_view(false);
+        assert_eq!(view.time_text, "25:00");
+        assert_eq!(view.progress, 0.0);
+        assert_eq!(info.lock().rest, ActiveTimer::new(config.long_break_duration_secs()));
+    }
+
+    #[test]
+    fn picked_durations_start_a_fresh_cycle() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        clock.advance(60);
+        timer.tick();
+
+        timer.set_config(Config { work_duration: 50, short_break_duration: 10, ..Config::default() });
+        timer.restart_cycle();
+        {
+            let info = info.lock();
+            assert_eq!(info.current_state, TimerState::Idle);
+            assert_eq!(info.focus, ActiveTimer::new(50 * 60));
+            assert_eq!(info.rest, ActiveTimer::new(10 * 60));
+            assert!(info.history[0].abandoned);
+        }
+
+        block_on(timer.start_work());
+        run_to_end(&timer, &clock);
+        assert_eq!(info.lock().history[1].duration_secs, 50 * 60);
+    }
+
+    #[test]
+    fn follows_a_timer_elsewhere() {
+        let (timer, clock, info) = timer(Config::default());
+        block_on(timer.start_work());
+        clock.advance(120);
+        timer.tick();
+
+        // Within a couple of seconds is close enough
+        assert_eq!(timer.follow(TimerState::Working, true, ActiveTimer { remaining_secs: 23 * 60 - 2, total_secs: 25 * 60 }), None);
+        // Pausing there pauses here, without ending the session
+        let paused = ActiveTimer { remaining_secs: 22 * 60, total_secs: 25 * 60 };
+        assert_eq!(timer.follow(TimerState::WorkPaused, true, paused.clone()), Some(SessionEvent::Paused));
+        assert_eq!(info.lock().focus, paused);
+        assert!(info.lock().history.is_empty());
+
+        block_on(timer.resume());
+        clock.advance(60);
+        timer.tick();
+        let rest = ActiveTimer { remaining_secs: 4 * 60, total_secs: 5 * 60 };
+        assert_eq!(timer.follow(TimerState::ShortBreak, false, rest.clone()), Some(SessionEvent::Started(TimerState::ShortBreak)));
+        {
+            let info = info.lock();
+            assert_eq!(info.rest, rest);
+            assert!(info.history[0].abandoned);
+            assert_eq!(info.history[0].duration_secs, 3 * 60);
+        }
+
+        clock.advance(1);
+        assert_eq!(timer.tick(), vec![SessionEvent::Tick]);
+        assert_eq!(info.lock().rest.remaining_secs, 4 * 60 - 1);
+    }
 }
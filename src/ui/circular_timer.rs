@@ -1,38 +1,262 @@
 use gpui::*;
 use gpui::prelude::*;
 use std::time::Duration;
-use crate::state::SessionInfo;
+use crate::state::{CompletedTimer, TimerState, TimerView};
 use crate::app::PomodoroApp;
 use crate::theme::Theme;
+use crate::ui::label_input;
+use crate::ui::particles::Burst;
+use crate::ui::Tooltip;
+
+const FINAL_SECONDS: u32 = 10;  // The digits pulse for this long before a session ends
+const START_IN_MINUTES: u32 = 10;  // "Start in 10 min" in the ⋮ menu
+
+// Emoji offered for the label while it's edited
+const LABEL_ICONS: [&str; 8] = ["📚", "💻", "✍️", "📧", "🎨", "🧠", "📞", "🏃"];
+
+// Handler for one of a prompt's buttons
+type Answer = fn(&mut PomodoroApp, &mut Context<'_, PomodoroApp>);
+
+// A question across the top of the timer, answered with one of its buttons
+pub struct Prompt {
+    pub message: String,
+    pub choices: Vec<(&'static str, Answer)>,
+}
+
+// Focus handles for the controls reachable with Tab, in tab order
+#[derive(Clone)]
+pub struct ControlFocus {
+    pub focus_tab: FocusHandle,
+    pub rest_tab: FocusHandle,
+    pub preset_menu: FocusHandle,
+    pub label_edit: FocusHandle,
+    pub start_pause: FocusHandle,
+    pub stop: FocusHandle,
+    pub skip: FocusHandle,
+    pub settings: FocusHandle,
+}
+
+impl ControlFocus {
+    pub fn new(cx: &mut App) -> Self {
+        Self {
+            focus_tab: cx.focus_handle().tab_index(1).tab_stop(true),
+            rest_tab: cx.focus_handle().tab_index(2).tab_stop(true),
+            preset_menu: cx.focus_handle().tab_index(3).tab_stop(true),
+            label_edit: cx.focus_handle().tab_index(4).tab_stop(true),
+            start_pause: cx.focus_handle().tab_index(5).tab_stop(true),
+            stop: cx.focus_handle().tab_index(6).tab_stop(true),
+            skip: cx.focus_handle().tab_index(7).tab_stop(true),
+            settings: cx.focus_handle().tab_index(8).tab_stop(true),
+        }
+    }
+}
+
+// What the timer panel shows, gathered by the app at each render
+pub struct TimerProps {
+    pub timer: TimerView,
+    pub sessions_until_long_break: u32,
+    pub label_input: String,
+    pub label_caret: usize,  // Characters into the label being edited
+    pub is_editing_label: bool,
+    pub show_preset_menu: bool,
+    pub private: bool,  // Privacy mode, labels are already hidden in the timer view
+    pub notice: Option<String>,
+    pub prompt: Option<Prompt>,  // Such as asking before history_max_age_days first deletes anything
+    pub badges: Vec<String>,  // Set by plugins
+    pub meeting: Option<String>,  // "In a meeting until 11:00" while the calendar has one
+    pub alarm: Option<TimerState>,  // Finished session whose alarm rings until dismissed
+    pub workday: Option<String>,  // Time left until the hard stop, when one is set
+    pub templates: Vec<String>,  // Names of the configured templates and projects
+    pub template: Option<String>,  // The picked one
+    pub break_presets: Vec<(String, u32)>,  // Name and minutes of each configured break preset
+    pub break_preset: Option<String>,  // The one picked for the current break
+    pub quick_timers: Vec<(String, u32)>,  // Name and minutes of each configured quick timer
+    pub quick_timer: Option<String>,  // The one counting down: "Tea 02:14"
+    pub reduce_motion: bool,
+    pub confetti: Option<Burst>,  // Thrown while the celebration shows
+}
 
 pub struct CircularTimer {
-    session_info: SessionInfo,
-    label_input: String,
-    is_editing_label: bool,
-    show_preset_menu: bool,
+    props: TimerProps,
     view: Entity<PomodoroApp>,
+    focus: ControlFocus,
     theme: Theme,
 }
 
 impl CircularTimer {
-    pub fn new(
-        session_info: SessionInfo,
-        _sessions_until_long_break: u32,
-        _total_duration_secs: u32,
-        label_input: String,
-        is_editing_label: bool,
-        show_preset_menu: bool,
-        view: Entity<PomodoroApp>,
-        theme: Theme,
-    ) -> Self {
-        Self {
-            session_info,
-            label_input,
-            is_editing_label,
-            show_preset_menu,
-            view,
-            theme,
-        }
+    pub fn new(props: TimerProps, view: Entity<PomodoroApp>, focus: ControlFocus, theme: Theme) -> Self {
+        Self { props, view, focus, theme }
+    }
+
+    // Make a control reachable with Tab, show a focus ring, and activate it with Enter/Space
+    fn keyboard_control(
+        &self,
+        element: Stateful<Div>,
+        handle: &FocusHandle,
+        activate: impl Fn(&mut PomodoroApp, &mut Context<'_, PomodoroApp>) + 'static,
+    ) -> Stateful<Div> {
+        let view = self.view.clone();
+        let is_editing_label = self.props.is_editing_label;
+        let focus_ring = self.theme.focus_ring;
+
+        element
+            .track_focus(handle)
+            .border_1()
+            .border_color(transparent_black())
+            .focus(move |style| style.border_color(focus_ring))
+            .on_key_down(move |event, _window, cx| {
+                let key = event.keystroke.key.as_str();
+                // Space is left alone while typing a label, and Enter keeps the label
+                if key == "enter" && is_editing_label {
+                    cx.update_entity(&view, |app, cx| app.handle_done_label(cx));
+                    cx.stop_propagation();
+                } else if key == "enter" || key == "space" && !is_editing_label {
+                    cx.update_entity(&view, |app, cx| activate(app, cx));
+                    cx.stop_propagation();
+                }
+            })
+    }
+
+    // The config's own plan first, then each template; the picked one stands out
+    fn render_template_picker(&self) -> impl IntoElement {
+        let choices = std::iter::once((None, "Standard".to_string()))
+            .chain(self.props.templates.iter().map(|name| (Some(name.clone()), name.clone())));
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pt_1()
+            .mt_1()
+            .border_t_1()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child("Templates")
+            )
+            .children(choices.enumerate().map(|(index, (template, name))| {
+                let view = self.view.clone();
+                let picked = template == self.props.template;
+                div()
+                    .id(("template-button", index))
+                    .px_3()
+                    .py_1()
+                    .rounded(px(6.0))
+                    .bg(if picked { self.theme.foreground } else { self.theme.secondary })
+                    .text_color(if picked { self.theme.background } else { self.theme.secondary_foreground })
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .cursor_pointer()
+                    .hover(|style| style.opacity(0.8))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_pick_template(template.clone(), cx);
+                        });
+                    })
+                    .child(name)
+            }))
+    }
+
+    // Quick timers to start from the ⋮ menu: "Tea 3m"
+    fn render_quick_timer_picker(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pt_1()
+            .mt_1()
+            .border_t_1()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child("Quick timers")
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .gap_1()
+                    .children(self.props.quick_timers.iter().enumerate().map(|(index, (name, minutes))| {
+                        let view = self.view.clone();
+                        div()
+                            .id(("quick-timer-button", index))
+                            .px_2()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_start_quick_timer(index, cx);
+                                });
+                            })
+                            .child(format!("{} {}m", name, minutes))
+                    }))
+            )
+    }
+
+    // Its own lane under the timer, so it doesn't get mixed up with the session; click to cancel
+    fn render_quick_timer(&self, text: String) -> impl IntoElement {
+        let view = self.view.clone();
+        div()
+            .id("quick-timer")
+            .flex()
+            .flex_row()
+            .gap_1()
+            .px_2()
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(self.theme.border)
+            .text_size(px(10.0))
+            .text_color(self.theme.foreground)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                cx.update_entity(&view, |app, cx| {
+                    app.handle_cancel_quick_timer(cx);
+                });
+                cx.stop_propagation();
+            })
+            .child(text)
+            .child(div().text_color(self.theme.muted_foreground).child("×"))
+    }
+
+    // Break lengths to pick from while resting: "Walk 15m"
+    fn render_break_presets(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .flex_wrap()
+            .justify_center()
+            .gap_1()
+            .children(self.props.break_presets.iter().enumerate().map(|(index, (name, minutes))| {
+                let view = self.view.clone();
+                let picked = self.props.break_preset.as_ref() == Some(name);
+                div()
+                    .id(("break-preset-button", index))
+                    .px_2()
+                    .rounded(px(6.0))
+                    .bg(if picked { self.theme.foreground } else { self.theme.secondary })
+                    .text_color(if picked { self.theme.background } else { self.theme.secondary_foreground })
+                    .text_size(px(10.0))
+                    .cursor_pointer()
+                    .hover(|style| style.opacity(0.8))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_pick_break_preset(index, cx);
+                        });
+                    })
+                    .child(format!("{} {}m", name, minutes))
+            }))
     }
 
     fn render_preset_menu(&self) -> impl IntoElement {
@@ -59,6 +283,7 @@ impl CircularTimer {
                         preset_durations.into_iter().map(|minutes| {
                             let view_clone = view.clone();
                             div()
+                                .id(("preset-button", minutes as usize))
                                 .px_3()
                                 .py_1()
                                 .rounded(px(6.0))
@@ -76,9 +301,293 @@ impl CircularTimer {
                                 .child(format!("{} min", minutes))
                         })
                     )
+                    .when(self.props.timer.current_state == TimerState::Idle, |menu| {
+                        let view_clone = view.clone();
+                        menu.child(
+                            div()
+                                .id("start-in-menu-button")
+                                .px_3()
+                                .py_1()
+                                .rounded(px(6.0))
+                                .bg(self.theme.secondary)
+                                .text_color(self.theme.secondary_foreground)
+                                .text_xs()
+                                .font_weight(FontWeight::MEDIUM)
+                                .cursor_pointer()
+                                .hover(|style| style.opacity(0.8))
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view_clone, |app, cx| {
+                                        app.handle_start_in(START_IN_MINUTES, cx);
+                                    });
+                                })
+                                .child(format!("Start in {} min", START_IN_MINUTES)),
+                        )
+                    })
+                    .when(self.props.timer.current_state != TimerState::Idle, |menu| {
+                        let view_clone = view.clone();
+                        menu.child(
+                            div()
+                                .id("switch-menu-button")
+                                .px_3()
+                                .py_1()
+                                .rounded(px(6.0))
+                                .bg(self.theme.secondary)
+                                .text_color(self.theme.secondary_foreground)
+                                .text_xs()
+                                .font_weight(FontWeight::MEDIUM)
+                                .cursor_pointer()
+                                .hover(|style| style.opacity(0.8))
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view_clone, |app, cx| {
+                                        app.handle_switch_session(cx);
+                                    });
+                                })
+                                .child(if self.props.timer.current_state.is_work() { "Switch to break" } else { "Switch to work" }),
+                        )
+                    })
+                    .child({
+                        let view_clone = view.clone();
+                        div()
+                            .id("stats-menu-button")
+                            .px_3()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_clone, |app, cx| {
+                                    app.handle_toggle_stats(cx);
+                                });
+                            })
+                            .child("Today")
+                    })
+                    .child({
+                        let view_clone = view.clone();
+                        div()
+                            .id("history-menu-button")
+                            .px_3()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_clone, |app, cx| {
+                                    app.handle_toggle_history(cx);
+                                });
+                            })
+                            .child("History")
+                    })
+                    .child({
+                        let queued = self.props.timer.queued_sessions;
+                        div()
+                            .id("queue-menu-button")
+                            .px_3()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_toggle_queue(cx);
+                                });
+                            })
+                            .child(format!("Queue ({})", queued))
+                    })
+                    .child({
+                        let view_clone = self.view.clone();
+                        div()
+                            .id("privacy-menu-button")
+                            .px_3()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_clone, |app, cx| {
+                                    app.handle_toggle_privacy(cx);
+                                });
+                            })
+                            .child(if self.props.private { "Show labels" } else { "Hide labels" })
+                    })
+                    .child({
+                        let view_clone = self.view.clone();
+                        div()
+                            .id("present-menu-button")
+                            .px_3()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(self.theme.secondary)
+                            .text_color(self.theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_clone, |app, cx| {
+                                    app.handle_present(cx);
+                                });
+                            })
+                            .child("Present")
+                    })
+                    .when(!self.props.quick_timers.is_empty(), |menu| menu.child(self.render_quick_timer_picker()))
+                    .when(!self.props.templates.is_empty(), |menu| menu.child(self.render_template_picker()))
+            )
+    }
+
+    // Covers the timer while the alarm rings, so it can't be missed
+    fn render_alarm(&self, finished: &TimerState) -> impl IntoElement {
+        let view = self.view.clone();
+        let (title, message) = match finished {
+            TimerState::Working => ("Work session complete", "Time for a break"),
+            TimerState::LongBreak => ("Long break over", "A new cycle is ready to start"),
+            _ => ("Break over", "Ready to focus again?"),
+        };
+
+        div()
+            .id("alarm-banner")
+            .absolute()
+            .inset_0()
+            .flex()
+            .flex_col()
+            .items_center()
+            .justify_center()
+            .gap_2()
+            .rounded(px(16.0))
+            .bg(self.theme.session_color(finished.display_name()))
+            .text_color(gpui::white())
+            // Clicks must not reach the timer controls underneath
+            .on_mouse_down(MouseButton::Left, |_event, _window, cx| cx.stop_propagation())
+            .child(
+                div()
+                    .text_size(px(22.0))
+                    .font_weight(FontWeight::BOLD)
+                    .child(title)
+            )
+            .child(div().text_sm().child(message))
+            .child(
+                div()
+                    .id("dismiss-alarm-button")
+                    .px_4()
+                    .py_1()
+                    .rounded(px(8.0))
+                    .bg(gpui::white())
+                    .text_color(self.theme.session_color(finished.display_name()))
+                    .text_sm()
+                    .font_weight(FontWeight::BOLD)
+                    .cursor_pointer()
+                    .hover(|style| style.opacity(0.8))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_dismiss_alarm(cx);
+                        });
+                        cx.stop_propagation();
+                    })
+                    .child("Dismiss")
+            )
+    }
+
+    fn render_notice(&self, message: String) -> impl IntoElement {
+        let view = self.view.clone();
+
+        // Banner across the top, dismissed by clicking it
+        div()
+            .id("notice-banner")
+            .absolute()
+            .top(px(6.0))
+            .left(px(6.0))
+            .right(px(6.0))
+            .flex()
+            .flex_row()
+            .items_start()
+            .gap_1()
+            .px_2()
+            .py_1()
+            .rounded(px(8.0))
+            .bg(self.theme.warning_background)
+            .text_color(self.theme.warning_foreground)
+            .text_size(px(11.0))
+            .shadow_sm()
+            .cursor_pointer()
+            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                cx.update_entity(&view, |app, cx| {
+                    app.handle_dismiss_notice(cx);
+                });
+                cx.stop_propagation();
+            })
+            .child(div().flex_1().child(message))
+            .child("×")
+    }
+
+    // Banner across the top with a choice, instead of dismissing on click
+    fn render_prompt(&self, prompt: &Prompt) -> impl IntoElement {
+        div()
+            .absolute()
+            .top(px(6.0))
+            .left(px(6.0))
+            .right(px(6.0))
+            .flex()
+            .flex_col()
+            .gap_1()
+            .px_2()
+            .py_1()
+            .rounded(px(8.0))
+            .bg(self.theme.warning_background)
+            .text_color(self.theme.warning_foreground)
+            .text_size(px(11.0))
+            .shadow_sm()
+            .child(prompt.message.clone())
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_end()
+                    .gap_1()
+                    .children(prompt.choices.iter().enumerate().map(|(index, &(text, answer))| {
+                        let view = self.view.clone();
+                        div()
+                            .id(("prompt-choice", index))
+                            .px_2()
+                            .rounded(px(6.0))
+                            .bg(self.theme.warning_foreground)
+                            .text_color(self.theme.warning_background)
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .child(text)
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, answer);
+                                cx.stop_propagation();
+                            })
+                    }))
             )
     }
 
+    // Color of the session under way, or of the one Start begins: red for
+    // work, green for short breaks, blue for long breaks
+    fn accent(&self) -> Hsla {
+        match self.props.timer.current_state {
+            TimerState::Idle if self.props.timer.is_focus_mode => self.theme.work,
+            TimerState::Idle => self.theme.short_break,
+            ref state => self.theme.session_color(state.display_name()),
+        }
+    }
+
     fn render_active_timer(&self) -> impl IntoElement {
         div()
             .flex()
@@ -89,20 +598,164 @@ impl CircularTimer {
             // Focus/Rest tabs at top
             .child(self.render_tabs())
             // Compact time display
+            .child(self.render_countdown())
+            // Wall-clock finish, gone while paused and moved on by the pause at resume
+            .when_some(self.props.timer.ends_at.clone(), |d, ends_at| {
+                d.child(
+                    div()
+                        .text_size(px(10.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child(format!("ends {}", ends_at))
+                )
+            })
+            // Time left in the session, full while idle
             .child(
                 div()
-                    .text_size(px(48.0))
-                    .font_weight(FontWeight::BOLD)
-                    .text_color(self.theme.foreground)
-                    .child(self.session_info.format_time())
+                    .w(px(160.0))
+                    .h(px(4.0))
+                    .rounded(px(2.0))
+                    .bg(self.theme.border)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(relative(1.0 - self.props.timer.progress))
+                            .rounded(px(2.0))
+                            .bg(self.accent())
+                    )
             )
+            .when_some(self.props.meeting.clone(), |d, meeting| {
+                d.child(
+                    div()
+                        .px_2()
+                        .rounded(px(6.0))
+                        .bg(self.theme.secondary)
+                        .text_size(px(10.0))
+                        .text_color(self.theme.secondary_foreground)
+                        .child(meeting)
+                )
+            })
+            .when(!self.props.timer.is_focus_mode && !self.props.break_presets.is_empty(), |d| {
+                d.child(self.render_break_presets())
+            })
+            .when_some(self.props.quick_timer.clone(), |d, text| d.child(self.render_quick_timer(text)))
+            .when_some(self.props.workday.clone(), |d, workday| {
+                d.child(
+                    div()
+                        .text_size(px(10.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child(workday)
+                )
+            })
+            // Plugin badges, e.g. "2 unread"
+            .when(!self.props.badges.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(px(10.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child(self.props.badges.join(" · "))
+                )
+            })
             // Label in center (editable)
             .child(self.render_label_field())
             // Control buttons at bottom
             .child(self.render_control_buttons())
     }
 
+    // The digits pulse once a second through the final seconds, unless motion is turned down
+    fn render_countdown(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .when(!self.props.timer.current_icon.is_empty(), |d| {
+                d.child(div().text_size(px(32.0)).child(self.props.timer.current_icon.clone()))
+            })
+            .child(self.render_digits())
+    }
+
+    // Shown while hovering the digits: what the session is, where it is in the
+    // cycle and when it runs
+    fn session_details(&self) -> Vec<String> {
+        let timer = &self.props.timer;
+        let mut lines = vec![timer.current_state.display_name().to_string()];
+        if !timer.current_label.is_empty() {
+            lines.push(format!("{} {}", timer.current_icon, timer.current_label).trim_start().to_string());
+        }
+        lines.push(format!("Session {} of {}", timer.current_session, self.props.sessions_until_long_break));
+        if let Some(started_at) = &timer.started_at {
+            lines.push(format!("Started {}", started_at));
+        }
+        match &timer.ends_at {
+            Some(ends_at) => lines.push(format!("Ends {}", ends_at)),
+            None if timer.current_state != TimerState::Idle => lines.push("Paused, no end yet".to_string()),
+            None => {}
+        }
+        lines
+    }
+
+    fn render_digits(&self) -> AnyElement {
+        let digits = div()
+            .id("countdown")
+            .text_size(px(48.0))
+            .font_weight(FontWeight::BOLD)
+            // Dimmed while an armed session waits for its time
+            .text_color(if self.props.timer.starts_at.is_some() { self.theme.muted_foreground } else { self.theme.foreground })
+            .child(self.props.timer.time_text.clone())
+            .tooltip(Tooltip::text(self.session_details(), &self.theme));
+        let final_seconds = self.props.timer.current_state.is_running() && (1..=FINAL_SECONDS).contains(&self.props.timer.remaining_secs);
+        if !final_seconds || self.props.reduce_motion {
+            return digits.into_any_element();
+        }
+        digits
+            .with_animation(
+                "final-seconds-pulse",
+                Animation::new(Duration::from_secs(1)).repeat().with_easing(ease_in_out),
+                |digits, delta| {
+                    let swell = (delta * std::f32::consts::PI).sin();  // 0.0 to 1.0 and back
+                    digits.text_size(px(48.0 + 6.0 * swell)).opacity(1.0 - 0.4 * swell)
+                },
+            )
+            .into_any_element()
+    }
+
+    // Row of emoji to put before the label
+    fn render_icon_picker(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .gap_1()
+            .children(LABEL_ICONS.iter().enumerate().map(|(index, &icon)| {
+                let view = self.view.clone();
+                div()
+                    .id(("label-icon", index))
+                    .px_1()
+                    .rounded(px(4.0))
+                    .text_size(px(14.0))
+                    .cursor_pointer()
+                    .when(self.props.timer.current_icon == icon, |d| d.bg(self.theme.secondary))
+                    .hover(|style| style.bg(self.theme.muted_background))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_pick_icon(icon, cx);
+                        });
+                        cx.stop_propagation();
+                    })
+                    .child(icon)
+            }))
+    }
+
     fn render_label_field(&self) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_1()
+            .child(self.render_label_row())
+            .when(self.props.is_editing_label, |d| d.child(self.render_icon_picker()))
+    }
+
+    fn render_label_row(&self) -> impl IntoElement {
         let view = self.view.clone();
 
         div()
@@ -113,40 +766,40 @@ impl CircularTimer {
             .child(
                 // Label display/input
                 div()
+                    .id("label-field")
                     .px_2()
                     .py_1()
                     .rounded(px(6.0))
                     .min_w(px(120.0))
-                    .when(self.is_editing_label, |d| {
+                    .when(self.props.is_editing_label, |d| {
                         d.bg(rgb(0xeff6ff))
                            .border_1()
                            .border_color(rgb(0x3b82f6))
                     })
-                    .when(!self.is_editing_label, |d| {
+                    .when(!self.props.is_editing_label, |d| {
                         d.bg(self.theme.muted_background)
                     })
                     .text_size(px(13.0))
                     .text_color(self.theme.muted_foreground)
                     .text_align(TextAlign::Center)
                     .child(
-                        if self.is_editing_label {
-                            if self.label_input.is_empty() {
-                                "|".to_string()
-                            } else {
-                                format!("{}|", self.label_input)
-                            }
-                        } else if self.session_info.current_label.is_empty() {
+                        if self.props.is_editing_label {
+                            label_input::with_caret(&self.props.label_input, self.props.label_caret)
+                        } else if self.props.timer.current_label.is_empty() && !self.props.timer.suggested_label.is_empty() {
+                            format!("{}?", self.props.timer.suggested_label)
+                        } else if self.props.timer.current_label.is_empty() {
                             "Add label...".to_string()
                         } else {
-                            self.session_info.current_label.clone()
+                            self.props.timer.current_label.clone()
                         }
                     )
             )
             .child(
                 // Edit/Done button
-                if self.is_editing_label {
+                if self.props.is_editing_label {
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("label-done-button")
                         .flex()
                         .items_center()
                         .justify_center()
@@ -162,10 +815,12 @@ impl CircularTimer {
                                 app.handle_done_label(cx);
                             });
                         })
-                        .child("✓")
+                        .child("✓");
+                    self.keyboard_control(button, &self.focus.label_edit, |app, cx| app.handle_done_label(cx))
                 } else {
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("label-edit-button")
                         .flex()
                         .items_center()
                         .justify_center()
@@ -181,13 +836,14 @@ impl CircularTimer {
                                 app.handle_edit_label(cx);
                             });
                         })
-                        .child("✎")
+                        .child("✎");
+                    self.keyboard_control(button, &self.focus.label_edit, |app, cx| app.handle_edit_label(cx))
                 }
             )
     }
 
     fn render_tabs(&self) -> impl IntoElement {
-        let is_work = self.session_info.is_focus_mode;
+        let is_work = self.props.timer.is_focus_mode;
         let view = self.view.clone();
 
         div()
@@ -202,7 +858,8 @@ impl CircularTimer {
                 // Focus tab
                 {
                     let view_clone = view.clone();
-                    div()
+                    let tab = div()
+                        .id("focus-tab")
                         .px_3()
                         .py_1()
                         .rounded(px(8.0))
@@ -220,14 +877,16 @@ impl CircularTimer {
                                 app.handle_switch_to_focus(cx);
                             });
                         })
-                        .child("Focus")
+                        .child("Focus");
+                    self.keyboard_control(tab, &self.focus.focus_tab, |app, cx| app.handle_switch_to_focus(cx))
                 }
             )
             .child(
                 // Rest tab
                 {
                     let view_clone = view.clone();
-                    div()
+                    let tab = div()
+                        .id("rest-tab")
                         .px_3()
                         .py_1()
                         .rounded(px(8.0))
@@ -245,14 +904,16 @@ impl CircularTimer {
                                 app.handle_switch_to_rest(cx);
                             });
                         })
-                        .child("Rest")
+                        .child("Rest");
+                    self.keyboard_control(tab, &self.focus.rest_tab, |app, cx| app.handle_switch_to_rest(cx))
                 }
             )
             // Handle icon for preset timers (only in focus mode)
             .when(is_work, |d| {
                 d.child({
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("preset-menu-button")
                         .size(px(20.0))
                         .flex()
                         .items_center()
@@ -268,14 +929,38 @@ impl CircularTimer {
                                 app.handle_toggle_preset_menu(cx);
                             });
                         })
-                        .child("⋮")
+                        .child("⋮");
+                    self.keyboard_control(button, &self.focus.preset_menu, |app, cx| app.handle_toggle_preset_menu(cx))
                 })
             })
+            // Settings button
+            .child({
+                let view_clone = view.clone();
+                let button = div()
+                    .id("settings-button")
+                    .size(px(20.0))
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .rounded(px(6.0))
+                    .bg(self.theme.secondary)
+                    .text_color(self.theme.secondary_foreground)
+                    .text_xs()
+                    .cursor_pointer()
+                    .hover(|style| style.opacity(0.8))
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view_clone, |app, cx| {
+                            app.handle_toggle_settings(cx);
+                        });
+                    })
+                    .child("⚙");
+                self.keyboard_control(button, &self.focus.settings, |app, cx| app.handle_toggle_settings(cx))
+            })
     }
 
 
     fn render_control_buttons(&self) -> impl IntoElement {
-        let is_running = self.session_info.current_state.is_running();
+        let is_running = self.props.timer.current_state.is_running();
         let view = self.view.clone();
 
         div()
@@ -288,7 +973,35 @@ impl CircularTimer {
                 {
                     let button_text = if is_running { "Pause" } else { "Start" };
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("start-pause-button")
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .px_4()
+                        .py_1()
+                        .rounded(px(6.0))
+                        .bg(self.accent())
+                        .text_color(white())
+                        .text_xs()
+                        .font_weight(FontWeight::MEDIUM)
+                        .cursor_pointer()
+                        .hover(|style| style.opacity(0.8))
+                        .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                            cx.update_entity(&view_clone, |app, cx| {
+                                app.handle_toggle(cx);
+                            });
+                        })
+                        .child(button_text);
+                    self.keyboard_control(button, &self.focus.start_pause, |app, cx| app.handle_toggle(cx))
+                }
+            )
+            .child(
+                // Stop button
+                {
+                    let view_clone = view.clone();
+                    let button = div()
+                        .id("stop-button")
                         .flex()
                         .items_center()
                         .justify_center()
@@ -303,17 +1016,19 @@ impl CircularTimer {
                         .hover(|style| style.opacity(0.8))
                         .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
                             cx.update_entity(&view_clone, |app, cx| {
-                                app.handle_toggle(cx);
+                                app.handle_stop(cx);
                             });
                         })
-                        .child(button_text)
+                        .child("Stop");
+                    self.keyboard_control(button, &self.focus.stop, |app, cx| app.handle_stop(cx))
                 }
             )
             .child(
-                // Reset button
+                // Skip button
                 {
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("skip-button")
                         .flex()
                         .items_center()
                         .justify_center()
@@ -328,17 +1043,53 @@ impl CircularTimer {
                         .hover(|style| style.opacity(0.8))
                         .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
                             cx.update_entity(&view_clone, |app, cx| {
-                                app.handle_reset(cx);
+                                app.handle_skip(cx);
                             });
                         })
-                        .child("Reset")
+                        .child("Skip");
+                    self.keyboard_control(button, &self.focus.skip, |app, cx| app.handle_skip(cx))
                 }
             )
     }
 
 
-    fn render_idle_state(&self) -> impl IntoElement {
+    fn render_history_button(
+        &self,
+        id: &'static str,
+        text: &'static str,
+        handler: impl Fn(&mut PomodoroApp, &mut Context<PomodoroApp>) + Clone + 'static,
+    ) -> Stateful<Div> {
         let view = self.view.clone();
+        let on_click = handler.clone();
+
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                cx.update_entity(&view, |app, cx| on_click(app, cx));
+            })
+            .child(text)
+    }
+
+    // Past session shown while browsing history, with a way to pick the task back up
+    fn render_history_entry(&self, index: usize, total: usize, entry: &CompletedTimer) -> impl IntoElement {
+        let label = if entry.label.is_empty() { "Untitled".to_string() } else { entry.label.clone() };
+        let completed_at = entry.completed_at.with_timezone(&chrono::Local).format("%b %d, %H:%M");
+
+        let continue_button = self.render_history_button("history-continue-button", "Continue", |app, cx| {
+            app.handle_continue_from_history(cx)
+        });
 
         div()
             .flex()
@@ -346,43 +1097,145 @@ impl CircularTimer {
             .items_center()
             .gap_2()
             .w_full()
-            // Focus/Rest tabs at top
-            .child(self.render_tabs())
-            // Compact time display
             .child(
                 div()
-                    .text_size(px(48.0))
+                    .text_xs()
+                    .text_color(self.theme.muted_foreground)
+                    .child(format!("History {}/{}", index + 1, total))
+            )
+            .child(
+                div()
+                    .text_size(px(20.0))
                     .font_weight(FontWeight::BOLD)
                     .text_color(self.theme.foreground)
-                    .child(self.session_info.format_time())
+                    .child(label)
             )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted_foreground)
+                    .child(format!(
+                        "{}{} · {} min{} · {}",
+                        entry.session_type,
+                        entry.break_preset.as_ref().map(|preset| format!(" ({})", preset)).unwrap_or_default(),
+                        entry.duration_secs.div_ceil(60),
+                        if entry.abandoned { " (abandoned)" } else if entry.switched { " (switched)" } else { "" },
+                        completed_at
+                    ))
+            )
+            // When it ran, for a session that was paused
+            .when(entry.segments.len() > 1, |d| {
+                let runs: Vec<String> = entry
+                    .segments
+                    .iter()
+                    .map(|segment| {
+                        let time = |at: chrono::DateTime<chrono::Utc>| at.with_timezone(&chrono::Local).format("%H:%M");
+                        format!("{}–{}", time(segment.start), time(segment.end))
+                    })
+                    .collect();
+                d.child(div().text_xs().text_color(self.theme.muted_foreground).child(format!("Ran {}", runs.join(", "))))
+            })
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(self.render_history_button("history-search-button", "Search", |app, cx| {
+                        app.handle_toggle_history(cx)
+                    }))
+                    .child(self.keyboard_control(continue_button, &self.focus.start_pause, |app, cx| {
+                        app.handle_continue_from_history(cx)
+                    }))
+                    .child(self.render_history_button("history-exit-button", "Back", |app, cx| {
+                        app.handle_exit_history(cx)
+                    }))
+            )
+    }
+
+    fn render_idle_state(&self) -> impl IntoElement {
+        let view = self.view.clone();
+
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_2()
+            .w_full()
+            // Focus/Rest tabs at top
+            .child(self.render_tabs())
+            // Compact time display
+            .child(self.render_countdown())
             // Label in center (editable)
             .child(self.render_label_field())
-            // Start button
-            .child(
+            // Start button, or when the next session is armed, when it starts
+            .when_some(self.props.timer.starts_at.clone(), |d, starts_at| d.child(self.render_armed(starts_at)))
+            .when(self.props.timer.starts_at.is_none(), |d| d.child(
                 {
                     let view_clone = view.clone();
-                    div()
+                    let button = div()
+                        .id("start-button")
                         .flex()
                         .items_center()
                         .justify_center()
                         .px_4()
                         .py_1()
                         .rounded(px(6.0))
-                        .bg(rgb(0xe5e7eb))
-                        .text_color(rgb(0x374151))
+                        .bg(self.accent())
+                        .text_color(white())
                         .text_xs()
                         .font_weight(FontWeight::MEDIUM)
                         .cursor_pointer()
-                        .hover(|style| style.bg(rgb(0xd1d5db)))
+                        .hover(|style| style.opacity(0.8))
                         .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
                             cx.update_entity(&view_clone, |app, cx| {
                                 app.handle_toggle(cx);
                             });
                         })
-                        .child("Start")
+                        .child("Start");
+                    self.keyboard_control(button, &self.focus.start_pause, |app, cx| app.handle_toggle(cx))
                 }
+            ))
+    }
+
+    // "Starting at 14:30" with a way to call it off
+    fn render_armed(&self, starts_at: String) -> impl IntoElement {
+        let view = self.view.clone();
+        let cancel = div()
+            .id("cancel-start-button")
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                cx.update_entity(&view, |app, cx| app.handle_cancel_start(cx));
+                cx.stop_propagation();
+            })
+            .child("Cancel");
+
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(self.accent())
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(self.accent())
+                    .child(format!("Starting at {}", starts_at)),
             )
+            .child(self.keyboard_control(cancel, &self.focus.start_pause, |app, cx| app.handle_cancel_start(cx)))
     }
 }
 
@@ -390,9 +1243,9 @@ impl IntoElement for CircularTimer {
     type Element = Div;
 
     fn into_element(self) -> Self::Element {
-        let is_idle = matches!(self.session_info.current_state, crate::state::TimerState::Idle);
-        let show_celebration = self.session_info.show_celebration;
-        let show_preset_menu = self.show_preset_menu;
+        let is_idle = matches!(self.props.timer.current_state, crate::state::TimerState::Idle);
+        let show_celebration = self.props.timer.show_celebration;
+        let show_preset_menu = self.props.show_preset_menu;
         let view = self.view.clone();
 
         // Build the main content element
@@ -407,11 +1260,13 @@ impl IntoElement for CircularTimer {
             .bg(self.theme.background)
             .rounded(px(16.0))  // Smaller rounded corners
             .border_2()
-            .border_color(self.theme.border)
+            .border_color(if is_idle { self.theme.border } else { self.accent() })
             .relative();  // Enable absolute positioning for menu
 
         // Add children based on state
-        if is_idle {
+        if let Some((index, total, entry)) = &self.props.timer.history_entry {
+            base_div = base_div.child(self.render_history_entry(*index, *total, entry));
+        } else if is_idle {
             base_div = base_div.child(self.render_idle_state());
         } else {
             base_div = base_div.child(self.render_active_timer());
@@ -422,9 +1277,30 @@ impl IntoElement for CircularTimer {
             base_div = base_div.child(self.render_preset_menu());
         }
 
+        // Startup notices (config warnings) sit on top of everything
+        if let Some(message) = self.props.notice.clone() {
+            base_div = base_div.child(self.render_notice(message));
+        } else if let Some(prompt) = &self.props.prompt {
+            base_div = base_div.child(self.render_prompt(prompt));
+        }
+
+        // ...except a ringing alarm
+        if let Some(finished) = &self.props.alarm {
+            base_div = base_div.child(self.render_alarm(finished));
+        }
+
+        if let Some(confetti) = self.props.confetti.as_ref().filter(|_| show_celebration) {
+            base_div = base_div.child(confetti.render());
+        }
+
+        // With motion turned down the celebration tint holds still
+        if show_celebration && self.props.reduce_motion {
+            base_div = base_div.bg(rgba(0xff000033));
+        }
+
         // Wrap in a container with mouse handler and click-outside detection
         // Apply breathing animation conditionally
-        if show_celebration {
+        if show_celebration && !self.props.reduce_motion {
             let view_for_mouse = view.clone();
             let view_for_click = view.clone();
 
@@ -0,0 +1,207 @@
+use anyhow::{Context as _, Result};
+use gpui::*;
+use gpui::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::app::{ActivePanel, PomodoroApp};
+use crate::persistence::Persistence;
+
+const FILE: &str = "windows.json";
+
+// Where a detached panel's window was, in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedBounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl SavedBounds {
+    pub fn from_bounds(bounds: Bounds<Pixels>) -> Self {
+        Self {
+            x: f32::from(bounds.origin.x),
+            y: f32::from(bounds.origin.y),
+            width: f32::from(bounds.size.width),
+            height: f32::from(bounds.size.height),
+        }
+    }
+
+    pub fn to_bounds(self) -> Bounds<Pixels> {
+        Bounds::new(point(px(self.x), px(self.y)), size(px(self.width), px(self.height)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedWindow {
+    pub bounds: Option<SavedBounds>,  // None until it's been seen on screen
+    pub open: bool,  // Open when the app last quit, so it opens again at launch
+}
+
+// The panels popped out of the timer into windows of their own, kept in
+// windows.json in the data directory. A panel listed here opens in its window
+// from then on, until it's docked again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowLayout {
+    #[serde(default)]
+    windows: BTreeMap<String, SavedWindow>,
+}
+
+impl WindowLayout {
+    pub fn load() -> Result<Self> {
+        let path = Persistence::data_dir()?.join(FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", FILE))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", FILE))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize the window layout")?;
+        std::fs::create_dir_all(Persistence::data_dir()?).context("Failed to create data directory")?;
+        std::fs::write(Persistence::data_dir()?.join(FILE), content).with_context(|| format!("Failed to write {}", FILE))
+    }
+
+    pub fn is_detached(&self, panel: ActivePanel) -> bool {
+        panel_key(panel).is_some_and(|key| self.windows.contains_key(key))
+    }
+
+    pub fn window(&self, panel: ActivePanel) -> Option<&SavedWindow> {
+        self.windows.get(panel_key(panel)?)
+    }
+
+    // Panels whose windows were open at the last quit
+    pub fn open_panels(&self) -> Vec<ActivePanel> {
+        DETACHABLE.into_iter().filter(|&panel| self.window(panel).is_some_and(|window| window.open)).collect()
+    }
+
+    // Detach the panel if it isn't yet, and note whether its window is open
+    pub fn set_open(&mut self, panel: ActivePanel, open: bool) {
+        if let Some(key) = panel_key(panel) {
+            self.windows.entry(key.to_string()).or_default().open = open;
+        }
+    }
+
+    pub fn moved(&mut self, panel: ActivePanel, bounds: SavedBounds) {
+        if let Some(window) = panel_key(panel).and_then(|key| self.windows.get_mut(key)) {
+            window.bounds = Some(bounds);
+        }
+    }
+
+    // Back into the timer window; where it was is forgotten
+    pub fn dock(&mut self, panel: ActivePanel) {
+        if let Some(key) = panel_key(panel) {
+            self.windows.remove(key);
+        }
+    }
+}
+
+// Panels that can have a window of their own
+const DETACHABLE: [ActivePanel; 2] = [ActivePanel::Stats, ActivePanel::Settings];
+
+fn panel_key(panel: ActivePanel) -> Option<&'static str> {
+    match panel {
+        ActivePanel::Stats => Some("stats"),
+        ActivePanel::Settings => Some("settings"),
+        _ => None,
+    }
+}
+
+// A panel shown in a window of its own, drawn by the app like in the timer
+// window. Escape closes it.
+pub struct DetachedPanel {
+    app: Entity<PomodoroApp>,
+    panel: ActivePanel,
+    focus_handle: FocusHandle,
+    _redraw: Subscription,
+    _track_bounds: Subscription,
+}
+
+impl DetachedPanel {
+    pub fn new(app: Entity<PomodoroApp>, panel: ActivePanel, window: &mut Window, cx: &mut Context<'_, Self>) -> Self {
+        // Redraw along with the timer window
+        let redraw = cx.observe(&app, |_this, _app, cx| cx.notify());
+        let track_bounds = cx.observe_window_bounds(window, move |this, window, cx| {
+            let bounds = SavedBounds::from_bounds(window.bounds());
+            this.app.update(cx, |app, cx| app.handle_detached_moved(panel, bounds, cx));
+        });
+        // Closed from the title bar: it stays detached, but doesn't open at the next launch
+        let closing = app.downgrade();
+        window.on_window_should_close(cx, move |_window, cx| {
+            let _ = closing.update(cx, |app, cx| app.handle_detached_closed(panel, cx));
+            true
+        });
+        Self { app, panel, focus_handle: cx.focus_handle(), _redraw: redraw, _track_bounds: track_bounds }
+    }
+}
+
+impl Render for DetachedPanel {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        if window.focused(cx).is_none() {
+            self.focus_handle.focus(window);
+        }
+        let panel = self.panel;
+        let app = self.app.clone();
+        let typing = self.app.read(cx).is_typing_in(panel);
+        let content = self.app.update(cx, |app, cx| app.render_detached(panel, window, cx));
+
+        div()
+            .size_full()
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(move |this, event: &KeyDownEvent, _window, cx| {
+                let keystroke = &event.keystroke;
+                if keystroke.modifiers.platform || keystroke.modifiers.control {
+                    return;
+                }
+                let key = keystroke.key.clone();
+                this.app.update(cx, |app, cx| app.handle_detached_key(panel, &key, cx));
+            }))
+            // Typed text goes to the day's note through the platform's input handling
+            .when(typing, |d| {
+                d.child(
+                    canvas(
+                        |_bounds, _window, _cx| {},
+                        move |bounds, _, window, cx| {
+                            if let Some(focused) = window.focused(cx) {
+                                window.handle_input(&focused, ElementInputHandler::new(bounds, app), cx);
+                            }
+                        },
+                    )
+                    .absolute()
+                    .size_full(),
+                )
+            })
+            .child(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Not a glob: gpui has a test attribute of its own
+    use super::{ActivePanel, SavedBounds, WindowLayout};
+
+    #[test]
+    fn remembers_detached_panels() {
+        let mut layout = WindowLayout::default();
+        assert!(!layout.is_detached(ActivePanel::Stats));
+        layout.set_open(ActivePanel::Stats, true);
+        layout.set_open(ActivePanel::Settings, true);
+        let bounds = SavedBounds { x: 40.0, y: 60.0, width: 320.0, height: 420.0 };
+        layout.moved(ActivePanel::Stats, bounds);
+        // Closed, but still detached and where it was
+        layout.set_open(ActivePanel::Settings, false);
+        // Only the detachable ones
+        layout.set_open(ActivePanel::History, true);
+
+        let saved: WindowLayout = serde_json::from_str(&serde_json::to_string(&layout).unwrap()).unwrap();
+        assert_eq!(saved, layout);
+        assert_eq!(saved.open_panels(), [ActivePanel::Stats]);
+        assert!(saved.is_detached(ActivePanel::Settings));
+        assert_eq!(saved.window(ActivePanel::Stats).and_then(|window| window.bounds), Some(bounds));
+
+        layout.dock(ActivePanel::Stats);
+        assert!(!layout.is_detached(ActivePanel::Stats));
+    }
+}
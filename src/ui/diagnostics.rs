@@ -0,0 +1,111 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::PomodoroApp;
+use crate::theme::Theme;
+
+// The newest log lines, to copy into a bug report
+pub struct DiagnosticsPanel {
+    lines: Vec<String>,
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+impl DiagnosticsPanel {
+    pub fn new(lines: Vec<String>, view: Entity<PomodoroApp>, theme: Theme) -> Self {
+        Self { lines, view, theme }
+    }
+
+    fn render_button(&self, id: &'static str, text: &'static str) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .min_w(px(20.0))
+            .px_2()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text)
+    }
+}
+
+impl IntoElement for DiagnosticsPanel {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view_copy = self.view.clone();
+        let view_back = self.view.clone();
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .gap_1()
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(self.theme.foreground)
+                            .child("Diagnostics")
+                    )
+                    .when(!self.lines.is_empty(), |d| {
+                        d.child(
+                            self.render_button("diagnostics-copy-button", "Copy")
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view_copy, |app, cx| {
+                                        app.handle_copy_diagnostics(cx);
+                                    });
+                                })
+                        )
+                    })
+                    .child(
+                        self.render_button("diagnostics-back-button", "Back")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_back, |app, cx| {
+                                    app.handle_toggle_diagnostics(cx);
+                                });
+                            })
+                    )
+            )
+            .when(self.lines.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child("Nothing logged since the app started.")
+                )
+            })
+            // Newest at the top, where it's seen without scrolling
+            .child(
+                div()
+                    .id("diagnostics-log")
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .children(self.lines.into_iter().rev().map(|line| div().child(line)))
+            )
+    }
+}
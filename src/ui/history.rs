@@ -0,0 +1,246 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::PomodoroApp;
+use crate::state::{CompletedTimer, HistoryFilter};
+use crate::theme::Theme;
+
+// Rows shown at once; the list scrolls with the selection
+const VISIBLE_RESULTS: usize = 5;
+
+// Searchable list of past sessions
+pub struct HistoryPanel {
+    results: Vec<(usize, CompletedTimer)>,
+    total: usize,
+    filter: HistoryFilter,
+    selected: usize,
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+impl HistoryPanel {
+    pub fn new(
+        results: Vec<(usize, CompletedTimer)>,
+        total: usize,
+        filter: HistoryFilter,
+        selected: usize,
+        view: Entity<PomodoroApp>,
+        theme: Theme,
+    ) -> Self {
+        Self { results, total, filter, selected, view, theme }
+    }
+
+    fn render_button(&self, id: &'static str, text: String) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .px_2()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_size(px(11.0))
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text)
+    }
+
+    fn render_filters(&self) -> impl IntoElement {
+        let view_type = self.view.clone();
+        let view_range = self.view.clone();
+        let placeholder = self.filter.query.is_empty();
+        let session_type = self.filter.session_type.clone().unwrap_or_else(|| "All types".to_string());
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .w_full()
+                    .px_2()
+                    .rounded(px(6.0))
+                    .border_1()
+                    .border_color(self.theme.border)
+                    .text_xs()
+                    .text_color(if placeholder { self.theme.muted_foreground } else { self.theme.foreground })
+                    .child(if placeholder { "Search labels...".to_string() } else { format!("{}|", self.filter.query) })
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(
+                        self.render_button("history-type-filter", session_type)
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_type, |app, cx| {
+                                    app.handle_cycle_history_type(cx);
+                                });
+                            })
+                    )
+                    .child(
+                        self.render_button("history-range-filter", self.filter.range.display_name().to_string())
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_range, |app, cx| {
+                                    app.handle_cycle_history_range(cx);
+                                });
+                            })
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_size(px(11.0))
+                            .text_align(TextAlign::Right)
+                            .text_color(self.theme.muted_foreground)
+                            .child(format!("{} of {}", self.results.len(), self.total))
+                    )
+            )
+    }
+
+    fn render_result(&self, position: usize, index: usize, entry: &CompletedTimer) -> impl IntoElement {
+        let view = self.view.clone();
+        let selected = position == self.selected;
+        let label = match &entry.break_preset {
+            Some(preset) => preset.clone(),
+            None if entry.label.is_empty() => "Untitled".to_string(),
+            None => entry.label.clone(),
+        };
+        let completed_at = entry.completed_at.with_timezone(&chrono::Local).format("%b %d %H:%M");
+
+        div()
+            .id(("history-result", index))
+            .flex()
+            .flex_col()
+            .w_full()
+            .px_1()
+            .rounded(px(4.0))
+            .text_size(px(11.0))
+            .cursor_pointer()
+            .when(selected, |row| row.bg(self.theme.muted_background))
+            .hover(|style| style.bg(self.theme.muted_background))
+            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                cx.update_entity(&view, |app, cx| {
+                    app.handle_open_history_entry(index, cx);
+                });
+            })
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .w_full()
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .text_color(self.theme.foreground)
+                            .child(label)
+                    )
+                    .child(
+                        div()
+                            .text_color(self.theme.muted_foreground)
+                            .child(format!(
+                                "{}m{}{} · {}",
+                                entry.duration_secs.div_ceil(60),
+                                if entry.abandoned { "✕" } else if entry.switched { "⇄" } else { "" },
+                                if entry.distracted { " ⚠" } else { "" },
+                                completed_at
+                            ))
+                    )
+            )
+            // Where the time went, for sessions recorded with track_apps
+            .when(selected && !entry.apps.is_empty(), |row| {
+                row.child(
+                    div()
+                        .w_full()
+                        .overflow_hidden()
+                        .text_size(px(10.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child(app_summary(entry))
+                )
+            })
+    }
+}
+
+// "Code 18m · Slack 6m ⚠", most used app first
+fn app_summary(entry: &CompletedTimer) -> String {
+    let mut apps: Vec<_> = entry.apps.iter().collect();
+    apps.sort_by_key(|usage| std::cmp::Reverse(usage.secs));
+    apps.iter()
+        .map(|usage| format!("{} {}m{}", usage.app, usage.secs.div_ceil(60), if usage.distracting { " ⚠" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+impl IntoElement for HistoryPanel {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view = self.view.clone();
+
+        // Keep the selected row inside the visible window
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE_RESULTS - 1)
+            .min(self.results.len().saturating_sub(VISIBLE_RESULTS));
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(self.theme.foreground)
+                            .child("History")
+                    )
+                    .child(
+                        self.render_button("history-back-button", "Back".to_string())
+                            .py_1()
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_toggle_history(cx);
+                                });
+                            })
+                    )
+            )
+            .child(self.render_filters())
+            .when(self.results.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child("No matching sessions")
+                )
+            })
+            .children(
+                self.results
+                    .iter()
+                    .enumerate()
+                    .skip(first)
+                    .take(VISIBLE_RESULTS)
+                    .map(|(position, (index, entry))| self.render_result(position, *index, entry))
+                    .collect::<Vec<_>>()
+            )
+    }
+}
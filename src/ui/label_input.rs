@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use crate::config::Config;
+
+// Labels (and day notes) are typed through the platform's text input rather than raw key
+// presses, so accents, dead keys, compose sequences, and IME input for CJK
+// all work. The platform counts in UTF-16; the label is a String.
+
+pub fn to_utf16(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].encode_utf16().count()
+}
+
+pub fn from_utf16(text: &str, offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (index, c) in text.char_indices() {
+        if utf16 >= offset {
+            return index;
+        }
+        utf16 += c.len_utf16();
+    }
+    text.len()
+}
+
+pub fn range_to_utf16(text: &str, range: &Range<usize>) -> Range<usize> {
+    to_utf16(text, range.start)..to_utf16(text, range.end)
+}
+
+pub fn range_from_utf16(text: &str, range: &Range<usize>) -> Range<usize> {
+    from_utf16(text, range.start)..from_utf16(text, range.end)
+}
+
+// Where the caret is, in bytes; None, or a spot that's no longer in the text, is the end
+pub fn caret(text: &str, cursor: Option<usize>) -> usize {
+    cursor.filter(|cursor| text.is_char_boundary(*cursor)).unwrap_or(text.len())
+}
+
+// Move the caret or delete next to it for an editing key, returning where the
+// caret ends up. None when `key` isn't one of them.
+pub fn edit(text: &mut String, caret: usize, key: &str) -> Option<usize> {
+    let before = text[..caret].chars().next_back().map_or(0, char::len_utf8);
+    let after = text[caret..].chars().next().map_or(0, char::len_utf8);
+    match key {
+        "left" => Some(caret - before),
+        "right" => Some(caret + after),
+        "home" => Some(0),
+        "end" => Some(text.len()),
+        "backspace" => {
+            text.replace_range(caret - before..caret, "");
+            Some(caret - before)
+        }
+        "delete" => {
+            text.replace_range(caret..caret + after, "");
+            Some(caret)
+        }
+        _ => None,
+    }
+}
+
+// The text as shown while it's typed, with a bar for the caret `chars` characters in
+pub fn with_caret(text: &str, chars: usize) -> String {
+    let at = text.char_indices().nth(chars).map_or(text.len(), |(index, _)| index);
+    format!("{}|{}", &text[..at], &text[at..])
+}
+
+// The label rules from the config: most characters, and characters left out
+pub fn label_rules(config: &Config) -> (usize, &str) {
+    (config.label_max_length as usize, &config.label_forbidden_chars)
+}
+
+// Put `new` in place of `range` in the text, leaving out what the rules don't
+// allow, and return where it went
+pub fn replace(rules: (usize, &str), text: &mut String, range: Range<usize>, new: &str) -> Range<usize> {
+    let (max_chars, forbidden) = rules;
+    let room = max_chars.saturating_sub(text.chars().count() - text[range.clone()].chars().count());
+    let new: String = new
+        .chars()
+        .filter(|c| !c.is_control() && !forbidden.contains(*c))
+        .take(room)
+        .collect();
+    text.replace_range(range.clone(), &new);
+    range.start..range.start + new.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_text_from_the_platform() {
+        let config = Config { label_max_length: 6, label_forbidden_chars: ",".to_string(), ..Config::default() };
+        let mut text = "Café".to_string();
+        // "é" is one UTF-16 unit but two bytes
+        assert_eq!(to_utf16(&text, text.len()), 4);
+        assert_eq!(from_utf16(&text, 4), text.len());
+
+        let end = text.len();
+        assert_eq!(replace(label_rules(&config), &mut text, end..end, "\n, 会議"), 5..9);
+        assert_eq!(text, "Café 会");
+        assert_eq!(range_to_utf16(&text, &(5..9)), 4..6);
+
+        // The IME swaps composed text in place
+        assert_eq!(replace(label_rules(&config), &mut text, 6..9, "x"), 6..7);
+        assert_eq!(text, "Café x");
+    }
+
+    #[test]
+    fn moves_and_deletes_around_the_caret() {
+        let mut text = "Café".to_string();
+        let caret = caret(&text, None);
+        let caret = edit(&mut text, caret, "left").unwrap();
+        assert_eq!(caret, 3);
+        assert_eq!(with_caret(&text, 3), "Caf|é");
+        assert_eq!(edit(&mut text, caret, "backspace"), Some(2));
+        assert_eq!(text, "Caé");
+        assert_eq!(edit(&mut text, 2, "delete"), Some(2));
+        assert_eq!(text, "Ca");
+        assert_eq!(edit(&mut text, 2, "right"), Some(2));
+        assert_eq!(edit(&mut text, 2, "home"), Some(0));
+        assert_eq!(edit(&mut text, 0, "backspace"), Some(0));
+        assert_eq!(edit(&mut text, 0, "x"), None);
+        assert_eq!(super::caret("Ca", Some(9)), 2);
+    }
+}
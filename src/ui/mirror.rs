@@ -0,0 +1,123 @@
+use gpui::*;
+use gpui::prelude::*;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::status::{self, StatusReport};
+use crate::theme::Theme;
+
+// How often the mirror asks the timer for its state
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Display-only copy of the timer running in another process, for a second
+// monitor or a wall display. Follows the timer over its socket, with no controls.
+pub struct Mirror {
+    config: Config,
+    report: Result<StatusReport, String>,
+}
+
+impl Mirror {
+    pub fn new(config: Config, cx: &mut Context<'_, Self>) -> Self {
+        cx.spawn(async move |this, cx| loop {
+            let report = cx
+                .background_spawn(async {
+                    // Asking the timer blocks on its socket
+                    status::query().unwrap_or_else(|| Err("No timer is running".to_string()))
+                })
+                .await;
+            let updated = this.update(cx, |mirror, cx| {
+                if mirror.report != report {
+                    mirror.report = report;
+                    cx.notify();
+                }
+            });
+            if updated.is_err() {
+                break;
+            }
+            cx.background_spawn(async { std::thread::sleep(POLL_INTERVAL) }).await;
+        })
+        .detach();
+
+        Self { config, report: Err("Connecting...".to_string()) }
+    }
+}
+
+impl Render for Mirror {
+    fn render(&mut self, window: &mut Window, _cx: &mut Context<'_, Self>) -> impl IntoElement {
+        let theme = Theme::current(self.config.theme, window.appearance());
+        // Digits grow with the window, so it reads from across a room
+        let viewport = window.viewport_size();
+        let digits = (viewport.width / 3.5).min(viewport.height / 2.5);
+
+        let base = div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .items_center()
+            .justify_center()
+            .gap_2()
+            .bg(theme.background);
+
+        let report = match &self.report {
+            Ok(report) => report,
+            Err(message) => {
+                return base.child(div().text_color(theme.muted_foreground).child(message.clone()));
+            }
+        };
+
+        let paused = !report.running && report.state != "Ready";
+        let heading = if paused { format!("{} · Paused", report.state) } else { report.state.clone() };
+        let progress = match report.total_secs {
+            0 => 0.0,
+            total => 1.0 - report.remaining_secs as f32 / total as f32,
+        };
+
+        base.child(
+            div()
+                .text_size(digits / 5.0)
+                .text_color(theme.muted_foreground)
+                .child(heading)
+        )
+        .child(
+            div()
+                .text_size(digits)
+                .line_height(digits)
+                .font_weight(FontWeight::BOLD)
+                .text_color(theme.foreground)
+                .when(paused, |time| time.opacity(0.5))
+                .child(report.time_text.clone())
+        )
+        // Time left in the session
+        .child(
+            div()
+                .w(viewport.width * 0.6)
+                .h(digits / 16.0)
+                .rounded_full()
+                .bg(theme.border)
+                .child(
+                    div()
+                        .h_full()
+                        .w(relative(1.0 - progress))
+                        .rounded_full()
+                        .bg(theme.session_color(&report.state))
+                )
+        )
+        .when(!report.label.is_empty(), |d| {
+            d.child(
+                div()
+                    .text_size(digits / 4.0)
+                    .text_color(theme.foreground)
+                    .child(report.label.clone())
+            )
+        })
+        .child(
+            div()
+                .text_size(digits / 7.0)
+                .text_color(theme.muted_foreground)
+                .child(format!(
+                    "Session {} of {} · {} today",
+                    report.session, report.sessions_until_long_break, report.today_completed
+                ))
+        )
+    }
+}
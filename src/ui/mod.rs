@@ -1,3 +1,29 @@
 pub mod circular_timer;
+pub mod detached;
+pub mod diagnostics;
+pub mod history;
+pub mod label_input;
+pub mod mirror;
+pub mod new_timer;
+pub mod onboarding;
+pub mod particles;
+pub mod presentation;
+pub mod queue;
+pub mod settings;
+pub mod stats;
+pub mod tooltip;
+pub mod unlock;
 
-pub use circular_timer::CircularTimer;
+pub use circular_timer::{CircularTimer, ControlFocus, Prompt, TimerProps};
+pub use detached::{DetachedPanel, WindowLayout};
+pub use diagnostics::DiagnosticsPanel;
+pub use history::HistoryPanel;
+pub use mirror::Mirror;
+pub use new_timer::{NewTimerDialog, NewTimerDraft};
+pub use onboarding::Onboarding;
+pub use presentation::Presentation;
+pub use queue::QueuePanel;
+pub use settings::{NotificationTestStatus, SettingsPanel};
+pub use stats::{StatsContent, StatsPanel, StatsTab};
+pub use tooltip::Tooltip;
+pub use unlock::Unlock;
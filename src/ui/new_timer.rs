@@ -0,0 +1,223 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::PomodoroApp;
+use crate::theme::Theme;
+use crate::ui::label_input;
+
+// Label, duration and session type collected by the new-timer dialog
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTimerDraft {
+    pub label: String,
+    pub minutes: u32,
+    pub is_focus: bool,
+    pub category: Option<String>,  // Session category for a focus session, None for deep work
+}
+
+pub struct NewTimerDialog {
+    draft: NewTimerDraft,
+    caret: usize,  // Characters into the label
+    categories: Vec<String>,  // From session_categories in the config
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+impl NewTimerDialog {
+    pub fn new(draft: NewTimerDraft, caret: usize, categories: Vec<String>, view: Entity<PomodoroApp>, theme: Theme) -> Self {
+        Self { draft, caret, categories, view, theme }
+    }
+
+    fn render_button(&self, id: impl Into<ElementId>, text: impl Into<SharedString>, selected: bool) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .min_w(px(20.0))
+            .px_2()
+            .rounded(px(6.0))
+            .bg(if selected { self.theme.foreground } else { self.theme.secondary })
+            .text_color(if selected { self.theme.background } else { self.theme.secondary_foreground })
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text.into())
+    }
+
+    fn render_row(&self, label: &'static str, value: impl IntoElement) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .w_full()
+            .text_xs()
+            .child(div().text_color(self.theme.muted_foreground).child(label))
+            .child(value)
+    }
+
+    fn render_label_field(&self) -> impl IntoElement {
+        let placeholder = self.draft.label.is_empty();
+
+        div()
+            .w_full()
+            .px_2()
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(self.theme.border)
+            .text_xs()
+            .text_color(if placeholder { self.theme.muted_foreground } else { self.theme.foreground })
+            .child(if placeholder { "Label (optional)...".to_string() } else { label_input::with_caret(&self.draft.label, self.caret) })
+    }
+
+    fn render_session_type(&self) -> impl IntoElement {
+        let view_focus = self.view.clone();
+        let view_rest = self.view.clone();
+
+        div()
+            .flex()
+            .flex_row()
+            .gap_1()
+            .child(
+                self.render_button("new-timer-focus", "Focus", self.draft.is_focus)
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view_focus, |app, cx| {
+                            app.handle_set_new_timer_kind(true, cx);
+                        });
+                    })
+            )
+            .child(
+                self.render_button("new-timer-rest", "Rest", !self.draft.is_focus)
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view_rest, |app, cx| {
+                            app.handle_set_new_timer_kind(false, cx);
+                        });
+                    })
+            )
+    }
+
+    // Deep work, or one of the configured kinds of shallow work
+    fn render_categories(&self) -> impl IntoElement {
+        let choices = std::iter::once(None).chain(self.categories.iter().cloned().map(Some));
+
+        div()
+            .flex()
+            .flex_row()
+            .flex_wrap()
+            .justify_end()
+            .gap_1()
+            .children(choices.enumerate().map(|(index, category)| {
+                let view = self.view.clone();
+                let selected = self.draft.category == category;
+                let text = category.clone().unwrap_or_else(|| "Deep work".to_string());
+                self.render_button(("new-timer-category", index), text, selected)
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        let category = category.clone();
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_set_new_timer_category(category, cx);
+                        });
+                    })
+            }))
+    }
+
+    fn render_duration(&self) -> impl IntoElement {
+        let view_minus = self.view.clone();
+        let view_plus = self.view.clone();
+
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .child(
+                self.render_button("new-timer-minus", "-", false)
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view_minus, |app, cx| {
+                            app.handle_adjust_new_timer_minutes(-1, cx);
+                        });
+                    })
+            )
+            .child(
+                div()
+                    .min_w(px(44.0))
+                    .text_align(TextAlign::Center)
+                    .text_color(self.theme.foreground)
+                    .child(format!("{} min", self.draft.minutes))
+            )
+            .child(
+                self.render_button("new-timer-plus", "+", false)
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view_plus, |app, cx| {
+                            app.handle_adjust_new_timer_minutes(1, cx);
+                        });
+                    })
+            )
+    }
+}
+
+impl IntoElement for NewTimerDialog {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view_cancel = self.view.clone();
+        let view_start = self.view.clone();
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(self.theme.foreground)
+                    .child("New timer")
+            )
+            .child(self.render_label_field())
+            .child(self.render_row("Type", self.render_session_type()))
+            .when(self.draft.is_focus && !self.categories.is_empty(), |d| {
+                d.child(self.render_row("Category", self.render_categories()))
+            })
+            .child(self.render_row("Duration", self.render_duration()))
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child("Up/Down changes the duration")
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_center()
+                    .gap_2()
+                    .child(
+                        self.render_button("new-timer-cancel", "Cancel", false)
+                            .py_1()
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_cancel, |app, cx| {
+                                    app.handle_cancel_new_timer(cx);
+                                });
+                            })
+                    )
+                    .child(
+                        self.render_button("new-timer-start", "Start", true)
+                            .px_4()
+                            .py_1()
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_start, |app, cx| {
+                                    app.handle_start_new_timer(cx);
+                                });
+                            })
+                    )
+            )
+    }
+}
@@ -0,0 +1,194 @@
+use gpui::*;
+
+use crate::app::PomodoroApp;
+use crate::config::Config;
+use crate::notifications;
+use crate::theme::Theme;
+
+// First-run setup shown when neither a config nor a saved state exists
+pub struct Onboarding {
+    config: Config,
+}
+
+impl Onboarding {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn adjust_work(&mut self, delta: i32, cx: &mut Context<'_, Self>) {
+        self.config.work_duration = step(self.config.work_duration, delta * 5, 5, 120);
+        cx.notify();
+    }
+
+    fn adjust_short_break(&mut self, delta: i32, cx: &mut Context<'_, Self>) {
+        self.config.short_break_duration = step(self.config.short_break_duration, delta, 1, 30);
+        cx.notify();
+    }
+
+    fn adjust_long_break(&mut self, delta: i32, cx: &mut Context<'_, Self>) {
+        self.config.long_break_duration = step(self.config.long_break_duration, delta * 5, 5, 60);
+        cx.notify();
+    }
+
+    fn toggle_notifications(&mut self, cx: &mut Context<'_, Self>) {
+        self.config.enable_notifications = !self.config.enable_notifications;
+        cx.notify();
+    }
+
+    fn cycle_theme(&mut self, cx: &mut Context<'_, Self>) {
+        self.config.theme = self.config.theme.next();
+        cx.notify();
+    }
+
+    fn finish(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) {
+        if let Err(e) = self.config.save() {
+            notifications::log_error(&format!("Failed to save config: {}", e));
+        } else {
+            notifications::log_info("Saved configuration from first-run setup");
+        }
+
+        // Hand the window over to the timer with the chosen settings
+        let config = self.config.clone();
+        window.replace_root(cx, |window, cx| PomodoroApp::new(config, Vec::new(), window, cx));
+    }
+
+    fn render_row(&self, label: &str, value: impl IntoElement, theme: &Theme) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .w_full()
+            .text_xs()
+            .child(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .child(label.to_string())
+            )
+            .child(value)
+    }
+
+    fn render_stepper(
+        &self,
+        id: &'static str,
+        value: String,
+        theme: &Theme,
+        cx: &mut Context<'_, Self>,
+        adjust: fn(&mut Self, i32, &mut Context<'_, Self>),
+    ) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .child(
+                self.render_button((id, 0usize), "-", theme)
+                    .on_mouse_down(MouseButton::Left, cx.listener(move |this, _event, _window, cx| adjust(this, -1, cx)))
+            )
+            .child(
+                div()
+                    .min_w(px(44.0))
+                    .text_align(TextAlign::Center)
+                    .text_color(theme.foreground)
+                    .child(value)
+            )
+            .child(
+                self.render_button((id, 1usize), "+", theme)
+                    .on_mouse_down(MouseButton::Left, cx.listener(move |this, _event, _window, cx| adjust(this, 1, cx)))
+            )
+    }
+
+    fn render_button(&self, id: impl Into<ElementId>, text: &str, theme: &Theme) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .min_w(px(20.0))
+            .px_2()
+            .rounded(px(6.0))
+            .bg(theme.secondary)
+            .text_color(theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text.to_string())
+    }
+}
+
+impl Render for Onboarding {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        // Preview the selected theme while choosing it
+        let theme = Theme::current(self.config.theme, window.appearance());
+        let notifications_text = if self.config.enable_notifications { "On" } else { "Off" };
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p_3()
+            .bg(theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(theme.border)
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(theme.foreground)
+                    .child("Welcome!")
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child("Pick your defaults. You can change them later in config.toml.")
+            )
+            .child(self.render_row(
+                "Focus",
+                self.render_stepper("work", format!("{} min", self.config.work_duration), &theme, cx, Self::adjust_work),
+                &theme,
+            ))
+            .child(self.render_row(
+                "Short break",
+                self.render_stepper("short-break", format!("{} min", self.config.short_break_duration), &theme, cx, Self::adjust_short_break),
+                &theme,
+            ))
+            .child(self.render_row(
+                "Long break",
+                self.render_stepper("long-break", format!("{} min", self.config.long_break_duration), &theme, cx, Self::adjust_long_break),
+                &theme,
+            ))
+            .child(self.render_row(
+                "Notifications",
+                self.render_button("notifications-toggle", notifications_text, &theme)
+                    .on_mouse_down(MouseButton::Left, cx.listener(|this, _event, _window, cx| this.toggle_notifications(cx))),
+                &theme,
+            ))
+            .child(self.render_row(
+                "Theme",
+                self.render_button("theme-toggle", self.config.theme.display_name(), &theme)
+                    .on_mouse_down(MouseButton::Left, cx.listener(|this, _event, _window, cx| this.cycle_theme(cx))),
+                &theme,
+            ))
+            .child(
+                div()
+                    .flex()
+                    .justify_center()
+                    .mt_1()
+                    .child(
+                        self.render_button("finish-setup", "Get started", &theme)
+                            .px_4()
+                            .py_1()
+                            .on_mouse_down(MouseButton::Left, cx.listener(|this, _event, window, cx| this.finish(window, cx)))
+                    )
+            )
+    }
+}
+
+fn step(value: u32, delta: i32, min: u32, max: u32) -> u32 {
+    (value as i32 + delta).clamp(min as i32, max as i32) as u32
+}
@@ -0,0 +1,160 @@
+use gpui::*;
+use gpui::prelude::*;
+use std::time::Duration;
+
+use crate::config::{ConfettiStyle, Config};
+use crate::theme::{parse_hex, Theme};
+
+pub const MAX_PARTICLES: u32 = 200;
+
+// One scrap of confetti or spark. Positions are fractions of the window, so
+// the burst fits any size; speeds are per run of the animation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    pub dx: f32,
+    pub dy: f32,
+    pub fall: f32,  // Pull of gravity
+    pub size: f32,  // Pixels
+    pub round: bool,
+    pub color: Hsla,
+}
+
+impl Particle {
+    // Where it is `progress` (0.0 to 1.0) through the animation
+    pub fn at(&self, progress: f32) -> (f32, f32) {
+        (self.x + self.dx * progress, self.y + self.dy * progress + self.fall * progress * progress)
+    }
+}
+
+// Small xorshift generator; the burst only has to look random
+struct Scatter(u64);
+
+impl Scatter {
+    fn between(&mut self, low: f32, high: f32) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        low + (high - low) * (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// The particles thrown over the window when a session completes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Burst {
+    pub style: ConfettiStyle,
+    pub particles: Vec<Particle>,
+    pub duration: Duration,
+}
+
+impl Burst {
+    // None when confetti is turned off or motion is reduced. The same seed gives
+    // the same burst, so it holds still from one frame to the next.
+    pub fn new(config: &Config, theme: &Theme, seed: u64) -> Option<Self> {
+        if config.reduce_motion || config.confetti_count == 0 {
+            return None;
+        }
+        let mut colors: Vec<Hsla> = config.confetti_colors.iter().filter_map(|color| parse_hex(color)).collect();
+        if colors.is_empty() {
+            colors = vec![theme.work, theme.short_break, theme.long_break, theme.warning_foreground];
+        }
+        let mut scatter = Scatter(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+        // Fireworks go off from a few points in the upper half
+        let centers: Vec<(f32, f32)> =
+            (0..3).map(|_| (scatter.between(0.2, 0.8), scatter.between(0.15, 0.5))).collect();
+
+        let particles = (0..config.confetti_count.min(MAX_PARTICLES) as usize)
+            .map(|i| {
+                let color = colors[i % colors.len()];
+                match config.confetti_style {
+                    ConfettiStyle::Confetti => Particle {
+                        x: scatter.between(0.0, 1.0),
+                        y: scatter.between(-0.3, 0.0),
+                        dx: scatter.between(-0.15, 0.15),
+                        dy: scatter.between(0.3, 0.6),
+                        fall: 0.6,
+                        size: scatter.between(4.0, 8.0),
+                        round: false,
+                        color,
+                    },
+                    ConfettiStyle::Fireworks => {
+                        let (x, y) = centers[i % centers.len()];
+                        let angle = scatter.between(0.0, std::f32::consts::TAU);
+                        let speed = scatter.between(0.15, 0.35);
+                        Particle {
+                            x,
+                            y,
+                            dx: angle.cos() * speed,
+                            dy: angle.sin() * speed,
+                            fall: 0.3,
+                            size: scatter.between(2.0, 4.0),
+                            round: true,
+                            color,
+                        }
+                    }
+                }
+            })
+            .collect();
+        Some(Self { style: config.confetti_style, particles, duration: Duration::from_secs(config.confetti_seconds.max(1) as u64) })
+    }
+
+    // Overlay that plays the burst once over whatever it's placed in
+    pub fn render(&self) -> impl IntoElement {
+        let style = self.style;
+        div()
+            .absolute()
+            .top_0()
+            .left_0()
+            .size_full()
+            .overflow_hidden()
+            .children(self.particles.iter().cloned().enumerate().map(|(index, particle)| {
+                div()
+                    .absolute()
+                    .size(px(particle.size))
+                    .when(particle.round, |d| d.rounded_full())
+                    .bg(particle.color)
+                    .with_animation(
+                        ("particle", index),
+                        Animation::new(self.duration),
+                        move |d, progress| {
+                            let (x, y) = particle.at(progress);
+                            d.left(relative(x)).top(relative(y)).opacity(opacity(style, progress))
+                        },
+                    )
+            }))
+    }
+}
+
+// Confetti fades out as it lands, sparks as they fly
+fn opacity(style: ConfettiStyle, progress: f32) -> f32 {
+    match style {
+        ConfettiStyle::Confetti => ((1.0 - progress) / 0.3).min(1.0),
+        ConfettiStyle::Fireworks => 1.0 - progress,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Burst, ConfettiStyle, Config, Theme, MAX_PARTICLES};
+
+    #[test]
+    fn bursts_follow_the_config() {
+        let theme = Theme::light();
+        let config = Config { confetti_count: 30, ..Config::default() };
+        let burst = Burst::new(&config, &theme, 7).unwrap();
+        assert_eq!(burst.particles.len(), 30);
+        assert_eq!(Burst::new(&config, &theme, 7), Some(burst.clone()));
+        // Everything starts above the window and falls into it
+        assert!(burst.particles.iter().all(|particle| particle.y <= 0.0 && particle.at(1.0).1 > 0.5));
+
+        let fireworks = Config { confetti_style: ConfettiStyle::Fireworks, confetti_colors: vec!["#ff0000".to_string()], ..config.clone() };
+        let burst = Burst::new(&fireworks, &theme, 7).unwrap();
+        assert!(burst.particles.iter().all(|particle| particle.color == gpui::rgb(0xff0000).into()));
+
+        let huge = Config { confetti_count: 10_000, ..config.clone() };
+        assert_eq!(Burst::new(&huge, &theme, 7).unwrap().particles.len(), MAX_PARTICLES as usize);
+        assert_eq!(Burst::new(&Config { confetti_count: 0, ..config.clone() }, &theme, 7), None);
+        assert_eq!(Burst::new(&Config { reduce_motion: true, ..config }, &theme, 7), None);
+    }
+}
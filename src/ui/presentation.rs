@@ -0,0 +1,95 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::PomodoroApp;
+use crate::theme::Theme;
+
+// Full-screen timer for a projector: huge high-contrast digits and no buttons.
+// Space starts and pauses, Escape ends the presentation.
+pub struct Presentation {
+    app: Entity<PomodoroApp>,
+    focus_handle: FocusHandle,
+    _redraw: Subscription,
+}
+
+impl Presentation {
+    pub fn new(app: Entity<PomodoroApp>, cx: &mut Context<'_, Self>) -> Self {
+        // Redraw along with the timer window
+        let redraw = cx.observe(&app, |_this, _app, cx| cx.notify());
+        Self { app, focus_handle: cx.focus_handle(), _redraw: redraw }
+    }
+
+    fn handle_key(&mut self, key: &str, window: &mut Window, cx: &mut Context<'_, Self>) {
+        match key {
+            "space" => self.app.update(cx, |app, cx| app.handle_toggle(cx)),
+            "escape" => window.remove_window(),
+            _ => {}
+        }
+    }
+}
+
+impl Render for Presentation {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        if window.focused(cx).is_none() {
+            self.focus_handle.focus(window);
+        }
+        // Always the dark theme: light digits on black read best on a projector
+        let theme = Theme::dark();
+        let timer = self.app.read(cx).timer_view();
+        let viewport = window.viewport_size();
+        let digits = (viewport.width / 3.2).min(viewport.height / 2.0);
+        let paused = timer.current_state.resume().is_some();
+        let session_color = theme.session_color(timer.current_state.display_name());
+
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .items_center()
+            .justify_center()
+            .gap_4()
+            .bg(black())
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                this.handle_key(event.keystroke.key.as_str(), window, cx);
+            }))
+            .child(
+                div()
+                    .text_size(digits / 5.0)
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(session_color)
+                    .child(if paused { "Paused".to_string() } else { timer.current_state.display_name().to_string() })
+            )
+            .child(
+                div()
+                    .text_size(digits)
+                    .line_height(digits)
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(white())
+                    .when(paused, |time| time.opacity(0.6))
+                    .child(timer.time_text)
+            )
+            .child(
+                div()
+                    .w(viewport.width * 0.7)
+                    .h(digits / 14.0)
+                    .rounded_full()
+                    .bg(theme.secondary)
+                    .child(
+                        div()
+                            .h_full()
+                            .w(relative(1.0 - timer.progress))
+                            .rounded_full()
+                            .bg(session_color)
+                    )
+            )
+            .when(!timer.current_label.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(digits / 4.0)
+                        .text_color(white())
+                        .child(timer.current_label)
+                )
+            })
+    }
+}
@@ -0,0 +1,180 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::PomodoroApp;
+use crate::state::QueuedLabel;
+use crate::theme::Theme;
+use crate::ui::label_input;
+
+// Editable list of labels that upcoming work sessions pick up in order
+pub struct QueuePanel {
+    queue: Vec<QueuedLabel>,
+    input: String,
+    caret: usize,  // Characters into the input
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+impl QueuePanel {
+    pub fn new(queue: Vec<QueuedLabel>, input: String, caret: usize, view: Entity<PomodoroApp>, theme: Theme) -> Self {
+        Self { queue, input, caret, view, theme }
+    }
+
+    fn render_button(&self, id: impl Into<ElementId>, text: &'static str) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .min_w(px(20.0))
+            .px_2()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text)
+    }
+
+    fn render_entry(&self, index: usize, entry: &QueuedLabel) -> impl IntoElement {
+        let view_minus = self.view.clone();
+        let view_plus = self.view.clone();
+        let view_remove = self.view.clone();
+
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .w_full()
+            .text_xs()
+            .child(
+                div()
+                    .flex_1()
+                    .overflow_hidden()
+                    .text_color(self.theme.foreground)
+                    .child(format!("{} ×{}", entry.label, entry.count))
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .child(
+                        self.render_button(("queue-minus", index), "-")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_minus, |app, cx| {
+                                    app.handle_adjust_queue_entry(index, -1, cx);
+                                });
+                            })
+                    )
+                    .child(
+                        self.render_button(("queue-plus", index), "+")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_plus, |app, cx| {
+                                    app.handle_adjust_queue_entry(index, 1, cx);
+                                });
+                            })
+                    )
+                    .child(
+                        self.render_button(("queue-remove", index), "×")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_remove, |app, cx| {
+                                    app.handle_remove_queue_entry(index, cx);
+                                });
+                            })
+                    )
+            )
+    }
+
+    fn render_input(&self) -> impl IntoElement {
+        let view = self.view.clone();
+        let placeholder = self.input.is_empty();
+
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex_1()
+                    .px_2()
+                    .rounded(px(6.0))
+                    .border_1()
+                    .border_color(self.theme.border)
+                    .text_xs()
+                    .text_color(if placeholder { self.theme.muted_foreground } else { self.theme.foreground })
+                    .child(if placeholder { "Type a label...".to_string() } else { label_input::with_caret(&self.input, self.caret) })
+            )
+            .child(
+                self.render_button("queue-add", "Add")
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_add_to_queue(cx);
+                        });
+                    })
+            )
+    }
+}
+
+impl IntoElement for QueuePanel {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view = self.view.clone();
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(self.theme.foreground)
+                            .child("Up next")
+                    )
+                    .child(
+                        self.render_button("queue-back-button", "Back")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_toggle_queue(cx);
+                                });
+                            })
+                    )
+            )
+            .child(self.render_input())
+            .when(self.queue.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child("Queued labels are used by the next work sessions, in order.")
+                )
+            })
+            .children(
+                self.queue
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| self.render_entry(index, entry))
+                    .collect::<Vec<_>>()
+            )
+    }
+}
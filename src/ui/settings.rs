@@ -0,0 +1,517 @@
+use gpui::*;
+use gpui::prelude::*;
+use qrcodegen::{QrCode, QrCodeEcc};
+
+use crate::app::{ActivePanel, PomodoroApp};
+use crate::notifications::NotificationCapabilities;
+use crate::sound;
+use crate::theme::{Theme, ThemePreference};
+use crate::timer::TickStats;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationTestStatus {
+    Sending,
+    Sent,
+    Failed(String),
+}
+
+pub struct SettingsPanel {
+    notifications_enabled: bool,
+    notification_test: Option<NotificationTestStatus>,
+    import_status: Option<String>,
+    capabilities: Option<NotificationCapabilities>,
+    remote_url: Option<String>,  // Phone remote address, when it's running
+    show_remote_qr: bool,
+    ambient_sound: Option<String>,  // Focus sound, None when off
+    ambient_volume: u32,
+    theme_preference: ThemePreference,
+    tick_stats: TickStats,  // Timer accuracy since launch, under About
+    detached: bool,  // In a window of its own rather than the timer's
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+// Side of the QR code in pixels, quiet zone included
+const QR_SIZE: f32 = 150.0;
+
+// Blank modules around the code that scanners need to find it
+const QR_QUIET_ZONE: i32 = 2;
+
+// Steps of the volume slider, 10% each
+const VOLUME_STEPS: u32 = 10;
+
+impl SettingsPanel {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        notifications_enabled: bool,
+        notification_test: Option<NotificationTestStatus>,
+        import_status: Option<String>,
+        capabilities: Option<NotificationCapabilities>,
+        remote_url: Option<String>,
+        show_remote_qr: bool,
+        ambient_sound: Option<String>,
+        ambient_volume: u32,
+        theme_preference: ThemePreference,
+        tick_stats: TickStats,
+        detached: bool,
+        view: Entity<PomodoroApp>,
+        theme: Theme,
+    ) -> Self {
+        Self {
+            notifications_enabled,
+            notification_test,
+            import_status,
+            capabilities,
+            remote_url,
+            show_remote_qr,
+            ambient_sound,
+            ambient_volume,
+            theme_preference,
+            tick_stats,
+            detached,
+            view,
+            theme,
+        }
+    }
+
+    fn render_button(&self, id: &'static str, text: impl Into<SharedString>) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text.into())
+    }
+
+    fn render_notifications_section(&self) -> impl IntoElement {
+        let view = self.view.clone();
+
+        let status = match &self.notification_test {
+            None if self.notifications_enabled => "Send a sample to check your setup".to_string(),
+            None => "Notifications are turned off in config.toml".to_string(),
+            Some(NotificationTestStatus::Sending) => "Sending...".to_string(),
+            Some(NotificationTestStatus::Sent) => "Sent. If nothing appeared, check your system settings.".to_string(),
+            Some(NotificationTestStatus::Failed(e)) => format!("Failed: {}", e),
+        };
+        let is_failure = matches!(self.notification_test, Some(NotificationTestStatus::Failed(_)));
+
+        let server = match &self.capabilities {
+            Some(caps) => {
+                let mark = |supported: bool| if supported { "yes" } else { "no" };
+                format!(
+                    "{}: sound {}, actions {}, persistent {}",
+                    caps.server,
+                    mark(caps.sound),
+                    mark(caps.actions),
+                    mark(caps.persistence)
+                )
+            }
+            None => "Notification server not detected".to_string(),
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(self.theme.foreground)
+                            .child("Notifications")
+                    )
+                    .child(
+                        self.render_button("test-notification-button", "Test")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_test_notification(cx);
+                                });
+                            })
+                    )
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(if is_failure {
+                        self.theme.warning_foreground
+                    } else {
+                        self.theme.muted_foreground
+                    })
+                    .when(is_failure, |d| {
+                        d.bg(self.theme.warning_background)
+                            .rounded(px(6.0))
+                            .px_1()
+                    })
+                    .child(status)
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(server)
+            )
+    }
+
+    fn render_import_section(&self) -> impl IntoElement {
+        let view = self.view.clone();
+        let status = self
+            .import_status
+            .clone()
+            .unwrap_or_else(|| "Toggl, Focus To-Do or Pomofocus CSV exports".to_string());
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(self.theme.foreground)
+                            .child("History")
+                    )
+                    .child(
+                        self.render_button("import-csv-button", "Import CSV")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_import_csv(cx);
+                                });
+                            })
+                    )
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(status)
+            )
+    }
+
+    fn render_theme_section(&self) -> impl IntoElement {
+        let view = self.view.clone();
+
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .justify_between()
+            .w_full()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.foreground)
+                    .child("Theme")
+            )
+            .child(
+                self.render_button("theme-button", self.theme_preference.display_name().to_string())
+                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                        cx.update_entity(&view, |app, cx| {
+                            app.handle_cycle_theme(cx);
+                        });
+                    })
+            )
+    }
+
+    fn render_sound_section(&self) -> impl IntoElement {
+        let view = self.view.clone();
+        let name = self.ambient_sound.as_deref().map(sound::sound_name).unwrap_or_else(|| "Off".to_string());
+        let filled = self.ambient_volume.div_ceil(100 / VOLUME_STEPS);
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(self.theme.foreground)
+                            .child("Focus sound")
+                    )
+                    .child(
+                        self.render_button("ambient-sound-button", name)
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_cycle_ambient_sound(cx);
+                                });
+                            })
+                    )
+            )
+            // Volume slider: click a step to set it
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(
+                        div()
+                            .w(px(44.0))
+                            .text_size(px(11.0))
+                            .text_color(self.theme.muted_foreground)
+                            .child(format!("{}%", self.ambient_volume))
+                    )
+                    .children((1..=VOLUME_STEPS).map(|step| {
+                        let view = self.view.clone();
+                        div()
+                            .id(("ambient-volume-step", step as usize))
+                            .flex_1()
+                            .h(px(8.0))
+                            .rounded(px(2.0))
+                            .cursor_pointer()
+                            .bg(if step <= filled { self.theme.foreground } else { self.theme.border })
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_set_ambient_volume(step * 100 / VOLUME_STEPS, cx);
+                                });
+                            })
+                    }))
+            )
+    }
+
+    fn render_remote_section(&self) -> impl IntoElement {
+        let view = self.view.clone();
+        let status = match &self.remote_url {
+            Some(_) => "Pause or skip from your phone on the same network",
+            None => "Set web_remote = true in config.toml to control the timer from your phone",
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(self.theme.foreground)
+                            .child("Phone remote")
+                    )
+                    .when(self.remote_url.is_some(), |d| {
+                        d.child(
+                            self.render_button("show-remote-qr-button", "Show QR")
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view, |app, cx| {
+                                        app.handle_toggle_remote_qr(cx);
+                                    });
+                                })
+                        )
+                    })
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(status)
+            )
+    }
+
+    // QR code linking to the phone remote, always dark on light so phones can read it
+    fn render_remote_qr(&self, url: &str) -> impl IntoElement {
+        let code = QrCode::encode_text(url, QrCodeEcc::Low).ok();
+
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_1()
+            .w_full()
+            .child(
+                canvas(
+                    |_bounds, _window, _cx| {},
+                    move |bounds, _, window, _cx| {
+                        window.paint_quad(fill(bounds, gpui::white()));
+                        let Some(code) = code else { return };
+                        let modules = code.size() + QR_QUIET_ZONE * 2;
+                        let module = (QR_SIZE / modules as f32).floor();
+                        // Center the code when the modules don't divide the size evenly
+                        let offset = (QR_SIZE - module * modules as f32) / 2.0 + module * QR_QUIET_ZONE as f32;
+                        for y in 0..code.size() {
+                            for x in 0..code.size() {
+                                if code.get_module(x, y) {
+                                    let origin = bounds.origin + point(px(offset + module * x as f32), px(offset + module * y as f32));
+                                    window.paint_quad(fill(Bounds::new(origin, size(px(module), px(module))), gpui::black()));
+                                }
+                            }
+                        }
+                    },
+                )
+                .size(px(QR_SIZE))
+            )
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(url.split('?').next().unwrap_or(url).to_string())
+            )
+    }
+
+    // Version and how well the timer keeps time, for bug reports
+    fn render_about_section(&self) -> impl IntoElement {
+        let stats = &self.tick_stats;
+        let timing = match stats.mean_interval_ms() {
+            None => "Timer accuracy shows once a session has run".to_string(),
+            Some(mean) => format!(
+                "{} ticks, {} ms apart on average, longest {} ms",
+                stats.ticks, mean, stats.longest_interval_ms
+            ),
+        };
+        let drift = format!(
+            "Countdown {} ms behind the clock (worst {} ms); counting ticks alone would be {:+.1} s off",
+            stats.drift_ms,
+            stats.worst_drift_ms,
+            stats.tick_drift_ms as f64 / 1000.0
+        );
+
+        let view = self.view.clone();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(self.theme.foreground)
+                            .child(format!("About: Pomodoro Timer {}", env!("CARGO_PKG_VERSION")))
+                    )
+                    // The recent log, for when something went wrong
+                    .child(
+                        self.render_button("settings-diagnostics-button", "Diagnostics")
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_toggle_diagnostics(cx);
+                                });
+                            })
+                    )
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(timing)
+            )
+            .when(stats.ticks > 0, |d| {
+                d.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child(drift)
+                )
+            })
+    }
+}
+
+impl IntoElement for SettingsPanel {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view = self.view.clone();
+        let view_window = self.view.clone();
+        let showing_qr = self.show_remote_qr && self.remote_url.is_some();
+        let detached = self.detached;
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(self.theme.foreground)
+                            .child(if showing_qr { "Phone remote" } else { "Settings" })
+                    )
+                    // Pop out into a window of its own, or dock back into the timer
+                    .child(
+                        self.render_button("settings-window-button", if detached { "Dock" } else { "⧉" })
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_window, |app, cx| {
+                                    if detached {
+                                        app.handle_dock_panel(ActivePanel::Settings, cx);
+                                    } else {
+                                        app.handle_detach_panel(ActivePanel::Settings, cx);
+                                    }
+                                });
+                            })
+                    )
+                    .child(
+                        // Back from the QR code returns to the settings
+                        self.render_button("settings-back-button", if detached && !showing_qr { "Close" } else { "Back" })
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    if showing_qr {
+                                        app.handle_toggle_remote_qr(cx);
+                                    } else {
+                                        app.handle_toggle_settings(cx);
+                                    }
+                                });
+                            })
+                    )
+            )
+            .when_some(self.remote_url.clone().filter(|_| showing_qr), |d, url| {
+                d.child(self.render_remote_qr(&url))
+            })
+            .when(!showing_qr, |d| {
+                d.child(self.render_notifications_section())
+                    .child(self.render_theme_section())
+                    .child(self.render_sound_section())
+                    .child(self.render_import_section())
+                    .child(self.render_remote_section())
+                    .child(self.render_about_section())
+            })
+    }
+}
@@ -0,0 +1,698 @@
+use gpui::*;
+use gpui::prelude::*;
+
+use crate::app::{ActivePanel, PomodoroApp};
+use crate::state::HistoryRange;
+use crate::stats::analytics::Analytics;
+use crate::stats::breaks::BreakCounts;
+use crate::stats::goals::GoalProgress;
+use crate::stats::labels::LabelTotal;
+use crate::stats::tasks::TaskTotal;
+use crate::stats::timeline::Timeline;
+use crate::stats::usage::UsageStats;
+use crate::strict::StrictDay;
+use crate::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsTab {
+    Today,
+    Trends,
+    Labels,
+    Tasks,
+    Usage,
+}
+
+impl StatsTab {
+    pub fn next(&self) -> Self {
+        match self {
+            StatsTab::Today => StatsTab::Trends,
+            StatsTab::Trends => StatsTab::Labels,
+            StatsTab::Labels => StatsTab::Tasks,
+            StatsTab::Tasks => StatsTab::Usage,
+            StatsTab::Usage => StatsTab::Today,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            StatsTab::Today => "Today",
+            StatsTab::Trends => "Trends",
+            StatsTab::Labels => "By label",
+            StatsTab::Tasks => "Tasks",
+            StatsTab::Usage => "Usage",
+        }
+    }
+}
+
+// Data for the visible tab, computed only when that tab is shown
+pub enum StatsContent {
+    Today {
+        timeline: Option<Timeline>,
+        note: Option<String>,  // Today's note, or while it's written, the text with its caret
+        editing_note: bool,
+        strict: Option<StrictDay>,  // Today's strict pomodoros, while strict mode is on or there were some
+    },
+    Trends {
+        analytics: Analytics,
+        goals: Vec<GoalProgress>,  // Weekly and monthly goals that are set
+        pomodoro_secs: u32,  // Work session length, to tell the pace in pomodoros
+        breaks: BreakCounts,  // Over the last week
+        break_target: u32,  // Percent of breaks taken below which they're flagged
+    },
+    Labels {
+        totals: Vec<LabelTotal>,
+        icons: Vec<LabelTotal>,  // Per label emoji, empty in privacy mode
+        categories: Vec<LabelTotal>,  // Deep work, then each session category; empty without categories
+        query: String,
+        range: HistoryRange,
+    },
+    Tasks(Vec<TaskTotal>),
+    Usage(Option<Box<UsageStats>>),  // None while usage_stats is off
+}
+
+impl StatsContent {
+    fn tab(&self) -> StatsTab {
+        match self {
+            StatsContent::Today { .. } => StatsTab::Today,
+            StatsContent::Trends { .. } => StatsTab::Trends,
+            StatsContent::Labels { .. } => StatsTab::Labels,
+            StatsContent::Tasks(_) => StatsTab::Tasks,
+            StatsContent::Usage(_) => StatsTab::Usage,
+        }
+    }
+}
+
+pub struct StatsPanel {
+    content: StatsContent,
+    detached: bool,  // In a window of its own rather than the timer's
+    view: Entity<PomodoroApp>,
+    theme: Theme,
+}
+
+impl StatsPanel {
+    pub fn new(content: StatsContent, detached: bool, view: Entity<PomodoroApp>, theme: Theme) -> Self {
+        Self { content, detached, view, theme }
+    }
+
+    fn render_button(&self, id: &'static str, text: &'static str) -> Stateful<Div> {
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .justify_center()
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .bg(self.theme.secondary)
+            .text_color(self.theme.secondary_foreground)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer()
+            .hover(|style| style.opacity(0.8))
+            .child(text)
+    }
+
+    fn render_summary(&self, timeline: &Timeline) -> impl IntoElement {
+        let focus_minutes = timeline.focus_secs / 60;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .child(
+                div()
+                    .text_size(px(28.0))
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(self.theme.foreground)
+                    .child(format!("{}h {:02}m", focus_minutes / 60, focus_minutes % 60))
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(self.theme.muted_foreground)
+                    .child(format!("focused today in {} work session(s)", timeline.work_sessions))
+            )
+    }
+
+    // Colored blocks for each session across the day, empty space for idle time
+    fn render_timeline(&self, timeline: &Timeline) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .relative()
+                    .w_full()
+                    .h(px(14.0))
+                    .rounded(px(4.0))
+                    .bg(self.theme.muted_background)
+                    .children(timeline.blocks.iter().map(|block| {
+                        div()
+                            .absolute()
+                            .top_0()
+                            .bottom_0()
+                            .left(relative(block.offset))
+                            .w(relative(block.width))
+                            .min_w(px(1.0))
+                            .bg(self.theme.session_color(&block.session_type))
+                            .when(block.abandoned, |block| block.opacity(0.4))
+                    }))
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_between()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(timeline.start.format("%H:%M").to_string())
+                    .child(timeline.end.format("%H:%M").to_string())
+            )
+    }
+
+    fn render_stat(&self, label: &'static str, value: String) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .justify_between()
+            .w_full()
+            .text_xs()
+            .child(div().text_color(self.theme.muted_foreground).child(label))
+            .child(div().text_color(self.theme.foreground).child(value))
+    }
+
+    // Bar per hour of day, scaled to the busiest hour
+    fn render_hour_histogram(&self, analytics: &Analytics) -> impl IntoElement {
+        let max = analytics.hour_histogram.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_end()
+                    .gap(px(1.0))
+                    .w_full()
+                    .h(px(32.0))
+                    .children(analytics.hour_histogram.iter().map(|&count| {
+                        div()
+                            .flex_1()
+                            .h(relative(count as f32 / max))
+                            .min_h(px(1.0))
+                            .rounded_t(px(1.0))
+                            .bg(if count > 0 { self.theme.work } else { self.theme.muted_background })
+                    }))
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .justify_between()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child("0h")
+                    .child("12h")
+                    .child("23h")
+            )
+    }
+
+    // "This week  6h 20m / 14h" over a bar, and how the pace looks
+    fn render_goal(&self, goal: &GoalProgress, pomodoro_secs: u32) -> impl IntoElement {
+        let minutes = goal.focus_secs / 60;
+        let pace = goal.pace_text(pomodoro_secs);
+        let behind = pace.starts_with("Behind");
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .child(self.render_stat(
+                goal.period.display_name(),
+                format!("{}h {:02}m / {}h", minutes / 60, minutes % 60, goal.goal_secs / 3600),
+            ))
+            .child(
+                div()
+                    .w_full()
+                    .h(px(6.0))
+                    .rounded(px(3.0))
+                    .bg(self.theme.muted_background)
+                    .child(div().h_full().w(relative(goal.fraction())).rounded(px(3.0)).bg(self.theme.work)),
+            )
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(if behind { self.theme.warning_foreground } else { self.theme.muted_foreground })
+                    .child(pace),
+            )
+    }
+
+    fn render_breaks(&self, breaks: &BreakCounts, break_target: u32) -> impl IntoElement {
+        let percent = breaks.percent_taken().unwrap_or(100);
+        div()
+            .flex()
+            .flex_col()
+            .w_full()
+            .child(self.render_stat("Breaks taken, last 7 days", format!("{}%", percent)))
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(if percent < break_target { self.theme.warning_foreground } else { self.theme.muted_foreground })
+                    .child(format!("{} in full, {} shortened, {} skipped", breaks.taken, breaks.shortened, breaks.skipped)),
+            )
+    }
+
+    fn render_trends(&self, analytics: &Analytics) -> Div {
+        if analytics.completed + analytics.abandoned == 0 {
+            return div()
+                .flex_1()
+                .text_xs()
+                .text_color(self.theme.muted_foreground)
+                .child("Complete a work session to see trends");
+        }
+
+        let average = analytics.average_session_secs / 60;
+        let completion = analytics
+            .completion_rate()
+            .map(|rate| format!("{:.0}% ({}/{})", rate * 100.0, analytics.completed, analytics.completed + analytics.abandoned))
+            .unwrap_or_else(|| "-".to_string());
+        let best_hour = analytics
+            .most_productive_hour()
+            .map(|hour| format!("{:02}:00", hour))
+            .unwrap_or_else(|| "-".to_string());
+
+        div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .gap_1()
+            .child(self.render_stat("Average session", format!("{} min", average)))
+            .child(self.render_stat("Completion rate", completion))
+            .child(self.render_stat("Longest streak", format!("{} day(s)", analytics.longest_streak_days)))
+            .child(self.render_stat("Most productive", best_hour))
+            .child(self.render_hour_histogram(analytics))
+    }
+
+    // Row per weekday, a cell per hour, brighter for more completed sessions
+    fn render_week_grid(&self, usage: &UsageStats) -> impl IntoElement {
+        let max = usage.most_in_an_hour().max(1) as f32;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(1.0))
+            .w_full()
+            .children(usage.by_weekday_hour.iter().zip(["M", "T", "W", "T", "F", "S", "S"]).map(|(hours, day)| {
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap(px(1.0))
+                    .child(
+                        div()
+                            .w(px(10.0))
+                            .text_size(px(9.0))
+                            .text_color(self.theme.muted_foreground)
+                            .child(day)
+                    )
+                    .children(hours.iter().map(|&count| {
+                        div()
+                            .flex_1()
+                            .h(px(7.0))
+                            .rounded(px(1.0))
+                            .when(count == 0, |cell| cell.bg(self.theme.muted_background))
+                            .when(count > 0, |cell| cell.bg(self.theme.work).opacity(0.25 + 0.75 * count as f32 / max))
+                    }))
+            }))
+    }
+
+    fn render_usage(&self, usage: Option<&UsageStats>) -> Div {
+        let Some(usage) = usage else {
+            return div()
+                .flex_1()
+                .text_xs()
+                .text_color(self.theme.muted_foreground)
+                .child("Set usage_stats = true in the config to count sessions by weekday and hour");
+        };
+        if usage.work_sessions == 0 {
+            return div()
+                .flex_1()
+                .text_xs()
+                .text_color(self.theme.muted_foreground)
+                .child("Complete a work session to see usage");
+        }
+
+        let interruptions = usage
+            .average_interruptions()
+            .map(|average| format!("{:.1} per session", average))
+            .unwrap_or_else(|| "-".to_string());
+        let busiest = usage
+            .busiest()
+            .map(|(day, hour)| format!("{} {:02}:00", day, hour))
+            .unwrap_or_else(|| "-".to_string());
+
+        div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .gap_1()
+            .child(self.render_stat("Work sessions", usage.work_sessions.to_string()))
+            .child(self.render_stat("Interruptions", interruptions))
+            .child(self.render_stat("Busiest", busiest))
+            .child(self.render_week_grid(usage))
+    }
+
+    fn render_today(&self, timeline: &Option<Timeline>, note: Option<&str>, editing_note: bool, strict: Option<&StrictDay>) -> Div {
+        let sessions = match timeline {
+            Some(timeline) => div()
+                .flex()
+                .flex_col()
+                .flex_1()
+                .justify_between()
+                .child(self.render_summary(timeline))
+                .child(self.render_timeline(timeline)),
+            None => div()
+                .flex_1()
+                .text_xs()
+                .text_color(self.theme.muted_foreground)
+                .child("No sessions recorded today yet"),
+        };
+        div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .gap_2()
+            .child(sessions)
+            .when_some(strict, |d, strict| d.child(self.render_strict(strict)))
+            .child(self.render_note(note, editing_note))
+    }
+
+    // Strict pomodoros count apart from the sessions above, which leave out void ones
+    fn render_strict(&self, strict: &StrictDay) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .w_full()
+            .child(self.render_stat("Strict pomodoros", strict.completed.to_string()))
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(self.theme.muted_foreground)
+                    .child(format!(
+                        "{} void, {} internal and {} external interruptions",
+                        strict.voided, strict.internal, strict.external
+                    )),
+            )
+    }
+
+    // Today's note, written at the end of the day and kept with its stats
+    fn render_note(&self, note: Option<&str>, editing: bool) -> impl IntoElement {
+        let view = self.view.clone();
+        let view_export = self.view.clone();
+        let view_plan = self.view.clone();
+        let has_note = note.is_some();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .w_full()
+            .when_some(note.map(str::to_string), |d, note| {
+                d.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .rounded(px(6.0))
+                        .border_1()
+                        .border_color(if editing { self.theme.focus_ring } else { self.theme.border })
+                        .text_size(px(11.0))
+                        .text_color(self.theme.foreground)
+                        .child(note),
+                )
+            })
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .gap_1()
+                    .when(editing, |d| {
+                        d.child(
+                            div()
+                                .text_size(px(10.0))
+                                .text_color(self.theme.muted_foreground)
+                                .child("Enter to save, Escape to cancel"),
+                        )
+                    })
+                    .when(!editing, |d| {
+                        d.child(
+                            self.render_button("note-button", if has_note { "Edit note" } else { "Write note" })
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view, |app, cx| app.handle_edit_note(cx));
+                                    cx.stop_propagation();
+                                }),
+                        )
+                        .child(
+                            self.render_button("export-week-button", "Export week")
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view_export, |app, cx| app.handle_export_week(cx));
+                                    cx.stop_propagation();
+                                }),
+                        )
+                        .child(
+                            self.render_button("export-plan-button", "Plan vs actual")
+                                .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                    cx.update_entity(&view_plan, |app, cx| app.handle_export_plan(cx));
+                                    cx.stop_propagation();
+                                }),
+                        )
+                    }),
+            )
+    }
+
+    // "Meeting 1h 30m" chips, as for label emoji and session categories
+    fn render_chips(&self, totals: &[LabelTotal]) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_row()
+            .flex_wrap()
+            .gap_2()
+            .text_size(px(11.0))
+            .text_color(self.theme.muted_foreground)
+            .children(totals.iter().map(|total| {
+                let minutes = total.focus_secs / 60;
+                format!("{} {}h {:02}m", total.label, minutes / 60, minutes % 60)
+            }))
+    }
+
+    fn render_labels(&self, totals: &[LabelTotal], icons: &[LabelTotal], categories: &[LabelTotal], query: &str, range: HistoryRange) -> Div {
+        let view = self.view.clone();
+        let placeholder = query.is_empty();
+
+        div()
+            .flex()
+            .flex_col()
+            .flex_1()
+            .gap_1()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(
+                        div()
+                            .flex_1()
+                            .px_2()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(self.theme.border)
+                            .text_xs()
+                            .text_color(if placeholder { self.theme.muted_foreground } else { self.theme.foreground })
+                            .child(if placeholder { "Search labels...".to_string() } else { format!("{}|", query) })
+                    )
+                    .child(
+                        self.render_button("label-stats-range", range.display_name())
+                            .px_2()
+                            .py_0()
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_cycle_stats_range(cx);
+                                });
+                            })
+                    )
+            )
+            .when(totals.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(self.theme.muted_foreground)
+                        .child("No focus time in this range")
+                )
+            })
+            // Deep work against shallow work such as meetings, then focus time
+            // per label emoji, e.g. "📚 3h 10m"
+            .when(!categories.is_empty(), |d| d.child(self.render_chips(categories)))
+            .when(!icons.is_empty(), |d| d.child(self.render_chips(icons)))
+            .child(
+                div()
+                    .id("label-totals")
+                    .flex()
+                    .flex_col()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(totals.iter().map(|total| {
+                        let minutes = total.focus_secs / 60;
+                        let label = if total.label.is_empty() { "Untitled".to_string() } else { total.label.clone() };
+                        div()
+                            .flex()
+                            .flex_row()
+                            .justify_between()
+                            .w_full()
+                            .text_size(px(11.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .overflow_hidden()
+                                    .text_color(self.theme.foreground)
+                                    .child(label)
+                            )
+                            .child(
+                                div()
+                                    .text_color(self.theme.muted_foreground)
+                                    .child(format!("{}h {:02}m · {}", minutes / 60, minutes % 60, total.sessions))
+                            )
+                    }))
+            )
+    }
+
+    // Tasks worked on over several sessions, from the history
+    fn render_tasks(&self, tasks: &[TaskTotal]) -> Div {
+        if tasks.is_empty() {
+            return div()
+                .flex_1()
+                .text_xs()
+                .text_color(self.theme.muted_foreground)
+                .child("Work on a label again, or continue a session from History, to follow a task here");
+        }
+
+        div().flex().flex_col().flex_1().child(
+            div()
+                .id("task-totals")
+                .flex()
+                .flex_col()
+                .flex_1()
+                .gap_1()
+                .overflow_y_scroll()
+                .children(tasks.iter().map(|task| {
+                let minutes = task.focus_secs / 60;
+                let label = if task.label.is_empty() { "Untitled".to_string() } else { task.label.clone() };
+                let days = if task.days == 1 { "1 day".to_string() } else { format!("{} days", task.days) };
+                div()
+                    .flex()
+                    .flex_col()
+                    .w_full()
+                    .text_size(px(11.0))
+                    .child(div().overflow_hidden().text_color(self.theme.foreground).child(label))
+                    .child(
+                        div()
+                            .text_color(self.theme.muted_foreground)
+                            .child(format!("{} pomodoros over {} · {}h {:02}m", task.pomodoros, days, minutes / 60, minutes % 60))
+                    )
+            }))
+        )
+    }
+}
+
+impl IntoElement for StatsPanel {
+    type Element = Div;
+
+    fn into_element(self) -> Self::Element {
+        let view = self.view.clone();
+        let view_tab = self.view.clone();
+        let view_window = self.view.clone();
+        let detached = self.detached;
+
+        let tab = self.content.tab();
+        let content = match &self.content {
+            StatsContent::Today { timeline, note, editing_note, strict } => {
+                self.render_today(timeline, note.as_deref(), *editing_note, strict.as_ref())
+            }
+            StatsContent::Trends { analytics, goals, pomodoro_secs, breaks, break_target } => div()
+                .flex()
+                .flex_col()
+                .flex_1()
+                .gap_2()
+                .children(goals.iter().map(|goal| self.render_goal(goal, *pomodoro_secs)))
+                .when(!breaks.is_empty(), |trends| trends.child(self.render_breaks(breaks, *break_target)))
+                .child(self.render_trends(analytics)),
+            StatsContent::Labels { totals, icons, categories, query, range } => {
+                self.render_labels(totals, icons, categories, query, *range)
+            }
+            StatsContent::Tasks(tasks) => self.render_tasks(tasks),
+            StatsContent::Usage(usage) => self.render_usage(usage.as_deref()),
+        };
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .p_3()
+            .bg(self.theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(self.theme.border)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(self.theme.foreground)
+                            .child(tab.title())
+                    )
+                    .child(
+                        self.render_button("stats-tab-button", tab.next().title())
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_tab, |app, cx| {
+                                    app.handle_next_stats_tab(cx);
+                                });
+                            })
+                    )
+                    // Pop out into a window of its own, or dock back into the timer
+                    .child(
+                        self.render_button("stats-window-button", if detached { "Dock" } else { "⧉" })
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view_window, |app, cx| {
+                                    if detached {
+                                        app.handle_dock_panel(ActivePanel::Stats, cx);
+                                    } else {
+                                        app.handle_detach_panel(ActivePanel::Stats, cx);
+                                    }
+                                });
+                            })
+                    )
+                    .child(
+                        self.render_button("stats-back-button", if detached { "Close" } else { "Back" })
+                            .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                cx.update_entity(&view, |app, cx| {
+                                    app.handle_toggle_stats(cx);
+                                });
+                            })
+                    )
+            )
+            .child(content)
+    }
+}
@@ -0,0 +1,40 @@
+use gpui::*;
+
+use crate::theme::Theme;
+
+// A few lines of text in a box, shown while the pointer rests on an element.
+// Built through `Tooltip::text`, which goes to an element's `.tooltip()`.
+pub struct Tooltip {
+    lines: Vec<String>,
+    background: Hsla,
+    foreground: Hsla,
+    border: Hsla,
+}
+
+impl Tooltip {
+    pub fn text(lines: Vec<String>, theme: &Theme) -> impl Fn(&mut Window, &mut App) -> AnyView + 'static {
+        let (background, foreground, border) = (theme.background, theme.foreground, theme.border);
+        move |_window, cx| {
+            let lines = lines.clone();
+            cx.new(|_cx| Tooltip { lines, background, foreground, border }).into()
+        }
+    }
+}
+
+impl Render for Tooltip {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<'_, Self>) -> impl IntoElement {
+        div()
+            .flex()
+            .flex_col()
+            .px_2()
+            .py_1()
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(self.border)
+            .bg(self.background)
+            .shadow_md()
+            .text_size(px(12.0))
+            .text_color(self.foreground)
+            .children(self.lines.iter().map(|line| div().child(line.clone())))
+    }
+}
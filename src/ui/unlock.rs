@@ -0,0 +1,158 @@
+use gpui::*;
+
+use crate::app::PomodoroApp;
+use crate::config::Config;
+use crate::notifications;
+use crate::persistence::Persistence;
+use crate::theme::Theme;
+
+// Passphrase prompt shown at startup when the saved state is encrypted
+pub struct Unlock {
+    config: Config,
+    notices: Vec<String>,
+    passphrase: String,
+    error: Option<String>,
+    is_new: bool,  // No encrypted state yet, so the passphrase is being chosen
+    focus_handle: FocusHandle,
+}
+
+impl Unlock {
+    pub fn new(config: Config, notices: Vec<String>, cx: &mut Context<'_, Self>) -> Self {
+        Self {
+            config,
+            notices,
+            passphrase: String::new(),
+            error: None,
+            is_new: !Persistence::is_encrypted(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn submit(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) {
+        if self.passphrase.is_empty() {
+            return;
+        }
+
+        if let Err(e) = Persistence::unlock(&self.passphrase, self.config.encrypt_state) {
+            notifications::log_error(&format!("Failed to unlock state: {:#}", e));
+            self.error = Some(format!("{}", e));
+            self.passphrase.clear();
+            cx.notify();
+            return;
+        }
+
+        notifications::log_info("Unlocked saved state");
+        let config = self.config.clone();
+        let notices = std::mem::take(&mut self.notices);
+        window.replace_root(cx, |window, cx| PomodoroApp::new(config, notices, window, cx));
+    }
+
+    fn handle_key(&mut self, key: &str, window: &mut Window, cx: &mut Context<'_, Self>) {
+        match key {
+            "enter" => self.submit(window, cx),
+            "backspace" => {
+                self.passphrase.pop();
+                cx.notify();
+            }
+            "space" => {
+                self.passphrase.push(' ');
+                cx.notify();
+            }
+            _ if key.chars().count() == 1 => {
+                self.passphrase.push_str(key);
+                self.error = None;
+                cx.notify();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Render for Unlock {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        let theme = Theme::current(self.config.theme, window.appearance());
+        if window.focused(cx).is_none() {
+            self.focus_handle.focus(window);
+        }
+
+        let (title, hint) = if self.is_new {
+            ("Choose a passphrase", "It encrypts your timer history. It can't be recovered if lost.")
+        } else {
+            ("Locked", "Enter your passphrase to open your timer history.")
+        };
+        let masked = if self.passphrase.is_empty() {
+            "Passphrase...".to_string()
+        } else {
+            format!("{}|", "•".repeat(self.passphrase.chars().count()))
+        };
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .p_3()
+            .bg(theme.background)
+            .rounded(px(16.0))
+            .border_2()
+            .border_color(theme.border)
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                this.handle_key(event.keystroke.key.as_str(), window, cx);
+            }))
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(theme.foreground)
+                    .child(title)
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(hint)
+            )
+            .child(
+                div()
+                    .w_full()
+                    .px_2()
+                    .rounded(px(6.0))
+                    .border_1()
+                    .border_color(theme.border)
+                    .text_xs()
+                    .text_color(if self.passphrase.is_empty() { theme.muted_foreground } else { theme.foreground })
+                    .child(masked)
+            )
+            .children(self.error.clone().map(|error| {
+                div()
+                    .px_1()
+                    .rounded(px(6.0))
+                    .bg(theme.warning_background)
+                    .text_color(theme.warning_foreground)
+                    .text_size(px(11.0))
+                    .child(error)
+            }))
+            .child(
+                div()
+                    .flex()
+                    .justify_center()
+                    .child(
+                        div()
+                            .id("unlock-button")
+                            .px_4()
+                            .py_1()
+                            .rounded(px(6.0))
+                            .bg(theme.secondary)
+                            .text_color(theme.secondary_foreground)
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .cursor_pointer()
+                            .hover(|style| style.opacity(0.8))
+                            .on_mouse_down(MouseButton::Left, cx.listener(|this, _event, window, cx| this.submit(window, cx)))
+                            .child(if self.is_new { "Encrypt" } else { "Unlock" })
+                    )
+            )
+    }
+}
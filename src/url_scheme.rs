@@ -0,0 +1,308 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use futures::channel::mpsc::UnboundedSender;
+use futures::channel::oneshot;
+
+use crate::notifications;
+use crate::persistence::Persistence;
+
+// pomodoro:// links for Shortcuts, Raycast, Alfred and the like:
+//   pomodoro://start?label=Write%20report&minutes=50
+//   pomodoro://pause
+//   pomodoro://status
+//   pomodoro://state (everything, for `pomodoro-timer dump-state`)
+// The x-callback-url form (pomodoro://x-callback-url/status?x-success=...) is
+// accepted too; x-success is opened with the result and x-error with the failure.
+pub const SCHEME: &str = "pomodoro://";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrlAction {
+    Start { label: Option<String>, minutes: Option<u32> },
+    Pause,
+    Status,
+    State,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlRequest {
+    pub action: UrlAction,
+    pub success: Option<String>,  // x-success
+    pub error: Option<String>,  // x-error
+}
+
+impl UrlRequest {
+    pub fn parse(url: &str) -> Result<Self> {
+        let Some(rest) = url.strip_prefix(SCHEME) else {
+            bail!("Not a {} link", SCHEME);
+        };
+        let rest = rest.strip_prefix("x-callback-url/").unwrap_or(rest);
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let action = match action.trim_matches('/') {
+            "start" => {
+                let minutes = match param("minutes") {
+                    Some(minutes) => match minutes.parse::<u32>() {
+                        Ok(minutes) if minutes > 0 => Some(minutes),
+                        _ => bail!("minutes must be a whole number above 0 (got \"{}\")", minutes),
+                    },
+                    None => None,
+                };
+                UrlAction::Start { label: param("label"), minutes }
+            }
+            "pause" => UrlAction::Pause,
+            "status" => UrlAction::Status,
+            "state" => UrlAction::State,
+            other => bail!("Unknown action \"{}\"; use start, pause, status or state", other),
+        };
+        Ok(Self { action, success: param("x-success"), error: param("x-error") })
+    }
+}
+
+// The error callback of a link that may not have parsed
+pub fn error_callback(url: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "x-error")
+        .map(|(_, value)| value.into_owned())
+}
+
+// A callback address with the result added to its query
+pub fn callback_url(base: &str, params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return base.to_string();
+    }
+    let query = form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+    let separator = if base.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", base, separator, query)
+}
+
+// A link handed to the running timer, and where the outcome goes: the status
+// or state JSON or "ok" on success, a message on failure
+pub struct IncomingUrl {
+    pub url: String,
+    pub reply: Option<oneshot::Sender<Result<String, String>>>,
+}
+
+// The timer listens on a Unix socket where there is one. Windows gets a
+// loopback port instead, written with a random token to pomodoro.port in the
+// data directory; a link is only taken from a process that could read that
+// file, so other users on the machine can't drive the timer.
+#[cfg(unix)]
+fn socket_path() -> Result<PathBuf> {
+    Ok(Persistence::data_dir()?.join("pomodoro.sock"))
+}
+
+#[cfg_attr(unix, allow(dead_code))]
+fn port_path() -> Result<PathBuf> {
+    Ok(Persistence::data_dir()?.join("pomodoro.port"))
+}
+
+// Accepts links from `pomodoro-timer pomodoro://...` run while the timer is
+// open, which is how Linux desktops hand over links (see the README)
+#[cfg(unix)]
+pub fn listen(urls: UnboundedSender<IncomingUrl>) {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let listener = socket_path().and_then(|path| {
+        if UnixStream::connect(&path).is_ok() {
+            bail!("another timer is already running");
+        }
+        // Left behind by a timer that didn't shut down cleanly
+        let _ = std::fs::remove_file(&path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(UnixListener::bind(&path)?)
+    });
+    let listener = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            notifications::log_error(&format!("Not accepting {} links: {:#}", SCHEME, e));
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            answer(BufReader::new(&stream), &stream, None, &urls);
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn listen(urls: UnboundedSender<IncomingUrl>) {
+    listen_tcp(urls);
+}
+
+#[cfg_attr(unix, allow(dead_code))]
+fn listen_tcp(urls: UnboundedSender<IncomingUrl>) {
+    let listener = port_path().and_then(|path| {
+        if forward_tcp(&path, "").is_some() {
+            bail!("another timer is already running");
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        std::fs::write(&path, format!("{} {}\n", listener.local_addr()?.port(), token))
+            .context("Failed to write the port file")?;
+        Ok((listener, token))
+    });
+    let (listener, token) = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            notifications::log_error(&format!("Not accepting {} links: {:#}", SCHEME, e));
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            answer(BufReader::new(&stream), &stream, Some(&token), &urls);
+        }
+    });
+}
+
+// One link per connection, preceded by the token over TCP. The reply is a
+// single line: "ok <result>" or "error <message>".
+fn answer(mut reader: impl BufRead, mut writer: impl Write, token: Option<&str>, urls: &UnboundedSender<IncomingUrl>) {
+    let mut sent_token = String::new();
+    if token.is_some() && reader.read_line(&mut sent_token).is_err() {
+        return;
+    }
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    // Checked after reading the link, as closing with it unread would reset
+    // the connection before the reply arrives
+    if token.is_some_and(|token| sent_token.trim() != token) {
+        let _ = writeln!(writer, "error Wrong token");
+        return;
+    }
+    // An empty line only asks whether a timer is there
+    if line.trim().is_empty() {
+        let _ = writeln!(writer, "ok");
+        return;
+    }
+    let (reply, outcome) = oneshot::channel();
+    let _ = urls.unbounded_send(IncomingUrl { url: line.trim().to_string(), reply: Some(reply) });
+    let line = match futures::executor::block_on(outcome) {
+        Ok(Ok(result)) => format!("ok {}", result),
+        Ok(Err(message)) => format!("error {}", message),
+        Err(_) => "error The timer is shutting down".to_string(),
+    };
+    let _ = writeln!(writer, "{}", line);
+}
+
+fn ask(mut reader: impl BufRead, mut writer: impl Write, token: Option<&str>, url: &str) -> Option<Result<String, String>> {
+    if let Some(token) = token {
+        writeln!(writer, "{}", token).ok()?;
+    }
+    writeln!(writer, "{}", url).ok()?;
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let line = line.trim_end();
+    match line.split_once(' ').unwrap_or((line, "")) {
+        ("ok", result) => Some(Ok(result.to_string())),
+        (_, message) => Some(Err(message.to_string())),
+    }
+}
+
+// Hands a link to the timer if one is running. Returns its outcome, or None
+// when no timer answered and this process should open one.
+#[cfg(unix)]
+pub fn forward(url: &str) -> Option<Result<String, String>> {
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(socket_path().ok()?).ok()?;
+    ask(BufReader::new(&stream), &stream, None, url)
+}
+
+#[cfg(not(unix))]
+pub fn forward(url: &str) -> Option<Result<String, String>> {
+    forward_tcp(&port_path().ok()?, url)
+}
+
+// A port file left by a timer that has since quit finds nobody listening
+#[cfg_attr(unix, allow(dead_code))]
+fn forward_tcp(path: &Path, url: &str) -> Option<Result<String, String>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let (port, token) = content.trim().split_once(' ')?;
+    let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port.parse().ok()?)).ok()?;
+    ask(BufReader::new(&stream), &stream, Some(token), url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_links() {
+        let request = UrlRequest::parse("pomodoro://start?label=Write%20report&minutes=50").unwrap();
+        assert_eq!(request.action, UrlAction::Start { label: Some("Write report".to_string()), minutes: Some(50) });
+        assert_eq!(UrlRequest::parse("pomodoro://start").unwrap().action, UrlAction::Start { label: None, minutes: None });
+        assert_eq!(UrlRequest::parse("pomodoro://pause/").unwrap().action, UrlAction::Pause);
+
+        let request = UrlRequest::parse("pomodoro://x-callback-url/status?x-success=shortcuts%3A%2F%2Fcallback").unwrap();
+        assert_eq!(request.action, UrlAction::Status);
+        assert_eq!(request.success.as_deref(), Some("shortcuts://callback"));
+
+        assert!(UrlRequest::parse("pomodoro://start?minutes=0").is_err());
+        assert!(UrlRequest::parse("pomodoro://stop").is_err());
+        assert!(UrlRequest::parse("https://example.com").is_err());
+        assert_eq!(error_callback("pomodoro://stop?x-error=raycast%3A%2F%2Ffail").as_deref(), Some("raycast://fail"));
+    }
+
+    #[test]
+    fn adds_results_to_callbacks() {
+        let params = [("state".to_string(), "Working".to_string()), ("label".to_string(), "a b&c".to_string())];
+        assert_eq!(callback_url("shortcuts://done", &params), "shortcuts://done?state=Working&label=a+b%26c");
+        assert_eq!(callback_url("app://x?id=1", &params[..1]), "app://x?id=1&state=Working");
+        assert_eq!(callback_url("app://x", &[]), "app://x");
+    }
+
+    #[test]
+    fn hands_links_over_loopback_with_the_token() {
+        use futures::StreamExt;
+
+        let (urls, mut incoming) = futures::channel::mpsc::unbounded::<IncomingUrl>();
+        std::thread::spawn(move || {
+            while let Some(url) = futures::executor::block_on(incoming.next()) {
+                let _ = url.reply.unwrap().send(Ok(format!("got {}", url.url)));
+            }
+        });
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                answer(BufReader::new(&stream), &stream, Some("secret"), &urls);
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("pomodoro-links-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pomodoro.port");
+        std::fs::write(&path, format!("{} secret\n", port)).unwrap();
+        assert_eq!(forward_tcp(&path, "pomodoro://pause"), Some(Ok("got pomodoro://pause".to_string())));
+        assert_eq!(forward_tcp(&path, ""), Some(Ok(String::new())));
+
+        std::fs::write(&path, format!("{} guess\n", port)).unwrap();
+        assert_eq!(forward_tcp(&path, "pomodoro://pause"), Some(Err("Wrong token".to_string())));
+
+        assert_eq!(forward_tcp(&dir.join("missing.port"), "pomodoro://pause"), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}